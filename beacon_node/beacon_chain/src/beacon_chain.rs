@@ -6,6 +6,7 @@ use crate::fork_choice::{Error as ForkChoiceError, ForkChoice};
 use crate::head_tracker::HeadTracker;
 use crate::metrics;
 use crate::persisted_beacon_chain::{PersistedBeaconChain, BEACON_CHAIN_DB_KEY};
+use crate::sync_state::{sync_state, SyncState};
 use lmd_ghost::LmdGhost;
 use operation_pool::DepositInsertStatus;
 use operation_pool::{OperationPool, PersistedOperationPool};
@@ -451,6 +452,16 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         self.canonical_head.read().beacon_block.slot
     }
 
+    /// Returns `true` if the head is at most `tolerance_slots` behind the current slot, as per
+    /// `sync_state`.
+    ///
+    /// Always returns `false` if the current slot is unavailable (e.g., before genesis).
+    pub fn is_synced(&self, tolerance_slots: u64) -> bool {
+        self.slot().ok().map_or(false, |current_slot| {
+            sync_state(current_slot, self.best_slot(), tolerance_slots) == SyncState::Synced
+        })
+    }
+
     /// Returns the validator index (if any) for the given public key.
     ///
     /// Information is retrieved from the present `beacon_state.validators`.
@@ -1311,6 +1322,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         randao_reveal: Signature,
         slot: Slot,
     ) -> Result<BeaconBlockAndState<T::EthSpec>, BlockProductionError> {
+        // no blocks are produced before genesis
+        self.slot()
+            .map_err(|_| BlockProductionError::UnableToReadSlot)?;
+
         let state = self
             .state_at_slot(slot - 1)
             .map_err(|_| BlockProductionError::UnableToProduceAtSlot(slot))?;
@@ -1392,7 +1407,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
             &self.spec,
         )?;
 
-        let state_root = state.canonical_root();
+        let state_root = metrics::timed_state_root(&state);
 
         block.state_root = state_root;
 
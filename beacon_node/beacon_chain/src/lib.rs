@@ -12,6 +12,7 @@ mod fork_choice;
 mod head_tracker;
 mod metrics;
 mod persisted_beacon_chain;
+mod sync_state;
 pub mod test_utils;
 
 pub use self::beacon_chain::{
@@ -23,7 +24,7 @@ pub use eth1_chain::{Eth1Chain, Eth1ChainBackend};
 pub use events::EventHandler;
 pub use fork_choice::ForkChoice;
 pub use lmd_ghost;
-pub use metrics::scrape_for_metrics;
+pub use metrics::{scrape_for_metrics, timed_block_root, timed_state_root};
 pub use parking_lot;
 pub use slot_clock;
 pub use state_processing::per_block_processing::errors::{
@@ -31,4 +32,5 @@ pub use state_processing::per_block_processing::errors::{
     ExitValidationError, ProposerSlashingValidationError,
 };
 pub use store;
+pub use sync_state::{sync_state, SyncState};
 pub use types;
@@ -1,6 +1,6 @@
 use crate::{BeaconChain, BeaconChainTypes};
 pub use lighthouse_metrics::*;
-use types::{BeaconState, Epoch, Hash256, Slot};
+use types::{BeaconBlock, BeaconState, Epoch, EthSpec, Hash256, Slot};
 
 lazy_static! {
     /*
@@ -143,6 +143,21 @@ lazy_static! {
         "Time taken to add an attestation to fork choice"
     );
 
+    /*
+     * Tree Hashing
+     *
+     * Block processing is excluded, see `BLOCK_PROCESSING_BLOCK_ROOT` and
+     * `BLOCK_PROCESSING_STATE_ROOT`.
+     */
+    pub static ref BEACON_STATE_TREE_HASH_TIMES: Result<Histogram> = try_create_histogram(
+        "beacon_state_tree_hash_seconds",
+        "Time taken to calculate the tree hash root of a beacon state"
+    );
+    pub static ref BEACON_BLOCK_TREE_HASH_TIMES: Result<Histogram> = try_create_histogram(
+        "beacon_block_tree_hash_seconds",
+        "Time taken to calculate the tree hash root of a beacon block"
+    );
+
     /*
      * Persisting BeaconChain to disk
      */
@@ -186,6 +201,24 @@ lazy_static! {
         try_create_int_gauge("beacon_head_state_eth1_deposit_index", "Eth1 deposit index at the head of the chain");
 }
 
+/// Returns the canonical root of `state`, observing the time taken in
+/// `BEACON_STATE_TREE_HASH_TIMES`.
+pub fn timed_state_root<E: EthSpec>(state: &BeaconState<E>) -> Hash256 {
+    let timer = start_timer(&BEACON_STATE_TREE_HASH_TIMES);
+    let root = state.canonical_root();
+    stop_timer(timer);
+    root
+}
+
+/// Returns the canonical root of `block`, observing the time taken in
+/// `BEACON_BLOCK_TREE_HASH_TIMES`.
+pub fn timed_block_root<E: EthSpec>(block: &BeaconBlock<E>) -> Hash256 {
+    let timer = start_timer(&BEACON_BLOCK_TREE_HASH_TIMES);
+    let root = block.canonical_root();
+    stop_timer(timer);
+    root
+}
+
 /// Scrape the `beacon_chain` for metrics that are not constantly updated (e.g., the present slot,
 /// head state info, etc) and update the Prometheus `DEFAULT_REGISTRY`.
 pub fn scrape_for_metrics<T: BeaconChainTypes>(beacon_chain: &BeaconChain<T>) {
@@ -268,3 +301,36 @@ fn set_gauge_by_usize(gauge: &Result<IntGauge>, value: usize) {
 fn set_gauge_by_u64(gauge: &Result<IntGauge>, value: u64) {
     set_gauge(gauge, value as i64);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{Eth1Data, MinimalEthSpec};
+
+    fn sample_count(histogram: &Result<Histogram>) -> u64 {
+        histogram
+            .as_ref()
+            .map(Histogram::get_sample_count)
+            .expect("histogram should be registered")
+    }
+
+    #[test]
+    fn state_tree_hash_is_observed() {
+        let spec = MinimalEthSpec::default_spec();
+        let state = BeaconState::<MinimalEthSpec>::new(0, Eth1Data::default(), &spec);
+
+        let before = sample_count(&BEACON_STATE_TREE_HASH_TIMES);
+        assert_eq!(timed_state_root(&state), state.canonical_root());
+        assert!(sample_count(&BEACON_STATE_TREE_HASH_TIMES) > before);
+    }
+
+    #[test]
+    fn block_tree_hash_is_observed() {
+        let spec = MinimalEthSpec::default_spec();
+        let block = BeaconBlock::<MinimalEthSpec>::empty(&spec);
+
+        let before = sample_count(&BEACON_BLOCK_TREE_HASH_TIMES);
+        assert_eq!(timed_block_root(&block), block.canonical_root());
+        assert!(sample_count(&BEACON_BLOCK_TREE_HASH_TIMES) > before);
+    }
+}
@@ -0,0 +1,60 @@
+use types::Slot;
+
+/// Whether the head of the chain is close enough to the current slot for the node to be synced.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SyncState {
+    Synced,
+    Syncing,
+}
+
+/// Returns `SyncState::Synced` if `best_slot` is at most `tolerance_slots` behind `current_slot`.
+pub fn sync_state(current_slot: Slot, best_slot: Slot, tolerance_slots: u64) -> SyncState {
+    if current_slot.saturating_sub(best_slot) <= tolerance_slots {
+        SyncState::Synced
+    } else {
+        SyncState::Syncing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::{EthSpec, MinimalEthSpec};
+
+    #[test]
+    fn synced_at_head() {
+        let slot = Slot::new(100);
+
+        assert_eq!(sync_state(slot, slot, 0), SyncState::Synced);
+        assert_eq!(sync_state(slot + 1, slot, 0), SyncState::Syncing);
+    }
+
+    #[test]
+    fn one_epoch_behind() {
+        let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+        let best_slot = Slot::new(100);
+
+        assert_eq!(
+            sync_state(best_slot + slots_per_epoch, best_slot, slots_per_epoch),
+            SyncState::Synced
+        );
+        assert_eq!(
+            sync_state(best_slot + slots_per_epoch + 1, best_slot, slots_per_epoch),
+            SyncState::Syncing
+        );
+    }
+
+    #[test]
+    fn far_behind() {
+        let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+
+        assert_eq!(
+            sync_state(Slot::new(1_000_000), Slot::new(0), slots_per_epoch),
+            SyncState::Syncing
+        );
+        assert_eq!(
+            sync_state(Slot::new(u64::max_value()), Slot::new(0), slots_per_epoch),
+            SyncState::Syncing
+        );
+    }
+}
@@ -29,7 +29,8 @@ fn get_store(db_path: &TempDir) -> Arc<DiskStore> {
     let cold_path = db_path.path().join("cold_db");
     let log = NullLoggerBuilder.build().expect("logger should build");
     Arc::new(
-        DiskStore::open(&hot_path, &cold_path, spec, log).expect("disk store should initialize"),
+        DiskStore::open(&hot_path, &cold_path, spec, None, log)
+            .expect("disk store should initialize"),
     )
 }
 
@@ -9,7 +9,12 @@ use beacon_chain::test_utils::{
 use rand::Rng;
 use sloggers::{null::NullLoggerBuilder, Build};
 use std::sync::Arc;
-use store::DiskStore;
+use std::thread;
+use std::time::Duration;
+use store::{
+    migrate::{BackgroundMigrator, MigratorConfig},
+    DiskStore, Migrate, Store,
+};
 use tempfile::{tempdir, TempDir};
 use tree_hash::TreeHash;
 use types::test_utils::{SeedableRng, XorShiftRng};
@@ -32,7 +37,8 @@ fn get_store(db_path: &TempDir) -> Arc<DiskStore> {
     let cold_path = db_path.path().join("cold_db");
     let log = NullLoggerBuilder.build().expect("logger should build");
     Arc::new(
-        DiskStore::open(&hot_path, &cold_path, spec, log).expect("disk store should initialize"),
+        DiskStore::open(&hot_path, &cold_path, spec, None, log)
+            .expect("disk store should initialize"),
     )
 }
 
@@ -230,6 +236,88 @@ fn split_slot_restore() {
     assert_eq!(store.get_split_slot(), split_slot);
 }
 
+// Check that only states older than the prune depth are migrated to the freezer DB.
+#[test]
+fn prune_depth_migration() {
+    let prune_depth = E::slots_per_epoch() * 2;
+
+    // Build the chain in memory, so the states can be given to the migrator by hand.
+    let harness = BeaconChainHarness::new(MinimalEthSpec, KEYPAIRS[0..VALIDATOR_COUNT].to_vec());
+    harness.advance_slot();
+    harness.extend_chain(
+        (E::slots_per_epoch() * 4) as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+    let chain_dump = harness.chain.chain_dump().expect("should dump chain");
+
+    let db_path = tempdir().unwrap();
+    let store = get_store(&db_path);
+    for checkpoint in &chain_dump {
+        store
+            .put_state(&checkpoint.beacon_state_root, &checkpoint.beacon_state)
+            .expect("should store state");
+    }
+
+    let migrator = BackgroundMigrator::<E>::with_config(
+        store.clone(),
+        MigratorConfig {
+            max_finality_distance: prune_depth,
+            max_hot_db_size: None,
+        },
+    );
+    let checkpoint_at = |slot: u64| {
+        chain_dump
+            .iter()
+            .find(|checkpoint| checkpoint.beacon_state.slot == slot)
+            .expect("should have a state at every slot")
+    };
+
+    // Finalizing a state within the prune depth does not migrate any states.
+    let within_depth = checkpoint_at(prune_depth);
+    migrator.freeze_to_state(
+        within_depth.beacon_state_root,
+        within_depth.beacon_state.clone(),
+        0,
+    );
+    assert_eq!(store.get_split_slot(), 0);
+
+    // Finalizing a state beyond the prune depth migrates all older states.
+    let beyond_depth = checkpoint_at(prune_depth + E::slots_per_epoch());
+    let split_slot = beyond_depth.beacon_state.slot;
+    migrator.freeze_to_state(
+        beyond_depth.beacon_state_root,
+        beyond_depth.beacon_state.clone(),
+        0,
+    );
+
+    // The migration happens on a separate thread.
+    for _ in 0..100 {
+        if store.get_split_slot() == split_slot {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(store.get_split_slot(), split_slot);
+
+    for checkpoint in chain_dump
+        .iter()
+        .filter(|checkpoint| checkpoint.beacon_state.slot < split_slot)
+    {
+        let frozen_state = store
+            .get_state::<E>(
+                &checkpoint.beacon_state_root,
+                Some(checkpoint.beacon_state.slot),
+            )
+            .expect("should read state")
+            .expect("migrated state should be in the freezer");
+        assert_eq!(
+            Hash256::from_slice(&frozen_state.tree_hash_root()),
+            checkpoint.beacon_state_root
+        );
+    }
+}
+
 /// Check that the head state's slot matches `expected_slot`.
 fn check_slot(harness: &TestHarness, expected_slot: u64) {
     let state = &harness.chain.head().beacon_state;
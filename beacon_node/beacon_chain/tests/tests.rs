@@ -506,3 +506,30 @@ fn produces_and_processes_with_genesis_skip_slots() {
         run_skip_slot_test(i)
     }
 }
+
+#[test]
+fn is_synced_follows_the_head() {
+    let harness = get_harness(VALIDATOR_COUNT);
+    let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+
+    harness.extend_chain(
+        slots_per_epoch as usize,
+        BlockStrategy::OnCanonicalHead,
+        AttestationStrategy::AllValidators,
+    );
+
+    assert!(harness.chain.is_synced(0), "should be synced at the head");
+
+    for _ in 0..slots_per_epoch {
+        harness.advance_slot();
+    }
+
+    assert!(
+        harness.chain.is_synced(slots_per_epoch),
+        "should be synced one epoch behind the head"
+    );
+    assert!(
+        !harness.chain.is_synced(slots_per_epoch - 1),
+        "should not be synced beyond the tolerance"
+    );
+}
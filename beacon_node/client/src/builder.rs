@@ -1,4 +1,5 @@
 use crate::config::{ClientGenesis, Config as ClientConfig};
+use crate::notifier::{log_genesis_countdown, notify_slot_start, OnSlotTick, SyncStallDetector};
 use crate::Client;
 use beacon_chain::{
     builder::{BeaconChainBuilder, Witness},
@@ -6,8 +7,9 @@ use beacon_chain::{
     lmd_ghost::ThreadSafeReducedTree,
     slot_clock::{SlotClock, SystemTimeSlotClock},
     store::{
-        migrate::{BackgroundMigrator, Migrate, NullMigrator},
-        DiskStore, MemoryStore, SimpleDiskStore, Store,
+        migrate::{BackgroundMigrator, Migrate, MigratorConfig, NullMigrator},
+        CompressionLevel, DiskStore, Error as StoreError, HotColdDbError, MemoryStore,
+        SimpleDiskStore, Store,
     },
     BeaconChain, BeaconChainTypes, Eth1ChainBackend, EventHandler,
 };
@@ -29,7 +31,7 @@ use std::path::Path;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc::UnboundedSender;
-use tokio::timer::Interval;
+use tokio::timer::{Delay, Interval};
 use types::{BeaconState, ChainSpec, EthSpec};
 use websocket_server::{Config as WebSocketConfig, WebSocketSender};
 
@@ -272,9 +274,14 @@ where
             .ok_or_else(|| "libp2p_network requires a runtime_context")?
             .service_context("network".into());
 
-        let (network, network_send) =
-            NetworkService::new(beacon_chain, config, &context.executor, context.log)
-                .map_err(|e| format!("Failed to start libp2p network: {:?}", e))?;
+        let (network, network_send) = NetworkService::new(
+            beacon_chain,
+            config,
+            &context.executor,
+            &context.task_tracker,
+            context.log,
+        )
+        .map_err(|e| format!("Failed to start libp2p network: {:?}", e))?;
 
         self.libp2p_network = Some(network);
         self.libp2p_network_send = Some(network_send);
@@ -314,6 +321,7 @@ where
         let (exit_signal, listening_addr) = rest_api::start_server(
             &client_config.rest_api,
             &context.executor,
+            &context.task_tracker,
             beacon_chain.clone(),
             network_info,
             client_config
@@ -322,10 +330,10 @@ where
             eth2_config.clone(),
             context.log,
         )
-        .map_err(|e| format!("Failed to start HTTP API: {:?}", e))?;
+        .map_err(|e| format!("Failed to start HTTP API: {}", e))?;
 
         self.exit_signals.push(exit_signal);
-        self.http_listen_addr = Some(listening_addr);
+        self.http_listen_addr = listening_addr;
 
         Ok(self)
     }
@@ -366,15 +374,30 @@ where
             Ok(())
         });
 
-        context
-            .executor
-            .spawn(exit.until(interval_future).map(|_| ()));
+        context.executor.spawn(
+            context
+                .task_tracker
+                .track("peer_notifier", exit.until(interval_future).map(|_| ())),
+        );
 
         Ok(self)
     }
 
     /// Immediately starts the service that periodically logs information each slot.
-    pub fn slot_notifier(mut self) -> Result<Self, String> {
+    ///
+    /// The node is logged as synced whilst its head is at most `sync_tolerance_slots` behind the
+    /// current slot. A warning is logged if the head does not advance for `sync_stall_threshold`
+    /// consecutive slots whilst syncing. If `on_tick` is supplied, it is invoked with the status of
+    /// the node each slot.
+    ///
+    /// If the node is started before genesis, the time remaining until genesis is logged each slot
+    /// duration until genesis arrives.
+    pub fn slot_notifier(
+        mut self,
+        sync_tolerance_slots: u64,
+        sync_stall_threshold: u64,
+        on_tick: Option<OnSlotTick>,
+    ) -> Result<Self, String> {
         let context = self
             .runtime_context
             .as_ref()
@@ -400,20 +423,51 @@ where
 
         self.exit_signals.push(exit_signal);
 
+        if beacon_chain.slot_clock.now().is_none() {
+            // before genesis the next slot is the genesis slot
+            let log = log.clone();
+            let log_2 = log.clone();
+            let log_3 = log.clone();
+            let countdown_chain = beacon_chain.clone();
+            let genesis_future = Delay::new(Instant::now() + duration_to_next_slot).map_err(
+                move |e| error!(log_2, "Genesis timer failed"; "error" => format!("{:?}", e)),
+            );
+
+            let countdown_future = Interval::new(Instant::now(), slot_duration)
+                .map_err(
+                    move |e| error!(log_3, "Genesis countdown timer failed"; "error" => format!("{:?}", e)),
+                )
+                .for_each(move |_| {
+                    if let Some(time_to_genesis) = countdown_chain.slot_clock.duration_to_next_slot() {
+                        log_genesis_countdown(&log, time_to_genesis);
+                    }
+                    Ok(())
+                })
+                // the countdown ends once genesis arrives
+                .select(genesis_future)
+                .map(|_| ())
+                .map_err(|_| ());
+
+            context.executor.spawn(context.task_tracker.track(
+                "genesis_countdown",
+                exit.clone().until(countdown_future).map(|_| ()),
+            ));
+        }
+
+        let mut stall_detector = SyncStallDetector::new(sync_stall_threshold);
+
         let interval_future = Interval::new(Instant::now() + duration_to_next_slot, slot_duration)
             .map_err(move |e| error!(log_2, "Slot timer failed"; "error" => format!("{:?}", e)))
             .for_each(move |_| {
-                let best_slot = beacon_chain.head().beacon_block.slot;
-                let latest_block_root = beacon_chain.head().beacon_block_root;
-
                 if let Ok(current_slot) = beacon_chain.slot() {
-                    info!(
-                        log,
-                        "Slot start";
-                        "skip_slots" => current_slot.saturating_sub(best_slot),
-                        "best_block_root" => format!("{}", latest_block_root),
-                        "best_block_slot" => best_slot,
-                        "slot" => current_slot,
+                    notify_slot_start(
+                        &log,
+                        current_slot,
+                        &beacon_chain.head(),
+                        sync_tolerance_slots,
+                        slot_duration,
+                        &mut stall_detector,
+                        on_tick.as_ref(),
                     )
                 } else {
                     error!(
@@ -425,9 +479,11 @@ where
                 Ok(())
             });
 
-        context
-            .executor
-            .spawn(exit.until(interval_future).map(|_| ()));
+        context.executor.spawn(
+            context
+                .task_tracker
+                .track("slot_notifier", exit.until(interval_future).map(|_| ())),
+        );
 
         Ok(self)
     }
@@ -539,8 +595,12 @@ where
             Option<_>,
             Option<_>,
         ) = if config.enabled {
-            let (sender, exit, listening_addr) =
-                websocket_server::start_server(&config, &context.executor, &context.log)?;
+            let (sender, exit, listening_addr) = websocket_server::start_server(
+                &config,
+                &context.executor,
+                &context.task_tracker,
+                &context.log,
+            )?;
             (sender, Some(exit), Some(listening_addr))
         } else {
             (WebSocketSender::dummy(), None, None)
@@ -577,7 +637,15 @@ where
     TEventHandler: EventHandler<TEthSpec> + 'static,
 {
     /// Specifies that the `Client` should use a `DiskStore` database.
-    pub fn disk_store(mut self, hot_path: &Path, cold_path: &Path) -> Result<Self, String> {
+    ///
+    /// A database with an older or newer schema version fails to open. Values in the freezer
+    /// database are compressed if `freezer_compression` is set.
+    pub fn disk_store(
+        mut self,
+        hot_path: &Path,
+        cold_path: &Path,
+        freezer_compression: Option<CompressionLevel>,
+    ) -> Result<Self, String> {
         let context = self
             .runtime_context
             .as_ref()
@@ -588,8 +656,39 @@ where
             .clone()
             .ok_or_else(|| "disk_store requires a chain spec".to_string())?;
 
-        let store = DiskStore::open(hot_path, cold_path, spec, context.log)
-            .map_err(|e| format!("Unable to open database: {:?}", e).to_string())?;
+        let store = DiskStore::open(hot_path, cold_path, spec, freezer_compression, context.log)
+            .map_err(|e| match e {
+                StoreError::HotColdDbError(HotColdDbError::SchemaVersionTooNew {
+                    stored,
+                    supported,
+                }) => format!(
+                    "Database schema version {} is newer than the supported version {}. The \
+                 database was written by a newer version of Lighthouse.",
+                    stored, supported
+                ),
+                StoreError::HotColdDbError(HotColdDbError::UnsupportedSchemaMigration {
+                    from: 0,
+                }) => "The database has no schema version, it was written by an older version of \
+                 Lighthouse and can't be migrated. Remove the database and re-sync the chain."
+                    .to_string(),
+                StoreError::HotColdDbError(HotColdDbError::UnsupportedSchemaMigration { from }) => {
+                    format!(
+                        "Database schema version {} can't be migrated by this version of \
+                     Lighthouse. Remove the database and re-sync the chain.",
+                        from
+                    )
+                }
+                StoreError::HotColdDbError(HotColdDbError::FreezerCompressionMismatch {
+                    stored,
+                    ..
+                }) => format!(
+                    "The freezer database was written {} compression. Restart {} \
+                 --freezer-compression.",
+                    if stored { "with" } else { "without" },
+                    if stored { "with" } else { "without" }
+                ),
+                e => format!("Unable to open database: {:?}", e),
+            })?;
         self.store = Some(Arc::new(store));
         Ok(self)
     }
@@ -673,11 +772,13 @@ where
     TEthSpec: EthSpec + 'static,
     TEventHandler: EventHandler<TEthSpec> + 'static,
 {
-    pub fn background_migrator(mut self) -> Result<Self, String> {
+    /// Specifies that the `Client` should migrate states to the freezer database in a background
+    /// thread, according to `config`.
+    pub fn background_migrator(mut self, config: MigratorConfig) -> Result<Self, String> {
         let store = self.store.clone().ok_or_else(|| {
             "background_migrator requires the store to be initialized".to_string()
         })?;
-        self.store_migrator = Some(BackgroundMigrator::new(store));
+        self.store_migrator = Some(BackgroundMigrator::with_config(store, config));
         Ok(self)
     }
 }
@@ -746,7 +847,9 @@ where
         };
 
         // Starts the service that connects to an eth1 node and periodically updates caches.
-        context.executor.spawn(backend.start(exit));
+        context
+            .executor
+            .spawn(context.task_tracker.track("eth1_rpc", backend.start(exit)));
 
         self.beacon_chain_builder = Some(beacon_chain_builder.eth1_backend(Some(backend)));
 
@@ -2,6 +2,7 @@ use network::NetworkConfig;
 use serde_derive::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use store::CompressionLevel;
 
 /// The number initial validators when starting the `Minimal`.
 const TESTNET_SPEC_CONSTANTS: &str = "minimal";
@@ -55,6 +56,24 @@ pub struct Config {
     /// This is the method used for the 2019 client interop in Canada.
     pub dummy_eth1_backend: bool,
     pub sync_eth1_chain: bool,
+    /// The node is reported as synced whilst its head is at most this many slots behind the
+    /// current slot.
+    pub sync_tolerance_slots: u64,
+    /// A stalled sync is warned about after the head has not advanced for this many consecutive
+    /// slots whilst syncing.
+    pub sync_stall_threshold: u64,
+    /// If false, the number of connected peers is not periodically logged.
+    pub enable_peer_notifier: bool,
+    /// If false, the sync status is not logged at the start of each slot.
+    pub enable_slot_notifier: bool,
+    /// The number of finalized epochs of states kept in the hot database before they are migrated
+    /// to the freezer database.
+    pub prune_depth_epochs: u64,
+    /// If the hot database exceeds this many bytes, all finalized states are migrated to the
+    /// freezer database.
+    pub max_db_size: Option<u64>,
+    /// If set, values in the freezer database are compressed with zstd at this level.
+    pub freezer_compression: Option<CompressionLevel>,
     #[serde(skip)]
     /// The `genesis` field is not serialized or deserialized by `serde` to ensure it is defined
     /// via the CLI at runtime, instead of from a configuration file saved to disk.
@@ -81,6 +100,13 @@ impl Default for Config {
             spec_constants: TESTNET_SPEC_CONSTANTS.into(),
             dummy_eth1_backend: false,
             sync_eth1_chain: false,
+            sync_tolerance_slots: 32,
+            sync_stall_threshold: 10,
+            enable_peer_notifier: true,
+            enable_slot_notifier: true,
+            prune_depth_epochs: 0,
+            max_db_size: None,
+            freezer_compression: None,
             eth1: <_>::default(),
         }
     }
@@ -1,6 +1,7 @@
 extern crate slog;
 
 mod config;
+mod notifier;
 
 pub mod builder;
 pub mod error;
@@ -16,6 +17,7 @@ pub use beacon_chain::{BeaconChainTypes, Eth1ChainBackend};
 pub use builder::ClientBuilder;
 pub use config::{ClientGenesis, Config as ClientConfig};
 pub use eth2_config::Eth2Config;
+pub use notifier::{OnSlotTick, SlotNotifierInfo, SyncState};
 
 /// The core "beacon node" client.
 ///
@@ -35,7 +37,7 @@ impl<T: BeaconChainTypes> Client<T> {
         self.beacon_chain.clone()
     }
 
-    /// Returns the address of the client's HTTP API server, if it was started.
+    /// Returns the address of the client's HTTP API server, if it was started on TCP.
     pub fn http_listen_addr(&self) -> Option<SocketAddr> {
         self.http_listen_addr
     }
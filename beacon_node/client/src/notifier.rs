@@ -0,0 +1,468 @@
+pub use beacon_chain::{sync_state, SyncState};
+
+use beacon_chain::CheckPoint;
+use slog::{info, warn, Logger};
+use std::time::Duration;
+use types::{Epoch, EthSpec, Slot};
+
+const MINUTE: u128 = 60;
+const HOUR: u128 = 60 * MINUTE;
+const DAY: u128 = 24 * HOUR;
+const WEEK: u128 = 7 * DAY;
+const YEAR: u128 = 365 * DAY;
+
+/// The status of the node, given to the `OnSlotTick` callback of the slot notifier each slot.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SlotNotifierInfo {
+    pub current_slot: Slot,
+    pub best_slot: Slot,
+    pub finalized_epoch: Epoch,
+    pub sync_state: SyncState,
+}
+
+impl SlotNotifierInfo {
+    pub fn new<E: EthSpec>(
+        current_slot: Slot,
+        head: &CheckPoint<E>,
+        sync_tolerance_slots: u64,
+    ) -> Self {
+        let best_slot = head.beacon_block.slot;
+
+        Self {
+            current_slot,
+            best_slot,
+            finalized_epoch: head.beacon_state.finalized_checkpoint.epoch,
+            sync_state: sync_state(current_slot, best_slot, sync_tolerance_slots),
+        }
+    }
+}
+
+/// A callback invoked by the slot notifier at the start of each slot, after it has logged.
+pub type OnSlotTick = Box<dyn Fn(&SlotNotifierInfo) + Send>;
+
+/// Detects a stalled sync, where the head has stopped advancing whilst the node is not synced.
+pub struct SyncStallDetector {
+    /// The number of consecutive ticks without the head advancing before a stall is reported.
+    threshold_ticks: u64,
+    last_best_slot: Option<Slot>,
+    stalled_ticks: u64,
+}
+
+impl SyncStallDetector {
+    pub fn new(threshold_ticks: u64) -> Self {
+        Self {
+            threshold_ticks,
+            last_best_slot: None,
+            stalled_ticks: 0,
+        }
+    }
+
+    /// Records the status of the node for a tick, returning `true` if the sync is stalled.
+    ///
+    /// A stall is reported once every `threshold_ticks` consecutive ticks in which the node is
+    /// syncing and its best slot has not increased. A `threshold_ticks` of `0` disables
+    /// detection.
+    pub fn tick(&mut self, info: &SlotNotifierInfo) -> bool {
+        let advanced = self
+            .last_best_slot
+            .map_or(true, |last_best_slot| info.best_slot > last_best_slot);
+        self.last_best_slot = Some(info.best_slot);
+
+        if advanced || info.sync_state == SyncState::Synced {
+            self.stalled_ticks = 0;
+            return false;
+        }
+
+        self.stalled_ticks += 1;
+        self.threshold_ticks > 0 && self.stalled_ticks % self.threshold_ticks == 0
+    }
+
+    /// Returns the number of consecutive ticks the head has not advanced whilst syncing.
+    pub fn stalled_ticks(&self) -> u64 {
+        self.stalled_ticks
+    }
+}
+
+/// Logs the start of `current_slot`, warns if the sync has stalled and then invokes `on_tick`,
+/// if any.
+pub fn notify_slot_start<E: EthSpec>(
+    log: &Logger,
+    current_slot: Slot,
+    head: &CheckPoint<E>,
+    sync_tolerance_slots: u64,
+    slot_duration: Duration,
+    stall_detector: &mut SyncStallDetector,
+    on_tick: Option<&OnSlotTick>,
+) {
+    log_slot_start(log, current_slot, head, sync_tolerance_slots, slot_duration);
+
+    let info = SlotNotifierInfo::new(current_slot, head, sync_tolerance_slots);
+
+    if stall_detector.tick(&info) {
+        warn!(
+            log,
+            "Sync appears stalled — head not advancing";
+            "stalled_ticks" => stall_detector.stalled_ticks(),
+            "best_block_slot" => info.best_slot,
+            "slot" => info.current_slot,
+        );
+    }
+
+    if let Some(on_tick) = on_tick {
+        on_tick(&info);
+    }
+}
+
+/// Logs the "Slot start" message produced by the slot notifier for the given `head`.
+pub fn log_slot_start<E: EthSpec>(
+    log: &Logger,
+    current_slot: Slot,
+    head: &CheckPoint<E>,
+    sync_tolerance_slots: u64,
+    slot_duration: Duration,
+) {
+    let best_slot = head.beacon_block.slot;
+    let justified_checkpoint = &head.beacon_state.current_justified_checkpoint;
+    let skip_slots = current_slot.saturating_sub(best_slot);
+
+    info!(
+        log,
+        "Slot start";
+        "sync_state" => format!("{:?}", sync_state(current_slot, best_slot, sync_tolerance_slots)),
+        "skip_slots" => skip_slots,
+        "sync_distance" => slot_distance_pretty(skip_slots, slot_duration),
+        "best_block_root" => format!("{}", head.beacon_block_root),
+        "best_block_slot" => best_slot,
+        "justified_epoch" => justified_checkpoint.epoch,
+        "justified_root" => format!("{}", justified_checkpoint.root),
+        "slot" => current_slot,
+    )
+}
+
+/// Logs the time remaining until genesis, for a node started before genesis.
+pub fn log_genesis_countdown(log: &Logger, time_to_genesis: Duration) {
+    info!(
+        log,
+        "Waiting for genesis";
+        "time_to_genesis" => secs_pretty(u128::from(time_to_genesis.as_secs())),
+    )
+}
+
+/// Returns the time spanned by `slot_span` slots in a human readable form (e.g., `3 hrs`),
+/// rounded down to the largest whole unit.
+pub fn slot_distance_pretty(slot_span: Slot, slot_duration: Duration) -> String {
+    if slot_duration == Duration::from_secs(0) {
+        return String::from("Unknown");
+    }
+
+    // Computed in `u128` so that very large spans can neither truncate nor overflow.
+    secs_pretty(slot_duration.as_millis() * u128::from(slot_span.as_u64()) / 1_000)
+}
+
+/// Returns `secs` seconds in a human readable form, rounded down to the largest whole unit.
+fn secs_pretty(secs: u128) -> String {
+    if secs < MINUTE {
+        format!("{} secs", secs)
+    } else if secs < HOUR {
+        format!("{} mins", secs / MINUTE)
+    } else if secs < DAY {
+        format!("{} hrs", secs / HOUR)
+    } else if secs < WEEK {
+        format!("{} days", secs / DAY)
+    } else if secs < YEAR {
+        format!("{} weeks", secs / WEEK)
+    } else {
+        format!("{} years", secs / YEAR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::{o, Drain, Key, OwnedKVList, Record, Serializer, KV};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use types::{BeaconBlock, BeaconState, Checkpoint, Epoch, Eth1Data, Hash256, MinimalEthSpec};
+
+    type Records = Arc<Mutex<Vec<HashMap<String, String>>>>;
+
+    const SLOT_DURATION: Duration = Duration::from_secs(6);
+
+    /// A drain that stores the key-value pairs of each record it receives.
+    struct CaptureDrain(Records);
+
+    struct CaptureSerializer<'a>(&'a mut HashMap<String, String>);
+
+    impl<'a> Serializer for CaptureSerializer<'a> {
+        fn emit_arguments(&mut self, key: Key, val: &fmt::Arguments) -> slog::Result {
+            self.0.insert(key.to_string(), format!("{}", val));
+            Ok(())
+        }
+    }
+
+    impl Drain for CaptureDrain {
+        type Ok = ();
+        type Err = slog::Never;
+
+        fn log(&self, record: &Record, _values: &OwnedKVList) -> Result<(), slog::Never> {
+            let mut kv = HashMap::new();
+            record
+                .kv()
+                .serialize(record, &mut CaptureSerializer(&mut kv))
+                .expect("should serialize record");
+            self.0.lock().expect("should lock records").push(kv);
+            Ok(())
+        }
+    }
+
+    /// Returns a head at `best_slot`, justified at epoch 2 and finalized at epoch 1.
+    fn build_head(best_slot: Slot) -> CheckPoint<MinimalEthSpec> {
+        let spec = MinimalEthSpec::default_spec();
+
+        let mut beacon_block = BeaconBlock::empty(&spec);
+        beacon_block.slot = best_slot;
+        let mut beacon_state = BeaconState::<MinimalEthSpec>::new(0, Eth1Data::default(), &spec);
+        beacon_state.current_justified_checkpoint = Checkpoint {
+            epoch: Epoch::new(2),
+            root: Hash256::from_low_u64_be(42),
+        };
+        beacon_state.finalized_checkpoint = Checkpoint {
+            epoch: Epoch::new(1),
+            root: Hash256::from_low_u64_be(41),
+        };
+
+        CheckPoint::new(
+            beacon_block,
+            Hash256::from_low_u64_be(1),
+            beacon_state,
+            Hash256::zero(),
+        )
+    }
+
+    #[test]
+    fn slot_start_includes_justified_checkpoint() {
+        let records: Records = Arc::new(Mutex::new(vec![]));
+        let log = Logger::root(CaptureDrain(records.clone()).fuse(), o!());
+        let head = build_head(Slot::new(17));
+
+        log_slot_start(&log, Slot::new(20), &head, 2, SLOT_DURATION);
+
+        let records = records.lock().expect("should lock records");
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record["justified_epoch"], "2");
+        assert_eq!(
+            record["justified_root"],
+            format!("{}", Hash256::from_low_u64_be(42))
+        );
+        assert_eq!(record["best_block_slot"], "17");
+        assert_eq!(record["skip_slots"], "3");
+        assert_eq!(record["sync_distance"], "18 secs");
+        assert_eq!(record["slot"], "20");
+        assert_eq!(record["sync_state"], "Syncing");
+    }
+
+    #[test]
+    fn genesis_countdown_is_logged() {
+        let records: Records = Arc::new(Mutex::new(vec![]));
+        let log = Logger::root(CaptureDrain(records.clone()).fuse(), o!());
+
+        log_genesis_countdown(&log, Duration::from_millis(42_500));
+        log_genesis_countdown(&log, Duration::from_secs(2 * 60 * 60 + 5));
+
+        let records = records.lock().expect("should lock records");
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["time_to_genesis"], "42 secs");
+        assert_eq!(records[1]["time_to_genesis"], "2 hrs");
+    }
+
+    #[test]
+    fn on_tick_is_invoked_after_logging() {
+        let records: Records = Arc::new(Mutex::new(vec![]));
+        let log = Logger::root(CaptureDrain(records.clone()).fuse(), o!());
+        let head = build_head(Slot::new(17));
+
+        let ticks = Arc::new(Mutex::new(vec![]));
+        let ticks_2 = ticks.clone();
+        let records_2 = records.clone();
+        let on_tick: OnSlotTick = Box::new(move |info| {
+            // The slot start is always logged before the callback is invoked.
+            assert_eq!(records_2.lock().expect("should lock records").len(), 1);
+            ticks_2
+                .lock()
+                .expect("should lock ticks")
+                .push(info.clone());
+        });
+
+        let mut stall_detector = SyncStallDetector::new(10);
+        notify_slot_start(
+            &log,
+            Slot::new(20),
+            &head,
+            2,
+            SLOT_DURATION,
+            &mut stall_detector,
+            Some(&on_tick),
+        );
+
+        assert_eq!(
+            *ticks.lock().expect("should lock ticks"),
+            vec![SlotNotifierInfo {
+                current_slot: Slot::new(20),
+                best_slot: Slot::new(17),
+                finalized_epoch: Epoch::new(1),
+                sync_state: SyncState::Syncing,
+            }]
+        );
+
+        // Without a callback the slot start is still logged.
+        notify_slot_start(
+            &log,
+            Slot::new(21),
+            &head,
+            8,
+            SLOT_DURATION,
+            &mut stall_detector,
+            None,
+        );
+        assert_eq!(records.lock().expect("should lock records").len(), 2);
+    }
+
+    #[test]
+    fn stalled_sync_is_warned() {
+        let records: Records = Arc::new(Mutex::new(vec![]));
+        let log = Logger::root(CaptureDrain(records.clone()).fuse(), o!());
+        let head = build_head(Slot::new(17));
+        let threshold = 10;
+
+        let mut stall_detector = SyncStallDetector::new(threshold);
+        let stalled_warnings = |records: &Records| {
+            records
+                .lock()
+                .expect("should lock records")
+                .iter()
+                .filter(|record| record.contains_key("stalled_ticks"))
+                .count()
+        };
+
+        // The first tick only records the best slot, the head is stalled for each tick after.
+        for slot in 100..=100 + threshold {
+            assert_eq!(stalled_warnings(&records), 0);
+            notify_slot_start(
+                &log,
+                Slot::new(slot),
+                &head,
+                2,
+                SLOT_DURATION,
+                &mut stall_detector,
+                None,
+            );
+        }
+        assert_eq!(stalled_warnings(&records), 1);
+
+        let records = records.lock().expect("should lock records");
+        let warning = records.last().expect("should have a warning");
+        assert_eq!(warning["stalled_ticks"], threshold.to_string());
+        assert_eq!(warning["best_block_slot"], "17");
+    }
+
+    #[test]
+    fn stall_detector_resets_when_head_advances() {
+        let threshold = 3;
+        let mut stall_detector = SyncStallDetector::new(threshold);
+        let info = |best_slot: u64, sync_state| SlotNotifierInfo {
+            current_slot: Slot::new(100),
+            best_slot: Slot::new(best_slot),
+            finalized_epoch: Epoch::new(0),
+            sync_state,
+        };
+
+        assert!(!stall_detector.tick(&info(10, SyncState::Syncing)));
+        assert!(!stall_detector.tick(&info(10, SyncState::Syncing)));
+        assert!(!stall_detector.tick(&info(10, SyncState::Syncing)));
+        // the head advances, resetting the count
+        assert!(!stall_detector.tick(&info(11, SyncState::Syncing)));
+        assert_eq!(stall_detector.stalled_ticks(), 0);
+        assert!(!stall_detector.tick(&info(11, SyncState::Syncing)));
+        assert!(!stall_detector.tick(&info(11, SyncState::Syncing)));
+        assert!(stall_detector.tick(&info(11, SyncState::Syncing)));
+
+        // a synced node with a constant head is not stalled
+        let mut stall_detector = SyncStallDetector::new(threshold);
+        for _ in 0..=threshold {
+            assert!(!stall_detector.tick(&info(10, SyncState::Synced)));
+        }
+    }
+
+    #[test]
+    fn sync_state_at_tolerance_boundary() {
+        let tolerance = 8;
+        let best_slot = Slot::new(100);
+
+        assert_eq!(
+            sync_state(Slot::new(100), best_slot, tolerance),
+            SyncState::Synced
+        );
+        assert_eq!(
+            sync_state(Slot::new(108), best_slot, tolerance),
+            SyncState::Synced
+        );
+        assert_eq!(
+            sync_state(Slot::new(109), best_slot, tolerance),
+            SyncState::Syncing
+        );
+        // A head ahead of the clock (e.g. due to clock drift) is synced.
+        assert_eq!(
+            sync_state(Slot::new(99), best_slot, tolerance),
+            SyncState::Synced
+        );
+    }
+
+    #[test]
+    fn sync_state_ignores_epoch_boundaries() {
+        let slots_per_epoch = MinimalEthSpec::slots_per_epoch();
+        // The last slot of epoch 1 and the first slot of epoch 3, more than one epoch behind.
+        let best_slot = Slot::new(2 * slots_per_epoch - 1);
+        let current_slot = Slot::new(3 * slots_per_epoch);
+
+        assert_eq!(
+            sync_state(current_slot, best_slot, slots_per_epoch + 1),
+            SyncState::Synced
+        );
+        assert_eq!(
+            sync_state(current_slot, best_slot, slots_per_epoch),
+            SyncState::Syncing
+        );
+    }
+
+    #[test]
+    fn slot_distance_pretty_units() {
+        let distance = |slots| slot_distance_pretty(Slot::new(slots), SLOT_DURATION);
+
+        assert_eq!(distance(0), "0 secs");
+        assert_eq!(distance(9), "54 secs");
+        assert_eq!(distance(10), "1 mins");
+        assert_eq!(distance(600), "1 hrs");
+        assert_eq!(distance(14_400), "1 days");
+        assert_eq!(distance(100_800), "1 weeks");
+        assert_eq!(distance(5_256_000), "1 years");
+        assert_eq!(
+            slot_distance_pretty(Slot::new(1), Duration::from_secs(0)),
+            "Unknown"
+        );
+    }
+
+    #[test]
+    fn slot_distance_pretty_large_span() {
+        // 300 million seconds, which would wrap if the span were truncated to a `u32`.
+        assert_eq!(
+            slot_distance_pretty(Slot::new(50_000_000), SLOT_DURATION),
+            "9 years"
+        );
+        assert_eq!(
+            slot_distance_pretty(Slot::new(u64::max_value()), SLOT_DURATION),
+            format!("{} years", u128::from(u64::max_value()) * 6 / YEAR)
+        );
+    }
+}
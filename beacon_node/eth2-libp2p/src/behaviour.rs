@@ -1,8 +1,11 @@
 use crate::config::*;
 use crate::discovery::Discovery;
+use crate::duplicate_cache::DuplicateCache;
+use crate::metrics;
+use crate::multiaddr::Protocol;
 use crate::rpc::{RPCEvent, RPCMessage, RPC};
 use crate::{error, NetworkConfig};
-use crate::{Topic, TopicHash};
+use crate::{Enr, Topic, TopicHash};
 use crate::{BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC};
 use futures::prelude::*;
 use libp2p::{
@@ -15,11 +18,28 @@ use libp2p::{
     tokio_io::{AsyncRead, AsyncWrite},
     NetworkBehaviour, PeerId,
 };
-use slog::{debug, o};
+use slog::{debug, o, warn};
+use ssz::{Decode, DecodeError};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
 use std::num::NonZeroU32;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+use types::{Attestation, AttesterSlashing, BeaconBlock, EthSpec, ProposerSlashing, VoluntaryExit};
 
 const MAX_IDENTIFY_ADDRESSES: usize = 20;
+/// The maximum number of messages awaiting validation recorded by `PropagationLatencies`. Once
+/// reached, the oldest messages are no longer recorded.
+pub const MAX_PENDING_PROPAGATIONS: usize = 4_096;
+/// Messages awaiting validation for longer than this (in seconds) are assumed to never be
+/// propagated.
+const MAX_PROPAGATION_LATENCY: u64 = 60;
+/// The number of received gossipsub messages held while gossip is paused, after which the oldest
+/// held messages are dropped.
+const MAX_PAUSED_GOSSIP_MESSAGES: usize = 1_024;
+/// The number of distinct IP addresses of this node, as observed by peers, which are recorded.
+/// Observations of further addresses are ignored.
+const MAX_OBSERVED_IPS: usize = 64;
 
 /// Builds the network behaviour that manages the core protocols of eth2.
 /// This core behaviour is managed by `Behaviour` which adds peer management to all core
@@ -42,6 +62,57 @@ pub struct Behaviour<TSubstream: AsyncRead + AsyncWrite> {
     #[behaviour(ignore)]
     /// The events generated by this behaviour to be consumed in the swarm poll.
     events: Vec<BehaviourEvent>,
+    /// The maximum size of inbound gossipsub messages, keyed by topic name.
+    #[behaviour(ignore)]
+    gossip_max_size_per_topic: HashMap<String, usize>,
+    /// If `false`, no gossipsub topics are subscribed to and no messages are published.
+    #[behaviour(ignore)]
+    gossip_enabled: bool,
+    /// If `true`, received gossipsub messages are held in `paused_gossip` rather than being
+    /// passed to the application, and no messages are propagated.
+    #[behaviour(ignore)]
+    gossip_paused: bool,
+    /// The gossipsub messages received while gossip is paused, oldest first.
+    #[behaviour(ignore)]
+    paused_gossip: VecDeque<BehaviourEvent>,
+    /// The gossipsub topics that are currently subscribed to.
+    #[behaviour(ignore)]
+    subscribed_topics: HashSet<TopicHash>,
+    /// The connected peers that are subscribed to each gossipsub topic.
+    #[behaviour(ignore)]
+    topic_peers: HashMap<TopicHash, HashSet<PeerId>>,
+    /// The most recently received or published gossipsub messages, keyed by topic.
+    #[behaviour(ignore)]
+    recent_messages: HashMap<TopicHash, VecDeque<RecentMessage>>,
+    /// The topics whose `recent_messages` are republished once the delay elapses, oldest first.
+    #[behaviour(ignore)]
+    pending_replays: VecDeque<(TopicHash, Delay)>,
+    /// The time to wait after a peer subscribes to a topic before republishing its recent
+    /// messages, by which gossipsub has grafted the peer into the mesh of the topic.
+    #[behaviour(ignore)]
+    replay_delay: Duration,
+    /// The ids of recently received gossipsub messages, keyed by topic.
+    #[behaviour(ignore)]
+    duplicate_caches: HashMap<TopicHash, DuplicateCache>,
+    /// The maximum number of ids kept by each of the `duplicate_caches`.
+    #[behaviour(ignore)]
+    duplicate_cache_size: usize,
+    /// The time for which ids are kept by the `duplicate_caches`.
+    #[behaviour(ignore)]
+    duplicate_cache_time: Duration,
+    /// Tracks the time taken to validate and propagate received gossipsub messages.
+    #[behaviour(ignore)]
+    propagation_latencies: PropagationLatencies,
+    /// The maximum number of messages kept per topic in `recent_messages`.
+    #[behaviour(ignore)]
+    replay_buffer_size: usize,
+    /// The agent versions reported by connected peers via the identify protocol.
+    #[behaviour(ignore)]
+    peer_agent_versions: HashMap<PeerId, String>,
+    /// The IP addresses of this node observed by peers via the identify protocol, with the peers
+    /// which observed each.
+    #[behaviour(ignore)]
+    observed_ips: HashMap<IpAddr, HashSet<PeerId>>,
     /// Logger for behaviour actions.
     #[behaviour(ignore)]
     log: slog::Logger,
@@ -64,17 +135,37 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
 
         let identify = Identify::new(
             "lighthouse/libp2p".into(),
-            version::version(),
+            net_conf.client_version.clone(),
             local_key.public(),
         );
 
         Ok(Behaviour {
-            eth2_rpc: RPC::new(log.clone()),
+            eth2_rpc: RPC::new(
+                net_conf.rpc_request_timeouts.clone(),
+                net_conf.max_concurrent_rpc_per_peer,
+                log.clone(),
+            ),
             gossipsub: Gossipsub::new(local_peer_id.clone(), net_conf.gs_config.clone()),
             discovery: Discovery::new(local_key, net_conf, log)?,
             ping: Ping::new(ping_config),
             identify,
             events: Vec::new(),
+            gossip_max_size_per_topic: net_conf.gossip_max_size_per_topic.clone(),
+            gossip_enabled: net_conf.gossip_enabled,
+            gossip_paused: false,
+            paused_gossip: VecDeque::new(),
+            subscribed_topics: HashSet::new(),
+            topic_peers: HashMap::new(),
+            recent_messages: HashMap::new(),
+            pending_replays: VecDeque::new(),
+            replay_delay: net_conf.gs_config.heartbeat_interval * 2,
+            duplicate_caches: HashMap::new(),
+            duplicate_cache_size: net_conf.duplicate_cache_size,
+            duplicate_cache_time: net_conf.duplicate_cache_time,
+            propagation_latencies: PropagationLatencies::default(),
+            replay_buffer_size: net_conf.gossip_replay_buffer_size,
+            peer_agent_versions: HashMap::new(),
+            observed_ips: HashMap::new(),
             log: behaviour_log,
         })
     }
@@ -96,22 +187,74 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<GossipsubE
         match event {
             GossipsubEvent::Message(propagation_source, gs_msg) => {
                 let id = gs_msg.id();
+
+                if gossip_size_acceptance(
+                    &self.gossip_max_size_per_topic,
+                    &gs_msg.topics,
+                    gs_msg.data.len(),
+                ) == MessageAcceptance::Reject
+                {
+                    // The message is never propagated as propagation requires validation by the
+                    // beacon chain, which rejected messages do not reach.
+                    warn!(self.log, "Rejecting oversized gossipsub message";
+                        "peer_id" => format!("{}", propagation_source),
+                        "topics" => format!("{:?}", gs_msg.topics),
+                        "size" => gs_msg.data.len(),
+                    );
+                    metrics::inc_counter(&metrics::GOSSIP_OVERSIZED_MESSAGES_REJECTED);
+                    self.events
+                        .push(BehaviourEvent::GossipRejected(propagation_source));
+                    return;
+                }
+
+                if !self.is_new_message(&gs_msg.topics, &id) {
+                    debug!(self.log, "Dropping duplicate gossipsub message";
+                        "peer_id" => format!("{}", propagation_source),
+                        "topics" => format!("{:?}", gs_msg.topics),
+                    );
+                    return;
+                }
+
                 let msg = PubsubMessage::from_topics(&gs_msg.topics, gs_msg.data);
+                self.buffer_recent_message(&gs_msg.topics, &id, &msg, false);
+                self.propagation_latencies
+                    .message_received(id.clone(), msg.kind(), Instant::now());
 
                 // Note: We are keeping track here of the peer that sent us the message, not the
                 // peer that originally published the message.
-                self.events.push(BehaviourEvent::GossipMessage {
+                let event = BehaviourEvent::GossipMessage {
                     id,
                     source: propagation_source,
                     topics: gs_msg.topics,
                     message: msg,
-                });
+                };
+
+                if self.gossip_paused {
+                    if self.paused_gossip.len() == MAX_PAUSED_GOSSIP_MESSAGES {
+                        debug!(self.log, "Dropping gossipsub message held while paused");
+                        self.paused_gossip.pop_front();
+                    }
+                    self.paused_gossip.push_back(event);
+                } else {
+                    self.events.push(event);
+                }
             }
             GossipsubEvent::Subscribed { peer_id, topic } => {
+                self.topic_peers
+                    .entry(topic.clone())
+                    .or_insert_with(HashSet::new)
+                    .insert(peer_id.clone());
+                self.update_topic_metrics(&topic);
+                self.schedule_replay(&topic);
                 self.events
                     .push(BehaviourEvent::PeerSubscribed(peer_id, topic));
             }
-            GossipsubEvent::Unsubscribed { .. } => {}
+            GossipsubEvent::Unsubscribed { peer_id, topic } => {
+                if let Some(peers) = self.topic_peers.get_mut(&topic) {
+                    peers.remove(&peer_id);
+                }
+                self.update_topic_metrics(&topic);
+            }
         }
     }
 }
@@ -125,6 +268,16 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<RPCMessage
                 self.events.push(BehaviourEvent::PeerDialed(peer_id))
             }
             RPCMessage::PeerDisconnected(peer_id) => {
+                let mut topics = vec![];
+                for (topic, peers) in self.topic_peers.iter_mut() {
+                    if peers.remove(&peer_id) {
+                        topics.push(topic.clone());
+                    }
+                }
+                for topic in topics {
+                    self.update_topic_metrics(&topic);
+                }
+                self.peer_agent_versions.remove(&peer_id);
                 self.events.push(BehaviourEvent::PeerDisconnected(peer_id))
             }
             RPCMessage::RPC(peer_id, rpc_event) => {
@@ -147,6 +300,15 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
     fn poll<TBehaviourIn>(
         &mut self,
     ) -> Async<NetworkBehaviourAction<TBehaviourIn, BehaviourEvent>> {
+        while let Some((_, delay)) = self.pending_replays.front_mut() {
+            if let Ok(Async::NotReady) = delay.poll() {
+                break;
+            }
+            if let Some((topic, _)) = self.pending_replays.pop_front() {
+                self.replay_to_peers(&topic);
+            }
+        }
+
         if !self.events.is_empty() {
             return Async::Ready(NetworkBehaviourAction::GenerateEvent(self.events.remove(0)));
         }
@@ -172,6 +334,8 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<IdentifyEv
                     );
                     info.listen_addrs.truncate(MAX_IDENTIFY_ADDRESSES);
                 }
+                self.peer_agent_versions
+                    .insert(peer_id.clone(), info.agent_version.clone());
                 debug!(self.log, "Identified Peer"; "Peer" => format!("{}", peer_id),
                 "protocol_version" => info.protocol_version,
                 "agent_version" => info.agent_version,
@@ -179,6 +343,18 @@ impl<TSubstream: AsyncRead + AsyncWrite> NetworkBehaviourEventProcess<IdentifyEv
                 "observed_address" => format!("{:?}", observed_addr),
                 "protocols" => format!("{:?}", info.protocols)
                 );
+                let observed_ip = match observed_addr.iter().next() {
+                    Some(Protocol::Ip4(ip)) => Some(IpAddr::from(ip)),
+                    Some(Protocol::Ip6(ip)) => Some(IpAddr::from(ip)),
+                    _ => None,
+                };
+                if let Some(ip) = observed_ip {
+                    if self.observed_ips.len() < MAX_OBSERVED_IPS
+                        || self.observed_ips.contains_key(&ip)
+                    {
+                        self.observed_ips.entry(ip).or_default().insert(peer_id);
+                    }
+                }
             }
             IdentifyEvent::Sent { .. } => {}
             IdentifyEvent::Error { .. } => {}
@@ -199,30 +375,278 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
     /* Pubsub behaviour functions */
 
     /// Subscribes to a gossipsub topic.
+    ///
+    /// Always returns `false` if gossip is disabled.
     pub fn subscribe(&mut self, topic: Topic) -> bool {
-        self.gossipsub.subscribe(topic)
+        if !self.gossip_enabled {
+            return false;
+        }
+
+        let topic_hash = topic.no_hash();
+        let subscribed = self.gossipsub.subscribe(topic);
+        if subscribed {
+            self.subscribed_topics.insert(topic_hash.clone());
+            self.update_topic_metrics(&topic_hash);
+        }
+        subscribed
     }
 
     /// Unsubscribe from a gossipsub topic.
     pub fn unsubscribe(&mut self, topic: Topic) -> bool {
-        self.gossipsub.unsubscribe(topic)
+        let topic_hash = topic.no_hash();
+        self.subscribed_topics.remove(&topic_hash);
+        let unsubscribed = self.gossipsub.unsubscribe(topic);
+        self.update_topic_metrics(&topic_hash);
+        unsubscribed
+    }
+
+    /// Returns the gossipsub topics that are currently subscribed to.
+    pub fn subscribed_topics(&self) -> &HashSet<TopicHash> {
+        &self.subscribed_topics
+    }
+
+    /// Updates the subscribed peers gauge of `topic`.
+    fn update_topic_metrics(&self, topic: &TopicHash) {
+        let subscribed_peers = self.topic_peers.get(topic).map_or(0, HashSet::len);
+        metrics::set_gauge_vec(
+            &metrics::GOSSIP_SUBSCRIBED_PEERS,
+            &[topic.as_str()],
+            subscribed_peers as i64,
+        );
     }
 
-    /// Publishes a message on the pubsub (gossipsub) behaviour.
-    pub fn publish(&mut self, topics: &[Topic], message: PubsubMessage) {
+    /// Returns the recently received messages on `topic`, oldest first.
+    ///
+    /// Always empty if `gossip_replay_buffer_size` is `0`.
+    pub fn replay_recent(&self, topic: &Topic) -> Vec<PubsubMessage> {
+        self.recent_messages
+            .get(&topic.no_hash())
+            .map(|messages| {
+                messages
+                    .iter()
+                    .map(|recent| recent.message.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Records `id` in the duplicate cache of each of `topics`, returning `false` if any of them
+    /// already held it.
+    fn is_new_message(&mut self, topics: &[TopicHash], id: &str) -> bool {
+        let now = Instant::now();
+        let (max_len, ttl) = (self.duplicate_cache_size, self.duplicate_cache_time);
+        topics.iter().fold(true, |is_new, topic| {
+            self.duplicate_caches
+                .entry(topic.clone())
+                .or_insert_with(|| DuplicateCache::new(max_len, ttl))
+                .insert(id, now)
+                && is_new
+        })
+    }
+
+    /// Adds a message to the replay buffer of each of its topics, evicting the oldest message once
+    /// a buffer is full. `validated` is `true` if this node published the message.
+    fn buffer_recent_message(
+        &mut self,
+        topics: &[TopicHash],
+        id: &str,
+        message: &PubsubMessage,
+        validated: bool,
+    ) {
+        if self.replay_buffer_size == 0 {
+            return;
+        }
+
+        for topic in topics {
+            let messages = self
+                .recent_messages
+                .entry(topic.clone())
+                .or_insert_with(VecDeque::new);
+            if let Some(recent) = messages.iter_mut().find(|recent| recent.id == id) {
+                recent.validated |= validated;
+                continue;
+            }
+            if messages.len() == self.replay_buffer_size {
+                messages.pop_front();
+            }
+            messages.push_back(RecentMessage {
+                id: id.to_string(),
+                message: message.clone(),
+                validated,
+            });
+        }
+    }
+
+    /// Schedules the recent messages of `topic` to be republished, after a peer subscribed to it.
+    ///
+    /// A replay which is already pending for `topic` is postponed, so that it also reaches the
+    /// newly subscribed peer.
+    fn schedule_replay(&mut self, topic: &TopicHash) {
+        let has_validated = self.recent_messages.get(topic).map_or(false, |messages| {
+            messages.iter().any(|recent| recent.validated)
+        });
+        if !has_validated {
+            return;
+        }
+
+        self.pending_replays.retain(|(pending, _)| pending != topic);
+        self.pending_replays.push_back((
+            topic.clone(),
+            Delay::new(Instant::now() + self.replay_delay),
+        ));
+    }
+
+    /// Republishes the recent messages on `topic` which this node published or propagated, so
+    /// that peers which subscribed since receive them. Peers which already received a message
+    /// discard it as a duplicate. Unvalidated messages are never republished.
+    fn replay_to_peers(&mut self, topic: &TopicHash) {
+        if self.gossip_paused || !self.subscribed_topics.contains(topic) {
+            return;
+        }
+
+        if let Some(messages) = self.recent_messages.get(topic) {
+            let gossip_topic = Topic::new(topic.as_str().to_string());
+            for recent in messages.iter().filter(|recent| recent.validated) {
+                self.gossipsub
+                    .publish(&gossip_topic, recent.message.clone().into_data());
+            }
+            debug!(self.log, "Republished recent gossipsub messages"; "topic" => topic.as_str());
+        }
+    }
+
+    /// Publishes a message on the pubsub (gossipsub) behaviour, returning the number of distinct
+    /// peers subscribed to any of `topics`.
+    ///
+    /// Gossipsub sends the message to its mesh peers of each topic, which are a subset of the
+    /// subscribed peers, so this is not the number of peers the message is sent to.
+    ///
+    /// The message is still published if no peers are subscribed, but `PublishError::NoPeers` is
+    /// returned. If `gossip_replay_buffer_size` is not `0`, the message is republished to peers
+    /// which subscribe to its topics later. Does nothing if gossip is disabled.
+    pub fn publish(
+        &mut self,
+        topics: &[Topic],
+        message: PubsubMessage,
+    ) -> Result<PublishResult, PublishError> {
+        let result = self.publish_peers(topics);
+        if let Err(PublishError::GossipDisabled) = result {
+            return result;
+        }
+
         let message_data = message.into_data();
         for topic in topics {
             self.gossipsub.publish(topic, message_data.clone());
+            if self.replay_buffer_size > 0 {
+                // gossipsub publishes a separate message on each topic
+                let topic_hash = [topic.no_hash()];
+                let id = topic_message_id(&topic_hash, &message_data);
+                let message = PubsubMessage::from_topics(&topic_hash, message_data.clone());
+                self.buffer_recent_message(&topic_hash, &id, &message, true);
+            }
+        }
+
+        match &result {
+            Ok(PublishResult { subscribed_peers }) => {
+                metrics::observe(
+                    &metrics::GOSSIP_PUBLISH_SUBSCRIBED_PEERS,
+                    *subscribed_peers as f64,
+                );
+            }
+            Err(_) => {
+                metrics::observe(&metrics::GOSSIP_PUBLISH_SUBSCRIBED_PEERS, 0.0);
+                metrics::inc_counter(&metrics::GOSSIP_PUBLISH_NO_PEERS);
+            }
+        }
+
+        result
+    }
+
+    /// Returns the result that publishing a message on `topics` would currently have (see
+    /// `publish`), without publishing anything.
+    pub fn publish_peers(&self, topics: &[Topic]) -> Result<PublishResult, PublishError> {
+        if !self.gossip_enabled {
+            return Err(PublishError::GossipDisabled);
+        }
+
+        let mut subscribed_peers = HashSet::new();
+        for topic in topics {
+            if let Some(peers) = self.topic_peers.get(&topic.no_hash()) {
+                subscribed_peers.extend(peers.iter());
+            }
         }
+
+        if subscribed_peers.is_empty() {
+            return Err(PublishError::NoPeers);
+        }
+
+        Ok(PublishResult {
+            subscribed_peers: subscribed_peers.len(),
+        })
+    }
+
+    /// Holds received gossipsub messages until `resume_gossip` is called, without affecting RPC or
+    /// discovery. No messages are propagated while gossip is paused.
+    pub fn pause_gossip(&mut self) {
+        self.gossip_paused = true;
+    }
+
+    /// Passes the gossipsub messages held while paused to the application, in the order they were
+    /// received, and allows messages to be propagated again.
+    pub fn resume_gossip(&mut self) {
+        self.gossip_paused = false;
+        self.events.extend(self.paused_gossip.drain(..));
+    }
+
+    /// Returns `true` if gossip has been paused with `pause_gossip`.
+    pub fn is_gossip_paused(&self) -> bool {
+        self.gossip_paused
     }
 
     /// Forwards a message that is waiting in gossipsub's mcache. Messages are only propagated
     /// once validated by the beacon chain.
+    ///
+    /// Does nothing if gossip is paused.
     pub fn propagate_message(&mut self, propagation_source: &PeerId, message_id: String) {
+        if self.gossip_paused {
+            debug!(self.log, "Not propagating gossipsub message while paused"; "message_id" => &message_id);
+            return;
+        }
+
+        if let Some((kind, latency)) = self
+            .propagation_latencies
+            .message_validated(&message_id, Instant::now())
+        {
+            metrics::observe_vec(
+                &metrics::GOSSIP_PROPAGATION_LATENCY,
+                &[kind],
+                latency.as_secs_f64(),
+            );
+        }
+        for messages in self.recent_messages.values_mut() {
+            for recent in messages.iter_mut().filter(|recent| recent.id == message_id) {
+                recent.validated = true;
+            }
+        }
         self.gossipsub
             .propagate_message(&message_id, propagation_source);
     }
 
+    /* Identify behaviour functions */
+
+    /// Returns the agent version that `peer_id` reported via the identify protocol, if it has
+    /// been identified.
+    pub fn peer_agent_version(&self, peer_id: &PeerId) -> Option<&str> {
+        self.peer_agent_versions.get(peer_id).map(String::as_str)
+    }
+
+    /// Returns the IP addresses of this node observed by peers via the identify protocol, with
+    /// the number of distinct peers which observed each.
+    pub fn observed_ips(&self) -> impl Iterator<Item = (IpAddr, usize)> + '_ {
+        self.observed_ips
+            .iter()
+            .map(|(ip, peers)| (*ip, peers.len()))
+    }
+
     /* Eth2 RPC behaviour functions */
 
     /// Sends an RPC Request/Response via the RPC protocol.
@@ -236,11 +660,32 @@ impl<TSubstream: AsyncRead + AsyncWrite> Behaviour<TSubstream> {
         self.discovery.connected_peers()
     }
 
+    /// Returns `true` if another outbound connection may be made without exceeding the configured
+    /// peer limits.
+    pub fn outbound_slot_available(&self) -> bool {
+        self.discovery.outbound_slot_available()
+    }
+
+    /// Returns the inbound peers which connected after the inbound peer limit was reached.
+    pub fn take_excess_inbound_peers(&mut self) -> Vec<PeerId> {
+        self.discovery.take_excess_inbound_peers()
+    }
+
     /// Notify discovery that the peer has been banned.
     pub fn peer_banned(&mut self, peer_id: PeerId) {
         self.discovery.peer_banned(peer_id);
     }
 
+    /// Adds an ENR to the routing table of the discovery mechanism.
+    pub fn add_enr(&mut self, enr: Enr) {
+        self.discovery.add_enr(enr);
+    }
+
+    /// Immediately searches for up to `target` new peers to dial.
+    pub fn discover_peers(&mut self, target: usize) {
+        self.discovery.discover_peers(target);
+    }
+
     /// Informs the discovery behaviour if a new IP/Port is set at the application layer
     pub fn update_local_enr_socket(&mut self, socket: std::net::SocketAddr, is_tcp: bool) {
         self.discovery.update_local_enr(socket, is_tcp);
@@ -268,10 +713,89 @@ pub enum BehaviourEvent {
     },
     /// Subscribed to peer for given topic
     PeerSubscribed(PeerId, TopicHash),
+    /// A gossipsub message from the given peer failed validation and was rejected. The peer
+    /// should be penalized.
+    GossipRejected(PeerId),
+}
+
+/// The result of validating an inbound gossipsub message before it is passed to the application.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageAcceptance {
+    /// The message may be passed to the application.
+    Accept,
+    /// The message must not be processed or propagated and the sender should be penalized.
+    Reject,
+}
+
+/// Determines if a gossipsub message of `len` bytes is acceptable on all of the given `topics`.
+///
+/// Limits are looked up by topic name (e.g., `beacon_block`) in `max_size_per_topic`. Topics
+/// without a limit only need to respect `GOSSIP_MAX_SIZE`.
+pub fn gossip_size_acceptance(
+    max_size_per_topic: &HashMap<String, usize>,
+    topics: &[TopicHash],
+    len: usize,
+) -> MessageAcceptance {
+    let max_size = topics
+        .iter()
+        .filter_map(topic_name)
+        .filter_map(|name| max_size_per_topic.get(name))
+        .fold(GOSSIP_MAX_SIZE, |max, limit| std::cmp::min(max, *limit));
+
+    if len > max_size {
+        MessageAcceptance::Reject
+    } else {
+        MessageAcceptance::Accept
+    }
+}
+
+/// Returns the name of an eth2 topic (e.g., `beacon_block` for `/eth2/beacon_block/ssz`).
+///
+/// Returns `None` if the topic does not have the eth2 prefix and encoding postfix.
+fn topic_name(topic: &TopicHash) -> Option<&str> {
+    let topic_parts: Vec<&str> = topic.as_str().split('/').collect();
+    if topic_parts.len() == 4
+        && topic_parts[1] == TOPIC_PREFIX
+        && topic_parts[3] == TOPIC_ENCODING_POSTFIX
+    {
+        Some(topic_parts[2])
+    } else {
+        None
+    }
+}
+
+/// A gossipsub message kept in the replay buffer of a topic.
+struct RecentMessage {
+    /// The gossipsub message id.
+    id: String,
+    message: PubsubMessage,
+    /// Whether this node published or propagated the message, which may then be republished.
+    validated: bool,
+}
+
+/// The outcome of successfully publishing a gossipsub message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PublishResult {
+    /// The number of distinct peers subscribed to any of the topics of the message. Gossipsub
+    /// sends the message to its mesh of each topic, which may be fewer of these peers.
+    pub subscribed_peers: usize,
+}
+
+/// The reasons a gossipsub message may not have reached any peers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PublishError {
+    /// Gossipsub is disabled, the message was not published.
+    GossipDisabled,
+    /// The message was published but no connected peers are subscribed to its topics.
+    NoPeers,
+    /// Too many messages are waiting to be published, the message was dropped.
+    QueueFull,
 }
 
-/// Messages that are passed to and from the pubsub (Gossipsub) behaviour. These are encoded and
-/// decoded upstream.
+/// Messages that are passed to and from the pubsub (Gossipsub) behaviour.
+///
+/// Each message owns its (still encoded) bytes and is independent of any libp2p buffers, so it
+/// can be freely moved between tasks. Use `decode` to obtain the typed eth2 message.
 #[derive(Debug, Clone, PartialEq)]
 pub enum PubsubMessage {
     /// Gossipsub message providing notification of a new block.
@@ -295,15 +819,11 @@ impl PubsubMessage {
      * Also note that a message can be associated with many topics. As soon as one of the topics is
      * known we match. If none of the topics are known we return an unknown state.
      */
-    fn from_topics(topics: &[TopicHash], data: Vec<u8>) -> Self {
+    pub(crate) fn from_topics(topics: &[TopicHash], data: Vec<u8>) -> Self {
         for topic in topics {
             // compare the prefix and postfix, then match on the topic
-            let topic_parts: Vec<&str> = topic.as_str().split('/').collect();
-            if topic_parts.len() == 4
-                && topic_parts[1] == TOPIC_PREFIX
-                && topic_parts[3] == TOPIC_ENCODING_POSTFIX
-            {
-                match topic_parts[2] {
+            if let Some(name) = topic_name(topic) {
+                match name {
                     BEACON_BLOCK_TOPIC => return PubsubMessage::Block(data),
                     BEACON_ATTESTATION_TOPIC => return PubsubMessage::Attestation(data),
                     VOLUNTARY_EXIT_TOPIC => return PubsubMessage::VoluntaryExit(data),
@@ -316,6 +836,52 @@ impl PubsubMessage {
         PubsubMessage::Unknown(data)
     }
 
+    /// Decodes the SSZ bytes of this message into the eth2 type associated with its topic.
+    pub fn decode<E: EthSpec>(&self) -> Result<DecodedPubsubMessage<E>, PubsubDecodeError> {
+        match self {
+            PubsubMessage::Block(data) => BeaconBlock::from_ssz_bytes(data)
+                .map(DecodedPubsubMessage::Block)
+                .map_err(PubsubDecodeError::InvalidSsz),
+            PubsubMessage::Attestation(data) => Attestation::from_ssz_bytes(data)
+                .map(DecodedPubsubMessage::Attestation)
+                .map_err(PubsubDecodeError::InvalidSsz),
+            PubsubMessage::VoluntaryExit(data) => VoluntaryExit::from_ssz_bytes(data)
+                .map(DecodedPubsubMessage::VoluntaryExit)
+                .map_err(PubsubDecodeError::InvalidSsz),
+            PubsubMessage::ProposerSlashing(data) => ProposerSlashing::from_ssz_bytes(data)
+                .map(DecodedPubsubMessage::ProposerSlashing)
+                .map_err(PubsubDecodeError::InvalidSsz),
+            PubsubMessage::AttesterSlashing(data) => AttesterSlashing::from_ssz_bytes(data)
+                .map(DecodedPubsubMessage::AttesterSlashing)
+                .map_err(PubsubDecodeError::InvalidSsz),
+            PubsubMessage::Unknown(_) => Err(PubsubDecodeError::UnknownTopic),
+        }
+    }
+
+    /// Returns the name of the topic associated with the type of this message, or `"unknown"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PubsubMessage::Block(_) => BEACON_BLOCK_TOPIC,
+            PubsubMessage::Attestation(_) => BEACON_ATTESTATION_TOPIC,
+            PubsubMessage::VoluntaryExit(_) => VOLUNTARY_EXIT_TOPIC,
+            PubsubMessage::ProposerSlashing(_) => PROPOSER_SLASHING_TOPIC,
+            PubsubMessage::AttesterSlashing(_) => ATTESTER_SLASHING_TOPIC,
+            PubsubMessage::Unknown(_) => "unknown",
+        }
+    }
+
+    /// Returns the encoded bytes of this message.
+    pub(crate) fn data(&self) -> &[u8] {
+        match self {
+            PubsubMessage::Block(data)
+            | PubsubMessage::Attestation(data)
+            | PubsubMessage::VoluntaryExit(data)
+            | PubsubMessage::ProposerSlashing(data)
+            | PubsubMessage::AttesterSlashing(data)
+            | PubsubMessage::Unknown(data) => data,
+        }
+    }
+
     fn into_data(self) -> Vec<u8> {
         match self {
             PubsubMessage::Block(data)
@@ -327,3 +893,86 @@ impl PubsubMessage {
         }
     }
 }
+
+/// Records when each received gossipsub message was first seen, to measure the time until it is
+/// validated and propagated.
+#[derive(Default)]
+pub struct PropagationLatencies {
+    /// The time each message was received and its kind, keyed by message id.
+    first_seen: HashMap<String, (Instant, &'static str)>,
+    /// The ids of the messages in `first_seen`, oldest first. May also hold the ids of messages
+    /// which have since been validated.
+    order: VecDeque<String>,
+}
+
+impl PropagationLatencies {
+    /// Records that the message `id` of the given `kind` was received at `now`.
+    ///
+    /// Only the first receipt of a message is recorded.
+    pub fn message_received(&mut self, id: String, kind: &'static str, now: Instant) {
+        if self.first_seen.contains_key(&id) {
+            return;
+        }
+
+        // messages which failed validation are never propagated, drop the oldest messages once
+        // they have waited too long or there are too many
+        let max_latency = Duration::from_secs(MAX_PROPAGATION_LATENCY);
+        while let Some(oldest) = self.order.front() {
+            let expired = self
+                .first_seen
+                .get(oldest)
+                .map_or(true, |(seen, _)| now.duration_since(*seen) >= max_latency);
+            if !expired && self.first_seen.len() < MAX_PENDING_PROPAGATIONS {
+                break;
+            }
+            if let Some(oldest) = self.order.pop_front() {
+                self.first_seen.remove(&oldest);
+            }
+        }
+
+        self.first_seen.insert(id.clone(), (now, kind));
+        self.order.push_back(id);
+
+        // the ids of validated messages are left in `order`, remove them before they accumulate
+        if self.order.len() >= 2 * MAX_PENDING_PROPAGATIONS {
+            let first_seen = &self.first_seen;
+            self.order.retain(|id| first_seen.contains_key(id));
+        }
+    }
+
+    /// Returns the number of recorded messages awaiting validation.
+    pub fn pending(&self) -> usize {
+        self.first_seen.len()
+    }
+
+    /// Returns the kind of the message `id` and the time since it was received, if it was
+    /// recorded by `message_received`.
+    pub fn message_validated(
+        &mut self,
+        id: &str,
+        now: Instant,
+    ) -> Option<(&'static str, Duration)> {
+        self.first_seen
+            .remove(id)
+            .map(|(seen, kind)| (kind, now.duration_since(seen)))
+    }
+}
+
+/// A gossipsub message, decoded into the eth2 type associated with its topic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedPubsubMessage<E: EthSpec> {
+    Block(BeaconBlock<E>),
+    Attestation(Attestation<E>),
+    VoluntaryExit(VoluntaryExit),
+    ProposerSlashing(ProposerSlashing),
+    AttesterSlashing(AttesterSlashing<E>),
+}
+
+/// The reasons a `PubsubMessage` could not be decoded.
+#[derive(Debug, PartialEq)]
+pub enum PubsubDecodeError {
+    /// The message was received on a topic which is not associated with an eth2 type.
+    UnknownTopic,
+    /// The bytes are not a valid SSZ encoding of the type associated with the topic.
+    InvalidSsz(DecodeError),
+}
@@ -1,7 +1,9 @@
+use crate::rpc::{RPC_BLOCKS_BY_RANGE, RPC_BLOCKS_BY_ROOT, RPC_GOODBYE, RPC_STATUS};
 use enr::Enr;
-use libp2p::gossipsub::{GossipsubConfig, GossipsubConfigBuilder};
+use libp2p::gossipsub::{GossipsubConfig, GossipsubConfigBuilder, GossipsubMessage, TopicHash};
 use libp2p::Multiaddr;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -17,6 +19,63 @@ pub const PROPOSER_SLASHING_TOPIC: &str = "proposer_slashing";
 pub const ATTESTER_SLASHING_TOPIC: &str = "attester_slashing";
 pub const SHARD_TOPIC_PREFIX: &str = "shard";
 
+/// The maximum size (in bytes) of any gossipsub message.
+pub const GOSSIP_MAX_SIZE: usize = 1_048_576;
+/// The default maximum size (in bytes) of a gossiped attestation.
+pub const GOSSIP_MAX_ATTESTATION_SIZE: usize = 16_384;
+/// The default time (in seconds) to wait for a response to an outbound RPC request.
+pub const RPC_REQUEST_TIMEOUT: u64 = 10;
+/// The default time (in seconds) to wait for the TCP connection of an outbound dial.
+pub const CONNECT_TIMEOUT: u64 = 10;
+/// The default time (in seconds) to wait for a connection to be established, including the
+/// negotiation of encryption and multiplexing.
+pub const HANDSHAKE_TIMEOUT: u64 = 20;
+/// The default time (in seconds) to wait for a connection to any boot node before reporting them as
+/// unreachable.
+pub const BOOT_NODE_GRACE_PERIOD: u64 = 30;
+/// The default maximum number of received gossipsub messages waiting to be returned by the
+/// service.
+pub const GOSSIP_QUEUE_SIZE: usize = 1_024;
+/// The default maximum number of gossipsub message ids cached per topic to detect duplicates.
+pub const DUPLICATE_CACHE_SIZE: usize = 1_024;
+/// The default time (in seconds) for which the id of a gossipsub message is cached to detect
+/// duplicates.
+pub const DUPLICATE_CACHE_TIME: u64 = 60;
+/// The default maximum number of concurrent inbound RPC requests from each peer.
+pub const MAX_CONCURRENT_RPC_PER_PEER: usize = 16;
+/// The default maximum time (in seconds) between peer discovery searches.
+pub const DISCOVERY_INTERVAL: u64 = 60;
+/// The default time (in seconds) between checks of the connected peer count against
+/// `target_peers` and `max_peers`.
+pub const PEER_CHECK_INTERVAL: u64 = 10;
+/// The default time (in seconds) for which candidate listening addresses are collected before the
+/// address of the ENR is set.
+pub const ENR_ADDRESS_GRACE_PERIOD: u64 = 5;
+/// The default time (in seconds) between checks of the connected peer count at startup, until
+/// `target_peers` is first reached.
+pub const STARTUP_PEER_CHECK_INTERVAL: u64 = 2;
+/// The default maximum number of discovered peers being dialed at once.
+pub const MAX_CONCURRENT_DIALS: usize = 8;
+
+/// Returns the id of a gossipsub message, by which duplicate messages are detected.
+///
+/// As per the eth2 spec, the id is derived from the content of the message, the SHA256 hash of
+/// its topics followed by its data (hex-encoded), rather than its source and sequence number.
+pub fn gossip_message_id(message: &GossipsubMessage) -> String {
+    topic_message_id(&message.topics, &message.data)
+}
+
+/// Returns the `gossip_message_id` of a gossipsub message with `data` published on `topics`.
+pub(crate) fn topic_message_id(topics: &[TopicHash], data: &[u8]) -> String {
+    let mut preimage = vec![];
+    for topic in topics {
+        preimage.extend_from_slice(topic.as_str().as_bytes());
+    }
+    preimage.extend_from_slice(data);
+
+    hex::encode(eth2_hashing::hash(&preimage))
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 /// Network configuration for lighthouse.
@@ -36,31 +95,152 @@ pub struct Config {
     /// UDP port that discovery listens on.
     pub discovery_port: u16,
 
-    /// Target number of connected peers.
+    /// The period for which listening and observed external addresses are collected, after which
+    /// the best of them (public over private) is set as the address of the ENR.
+    pub enr_address_grace_period: Duration,
+
+    /// The time to wait for the TCP connection of an outbound dial, after which the dial fails.
+    pub connect_timeout: Duration,
+
+    /// The time to wait for a connection to be established, including the TCP connection (for
+    /// outbound dials) and the negotiation of encryption and multiplexing.
+    pub handshake_timeout: Duration,
+
+    /// If `true`, Nagle's algorithm is disabled on libp2p TCP connections, so small messages are
+    /// sent immediately rather than coalesced.
+    pub tcp_nodelay: bool,
+
+    /// The maximum number of connected peers. Any excess peers are disconnected.
     pub max_peers: usize,
 
+    /// The number of connected peers to maintain. Whenever fewer are connected, an on-demand peer
+    /// search is started for the remainder. Limited to `max_peers`.
+    pub target_peers: usize,
+
+    /// The time between checks of the connected peer count against `target_peers` and
+    /// `max_peers`.
+    pub peer_check_interval: Duration,
+
+    /// The time between checks of the connected peer count at startup. Until `target_peers` is
+    /// first reached, a peer search is started for the remainder at each check, after which checks
+    /// are made every `peer_check_interval`. Limited to `peer_check_interval`.
+    pub startup_peer_check_interval: Duration,
+
+    /// The maximum number of discovered peers being dialed at once. Further discovered peers are
+    /// queued until a dial completes.
+    pub max_concurrent_dials: usize,
+
+    /// The maximum number of connected peers which this node dialed. Once reached, discovered
+    /// peers are queued until an outbound connection closes.
+    pub max_outbound_peers: usize,
+
+    /// The maximum number of connected peers which dialed this node. Further inbound connections
+    /// are disconnected.
+    pub max_inbound_peers: usize,
+
     /// A secp256k1 secret key, as bytes in ASCII-encoded hex.
     ///
     /// With or without `0x` prefix.
     #[serde(skip)]
     pub secret_key_hex: Option<String>,
 
+    /// A seed, as bytes in ASCII-encoded hex, from which a deterministic secp256k1 key is derived.
+    ///
+    /// Ignored if `secret_key_hex` is set. With or without `0x` prefix.
+    #[serde(skip)]
+    pub p2p_seed_hex: Option<String>,
+
+    /// A file containing the password of the network key, if the network key on disk is an
+    /// encrypted JSON keystore.
+    #[serde(skip)]
+    pub p2p_key_password_file: Option<PathBuf>,
+
+    /// Replace the network key on disk with a newly generated one if it is not a valid secp256k1
+    /// key, rather than refusing to start.
+    #[serde(skip)]
+    pub force_regenerate_key: bool,
+
+    /// If `false`, the node does not subscribe to or publish on any gossipsub topics, serving only
+    /// RPC requests and discovery.
+    pub gossip_enabled: bool,
+
+    /// The number of recent gossipsub messages kept per topic, so they can be replayed to the
+    /// application after subscribing to a topic at runtime. The messages this node published or
+    /// propagated are also republished when a peer subscribes to their topic, so that it receives
+    /// them. `0` disables the buffer.
+    pub gossip_replay_buffer_size: usize,
+
+    /// The maximum number of received gossipsub messages waiting to be returned by the service,
+    /// which returns blocks ahead of all other messages. Once full, the oldest non-block messages
+    /// are dropped.
+    pub gossip_queue_size: usize,
+
+    /// The maximum number of received gossipsub message ids cached per topic, by which duplicate
+    /// messages are dropped before reaching the application. Once full, the oldest id is evicted.
+    /// `0` disables the cache, leaving only the seen-message cache of gossipsub.
+    pub duplicate_cache_size: usize,
+
+    /// The time for which the id of a received gossipsub message is cached to detect duplicates.
+    pub duplicate_cache_time: Duration,
+
     /// Gossipsub configuration parameters.
     #[serde(skip)]
     pub gs_config: GossipsubConfig,
 
+    /// The maximum size (in bytes) of an inbound gossipsub message, keyed by topic name (e.g.
+    /// `beacon_attestation`). Messages larger than this are rejected and the sender is penalized.
+    ///
+    /// Topics without an entry are only limited by `GOSSIP_MAX_SIZE`.
+    pub gossip_max_size_per_topic: HashMap<String, usize>,
+
+    /// The time to wait for a response to an outbound RPC request, keyed by protocol name (e.g.
+    /// `status`). Requests which time out are cancelled and the peer is penalized.
+    ///
+    /// Protocols without an entry use a timeout of `RPC_REQUEST_TIMEOUT` seconds.
+    pub rpc_request_timeouts: HashMap<String, Duration>,
+
+    /// The maximum number of concurrent inbound RPC requests from each peer. Further requests are
+    /// rejected with an error response and the peer is penalized.
+    pub max_concurrent_rpc_per_peer: usize,
+
     /// List of nodes to initially connect to.
     pub boot_nodes: Vec<Enr>,
 
+    /// If no peers are connected after this period, the boot nodes are reported as unreachable and
+    /// dialed again.
+    pub boot_node_grace_period: Duration,
+
+    /// The maximum time between peer discovery searches. Searches start at a short interval which
+    /// doubles after each search until it reaches `discovery_interval`.
+    pub discovery_interval: Duration,
+
+    /// The number of random node queries started by each peer discovery search. Must be at least
+    /// one.
+    pub discovery_parallelism: usize,
+
     /// List of libp2p nodes to initially connect to.
     pub libp2p_nodes: Vec<Multiaddr>,
 
-    /// Client version
+    /// The agent version advertised to peers via the identify protocol.
     pub client_version: String,
 
     /// List of extra topics to initially subscribe to as strings.
     pub topics: Vec<String>,
 
+    /// List of additional topics to subscribe to as strings, for mirroring gossip that the node
+    /// does not otherwise need (e.g., for analytics). These are subscribed to after `topics` and
+    /// may be unsubscribed from as a group.
+    pub archive_topics: Vec<String>,
+
+    /// If set, the events of the libp2p service are also written to clients of a Unix domain
+    /// socket at this path. Only available on Unix.
+    #[cfg(unix)]
+    pub event_socket_path: Option<PathBuf>,
+
+    /// If set, the events of the libp2p service are appended to a recording at this path, which
+    /// can be read back with `replay_events`.
+    pub record_events_path: Option<PathBuf>,
+
     /// Introduces randomization in network propagation of messages. This should only be set for
     /// testing purposes and will likely be removed in future versions.
     // TODO: Remove this functionality for mainnet
@@ -73,25 +253,77 @@ impl Default for Config {
         let mut network_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
         network_dir.push(".lighthouse");
         network_dir.push("network");
+
+        let mut gossip_max_size_per_topic = HashMap::new();
+        gossip_max_size_per_topic.insert(BEACON_BLOCK_TOPIC.to_string(), GOSSIP_MAX_SIZE);
+        gossip_max_size_per_topic.insert(
+            BEACON_ATTESTATION_TOPIC.to_string(),
+            GOSSIP_MAX_ATTESTATION_SIZE,
+        );
+
+        let rpc_request_timeouts = [
+            RPC_STATUS,
+            RPC_GOODBYE,
+            RPC_BLOCKS_BY_RANGE,
+            RPC_BLOCKS_BY_ROOT,
+        ]
+        .iter()
+        .map(|protocol| {
+            (
+                protocol.to_string(),
+                Duration::from_secs(RPC_REQUEST_TIMEOUT),
+            )
+        })
+        .collect();
+
         Config {
             network_dir,
             listen_address: "127.0.0.1".parse().expect("valid ip address"),
             libp2p_port: 9000,
             discovery_address: "127.0.0.1".parse().expect("valid ip address"),
             discovery_port: 9000,
+            enr_address_grace_period: Duration::from_secs(ENR_ADDRESS_GRACE_PERIOD),
+            connect_timeout: Duration::from_secs(CONNECT_TIMEOUT),
+            handshake_timeout: Duration::from_secs(HANDSHAKE_TIMEOUT),
+            tcp_nodelay: true,
             max_peers: 10,
+            target_peers: 0,
+            peer_check_interval: Duration::from_secs(PEER_CHECK_INTERVAL),
+            startup_peer_check_interval: Duration::from_secs(STARTUP_PEER_CHECK_INTERVAL),
+            max_concurrent_dials: MAX_CONCURRENT_DIALS,
+            max_outbound_peers: 10,
+            max_inbound_peers: 10,
             secret_key_hex: None,
+            p2p_seed_hex: None,
+            p2p_key_password_file: None,
+            force_regenerate_key: false,
+            gossip_enabled: true,
+            gossip_replay_buffer_size: 0,
+            gossip_queue_size: GOSSIP_QUEUE_SIZE,
+            duplicate_cache_size: DUPLICATE_CACHE_SIZE,
+            duplicate_cache_time: Duration::from_secs(DUPLICATE_CACHE_TIME),
             // Note: The topics by default are sent as plain strings. Hashes are an optional
             // parameter.
             gs_config: GossipsubConfigBuilder::new()
-                .max_transmit_size(1_048_576)
+                .max_transmit_size(GOSSIP_MAX_SIZE)
                 .heartbeat_interval(Duration::from_secs(20)) // TODO: Reduce for mainnet
                 .manual_propagation(true) // require validation before propagation
+                .message_id_fn(gossip_message_id)
                 .build(),
+            gossip_max_size_per_topic,
+            rpc_request_timeouts,
+            max_concurrent_rpc_per_peer: MAX_CONCURRENT_RPC_PER_PEER,
             boot_nodes: vec![],
+            boot_node_grace_period: Duration::from_secs(BOOT_NODE_GRACE_PERIOD),
+            discovery_interval: Duration::from_secs(DISCOVERY_INTERVAL),
+            discovery_parallelism: 1,
             libp2p_nodes: vec![],
             client_version: version::version(),
             topics: Vec::new(),
+            archive_topics: Vec::new(),
+            #[cfg(unix)]
+            event_socket_path: None,
+            record_events_path: None,
             propagation_percentage: None,
         }
     }
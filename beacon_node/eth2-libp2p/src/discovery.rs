@@ -1,5 +1,6 @@
+use crate::error::{self, ErrorKind};
 use crate::metrics;
-use crate::{error, NetworkConfig};
+use crate::NetworkConfig;
 /// This manages the discovery and management of peers.
 ///
 /// Currently using discv5 for peer discovery.
@@ -11,7 +12,7 @@ use libp2p::enr::{Enr, EnrBuilder, NodeId};
 use libp2p::multiaddr::Protocol;
 use libp2p::swarm::{NetworkBehaviour, NetworkBehaviourAction, PollParameters, ProtocolsHandler};
 use slog::{debug, info, warn};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
@@ -20,8 +21,6 @@ use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::timer::Delay;
 
-/// Maximum seconds before searching for extra peers.
-const MAX_TIME_BETWEEN_PEER_SEARCHES: u64 = 60;
 /// Initial delay between peer searches.
 const INITIAL_SEARCH_DELAY: u64 = 5;
 /// Local ENR storage filename.
@@ -33,21 +32,63 @@ pub struct Discovery<TSubstream> {
     /// The peers currently connected to libp2p streams.
     connected_peers: HashSet<PeerId>,
 
+    /// The endpoint of the connection to each connected peer.
+    connected_endpoints: HashMap<PeerId, ConnectedPoint>,
+
+    /// The connected peers which this node dialed.
+    outbound_peers: HashSet<PeerId>,
+
+    /// The connected peers which dialed this node.
+    inbound_peers: HashSet<PeerId>,
+
+    /// Inbound peers which connected once `max_inbound_peers` was reached and should be
+    /// disconnected.
+    excess_inbound_peers: Vec<PeerId>,
+
+    /// Discovered peers waiting for an outbound connection slot before being dialed, oldest first.
+    /// At most `max_outbound_peers` peers are queued.
+    queued_peers: VecDeque<PeerId>,
+
+    /// The peers in `queued_peers`.
+    queued_peer_ids: HashSet<PeerId>,
+
+    /// Discovered peers which are being dialed.
+    pending_dials: HashSet<PeerId>,
+
+    /// The maximum number of discovered peers being dialed at once.
+    max_concurrent_dials: usize,
+
     /// The currently banned peers.
     banned_peers: HashSet<PeerId>,
 
     /// The target number of connected peers on the libp2p interface.
     max_peers: usize,
 
+    /// The maximum number of connected peers which this node dialed.
+    max_outbound_peers: usize,
+
+    /// The maximum number of connected peers which dialed this node.
+    max_inbound_peers: usize,
+
     /// directory to save ENR to
     enr_dir: String,
 
     /// The delay between peer discovery searches.
     peer_discovery_delay: Delay,
 
-    /// Tracks the last discovery delay. The delay is doubled each round until the max
-    /// time is reached.
-    past_discovery_delay: u64,
+    /// Tracks the last discovery delay. The delay is doubled each round until the
+    /// `discovery_interval` is reached.
+    past_discovery_delay: Duration,
+
+    /// The maximum time between peer discovery searches.
+    discovery_interval: Duration,
+
+    /// The number of random node queries started by each peer discovery search.
+    discovery_parallelism: usize,
+
+    /// The number of discovered peers still to be queued for dialing by an on-demand search. `None`
+    /// if no on-demand search is in progress.
+    peer_target: Option<usize>,
 
     /// The TCP port for libp2p. Used to convert an updated IP address to a multiaddr. Note: This
     /// assumes that the external TCP port is the same as the internal TCP port if behind a NAT.
@@ -69,8 +110,15 @@ impl<TSubstream> Discovery<TSubstream> {
     ) -> error::Result<Self> {
         let log = log.clone();
 
+        if config.discovery_parallelism == 0 {
+            return Err(ErrorKind::DiscoveryFailed(
+                "discovery_parallelism must be at least 1".into(),
+            )
+            .into());
+        }
+
         // checks if current ENR matches that found on disk
-        let local_enr = load_enr(local_key, config, &log)?;
+        let local_enr = load_enr(local_key, config, &log).map_err(ErrorKind::DiscoveryFailed)?;
 
         let enr_dir = match config.network_dir.to_str() {
             Some(path) => String::from(path),
@@ -84,7 +132,9 @@ impl<TSubstream> Discovery<TSubstream> {
         // nodes on the same /24 subnet per table.
         // TODO: IP filtering is currently disabled for the DHT. Enable for production
         let mut discovery = Discv5::new(local_enr, local_key.clone(), config.listen_address, false)
-            .map_err(|e| format!("Discv5 service failed. Error: {:?}", e))?;
+            .map_err(|e| {
+                ErrorKind::DiscoveryFailed(format!("Discv5 service failed. Error: {:?}", e))
+            })?;
 
         // Add bootnodes to routing table
         for bootnode_enr in config.boot_nodes.clone() {
@@ -99,10 +149,23 @@ impl<TSubstream> Discovery<TSubstream> {
 
         Ok(Self {
             connected_peers: HashSet::new(),
+            connected_endpoints: HashMap::new(),
+            outbound_peers: HashSet::new(),
+            inbound_peers: HashSet::new(),
+            excess_inbound_peers: Vec::new(),
+            queued_peers: VecDeque::new(),
+            queued_peer_ids: HashSet::new(),
+            pending_dials: HashSet::new(),
+            max_concurrent_dials: config.max_concurrent_dials,
             banned_peers: HashSet::new(),
             max_peers: config.max_peers,
+            max_outbound_peers: config.max_outbound_peers,
+            max_inbound_peers: config.max_inbound_peers,
             peer_discovery_delay: Delay::new(Instant::now()),
-            past_discovery_delay: INITIAL_SEARCH_DELAY,
+            past_discovery_delay: Duration::from_secs(INITIAL_SEARCH_DELAY),
+            discovery_interval: config.discovery_interval,
+            discovery_parallelism: config.discovery_parallelism,
+            peer_target: None,
             tcp_port: config.libp2p_port,
             discovery,
             log,
@@ -133,13 +196,38 @@ impl<TSubstream> Discovery<TSubstream> {
         self.discovery.local_enr()
     }
 
-    /// Manually search for peers. This restarts the discovery round, sparking multiple rapid
-    /// queries.
-    pub fn discover_peers(&mut self) {
-        self.past_discovery_delay = INITIAL_SEARCH_DELAY;
+    /// Manually search for up to `target` new peers, which are dialed once discovered. This
+    /// restarts the discovery round, sparking multiple rapid queries.
+    ///
+    /// The search is always started, even if `max_peers` has been reached, however no more than
+    /// `max_peers` will be connected.
+    pub fn discover_peers(&mut self, target: usize) {
+        debug!(self.log, "Starting on-demand peer search"; "target" => target);
+        self.peer_target = Some(target);
+        self.past_discovery_delay = Duration::from_secs(INITIAL_SEARCH_DELAY);
         self.find_peers();
     }
 
+    /// The number of peers still to be found by the current on-demand search, if any.
+    pub fn peer_target(&self) -> Option<usize> {
+        self.peer_target
+    }
+
+    /// The maximum time between peer discovery searches.
+    pub fn discovery_interval(&self) -> Duration {
+        self.discovery_interval
+    }
+
+    /// The number of random node queries started by each peer discovery search.
+    pub fn discovery_parallelism(&self) -> usize {
+        self.discovery_parallelism
+    }
+
+    /// The time of the next peer discovery search.
+    pub fn next_search(&self) -> Instant {
+        self.peer_discovery_delay.deadline()
+    }
+
     /// Add an ENR to the routing table of the discovery mechanism.
     pub fn add_enr(&mut self, enr: Enr) {
         self.discovery.add_enr(enr);
@@ -155,6 +243,39 @@ impl<TSubstream> Discovery<TSubstream> {
         &self.connected_peers
     }
 
+    /// The endpoints of the connections to the connected libp2p peers.
+    pub fn connected_endpoints(&self) -> &HashMap<PeerId, ConnectedPoint> {
+        &self.connected_endpoints
+    }
+
+    /// The current number of connected peers which this node dialed.
+    pub fn outbound_peers(&self) -> usize {
+        self.outbound_peers.len()
+    }
+
+    /// The current number of connected peers which dialed this node.
+    pub fn inbound_peers(&self) -> usize {
+        self.inbound_peers.len()
+    }
+
+    /// The current number of discovered peers which are being dialed.
+    pub fn pending_dials(&self) -> usize {
+        self.pending_dials.len()
+    }
+
+    /// Returns `true` if another outbound connection may be made without exceeding
+    /// `max_outbound_peers` or `max_peers`.
+    pub fn outbound_slot_available(&self) -> bool {
+        self.outbound_peers.len() < self.max_outbound_peers
+            && self.connected_peers.len() < self.max_peers
+    }
+
+    /// Returns the inbound peers which connected after `max_inbound_peers` was reached, so they
+    /// may be disconnected.
+    pub fn take_excess_inbound_peers(&mut self) -> Vec<PeerId> {
+        std::mem::replace(&mut self.excess_inbound_peers, vec![])
+    }
+
     /// The peer has been banned. Add this peer to the banned list to prevent any future
     /// re-connections.
     // TODO: Remove the peer from the DHT if present
@@ -163,24 +284,41 @@ impl<TSubstream> Discovery<TSubstream> {
         self.banned_peers.insert(peer_id);
     }
 
+    /// Returns `true` if the peer has been banned.
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.banned_peers.contains(peer_id)
+    }
+
+    /// Queues a discovered peer to be dialed. If `max_outbound_peers` peers are already queued, the
+    /// oldest is dropped, as it is the most likely to have gone offline.
+    fn queue_peer(&mut self, peer_id: PeerId) {
+        if self.queued_peers.len() >= self.max_outbound_peers {
+            match self.queued_peers.pop_front() {
+                Some(oldest) => {
+                    self.queued_peer_ids.remove(&oldest);
+                }
+                // no outbound connections are allowed
+                None => return,
+            }
+        }
+        self.queued_peer_ids.insert(peer_id.clone());
+        self.queued_peers.push_back(peer_id);
+    }
+
     /// Search for new peers using the underlying discovery mechanism.
     fn find_peers(&mut self) {
-        // pick a random NodeId
-        let random_node = NodeId::random();
-        debug!(self.log, "Searching for peers");
-        self.discovery.find_node(random_node);
+        debug!(self.log, "Searching for peers"; "queries" => self.discovery_parallelism);
+        for _ in 0..self.discovery_parallelism {
+            // pick a random NodeId
+            let random_node = NodeId::random();
+            self.discovery.find_node(random_node);
+        }
 
         // update the time until next discovery
-        let delay = {
-            if self.past_discovery_delay < MAX_TIME_BETWEEN_PEER_SEARCHES {
-                self.past_discovery_delay *= 2;
-                self.past_discovery_delay
-            } else {
-                MAX_TIME_BETWEEN_PEER_SEARCHES
-            }
-        };
+        self.past_discovery_delay =
+            std::cmp::min(self.past_discovery_delay * 2, self.discovery_interval);
         self.peer_discovery_delay
-            .reset(Instant::now() + Duration::from_secs(delay));
+            .reset(Instant::now() + self.past_discovery_delay);
     }
 }
 
@@ -201,9 +339,23 @@ where
         self.discovery.addresses_of_peer(peer_id)
     }
 
-    fn inject_connected(&mut self, peer_id: PeerId, _endpoint: ConnectedPoint) {
+    fn inject_connected(&mut self, peer_id: PeerId, endpoint: ConnectedPoint) {
+        match &endpoint {
+            ConnectedPoint::Dialer { .. } => {
+                self.outbound_peers.insert(peer_id.clone());
+            }
+            ConnectedPoint::Listener { .. } => {
+                if self.inbound_peers.len() < self.max_inbound_peers {
+                    self.inbound_peers.insert(peer_id.clone());
+                } else {
+                    debug!(self.log, "Inbound peer limit reached, disconnecting peer"; "peer_id" => format!("{:?}", peer_id));
+                    self.excess_inbound_peers.push(peer_id.clone());
+                }
+            }
+        }
+        self.pending_dials.remove(&peer_id);
+        self.connected_endpoints.insert(peer_id.clone(), endpoint);
         self.connected_peers.insert(peer_id);
-        // TODO: Drop peers if over max_peer limit
 
         metrics::inc_counter(&metrics::PEER_CONNECT_EVENT_COUNT);
         metrics::set_gauge(&metrics::PEERS_CONNECTED, self.connected_peers() as i64);
@@ -211,18 +363,28 @@ where
 
     fn inject_disconnected(&mut self, peer_id: &PeerId, _endpoint: ConnectedPoint) {
         self.connected_peers.remove(peer_id);
+        self.connected_endpoints.remove(peer_id);
+        self.outbound_peers.remove(peer_id);
+        self.inbound_peers.remove(peer_id);
 
         metrics::inc_counter(&metrics::PEER_DISCONNECT_EVENT_COUNT);
         metrics::set_gauge(&metrics::PEERS_CONNECTED, self.connected_peers() as i64);
     }
 
+    fn inject_dial_failure(&mut self, peer_id: &PeerId) {
+        self.pending_dials.remove(peer_id);
+    }
+
     fn inject_replaced(
         &mut self,
-        _peer_id: PeerId,
+        peer_id: PeerId,
         _closed: ConnectedPoint,
-        _opened: ConnectedPoint,
+        opened: ConnectedPoint,
     ) {
         // discv5 doesn't implement
+        if self.connected_endpoints.contains_key(&peer_id) {
+            self.connected_endpoints.insert(peer_id, opened);
+        }
     }
 
     fn inject_node_event(
@@ -273,6 +435,10 @@ where
                             address.push(Protocol::Tcp(self.tcp_port));
                             let enr = self.discovery.local_enr();
                             save_enr_to_disc(Path::new(&self.enr_dir), enr, &self.log);
+                            info!(self.log, "Boot node details";
+                                "enr" => enr.to_base64(),
+                                "multiaddr" => boot_node_multiaddr(enr).map_or_else(|| "unknown".into(), |addr| addr.to_string()),
+                            );
 
                             return Async::Ready(NetworkBehaviourAction::ReportObservedAddr {
                                 address,
@@ -284,17 +450,26 @@ where
                                 debug!(self.log, "Discovery random query found no peers");
                             }
                             for peer_id in closer_peers {
-                                // if we need more peers, attempt a connection
+                                // an on-demand search queues no more than its target
+                                if self.peer_target == Some(0) {
+                                    break;
+                                }
+                                // if we need more peers, queue a connection attempt
                                 if self.connected_peers.len() < self.max_peers
                                     && self.connected_peers.get(&peer_id).is_none()
                                     && !self.banned_peers.contains(&peer_id)
+                                    && !self.queued_peer_ids.contains(&peer_id)
                                 {
                                     debug!(self.log, "Peer discovered"; "peer_id"=> format!("{:?}", peer_id));
-                                    return Async::Ready(NetworkBehaviourAction::DialPeer {
-                                        peer_id,
-                                    });
+                                    self.queue_peer(peer_id);
+                                    if let Some(target) = self.peer_target.as_mut() {
+                                        *target -= 1;
+                                    }
                                 }
                             }
+                            if self.peer_target == Some(0) {
+                                self.peer_target = None;
+                            }
                         }
                         _ => {}
                     }
@@ -304,6 +479,26 @@ where
                 Async::NotReady => break,
             }
         }
+
+        // dial queued peers whilst there are outbound connection slots, and not too many dials are
+        // in progress
+        while self.outbound_slot_available()
+            && self.pending_dials.len() < self.max_concurrent_dials
+        {
+            match self.queued_peers.pop_front() {
+                Some(peer_id) => {
+                    self.queued_peer_ids.remove(&peer_id);
+                    if self.connected_peers.contains(&peer_id)
+                        || self.banned_peers.contains(&peer_id)
+                        || !self.pending_dials.insert(peer_id.clone())
+                    {
+                        continue;
+                    }
+                    return Async::Ready(NetworkBehaviourAction::DialPeer { peer_id });
+                }
+                None => break,
+            }
+        }
         Async::NotReady
     }
 }
@@ -313,11 +508,35 @@ where
 ///
 /// If an ENR exists, with the same NodeId and IP address, we use the disk-generated one as its
 /// ENR sequence will be equal or higher than a newly generated one.
-fn load_enr(
+pub fn load_enr(
     local_key: &Keypair,
     config: &NetworkConfig,
     log: &slog::Logger,
 ) -> Result<Enr, String> {
+    let (local_enr, from_file) = local_enr(local_key, config, log)?;
+    if !from_file {
+        save_enr_to_disc(&config.network_dir, &local_enr, log);
+    }
+
+    Ok(local_enr)
+}
+
+/// Returns the ENR which `load_enr` would return for `local_key` and `config`, without writing it
+/// to disk. A new sequence number is therefore not persisted.
+pub fn build_enr(
+    local_key: &Keypair,
+    config: &NetworkConfig,
+    log: &slog::Logger,
+) -> Result<Enr, String> {
+    local_enr(local_key, config, log).map(|(enr, _)| enr)
+}
+
+/// Builds the ENR of `load_enr`, returning it with `true` if it is the ENR stored on disk.
+fn local_enr(
+    local_key: &Keypair,
+    config: &NetworkConfig,
+    log: &slog::Logger,
+) -> Result<(Enr, bool), String> {
     // Build the local ENR.
     // Note: Discovery should update the ENR record's IP to the external IP as seen by the
     // majority of our peers.
@@ -343,7 +562,7 @@ fn load_enr(
                             {
                                 debug!(log, "ENR loaded from file"; "file" => format!("{:?}", enr_f));
                                 // the stored ENR has the same configuration, use it
-                                return Ok(enr);
+                                return Ok((enr, true));
                             }
 
                             // same node id, different configuration - update the sequence number
@@ -362,15 +581,49 @@ fn load_enr(
         }
     }
 
-    save_enr_to_disc(&config.network_dir, &local_enr, log);
+    Ok((local_enr, false))
+}
 
-    Ok(local_enr)
+/// Returns the multiaddr by which other nodes can use the node of `enr` as a libp2p boot node,
+/// including its peer id (e.g., `/ip4/1.2.3.4/tcp/9000/p2p/16Uiu2...`).
+///
+/// Returns `None` if `enr` has no IP address or TCP port.
+pub fn boot_node_multiaddr(enr: &Enr) -> Option<Multiaddr> {
+    let mut multiaddr = Multiaddr::from(enr.ip()?);
+    multiaddr.push(Protocol::Tcp(enr.tcp()?));
+    multiaddr.push(Protocol::P2p(enr.peer_id().into()));
+    Some(multiaddr)
+}
+
+/// Returns the ENR which `load_enr` stored in `dir`, if any, without modifying it.
+pub fn read_enr_from_disc(dir: &Path) -> Result<Option<Enr>, String> {
+    let enr_f = dir.join(ENR_FILENAME);
+    if !enr_f.exists() {
+        return Ok(None);
+    }
+
+    let enr_string = std::fs::read_to_string(&enr_f)
+        .map_err(|e| format!("Unable to read ENR file {:?}: {}", enr_f, e))?;
+    Enr::from_str(&enr_string)
+        .map(Some)
+        .map_err(|e| format!("Unable to decode ENR file {:?}: {:?}", enr_f, e))
 }
 
+/// Writes `enr` to the ENR file in `dir`, which `load_enr` reads on restart to continue from its
+/// sequence number.
+///
+/// The ENR is written to a temporary file which then replaces the ENR file, so that a previously
+/// stored ENR is never lost to a partial write.
 fn save_enr_to_disc(dir: &Path, enr: &Enr, log: &slog::Logger) {
     let _ = std::fs::create_dir_all(dir);
-    match File::create(dir.join(Path::new(ENR_FILENAME)))
-        .and_then(|mut f| f.write_all(&enr.to_base64().as_bytes()))
+    let enr_f = dir.join(Path::new(ENR_FILENAME));
+    let tmp_f = enr_f.with_extension("tmp");
+    match File::create(&tmp_f)
+        .and_then(|mut f| {
+            f.write_all(&enr.to_base64().as_bytes())?;
+            f.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&tmp_f, &enr_f))
     {
         Ok(_) => {
             debug!(log, "ENR written to disk");
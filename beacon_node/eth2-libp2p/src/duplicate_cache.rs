@@ -0,0 +1,65 @@
+//! A cache of the ids of recently received gossipsub messages, by which duplicates are detected.
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Holds the ids of the gossipsub messages received on a single topic within the last `ttl`.
+///
+/// This is checked in addition to the seen-message cache of gossipsub, so that duplicates which
+/// have already been evicted from gossipsub's (fixed-size) cache are not processed again.
+pub struct DuplicateCache {
+    /// The cached ids with the time they were inserted, oldest first.
+    ids: VecDeque<(String, Instant)>,
+    /// The cached ids, for fast lookup.
+    seen: HashSet<String>,
+    /// The maximum number of cached ids. Once full, the oldest id is evicted.
+    max_len: usize,
+    /// The time after which an id is evicted.
+    ttl: Duration,
+}
+
+impl DuplicateCache {
+    /// Returns an empty cache, holding at most `max_len` ids for `ttl`.
+    pub fn new(max_len: usize, ttl: Duration) -> Self {
+        DuplicateCache {
+            ids: VecDeque::new(),
+            seen: HashSet::new(),
+            max_len,
+            ttl,
+        }
+    }
+
+    /// Inserts `id` at time `now`, returning `false` if it is already in the cache (i.e., the
+    /// message is a duplicate).
+    ///
+    /// The insertion time of an id already in the cache is not updated.
+    pub fn insert(&mut self, id: &str, now: Instant) -> bool {
+        self.prune(now);
+
+        if self.seen.contains(id) {
+            return false;
+        }
+        if self.max_len == 0 {
+            return true;
+        }
+
+        if self.ids.len() >= self.max_len {
+            if let Some((evicted, _)) = self.ids.pop_front() {
+                self.seen.remove(&evicted);
+            }
+        }
+        self.seen.insert(id.to_string());
+        self.ids.push_back((id.to_string(), now));
+        true
+    }
+
+    /// Evicts all ids which were inserted at least `ttl` before `now`.
+    fn prune(&mut self, now: Instant) {
+        while let Some((id, inserted)) = self.ids.front() {
+            if now.duration_since(*inserted) < self.ttl {
+                break;
+            }
+            self.seen.remove(id);
+            self.ids.pop_front();
+        }
+    }
+}
@@ -2,4 +2,42 @@
 
 use error_chain::error_chain;
 
-error_chain! {}
+error_chain! {
+    errors {
+        // The libp2p service was unable to listen on `address`.
+        ListenFailed(address: String) {
+            description("unable to listen on the libp2p address")
+            display("Libp2p was unable to listen on the given listen address: {}", address)
+        }
+        // A network key or the seed it is derived from is invalid.
+        KeyParse(reason: String) {
+            description("invalid network key")
+            display("{}", reason)
+        }
+        // A network key stored in a keystore could not be decrypted.
+        KeystoreDecrypt(reason: String) {
+            description("unable to decrypt the network key keystore")
+            display("{}", reason)
+        }
+        // The network key could not be rotated.
+        KeyRotation(reason: String) {
+            description("unable to rotate the network key")
+            display("{}", reason)
+        }
+        // The event socket could not be created.
+        EventSocketFailed(reason: String) {
+            description("unable to create the event socket")
+            display("{}", reason)
+        }
+        // The event recording could not be opened.
+        EventRecorderFailed(reason: String) {
+            description("unable to open the event recording")
+            display("{}", reason)
+        }
+        // The discovery service or the local ENR could not be initialised.
+        DiscoveryFailed(reason: String) {
+            description("unable to start discovery")
+            display("{}", reason)
+        }
+    }
+}
@@ -0,0 +1,451 @@
+//! Records the `Libp2pEvent` stream of the `Service` to a file, for reproducing networking bugs.
+//!
+//! Each event is appended to the file as a line of JSON, holding the time at which the event was
+//! produced and the event itself. Message payloads are recorded as hex-encoded SSZ bytes, so a
+//! recording is read back with `replay_events` as the `Libp2pEvent`s which were recorded.
+//!
+//! RPC errors which wrap an underlying error (e.g. an IO error) cannot be rebuilt, they are
+//! replayed as an `RPCError::Custom` holding the description of the original error.
+use crate::behaviour::PubsubMessage;
+use crate::rpc::methods::{BlocksByRangeRequest, BlocksByRootRequest, GoodbyeReason};
+use crate::rpc::{
+    ErrorMessage, RPCError, RPCErrorResponse, RPCEvent, RPCRequest, RPCResponse, RequestId,
+    ResponseTermination, StatusMessage,
+};
+use crate::service::Libp2pEvent;
+use crate::TopicHash;
+use libp2p::PeerId;
+use serde_derive::{Deserialize, Serialize};
+use slog::warn;
+use ssz::{Decode, Encode};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use types::Hash256;
+
+/// The maximum number of events held by a recording written by the `Service`.
+///
+/// Events already in the recording when it is opened count towards the limit.
+pub const MAX_RECORDED_EVENTS: usize = 1_000_000;
+
+/// An event, as written to a recording.
+#[derive(Serialize, Deserialize)]
+struct EventRecord {
+    timestamp: u64,
+    event: LoggedEvent,
+}
+
+/// A `Libp2pEvent` in its recorded form.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event")]
+enum LoggedEvent {
+    RPC {
+        peer_id: String,
+        rpc_event: LoggedRPCEvent,
+    },
+    PeerDialed {
+        peer_id: String,
+    },
+    PeerDisconnected {
+        peer_id: String,
+    },
+    PubsubMessage {
+        id: String,
+        source: String,
+        topics: Vec<String>,
+        data: String,
+    },
+    PeerSubscribed {
+        peer_id: String,
+        topic: String,
+    },
+    StatusResponse {
+        peer_id: String,
+        status: String,
+    },
+}
+
+/// An `RPCEvent` in its recorded form.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind")]
+enum LoggedRPCEvent {
+    Request {
+        id: RequestId,
+        request: LoggedRequest,
+    },
+    Response {
+        id: RequestId,
+        response: LoggedResponse,
+    },
+    Error {
+        id: RequestId,
+        error: LoggedRPCError,
+    },
+}
+
+/// An `RPCRequest`, with its SSZ bytes.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "method", content = "data")]
+enum LoggedRequest {
+    Status(String),
+    Goodbye(String),
+    BlocksByRange(String),
+    BlocksByRoot(String),
+}
+
+/// An `RPCErrorResponse`, with the SSZ bytes of its response or error message.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "code", content = "data")]
+enum LoggedResponse {
+    Status(String),
+    BlocksByRange(String),
+    BlocksByRoot(String),
+    InvalidRequest(String),
+    ServerError(String),
+    Unknown(String),
+    BlocksByRangeTermination,
+    BlocksByRootTermination,
+}
+
+/// An `RPCError`. Errors which wrap another error are recorded by their description.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "error", content = "description")]
+enum LoggedRPCError {
+    StreamTimeout,
+    ResponseTimeout,
+    RPCErrorResponse,
+    TooManyRequests,
+    Other(String),
+}
+
+impl From<&Libp2pEvent> for LoggedEvent {
+    fn from(event: &Libp2pEvent) -> Self {
+        match event {
+            Libp2pEvent::RPC(peer_id, rpc_event) => LoggedEvent::RPC {
+                peer_id: peer_id.to_base58(),
+                rpc_event: LoggedRPCEvent::from(rpc_event),
+            },
+            Libp2pEvent::PeerDialed(peer_id) => LoggedEvent::PeerDialed {
+                peer_id: peer_id.to_base58(),
+            },
+            Libp2pEvent::PeerDisconnected(peer_id) => LoggedEvent::PeerDisconnected {
+                peer_id: peer_id.to_base58(),
+            },
+            Libp2pEvent::PubsubMessage {
+                id,
+                source,
+                topics,
+                message,
+            } => LoggedEvent::PubsubMessage {
+                id: id.clone(),
+                source: source.to_base58(),
+                topics: topics.iter().map(|t| t.as_str().to_string()).collect(),
+                data: hex::encode(message.data()),
+            },
+            Libp2pEvent::PeerSubscribed(peer_id, topic) => LoggedEvent::PeerSubscribed {
+                peer_id: peer_id.to_base58(),
+                topic: topic.as_str().to_string(),
+            },
+            Libp2pEvent::StatusResponse { peer_id, status } => LoggedEvent::StatusResponse {
+                peer_id: peer_id.to_base58(),
+                status: hex::encode(status.as_ssz_bytes()),
+            },
+        }
+    }
+}
+
+impl From<&RPCEvent> for LoggedRPCEvent {
+    fn from(rpc_event: &RPCEvent) -> Self {
+        match rpc_event {
+            RPCEvent::Request(id, request) => LoggedRPCEvent::Request {
+                id: *id,
+                request: match request {
+                    RPCRequest::Status(status) => {
+                        LoggedRequest::Status(hex::encode(status.as_ssz_bytes()))
+                    }
+                    RPCRequest::Goodbye(reason) => {
+                        LoggedRequest::Goodbye(hex::encode(reason.as_ssz_bytes()))
+                    }
+                    RPCRequest::BlocksByRange(request) => {
+                        LoggedRequest::BlocksByRange(hex::encode(request.as_ssz_bytes()))
+                    }
+                    RPCRequest::BlocksByRoot(request) => {
+                        LoggedRequest::BlocksByRoot(hex::encode(request.block_roots.as_ssz_bytes()))
+                    }
+                },
+            },
+            RPCEvent::Response(id, response) => LoggedRPCEvent::Response {
+                id: *id,
+                response: match response {
+                    RPCErrorResponse::Success(RPCResponse::Status(status)) => {
+                        LoggedResponse::Status(hex::encode(status.as_ssz_bytes()))
+                    }
+                    RPCErrorResponse::Success(RPCResponse::BlocksByRange(bytes)) => {
+                        LoggedResponse::BlocksByRange(hex::encode(bytes))
+                    }
+                    RPCErrorResponse::Success(RPCResponse::BlocksByRoot(bytes)) => {
+                        LoggedResponse::BlocksByRoot(hex::encode(bytes))
+                    }
+                    RPCErrorResponse::InvalidRequest(message) => {
+                        LoggedResponse::InvalidRequest(hex::encode(&message.error_message))
+                    }
+                    RPCErrorResponse::ServerError(message) => {
+                        LoggedResponse::ServerError(hex::encode(&message.error_message))
+                    }
+                    RPCErrorResponse::Unknown(message) => {
+                        LoggedResponse::Unknown(hex::encode(&message.error_message))
+                    }
+                    RPCErrorResponse::StreamTermination(ResponseTermination::BlocksByRange) => {
+                        LoggedResponse::BlocksByRangeTermination
+                    }
+                    RPCErrorResponse::StreamTermination(ResponseTermination::BlocksByRoot) => {
+                        LoggedResponse::BlocksByRootTermination
+                    }
+                },
+            },
+            RPCEvent::Error(id, error) => LoggedRPCEvent::Error {
+                id: *id,
+                error: match error {
+                    RPCError::StreamTimeout => LoggedRPCError::StreamTimeout,
+                    RPCError::ResponseTimeout => LoggedRPCError::ResponseTimeout,
+                    RPCError::RPCErrorResponse => LoggedRPCError::RPCErrorResponse,
+                    RPCError::TooManyRequests => LoggedRPCError::TooManyRequests,
+                    other => LoggedRPCError::Other(other.to_string()),
+                },
+            },
+        }
+    }
+}
+
+impl LoggedEvent {
+    /// Rebuilds the recorded `Libp2pEvent`.
+    fn into_event(self) -> Result<Libp2pEvent, String> {
+        Ok(match self {
+            LoggedEvent::RPC { peer_id, rpc_event } => {
+                Libp2pEvent::RPC(parse_peer_id(&peer_id)?, rpc_event.into_event()?)
+            }
+            LoggedEvent::PeerDialed { peer_id } => {
+                Libp2pEvent::PeerDialed(parse_peer_id(&peer_id)?)
+            }
+            LoggedEvent::PeerDisconnected { peer_id } => {
+                Libp2pEvent::PeerDisconnected(parse_peer_id(&peer_id)?)
+            }
+            LoggedEvent::PubsubMessage {
+                id,
+                source,
+                topics,
+                data,
+            } => {
+                let topics: Vec<TopicHash> = topics.into_iter().map(TopicHash::from_raw).collect();
+                let message = PubsubMessage::from_topics(&topics, decode_hex(&data)?);
+                Libp2pEvent::PubsubMessage {
+                    id,
+                    source: parse_peer_id(&source)?,
+                    topics,
+                    message,
+                }
+            }
+            LoggedEvent::PeerSubscribed { peer_id, topic } => {
+                Libp2pEvent::PeerSubscribed(parse_peer_id(&peer_id)?, TopicHash::from_raw(topic))
+            }
+            LoggedEvent::StatusResponse { peer_id, status } => Libp2pEvent::StatusResponse {
+                peer_id: parse_peer_id(&peer_id)?,
+                status: decode_ssz(&status)?,
+            },
+        })
+    }
+}
+
+impl LoggedRPCEvent {
+    /// Rebuilds the recorded `RPCEvent`.
+    fn into_event(self) -> Result<RPCEvent, String> {
+        Ok(match self {
+            LoggedRPCEvent::Request { id, request } => RPCEvent::Request(
+                id,
+                match request {
+                    LoggedRequest::Status(data) => RPCRequest::Status(decode_ssz(&data)?),
+                    LoggedRequest::Goodbye(data) => {
+                        RPCRequest::Goodbye(decode_ssz::<GoodbyeReason>(&data)?)
+                    }
+                    LoggedRequest::BlocksByRange(data) => {
+                        RPCRequest::BlocksByRange(decode_ssz::<BlocksByRangeRequest>(&data)?)
+                    }
+                    LoggedRequest::BlocksByRoot(data) => {
+                        RPCRequest::BlocksByRoot(BlocksByRootRequest {
+                            block_roots: decode_ssz::<Vec<Hash256>>(&data)?,
+                        })
+                    }
+                },
+            ),
+            LoggedRPCEvent::Response { id, response } => RPCEvent::Response(
+                id,
+                match response {
+                    LoggedResponse::Status(data) => RPCErrorResponse::Success(RPCResponse::Status(
+                        decode_ssz::<StatusMessage>(&data)?,
+                    )),
+                    LoggedResponse::BlocksByRange(data) => {
+                        RPCErrorResponse::Success(RPCResponse::BlocksByRange(decode_hex(&data)?))
+                    }
+                    LoggedResponse::BlocksByRoot(data) => {
+                        RPCErrorResponse::Success(RPCResponse::BlocksByRoot(decode_hex(&data)?))
+                    }
+                    LoggedResponse::InvalidRequest(data) => {
+                        RPCErrorResponse::InvalidRequest(decode_error_message(&data)?)
+                    }
+                    LoggedResponse::ServerError(data) => {
+                        RPCErrorResponse::ServerError(decode_error_message(&data)?)
+                    }
+                    LoggedResponse::Unknown(data) => {
+                        RPCErrorResponse::Unknown(decode_error_message(&data)?)
+                    }
+                    LoggedResponse::BlocksByRangeTermination => {
+                        RPCErrorResponse::StreamTermination(ResponseTermination::BlocksByRange)
+                    }
+                    LoggedResponse::BlocksByRootTermination => {
+                        RPCErrorResponse::StreamTermination(ResponseTermination::BlocksByRoot)
+                    }
+                },
+            ),
+            LoggedRPCEvent::Error { id, error } => RPCEvent::Error(
+                id,
+                match error {
+                    LoggedRPCError::StreamTimeout => RPCError::StreamTimeout,
+                    LoggedRPCError::ResponseTimeout => RPCError::ResponseTimeout,
+                    LoggedRPCError::RPCErrorResponse => RPCError::RPCErrorResponse,
+                    LoggedRPCError::TooManyRequests => RPCError::TooManyRequests,
+                    LoggedRPCError::Other(description) => RPCError::Custom(description),
+                },
+            ),
+        })
+    }
+}
+
+fn parse_peer_id(peer_id: &str) -> Result<PeerId, String> {
+    peer_id
+        .parse()
+        .map_err(|_| format!("Invalid peer id: {}", peer_id))
+}
+
+fn decode_hex(data: &str) -> Result<Vec<u8>, String> {
+    hex::decode(data).map_err(|e| format!("Invalid hex data: {}", e))
+}
+
+fn decode_ssz<T: Decode>(data: &str) -> Result<T, String> {
+    T::from_ssz_bytes(&decode_hex(data)?).map_err(|e| format!("Invalid SSZ data: {:?}", e))
+}
+
+fn decode_error_message(data: &str) -> Result<ErrorMessage, String> {
+    Ok(ErrorMessage {
+        error_message: decode_hex(data)?,
+    })
+}
+
+/// An event read back from a recording.
+pub struct RecordedEvent {
+    /// The time the event was produced, in milliseconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// The recorded event.
+    pub event: Libp2pEvent,
+}
+
+/// Appends events to a recording, stopping once it holds `max_events`.
+pub struct EventRecorder {
+    file: File,
+    recorded: usize,
+    max_events: usize,
+    log: slog::Logger,
+}
+
+impl EventRecorder {
+    /// Opens the recording at `path`, creating it if it does not exist. New events are appended
+    /// to any already recorded, which count towards `max_events`.
+    pub fn create(path: &Path, max_events: usize, log: slog::Logger) -> Result<Self, String> {
+        let recorded = match File::open(path) {
+            Ok(file) => BufReader::new(file).lines().count(),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(format!("Unable to read event recording {:?}: {}", path, e)),
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Unable to open event recording {:?}: {}", path, e))?;
+
+        if recorded >= max_events {
+            warn!(log, "Event recording is full, no events will be recorded"; "events" => recorded);
+        }
+
+        Ok(Self {
+            file,
+            recorded,
+            max_events,
+            log,
+        })
+    }
+
+    /// Returns the number of events in the recording.
+    pub fn recorded(&self) -> usize {
+        self.recorded
+    }
+
+    /// Appends `event` to the recording, unless it already holds `max_events`.
+    ///
+    /// Recording stops if the recording cannot be written to.
+    pub fn record(&mut self, event: &Libp2pEvent) {
+        if self.recorded >= self.max_events {
+            return;
+        }
+
+        let record = EventRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or(0),
+            event: LoggedEvent::from(event),
+        };
+        let mut line = serde_json::to_vec(&record).expect("event records should always serialize");
+        line.push(b'\n');
+
+        if let Err(e) = self.file.write_all(&line) {
+            warn!(self.log, "Unable to record event, recording stopped"; "error" => format!("{}", e));
+            self.max_events = self.recorded;
+            return;
+        }
+
+        self.recorded += 1;
+        if self.recorded == self.max_events {
+            warn!(self.log, "Event recording limit reached"; "events" => self.recorded);
+        }
+    }
+}
+
+/// Reads the recording at `path`, passing each event to `consumer` in the order it was recorded.
+///
+/// Returns the number of events replayed.
+pub fn replay_events<F: FnMut(RecordedEvent)>(
+    path: &Path,
+    mut consumer: F,
+) -> Result<usize, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Unable to open event recording {:?}: {}", path, e))?;
+
+    let mut replayed = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("Unable to read event recording: {}", e))?;
+        let record: EventRecord = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid event {} in recording: {}", replayed, e))?;
+        let event = record
+            .event
+            .into_event()
+            .map_err(|e| format!("Invalid event {} in recording: {}", replayed, e))?;
+        consumer(RecordedEvent {
+            timestamp: record.timestamp,
+            event,
+        });
+        replayed += 1;
+    }
+
+    Ok(replayed)
+}
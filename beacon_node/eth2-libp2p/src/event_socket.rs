@@ -0,0 +1,174 @@
+//! Exposes the `Libp2pEvent` stream of the `Service` to local clients over a Unix domain socket.
+//!
+//! Each event is written to every connected client as a 4-byte little-endian length prefix,
+//! followed by the event as a JSON object. The `"event"` field of the object is the name of the
+//! `Libp2pEvent` variant.
+use crate::service::Libp2pEvent;
+use serde_derive::Serialize;
+use slog::{debug, warn};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+
+/// A `Libp2pEvent`, as serialized for clients of the event socket.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event")]
+pub(crate) enum SocketEvent {
+    RPC {
+        peer_id: String,
+        rpc_event: String,
+    },
+    PeerDialed {
+        peer_id: String,
+    },
+    PeerDisconnected {
+        peer_id: String,
+    },
+    PubsubMessage {
+        id: String,
+        source: String,
+        topics: Vec<String>,
+        kind: &'static str,
+    },
+    PeerSubscribed {
+        peer_id: String,
+        topic: String,
+    },
+    StatusResponse {
+        peer_id: String,
+        head_slot: u64,
+        finalized_epoch: u64,
+    },
+}
+
+impl From<&Libp2pEvent> for SocketEvent {
+    fn from(event: &Libp2pEvent) -> Self {
+        match event {
+            Libp2pEvent::RPC(peer_id, rpc_event) => SocketEvent::RPC {
+                peer_id: peer_id.to_base58(),
+                rpc_event: rpc_event.to_string(),
+            },
+            Libp2pEvent::PeerDialed(peer_id) => SocketEvent::PeerDialed {
+                peer_id: peer_id.to_base58(),
+            },
+            Libp2pEvent::PeerDisconnected(peer_id) => SocketEvent::PeerDisconnected {
+                peer_id: peer_id.to_base58(),
+            },
+            Libp2pEvent::PubsubMessage {
+                id,
+                source,
+                topics,
+                message,
+            } => SocketEvent::PubsubMessage {
+                id: id.clone(),
+                source: source.to_base58(),
+                topics: topics.iter().map(|t| t.as_str().to_string()).collect(),
+                kind: message.kind(),
+            },
+            Libp2pEvent::PeerSubscribed(peer_id, topic) => SocketEvent::PeerSubscribed {
+                peer_id: peer_id.to_base58(),
+                topic: topic.as_str().to_string(),
+            },
+            Libp2pEvent::StatusResponse { peer_id, status } => SocketEvent::StatusResponse {
+                peer_id: peer_id.to_base58(),
+                head_slot: status.head_slot.as_u64(),
+                finalized_epoch: status.finalized_epoch.as_u64(),
+            },
+        }
+    }
+}
+
+/// Returns `event` as it is written to the event socket, including the length prefix.
+pub fn encode_event(event: &Libp2pEvent) -> Vec<u8> {
+    let json = serde_json::to_vec(&SocketEvent::from(event))
+        .expect("socket events should always serialize");
+
+    let mut bytes = Vec::with_capacity(4 + json.len());
+    bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&json);
+    bytes
+}
+
+/// A Unix domain socket which writes each event it is sent to all of its connected clients.
+pub struct EventSocket {
+    listener: UnixListener,
+    clients: Vec<UnixStream>,
+    log: slog::Logger,
+}
+
+impl EventSocket {
+    /// Listens for clients on `path`.
+    ///
+    /// A stale socket left at `path` by a previous run is replaced, any other file at `path` is an
+    /// error.
+    pub fn bind(path: &Path, log: slog::Logger) -> Result<Self, String> {
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("Event socket path {:?} is not a socket", path));
+            }
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Unable to remove stale event socket {:?}: {}", path, e))?;
+        }
+
+        let listener = UnixListener::bind(path)
+            .map_err(|e| format!("Unable to bind event socket {:?}: {}", path, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Unable to configure event socket {:?}: {}", path, e))?;
+
+        Ok(Self {
+            listener,
+            clients: vec![],
+            log,
+        })
+    }
+
+    /// Returns the number of connected clients.
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Writes `event` to every connected client, after accepting any new clients.
+    ///
+    /// Writes never block the caller. A client which is not reading fast enough to accept the
+    /// whole event is disconnected.
+    pub fn send(&mut self, event: &Libp2pEvent) {
+        self.accept_clients();
+        if self.clients.is_empty() {
+            return;
+        }
+
+        let bytes = encode_event(event);
+        let log = &self.log;
+        self.clients
+            .retain(|client| match (&*client).write_all(&bytes) {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!(log, "Disconnecting event socket client"; "error" => format!("{}", e));
+                    false
+                }
+            });
+    }
+
+    /// Accepts all of the clients waiting to connect.
+    fn accept_clients(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((client, _)) => {
+                    if let Err(e) = client.set_nonblocking(true) {
+                        warn!(self.log, "Unable to configure event socket client"; "error" => format!("{}", e));
+                        continue;
+                    }
+                    debug!(self.log, "Event socket client connected");
+                    self.clients.push(client);
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    warn!(self.log, "Unable to accept event socket client"; "error" => format!("{}", e));
+                    break;
+                }
+            }
+        }
+    }
+}
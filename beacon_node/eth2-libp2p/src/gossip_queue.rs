@@ -0,0 +1,73 @@
+//! A bounded queue of received gossipsub messages, which returns blocks ahead of all other
+//! messages so that they are validated first.
+use crate::behaviour::PubsubMessage;
+use crate::service::Libp2pEvent;
+use std::collections::VecDeque;
+
+/// Holds `Libp2pEvent::PubsubMessage` events until they are returned by the service.
+///
+/// Blocks are returned first, in the order they were received, followed by all other messages in
+/// the order they were received.
+pub struct GossipQueue {
+    /// The queued block messages.
+    blocks: VecDeque<Libp2pEvent>,
+    /// The queued messages of all other topics (e.g., attestations).
+    others: VecDeque<Libp2pEvent>,
+    /// The maximum number of queued messages.
+    max_len: usize,
+}
+
+impl GossipQueue {
+    /// Returns an empty queue, holding at most `max_len` messages (and at least one).
+    pub fn new(max_len: usize) -> Self {
+        GossipQueue {
+            blocks: VecDeque::new(),
+            others: VecDeque::new(),
+            max_len: std::cmp::max(max_len, 1),
+        }
+    }
+
+    /// Queues `event`, returning `false` if a message was dropped to make room for it.
+    ///
+    /// When the queue is full, the oldest non-block message is dropped. If there are only blocks
+    /// queued, the oldest block is dropped for a new block and a new non-block message is dropped
+    /// itself.
+    pub fn push(&mut self, event: Libp2pEvent) -> bool {
+        let is_block = match &event {
+            Libp2pEvent::PubsubMessage { message, .. } => match message {
+                PubsubMessage::Block(_) => true,
+                _ => false,
+            },
+            _ => false,
+        };
+
+        let mut dropped = false;
+        if self.len() >= self.max_len {
+            dropped = true;
+            if self.others.pop_front().is_none() {
+                if is_block {
+                    self.blocks.pop_front();
+                } else {
+                    return false;
+                }
+            }
+        }
+
+        if is_block {
+            self.blocks.push_back(event);
+        } else {
+            self.others.push_back(event);
+        }
+        !dropped
+    }
+
+    /// Removes and returns the next message, blocks first.
+    pub fn pop(&mut self) -> Option<Libp2pEvent> {
+        self.blocks.pop_front().or_else(|| self.others.pop_front())
+    }
+
+    /// Returns the number of queued messages.
+    pub fn len(&self) -> usize {
+        self.blocks.len() + self.others.len()
+    }
+}
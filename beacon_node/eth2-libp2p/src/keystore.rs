@@ -0,0 +1,147 @@
+//! Decryption of network keys stored in an EIP-2335 style JSON keystore.
+//!
+//! Only decryption is supported, keys generated by Lighthouse are always stored in the raw
+//! format.
+use aes_ctr::stream_cipher::generic_array::GenericArray;
+use aes_ctr::stream_cipher::{NewStreamCipher, SyncStreamCipher};
+use aes_ctr::Aes128Ctr;
+use hmac::Hmac;
+use serde_derive::Deserialize;
+use sha2::Sha256;
+
+/// The length of the key derived from the password, half of which is the AES key.
+const DERIVED_KEY_LEN: usize = 32;
+
+/// An encrypted secret key.
+#[derive(Debug, Deserialize)]
+pub struct Keystore {
+    crypto: Crypto,
+}
+
+#[derive(Debug, Deserialize)]
+struct Crypto {
+    kdf: Module<KdfParams>,
+    checksum: Module<serde_json::Value>,
+    cipher: Module<CipherParams>,
+}
+
+/// A `crypto` module, identified by its `function`.
+#[derive(Debug, Deserialize)]
+struct Module<P> {
+    function: String,
+    params: P,
+    message: String,
+}
+
+/// The parameters of both the `scrypt` and `pbkdf2` key derivation functions.
+#[derive(Debug, Deserialize)]
+struct KdfParams {
+    dklen: usize,
+    salt: String,
+    // scrypt
+    n: Option<u32>,
+    r: Option<u32>,
+    p: Option<u32>,
+    // pbkdf2
+    c: Option<usize>,
+    prf: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+impl Keystore {
+    /// Parses a keystore from its JSON representation.
+    ///
+    /// Returns `None` if `bytes` are not a JSON keystore (e.g., a raw secret key).
+    pub fn from_json_bytes(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+
+    /// Decrypts the secret key with `password`.
+    ///
+    /// Returns an error if the password is incorrect or the keystore uses an unsupported
+    /// function.
+    pub fn decrypt(&self, password: &[u8]) -> Result<Vec<u8>, String> {
+        let crypto = &self.crypto;
+
+        let derived_key = derive_key(&crypto.kdf, password)?;
+        let cipher_message = decode_hex(&crypto.cipher.message)?;
+
+        if crypto.checksum.function != "sha256" {
+            return Err(format!(
+                "Unsupported keystore checksum function: {}",
+                crypto.checksum.function
+            ));
+        }
+        let mut checksum_preimage = derived_key[16..32].to_vec();
+        checksum_preimage.extend_from_slice(&cipher_message);
+        if eth2_hashing::hash(&checksum_preimage) != decode_hex(&crypto.checksum.message)? {
+            return Err("Incorrect keystore password".into());
+        }
+
+        if crypto.cipher.function != "aes-128-ctr" {
+            return Err(format!(
+                "Unsupported keystore cipher function: {}",
+                crypto.cipher.function
+            ));
+        }
+        let iv = decode_hex(&crypto.cipher.params.iv)?;
+        if iv.len() != 16 {
+            return Err(format!("Invalid keystore cipher iv length: {}", iv.len()));
+        }
+
+        let mut secret = cipher_message;
+        let mut cipher = Aes128Ctr::new(
+            GenericArray::from_slice(&derived_key[0..16]),
+            GenericArray::from_slice(&iv),
+        );
+        cipher.apply_keystream(&mut secret);
+
+        Ok(secret)
+    }
+}
+
+/// Derives the decryption key from `password` with the key derivation function of the keystore.
+fn derive_key(kdf: &Module<KdfParams>, password: &[u8]) -> Result<Vec<u8>, String> {
+    let params = &kdf.params;
+    if params.dklen != DERIVED_KEY_LEN {
+        return Err(format!("Unsupported keystore dklen: {}", params.dklen));
+    }
+    let salt = decode_hex(&params.salt)?;
+    let mut derived_key = vec![0; DERIVED_KEY_LEN];
+
+    match kdf.function.as_str() {
+        "scrypt" => {
+            let (n, r, p) = match (params.n, params.r, params.p) {
+                (Some(n), Some(r), Some(p)) => (n, r, p),
+                _ => return Err("Keystore scrypt params require n, r and p".into()),
+            };
+            if !n.is_power_of_two() || n < 2 {
+                return Err(format!("Invalid keystore scrypt n: {}", n));
+            }
+            let scrypt_params = scrypt::ScryptParams::new(n.trailing_zeros() as u8, r, p)
+                .map_err(|e| format!("Invalid keystore scrypt params: {:?}", e))?;
+            scrypt::scrypt(password, &salt, &scrypt_params, &mut derived_key)
+                .map_err(|e| format!("Unable to derive keystore key: {:?}", e))?;
+        }
+        "pbkdf2" => {
+            if params.prf.as_ref().map(String::as_str) != Some("hmac-sha256") {
+                return Err(format!("Unsupported keystore pbkdf2 prf: {:?}", params.prf));
+            }
+            let c = params
+                .c
+                .ok_or_else(|| "Keystore pbkdf2 params require c".to_string())?;
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(password, &salt, c, &mut derived_key);
+        }
+        function => return Err(format!("Unsupported keystore kdf function: {}", function)),
+    }
+
+    Ok(derived_key)
+}
+
+fn decode_hex(hex_str: &str) -> Result<Vec<u8>, String> {
+    hex::decode(hex_str).map_err(|e| format!("Invalid keystore hex: {:?}", e))
+}
@@ -8,16 +8,31 @@ extern crate lazy_static;
 pub mod behaviour;
 mod config;
 mod discovery;
+mod duplicate_cache;
 pub mod error;
+mod event_recorder;
+#[cfg(unix)]
+mod event_socket;
+mod gossip_queue;
+mod keystore;
 mod metrics;
 pub mod rpc;
 mod service;
+pub mod test_utils;
 
-pub use behaviour::PubsubMessage;
+pub use behaviour::{
+    DecodedPubsubMessage, MessageAcceptance, PropagationLatencies, PublishError, PublishResult,
+    PubsubDecodeError, PubsubMessage, MAX_PENDING_PROPAGATIONS,
+};
 pub use config::{
-    Config as NetworkConfig, BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC, SHARD_TOPIC_PREFIX,
-    TOPIC_ENCODING_POSTFIX, TOPIC_PREFIX,
+    gossip_message_id, Config as NetworkConfig, BEACON_ATTESTATION_TOPIC, BEACON_BLOCK_TOPIC,
+    GOSSIP_MAX_SIZE, SHARD_TOPIC_PREFIX, TOPIC_ENCODING_POSTFIX, TOPIC_PREFIX,
 };
+pub use discovery::{boot_node_multiaddr, build_enr, load_enr, read_enr_from_disc};
+pub use event_recorder::{replay_events, EventRecorder, RecordedEvent, MAX_RECORDED_EVENTS};
+#[cfg(unix)]
+pub use event_socket::encode_event;
+pub use libp2p::core::identity;
 pub use libp2p::enr::Enr;
 pub use libp2p::gossipsub::{Topic, TopicHash};
 pub use libp2p::multiaddr;
@@ -29,3 +44,9 @@ pub use libp2p::{
 pub use rpc::RPCEvent;
 pub use service::Libp2pEvent;
 pub use service::Service;
+pub use service::{
+    keypair_from_seed, load_private_key, read_private_key, rotate_private_key,
+    MAX_GOSSIP_REJECTIONS, MAX_PUBLISH_QUEUE_LEN, MAX_RPC_TIMEOUTS, NETWORK_KEY_BACKUP_FILENAME,
+    NETWORK_KEY_FILENAME, PUBLISH_BATCH_SIZE,
+};
+pub use service::{PeerDirection, PeerInfo};
@@ -17,4 +17,30 @@ lazy_static! {
         "libp2p_peer_disconnect_event_total",
         "Count of libp2p peer disconnect events"
     );
+    pub static ref GOSSIP_OVERSIZED_MESSAGES_REJECTED: Result<IntCounter> = try_create_int_counter(
+        "gossipsub_oversized_messages_rejected_total",
+        "Count of inbound gossipsub messages rejected for exceeding their topic's size limit"
+    );
+    pub static ref GOSSIP_PUBLISH_SUBSCRIBED_PEERS: Result<Histogram> = try_create_histogram(
+        "gossipsub_publish_subscribed_peers",
+        "Number of peers subscribed to the topics of a published gossipsub message"
+    );
+    pub static ref GOSSIP_PUBLISH_QUEUE_FULL: Result<IntCounter> = try_create_int_counter(
+        "gossipsub_publish_queue_full_total",
+        "Count of outbound gossipsub messages dropped because the publish queue was full"
+    );
+    pub static ref GOSSIP_PROPAGATION_LATENCY: Result<HistogramVec> = try_create_histogram_vec(
+        "gossipsub_propagation_latency_seconds",
+        "Time between receiving a gossipsub message and propagating it once validated",
+        &["kind"]
+    );
+    pub static ref GOSSIP_SUBSCRIBED_PEERS: Result<IntGaugeVec> = try_create_int_gauge_vec(
+        "gossipsub_subscribed_peers",
+        "Number of connected peers subscribed to each gossipsub topic",
+        &["topic"]
+    );
+    pub static ref GOSSIP_PUBLISH_NO_PEERS: Result<IntCounter> = try_create_int_counter(
+        "gossipsub_publish_no_peers_total",
+        "Count of gossipsub messages published without any subscribed peers"
+    );
 }
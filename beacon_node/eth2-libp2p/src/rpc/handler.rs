@@ -1,7 +1,7 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::cognitive_complexity)]
 
-use super::methods::{RPCErrorResponse, RequestId};
+use super::methods::{ErrorMessage, RPCErrorResponse, RequestId};
 use super::protocol::{RPCError, RPCProtocol, RPCRequest};
 use super::RPCEvent;
 use crate::rpc::protocol::{InboundFramed, OutboundFramed};
@@ -15,6 +15,7 @@ use libp2p::swarm::protocols_handler::{
 use slog::{crit, debug, error, trace, warn};
 use smallvec::SmallVec;
 use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::timer::{delay_queue, DelayQueue};
@@ -23,6 +24,9 @@ use tokio::timer::{delay_queue, DelayQueue};
 //TODO: Implement check_timeout() on the substream types
 
 /// The time (in seconds) before a substream that is awaiting a response from the user times out.
+///
+/// This is also the time waited for a response to an outbound request, unless a timeout is
+/// configured for the request's protocol.
 pub const RESPONSE_TIMEOUT: u64 = 10;
 
 /// Inbound requests are given a sequential `RequestId` to keep track of.
@@ -81,6 +85,13 @@ where
     /// After the given duration has elapsed, an inactive connection will shutdown.
     inactive_timeout: Duration,
 
+    /// The time to wait for a response to an outbound request, keyed by protocol name.
+    request_timeouts: HashMap<String, Duration>,
+
+    /// The maximum number of concurrent inbound substreams. Further substreams are rejected with
+    /// an error response.
+    max_concurrent_inbound: usize,
+
     /// Logger for handling RPC streams
     log: slog::Logger,
 
@@ -133,6 +144,8 @@ where
     pub fn new(
         listen_protocol: SubstreamProtocol<RPCProtocol>,
         inactive_timeout: Duration,
+        request_timeouts: HashMap<String, Duration>,
+        max_concurrent_inbound: usize,
         log: &slog::Logger,
     ) -> Self {
         RPCHandler {
@@ -150,6 +163,8 @@ where
             max_dial_negotiated: 8,
             keep_alive: KeepAlive::Yes,
             inactive_timeout,
+            request_timeouts,
+            max_concurrent_inbound,
             log: log.clone(),
             _phantom: PhantomData,
         }
@@ -216,11 +231,34 @@ where
             return;
         }
 
-        // New inbound request. Store the stream and tag the output.
         let delay_key = self.inbound_substreams_delay.insert(
             self.current_substream_id,
             Duration::from_secs(RESPONSE_TIMEOUT),
         );
+
+        // Reject the request if the peer already has the maximum number of concurrent requests,
+        // the error response is sent and the stream closed as any other response.
+        if self.inbound_substreams.len() >= self.max_concurrent_inbound {
+            debug!(self.log, "Rejecting inbound request, too many concurrent requests"; "request" => format!("{}", req));
+            let error_response = RPCErrorResponse::ServerError(ErrorMessage {
+                error_message: b"Too many concurrent requests".to_vec(),
+            });
+            let rejected_stream = InboundSubstreamState::ResponsePendingSend {
+                substream: substream.send(error_response),
+                closing: true,
+            };
+            self.inbound_substreams
+                .insert(self.current_substream_id, (rejected_stream, delay_key));
+
+            self.events_out.push(RPCEvent::Error(
+                self.current_substream_id,
+                RPCError::TooManyRequests,
+            ));
+            self.current_substream_id += 1;
+            return;
+        }
+
+        // New inbound request. Store the stream and tag the output.
         let awaiting_stream = InboundSubstreamState::ResponseIdle(substream);
         self.inbound_substreams
             .insert(self.current_substream_id, (awaiting_stream, delay_key));
@@ -252,7 +290,7 @@ where
                 // new outbound request. Store the stream and tag the output.
                 let delay_key = self
                     .outbound_substreams_delay
-                    .insert(id, Duration::from_secs(RESPONSE_TIMEOUT));
+                    .insert(id, request_timeout(&self.request_timeouts, &request));
                 let awaiting_stream = OutboundSubstreamState::RequestPendingResponse {
                     substream: out,
                     request,
@@ -384,13 +422,18 @@ where
             self.inbound_substreams.remove(stream_id.get_ref());
         }
 
-        // purge expired outbound substreams
-        while let Async::Ready(Some(stream_id)) = self
+        // cancel outbound requests which have not been responded to in time
+        if let Async::Ready(Some(stream_id)) = self
             .outbound_substreams_delay
             .poll()
             .map_err(|_| ProtocolsHandlerUpgrErr::Timer)?
         {
-            self.outbound_substreams.remove(stream_id.get_ref());
+            let request_id = *stream_id.get_ref();
+            debug!(self.log, "Outbound RPC request timed out"; "request_id" => request_id);
+            self.outbound_substreams.remove(&request_id);
+            return Ok(Async::Ready(ProtocolsHandlerEvent::Custom(
+                RPCEvent::Error(request_id, RPCError::ResponseTimeout),
+            )));
         }
 
         // drive inbound streams that need to be processed
@@ -493,14 +536,14 @@ where
                         } => match substream.poll() {
                             Ok(Async::Ready(Some(response))) => {
                                 if request.multiple_responses() {
+                                    let timeout = request_timeout(&self.request_timeouts, &request);
                                     entry.get_mut().0 =
                                         OutboundSubstreamState::RequestPendingResponse {
                                             substream,
                                             request,
                                         };
                                     let delay_key = &entry.get().1;
-                                    self.outbound_substreams_delay
-                                        .reset(delay_key, Duration::from_secs(RESPONSE_TIMEOUT));
+                                    self.outbound_substreams_delay.reset(delay_key, timeout);
                                 } else {
                                     trace!(self.log, "Closing single stream request");
                                     // only expect a single response, close the stream
@@ -599,6 +642,15 @@ where
     }
 }
 
+/// Returns the time to wait for a response to `request`, falling back to `RESPONSE_TIMEOUT` if no
+/// timeout is configured for its protocol.
+fn request_timeout(request_timeouts: &HashMap<String, Duration>, request: &RPCRequest) -> Duration {
+    request_timeouts
+        .get(request.protocol_name())
+        .copied()
+        .unwrap_or_else(|| Duration::from_secs(RESPONSE_TIMEOUT))
+}
+
 // Check for new items to send to the peer and update the underlying stream
 fn apply_queued_responses<TSubstream: AsyncRead + AsyncWrite>(
     raw_substream: InboundFramed<TSubstream>,
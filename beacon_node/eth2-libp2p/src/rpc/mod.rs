@@ -15,8 +15,12 @@ use libp2p::{Multiaddr, PeerId};
 pub use methods::{
     ErrorMessage, RPCErrorResponse, RPCResponse, RequestId, ResponseTermination, StatusMessage,
 };
-pub use protocol::{RPCError, RPCProtocol, RPCRequest};
+pub use protocol::{
+    RPCError, RPCProtocol, RPCRequest, RPC_BLOCKS_BY_RANGE, RPC_BLOCKS_BY_ROOT, RPC_GOODBYE,
+    RPC_STATUS,
+};
 use slog::o;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -65,6 +69,10 @@ impl std::fmt::Display for RPCEvent {
 pub struct RPC<TSubstream> {
     /// Queue of events to processed.
     events: Vec<NetworkBehaviourAction<RPCEvent, RPCMessage>>,
+    /// The time to wait for a response to an outbound request, keyed by protocol name.
+    request_timeouts: HashMap<String, Duration>,
+    /// The maximum number of concurrent inbound requests from each peer.
+    max_concurrent_requests: usize,
     /// Pins the generic substream.
     marker: PhantomData<(TSubstream)>,
     /// Slog logger for RPC behaviour.
@@ -72,10 +80,16 @@ pub struct RPC<TSubstream> {
 }
 
 impl<TSubstream> RPC<TSubstream> {
-    pub fn new(log: slog::Logger) -> Self {
+    pub fn new(
+        request_timeouts: HashMap<String, Duration>,
+        max_concurrent_requests: usize,
+        log: slog::Logger,
+    ) -> Self {
         let log = log.new(o!("service" => "libp2p_rpc"));
         RPC {
             events: Vec::new(),
+            request_timeouts,
+            max_concurrent_requests,
             marker: PhantomData,
             log,
         }
@@ -103,6 +117,8 @@ where
         RPCHandler::new(
             SubstreamProtocol::new(RPCProtocol),
             Duration::from_secs(30),
+            self.request_timeouts.clone(),
+            self.max_concurrent_requests,
             &self.log,
         )
     }
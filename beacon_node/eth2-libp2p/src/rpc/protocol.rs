@@ -190,6 +190,16 @@ impl RPCRequest {
         }
     }
 
+    /// Returns the name of the protocol used by the request (e.g., `RPC_STATUS`).
+    pub fn protocol_name(&self) -> &'static str {
+        match self {
+            RPCRequest::Status(_) => RPC_STATUS,
+            RPCRequest::Goodbye(_) => RPC_GOODBYE,
+            RPCRequest::BlocksByRange(_) => RPC_BLOCKS_BY_RANGE,
+            RPCRequest::BlocksByRoot(_) => RPC_BLOCKS_BY_ROOT,
+        }
+    }
+
     /* These functions are used in the handler for stream management */
 
     /// This specifies whether a stream should remain open and await a response, given a request.
@@ -270,8 +280,12 @@ pub enum RPCError {
     IoError(io::Error),
     /// Waiting for a request/response timed out, or timer error'd.
     StreamTimeout,
+    /// The peer accepted an outbound request but did not respond to it in time.
+    ResponseTimeout,
     /// The peer returned a valid RPCErrorResponse but the response was an error.
     RPCErrorResponse,
+    /// The peer opened more concurrent inbound requests than allowed, the request was rejected.
+    TooManyRequests,
     /// Custom message.
     Custom(String),
 }
@@ -321,6 +335,8 @@ impl std::fmt::Display for RPCError {
             RPCError::IoError(ref err) => write!(f, "IO Error: {}", err),
             RPCError::RPCErrorResponse => write!(f, "RPC Response Error"),
             RPCError::StreamTimeout => write!(f, "Stream Timeout"),
+            RPCError::ResponseTimeout => write!(f, "Response Timeout"),
+            RPCError::TooManyRequests => write!(f, "Too many concurrent requests"),
             RPCError::Custom(ref err) => write!(f, "{}", err),
         }
     }
@@ -334,7 +350,9 @@ impl std::error::Error for RPCError {
             RPCError::InvalidProtocol(_) => None,
             RPCError::IoError(ref err) => Some(err),
             RPCError::StreamTimeout => None,
+            RPCError::ResponseTimeout => None,
             RPCError::RPCErrorResponse => None,
+            RPCError::TooManyRequests => None,
             RPCError::Custom(_) => None,
         }
     }
@@ -1,32 +1,63 @@
-use crate::behaviour::{Behaviour, BehaviourEvent, PubsubMessage};
+use crate::behaviour::{Behaviour, BehaviourEvent, PublishError, PublishResult, PubsubMessage};
 use crate::config::*;
-use crate::error;
+use crate::error::{self, ErrorKind};
+use crate::event_recorder::{EventRecorder, MAX_RECORDED_EVENTS};
+#[cfg(unix)]
+use crate::event_socket::EventSocket;
+use crate::gossip_queue::GossipQueue;
+use crate::keystore::Keystore;
+use crate::metrics;
 use crate::multiaddr::Protocol;
-use crate::rpc::RPCEvent;
+use crate::rpc::{
+    RPCError, RPCErrorResponse, RPCEvent, RPCRequest, RPCResponse, RequestId, StatusMessage,
+};
 use crate::NetworkConfig;
-use crate::{Topic, TopicHash};
+use crate::{Enr, Topic, TopicHash};
 use futures::prelude::*;
+use futures::task::{self, Task};
 use futures::Stream;
 use libp2p::core::{
     identity::Keypair, multiaddr::Multiaddr, muxing::StreamMuxerBox, nodes::Substream,
     transport::boxed::Boxed, ConnectedPoint,
 };
 use libp2p::{core, secio, swarm::NetworkBehaviour, PeerId, Swarm, Transport};
+use serde_derive::Serialize;
 use slog::{crit, debug, info, trace, warn};
 use smallvec::SmallVec;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 use std::time::Duration;
 use std::time::Instant;
+use tokio::timer::Delay;
 
 type Libp2pStream = Boxed<(PeerId, StreamMuxerBox), Error>;
 type Libp2pBehaviour = Behaviour<Substream<StreamMuxerBox>>;
 
-const NETWORK_KEY_FILENAME: &str = "key";
+/// The name of the file in the network directory which stores the node's private key.
+pub const NETWORK_KEY_FILENAME: &str = "key";
+/// The name of the file in the network directory which `rotate_private_key` backs up the previous
+/// private key to.
+pub const NETWORK_KEY_BACKUP_FILENAME: &str = "key.bak";
+/// The time in seconds to wait before redialing a persistent peer that has disconnected.
+const PERSISTENT_PEER_REDIAL_TIMEOUT: u64 = 5;
 /// The time in milliseconds to wait before banning a peer. This allows for any Goodbye messages to be
 /// flushed and protocols to be negotiated.
 const BAN_PEER_TIMEOUT: u64 = 200;
+/// The number of consecutive RPC requests a peer may leave unanswered before it is disconnected.
+/// Peers which are not persistent peers are also banned.
+pub const MAX_RPC_TIMEOUTS: u32 = 3;
+/// The number of gossipsub messages relayed by a peer which may fail validation before it is
+/// disconnected. Peers which are not persistent peers are also banned.
+pub const MAX_GOSSIP_REJECTIONS: u32 = 5;
+/// The number of distinct peers which must observe the same IP address of this node before it is
+/// used as the ENR address.
+const MIN_IP_OBSERVATIONS: usize = 2;
+/// The maximum number of messages queued by `publish`, further messages are dropped.
+pub const MAX_PUBLISH_QUEUE_LEN: usize = 256;
+/// The maximum number of queued messages published by each call to `poll`.
+pub const PUBLISH_BATCH_SIZE: usize = 16;
 
 /// The configuration and state of the libp2p components for the beacon node.
 pub struct Service {
@@ -40,8 +71,83 @@ pub struct Service {
     /// A current list of peers to ban after a given timeout.
     peers_to_ban: SmallVec<[(PeerId, Instant); 4]>,
 
-    /// Indicates if the listening address have been verified and compared to the expected ENR.
-    verified_listen_address: bool,
+    /// The configured boot nodes and libp2p nodes, with the addresses they are dialed on. These
+    /// peers are redialed when they disconnect.
+    persistent_peers: HashMap<PeerId, Vec<Multiaddr>>,
+
+    /// A current list of persistent peers to redial after a given timeout.
+    peers_to_redial: SmallVec<[(PeerId, Instant); 4]>,
+
+    /// The number of consecutive RPC requests each connected peer has not responded to in time.
+    /// Entries are removed when the peer responds or disconnects.
+    rpc_timeouts: HashMap<PeerId, u32>,
+
+    /// The number of gossipsub messages relayed by each connected peer which failed validation.
+    /// Entries are removed when the peer disconnects.
+    gossip_rejections: HashMap<PeerId, u32>,
+
+    /// The time at which the node is checked for connected peers, and the boot node addresses to
+    /// redial if there are none. `None` once a peer has connected or if there are no boot nodes.
+    boot_node_check: Option<(Instant, Vec<Multiaddr>)>,
+
+    /// The period between checks of `boot_node_check`.
+    boot_node_grace_period: Duration,
+
+    /// The number of connected peers to maintain with on-demand peer searches.
+    target_peers: usize,
+
+    /// The number of connected peers above which the worst peers are disconnected.
+    max_peers: usize,
+
+    /// The timer for the next check of the connected peer count. See `maintain_peer_count`.
+    peer_check: Delay,
+
+    /// The period between checks of the connected peer count.
+    peer_check_interval: Duration,
+
+    /// The period between checks of the connected peer count at startup.
+    startup_peer_check_interval: Duration,
+
+    /// Whether the node is starting up, and has not yet reached `target_peers`. Peer count checks
+    /// are made every `startup_peer_check_interval` until then.
+    startup_ramp: bool,
+
+    /// The timer for setting the address of the ENR from the candidate addresses. `None` once the
+    /// address has been set. See `verify_listen_address`.
+    enr_address_check: Option<Delay>,
+
+    /// The period for which candidate addresses are collected before the ENR address is set.
+    enr_address_grace_period: Duration,
+
+    /// The outstanding requests made by `send_status_request`, whose responses are returned as
+    /// `Libp2pEvent::StatusResponse`.
+    status_requests: HashSet<(PeerId, RequestId)>,
+
+    /// The subscribed topics from `NetworkConfig::archive_topics`, which are not also core topics.
+    archive_topics: Vec<Topic>,
+
+    /// The Unix domain socket which events are also written to, if enabled.
+    #[cfg(unix)]
+    event_socket: Option<EventSocket>,
+
+    /// The recording which events are also appended to, if enabled.
+    event_recorder: Option<EventRecorder>,
+
+    /// The received gossipsub messages waiting to be returned from `poll`, blocks first.
+    gossip_queue: GossipQueue,
+
+    /// The messages queued by `publish`, waiting to be published by `poll`.
+    publish_queue: VecDeque<(Vec<Topic>, PubsubMessage)>,
+
+    /// The task polling the service, notified when a message is queued for publishing.
+    task: Option<Task>,
+
+    /// The id of the next request made by `send_status_request`. Starts at `1`, as the network
+    /// crate sends its `Status` requests with an id of `0`.
+    next_status_request_id: RequestId,
+
+    /// Whether Nagle's algorithm is disabled on the TCP connections of the transport.
+    tcp_nodelay: bool,
 
     /// The libp2p logger handle.
     pub log: slog::Logger,
@@ -51,10 +157,9 @@ impl Service {
     pub fn new(config: NetworkConfig, log: slog::Logger) -> error::Result<Self> {
         trace!(log, "Libp2p Service starting");
 
-        let local_keypair = if let Some(hex_bytes) = &config.secret_key_hex {
-            keypair_from_hex(hex_bytes)?
-        } else {
-            load_private_key(&config, &log)
+        let local_keypair = match keypair_from_config(&config)? {
+            Some(keypair) => keypair,
+            None => load_private_key(&config, &log)?,
         };
 
         // load the private key from CLI flag, disk or generate a new one
@@ -63,7 +168,7 @@ impl Service {
 
         let mut swarm = {
             // Set up the transport - tcp/ws with secio and mplex/yamux
-            let transport = build_transport(local_keypair.clone());
+            let transport = build_transport(local_keypair.clone(), &config);
             // Lighthouse network behaviour
             let behaviour = Behaviour::new(&local_keypair, &config, &log)?;
             Swarm::new(transport, behaviour, local_peer_id.clone())
@@ -89,14 +194,23 @@ impl Service {
                     "error" => format!("{:?}", err),
                     "listen_multiaddr" => format!("{}", listen_multiaddr),
                 );
-                return Err("Libp2p was unable to listen on the given listen address.".into());
+                return Err(ErrorKind::ListenFailed(listen_multiaddr.to_string()).into());
             }
         };
 
-        // helper closure for dialing peers
+        // helper closure for dialing peers, up to `max_outbound_peers`
+        let mut outbound_dials = 0;
+        let max_outbound_peers = config.max_outbound_peers;
         let mut dial_addr = |multiaddr: Multiaddr| {
+            if outbound_dials >= max_outbound_peers {
+                debug!(log, "Outbound peer limit reached, not dialing"; "address" => format!("{}", multiaddr));
+                return;
+            }
             match Swarm::dial_addr(&mut swarm, multiaddr.clone()) {
-                Ok(()) => debug!(log, "Dialing libp2p peer"; "address" => format!("{}", multiaddr)),
+                Ok(()) => {
+                    outbound_dials += 1;
+                    debug!(log, "Dialing libp2p peer"; "address" => format!("{}", multiaddr))
+                }
                 Err(err) => debug!(
                     log,
                     "Could not connect to peer"; "address" => format!("{}", multiaddr), "error" => format!("{:?}", err)
@@ -104,19 +218,35 @@ impl Service {
             };
         };
 
+        // peers that are redialed whenever they disconnect
+        let mut persistent_peers: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+
         // attempt to connect to user-input libp2p nodes
         for multiaddr in config.libp2p_nodes {
+            // only nodes with a known peer id can be recognised when they disconnect
+            let (multiaddr, peer_id) = split_peer_id(multiaddr);
+            if let Some(peer_id) = peer_id {
+                persistent_peers
+                    .entry(peer_id)
+                    .or_default()
+                    .push(multiaddr.clone());
+            }
             dial_addr(multiaddr);
         }
 
         // attempt to connect to any specified boot-nodes
+        let mut boot_node_addrs = vec![];
         for bootnode_enr in config.boot_nodes {
             for multiaddr in bootnode_enr.multiaddr() {
                 // ignore udp multiaddr if it exists
-                let components = multiaddr.iter().collect::<Vec<_>>();
-                if let Protocol::Udp(_) = components[1] {
+                if is_udp_multiaddr(&multiaddr) {
                     continue;
                 }
+                persistent_peers
+                    .entry(bootnode_enr.peer_id())
+                    .or_default()
+                    .push(multiaddr.clone());
+                boot_node_addrs.push(multiaddr.clone());
                 dial_addr(multiaddr);
             }
         }
@@ -143,22 +273,101 @@ impl Service {
         // Add any topics specified by the user
         topics.append(&mut config.topics.iter().cloned().map(Topic::new).collect());
 
-        let mut subscribed_topics = vec![];
-        for topic in topics {
-            if swarm.subscribe(topic.clone()) {
-                trace!(log, "Subscribed to topic"; "topic" => format!("{}", topic));
-                subscribed_topics.push(topic);
-            } else {
-                warn!(log, "Could not subscribe to topic"; "topic" => format!("{}", topic));
+        let mut archive_topics = vec![];
+        if config.gossip_enabled {
+            let mut subscribed_topics = vec![];
+            for topic in topics {
+                if swarm.subscribe(topic.clone()) {
+                    trace!(log, "Subscribed to topic"; "topic" => format!("{}", topic));
+                    subscribed_topics.push(topic);
+                } else {
+                    warn!(log, "Could not subscribe to topic"; "topic" => format!("{}", topic));
+                }
+            }
+            info!(log, "Subscribed to topics"; "topics" => format!("{:?}", subscribed_topics.iter().map(|t| format!("{}", t)).collect::<Vec<String>>()));
+
+            // archive topics that are already subscribed to are core topics and are never
+            // unsubscribed from as part of the archive set
+            for topic in config.archive_topics.iter().cloned().map(Topic::new) {
+                if swarm.subscribed_topics().contains(&topic.no_hash()) {
+                    continue;
+                }
+                if swarm.subscribe(topic.clone()) {
+                    archive_topics.push(topic);
+                } else {
+                    warn!(log, "Could not subscribe to archive topic"; "topic" => format!("{}", topic));
+                }
             }
+            if !archive_topics.is_empty() {
+                info!(log, "Subscribed to archive topics"; "count" => archive_topics.len());
+            }
+        } else {
+            info!(log, "Gossipsub disabled, not subscribing to any topics");
         }
-        info!(log, "Subscribed to topics"; "topics" => format!("{:?}", subscribed_topics.iter().map(|t| format!("{}", t)).collect::<Vec<String>>()));
+
+        #[cfg(unix)]
+        let event_socket = match &config.event_socket_path {
+            Some(path) => {
+                let event_socket =
+                    EventSocket::bind(path, log.clone()).map_err(ErrorKind::EventSocketFailed)?;
+                info!(log, "Event socket listening"; "path" => format!("{:?}", path));
+                Some(event_socket)
+            }
+            None => None,
+        };
+
+        let event_recorder = match &config.record_events_path {
+            Some(path) => {
+                let event_recorder = EventRecorder::create(path, MAX_RECORDED_EVENTS, log.clone())
+                    .map_err(ErrorKind::EventRecorderFailed)?;
+                info!(log, "Recording events"; "path" => format!("{:?}", path));
+                Some(event_recorder)
+            }
+            None => None,
+        };
+
+        let boot_node_check = if boot_node_addrs.is_empty() {
+            None
+        } else {
+            Some((
+                Instant::now() + config.boot_node_grace_period,
+                boot_node_addrs,
+            ))
+        };
+
+        let startup_peer_check_interval = std::cmp::min(
+            config.startup_peer_check_interval,
+            config.peer_check_interval,
+        );
 
         Ok(Service {
             local_peer_id,
             swarm,
             peers_to_ban: SmallVec::new(),
-            verified_listen_address: false,
+            persistent_peers,
+            peers_to_redial: SmallVec::new(),
+            rpc_timeouts: HashMap::new(),
+            gossip_rejections: HashMap::new(),
+            boot_node_check,
+            boot_node_grace_period: config.boot_node_grace_period,
+            target_peers: std::cmp::min(config.target_peers, config.max_peers),
+            max_peers: config.max_peers,
+            peer_check: Delay::new(Instant::now() + startup_peer_check_interval),
+            peer_check_interval: config.peer_check_interval,
+            startup_peer_check_interval,
+            startup_ramp: config.target_peers > 0,
+            enr_address_check: Some(Delay::new(Instant::now() + config.enr_address_grace_period)),
+            enr_address_grace_period: config.enr_address_grace_period,
+            status_requests: HashSet::new(),
+            archive_topics,
+            #[cfg(unix)]
+            event_socket,
+            event_recorder,
+            gossip_queue: GossipQueue::new(config.gossip_queue_size),
+            publish_queue: VecDeque::new(),
+            task: None,
+            next_status_request_id: 1,
+            tcp_nodelay: config.tcp_nodelay,
             log,
         })
     }
@@ -170,6 +379,403 @@ impl Service {
             Instant::now() + Duration::from_millis(BAN_PEER_TIMEOUT),
         ));
     }
+
+    /// Records an RPC request to `peer_id` which was not responded to in time.
+    ///
+    /// After `MAX_RPC_TIMEOUTS` consecutive timeouts the peer is disconnected, and banned unless
+    /// it is a persistent peer. A single timeout may be caused by a slow or loaded peer.
+    fn record_rpc_timeout(&mut self, peer_id: &PeerId) {
+        let timeouts = self.rpc_timeouts.entry(peer_id.clone()).or_insert(0);
+        *timeouts += 1;
+        debug!(self.log, "RPC request timed out"; "peer_id" => format!("{:?}", peer_id), "timeouts" => *timeouts);
+        if *timeouts < MAX_RPC_TIMEOUTS {
+            return;
+        }
+
+        self.rpc_timeouts.remove(peer_id);
+        self.penalize_peer(peer_id.clone());
+    }
+
+    /// Records a gossipsub message relayed by `peer_id` which failed validation.
+    ///
+    /// After `MAX_GOSSIP_REJECTIONS` rejected messages the peer is disconnected, and banned unless
+    /// it is a persistent peer. The peer may only have relayed the messages of another peer.
+    fn record_gossip_rejection(&mut self, peer_id: PeerId) {
+        let rejections = self.gossip_rejections.entry(peer_id.clone()).or_insert(0);
+        *rejections += 1;
+        debug!(self.log, "Gossipsub message rejected"; "peer_id" => format!("{:?}", peer_id), "rejections" => *rejections);
+        if *rejections < MAX_GOSSIP_REJECTIONS {
+            return;
+        }
+
+        self.gossip_rejections.remove(&peer_id);
+        self.penalize_peer(peer_id);
+    }
+
+    /// Disconnects a peer which has repeatedly misbehaved, and bans it unless it is a persistent
+    /// peer.
+    fn penalize_peer(&mut self, peer_id: PeerId) {
+        if self.persistent_peers.contains_key(&peer_id) {
+            // persistent peers are redialed once disconnected, rather than banned
+            self.disconnect_peer(peer_id);
+        } else if !self.is_banned(&peer_id) {
+            self.disconnect_and_ban_peer(peer_id);
+        }
+    }
+
+    /// Returns `true` if `peer_id` has been banned, or is waiting to be banned after
+    /// `disconnect_and_ban_peer`. Banned peers should not be dialed.
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.peers_to_ban.iter().any(|(id, _)| id == peer_id)
+            || self.swarm.discovery().is_banned(peer_id)
+    }
+
+    /// Schedules a redial of `peer_id` after a timeout period, if it is a persistent peer.
+    fn schedule_redial(&mut self, peer_id: &PeerId) {
+        if self.persistent_peers.contains_key(peer_id) && !self.is_redial_pending(peer_id) {
+            debug!(self.log, "Persistent peer disconnected, scheduling redial"; "peer_id" => format!("{:?}", peer_id));
+            self.peers_to_redial.push((
+                peer_id.clone(),
+                Instant::now() + Duration::from_secs(PERSISTENT_PEER_REDIAL_TIMEOUT),
+            ));
+        }
+    }
+
+    /// Dials all known addresses of a persistent peer.
+    fn redial_peer(&mut self, peer_id: &PeerId) {
+        if !self.swarm.outbound_slot_available() {
+            debug!(self.log, "Outbound peer limit reached, delaying redial"; "peer_id" => format!("{:?}", peer_id));
+            self.schedule_redial(peer_id);
+            return;
+        }
+
+        let multiaddrs = self
+            .persistent_peers
+            .get(peer_id)
+            .cloned()
+            .unwrap_or_default();
+        for multiaddr in multiaddrs {
+            match Swarm::dial_addr(&mut self.swarm, multiaddr.clone()) {
+                Ok(()) => {
+                    debug!(self.log, "Redialing persistent peer"; "peer_id" => format!("{:?}", peer_id), "address" => format!("{}", multiaddr))
+                }
+                Err(err) => {
+                    debug!(self.log, "Could not redial persistent peer"; "address" => format!("{}", multiaddr), "error" => format!("{:?}", err))
+                }
+            };
+        }
+    }
+
+    /// Sets the address of the ENR to the best of the candidate addresses. In order of preference,
+    /// these are a public listening address, a public IP address observed by at least
+    /// `MIN_IP_OBSERVATIONS` peers (on the listening port), then the first listening address.
+    ///
+    /// The external addresses of the swarm are not used, as a single peer can report any of them.
+    ///
+    /// If there are no candidates, the check is repeated after another grace period.
+    fn verify_listen_address(&mut self) {
+        let listeners: Vec<std::net::SocketAddr> = Swarm::listeners(&self.swarm)
+            .filter_map(multiaddr_to_socket_addr)
+            .collect();
+        let candidates: Vec<&std::net::SocketAddr> = listeners
+            .iter()
+            .filter(|socket_addr| !socket_addr.ip().is_unspecified())
+            .collect();
+
+        let observed = listeners.first().and_then(|listener| {
+            self.swarm
+                .observed_ips()
+                .filter(|(ip, peers)| *peers >= MIN_IP_OBSERVATIONS && is_public_ip(ip))
+                .max_by_key(|(_, peers)| *peers)
+                .map(|(ip, _)| std::net::SocketAddr::new(ip, listener.port()))
+        });
+
+        let best = candidates
+            .iter()
+            .find(|socket_addr| is_public_ip(&socket_addr.ip()))
+            .map(|socket_addr| **socket_addr)
+            .or(observed)
+            .or_else(|| candidates.first().map(|socket_addr| **socket_addr));
+
+        match best {
+            Some(socket_addr) => {
+                debug!(self.log, "Verified listening address"; "address" => format!("{}", socket_addr), "candidates" => candidates.len());
+                self.enr_address_check = None;
+                self.swarm.update_local_enr_socket(socket_addr, true);
+            }
+            None => {
+                if let Some(check) = self.enr_address_check.as_mut() {
+                    check.reset(Instant::now() + self.enr_address_grace_period);
+                }
+            }
+        }
+    }
+
+    /// Queues a gossipsub message to be published on `topics`, returning the number of peers
+    /// subscribed to them (see `Behaviour::publish`).
+    ///
+    /// Queued messages are published by `poll`, up to `PUBLISH_BATCH_SIZE` per call, so that
+    /// publishing many messages (e.g., a batch of attestations) does not hold up the delivery of
+    /// inbound events. Nothing is queued if gossip is disabled, and the message is dropped with
+    /// `PublishError::QueueFull` if `MAX_PUBLISH_QUEUE_LEN` messages are already queued. Queued
+    /// messages are lost if the service is dropped.
+    pub fn publish(
+        &mut self,
+        topics: &[Topic],
+        message: PubsubMessage,
+    ) -> Result<PublishResult, PublishError> {
+        let result = self.swarm.publish_peers(topics);
+        if let Err(PublishError::GossipDisabled) = result {
+            return result;
+        }
+        if self.publish_queue.len() >= MAX_PUBLISH_QUEUE_LEN {
+            metrics::inc_counter(&metrics::GOSSIP_PUBLISH_QUEUE_FULL);
+            return Err(PublishError::QueueFull);
+        }
+
+        self.publish_queue.push_back((topics.to_vec(), message));
+        if let Some(task) = &self.task {
+            task.notify();
+        }
+
+        result
+    }
+
+    /// Returns the number of messages queued by `publish` which are yet to be published.
+    pub fn pending_publishes(&self) -> usize {
+        self.publish_queue.len()
+    }
+
+    /// Stops passing received gossipsub messages to the caller of `poll`, which continues to
+    /// return RPC and peer events. Received messages are held until `resume_gossip` is called and
+    /// are not propagated in the meantime.
+    pub fn pause_gossip(&mut self) {
+        debug!(self.log, "Pausing gossip");
+        self.swarm.pause_gossip();
+    }
+
+    /// Resumes gossip after `pause_gossip`. The messages held while paused are returned by the
+    /// following calls to `poll`.
+    pub fn resume_gossip(&mut self) {
+        debug!(self.log, "Resuming gossip");
+        self.swarm.resume_gossip();
+    }
+
+    /// Returns the topics subscribed to from `NetworkConfig::archive_topics`.
+    pub fn archive_topics(&self) -> &[Topic] {
+        &self.archive_topics
+    }
+
+    /// Unsubscribes from all of the archive topics, leaving the core topics subscribed. Returns the
+    /// number of topics unsubscribed from.
+    pub fn unsubscribe_archive(&mut self) -> usize {
+        let archive_topics = std::mem::replace(&mut self.archive_topics, vec![]);
+        for topic in &archive_topics {
+            self.swarm.unsubscribe(topic.clone());
+        }
+        info!(self.log, "Unsubscribed from archive topics"; "count" => archive_topics.len());
+        archive_topics.len()
+    }
+
+    /// Returns the recently received gossipsub messages on `topic`, to be processed by the caller
+    /// after subscribing to that topic.
+    pub fn replay_recent(&self, topic: &Topic) -> Vec<PubsubMessage> {
+        self.swarm.replay_recent(topic)
+    }
+
+    /// Requests the `Status` of `peer_id`, sending `local_status` as the status of this node.
+    ///
+    /// The response is returned from `poll` as a `Libp2pEvent::StatusResponse`, rather than a
+    /// `Libp2pEvent::RPC`.
+    pub fn send_status_request(&mut self, peer_id: PeerId, local_status: StatusMessage) {
+        let request_id = self.next_status_request_id;
+        self.next_status_request_id += 1;
+
+        self.status_requests.insert((peer_id.clone(), request_id));
+        self.swarm.send_rpc(
+            peer_id,
+            RPCEvent::Request(request_id, RPCRequest::Status(local_status)),
+        );
+    }
+
+    /// Starts a peer discovery search immediately, rather than waiting for the next periodic
+    /// search. Up to `target` of the discovered peers are dialed.
+    pub fn discover_peers(&mut self, target: usize) {
+        self.swarm.discover_peers(target);
+    }
+
+    /// Adds boot nodes at runtime (e.g., after the boot node list has been updated), returning the
+    /// number of boot nodes added.
+    ///
+    /// As with the boot nodes given at startup, each ENR is added to the discovery routing table
+    /// and its node is dialed, then redialed whenever it disconnects. ENRs of peers which are
+    /// already persistent peers are ignored.
+    pub fn add_boot_nodes(&mut self, enrs: Vec<Enr>) -> usize {
+        let mut added = 0;
+        for enr in enrs {
+            let peer_id = enr.peer_id();
+            if self.persistent_peers.contains_key(&peer_id) {
+                debug!(self.log, "Ignoring known boot node"; "peer_id" => format!("{:?}", peer_id));
+                continue;
+            }
+
+            // ignore udp multiaddrs
+            let multiaddrs = enr
+                .multiaddr()
+                .into_iter()
+                .filter(|multiaddr| !is_udp_multiaddr(multiaddr))
+                .collect();
+            self.swarm.add_enr(enr);
+            self.persistent_peers.insert(peer_id.clone(), multiaddrs);
+            self.redial_peer(&peer_id);
+            added += 1;
+        }
+
+        info!(self.log, "Added boot nodes"; "count" => added);
+        added
+    }
+
+    /// Returns the currently connected peers and how they are connected.
+    pub fn peers_info(&self) -> Vec<PeerInfo> {
+        self.swarm
+            .discovery()
+            .connected_endpoints()
+            .iter()
+            .map(|(peer_id, endpoint)| PeerInfo::new(peer_id, endpoint))
+            .collect()
+    }
+
+    /// Returns `true` if Nagle's algorithm is disabled on the TCP connections of the transport. See
+    /// `NetworkConfig::tcp_nodelay`.
+    pub fn tcp_nodelay(&self) -> bool {
+        self.tcp_nodelay
+    }
+
+    /// Returns `true` if `peer_id` is a persistent peer which is waiting to be redialed.
+    pub fn is_redial_pending(&self, peer_id: &PeerId) -> bool {
+        self.peers_to_redial.iter().any(|(id, _)| id == peer_id)
+    }
+
+    /// Logs loudly if no peers have connected since the boot nodes were dialed, and dials them
+    /// again.
+    ///
+    /// The boot nodes are no longer checked once any peer has connected.
+    fn check_boot_node_connections(&mut self) {
+        if self.swarm.connected_peers() > 0 {
+            self.boot_node_check = None;
+            return;
+        }
+
+        if let Some((next_check, boot_node_addrs)) = self.boot_node_check.as_mut() {
+            crit!(
+                self.log,
+                "Failed to connect to any boot node";
+                "addresses" => format!("{:?}", boot_node_addrs.iter().map(|a| format!("{}", a)).collect::<Vec<String>>()),
+            );
+            for multiaddr in boot_node_addrs.iter() {
+                if let Err(err) = Swarm::dial_addr(&mut self.swarm, multiaddr.clone()) {
+                    debug!(self.log, "Could not redial boot node"; "address" => format!("{}", multiaddr), "error" => format!("{:?}", err));
+                }
+            }
+            *next_check = Instant::now() + self.boot_node_grace_period;
+        }
+    }
+
+    /// Starts an on-demand peer search for the remainder if fewer than `target_peers` are
+    /// connected, and disconnects the worst of the excess peers if more than `max_peers` are
+    /// connected.
+    ///
+    /// At startup, this ends the startup ramp once `target_peers` are first connected.
+    fn maintain_peer_count(&mut self) {
+        let connected_peers = self.swarm.connected_peers();
+        if self.startup_ramp && connected_peers >= self.target_peers {
+            info!(self.log, "Startup peer target reached"; "peer_count" => connected_peers, "target" => self.target_peers);
+            self.startup_ramp = false;
+        }
+
+        if connected_peers < self.target_peers {
+            debug!(self.log, "Below peer target, searching for peers"; "peer_count" => connected_peers, "target" => self.target_peers);
+            self.discover_peers(self.target_peers - connected_peers);
+        } else if connected_peers > self.max_peers {
+            debug!(self.log, "Above peer limit, pruning peers"; "peer_count" => connected_peers, "max_peers" => self.max_peers);
+            for peer_id in self.peers_to_prune(connected_peers - self.max_peers) {
+                self.disconnect_peer(peer_id);
+            }
+        }
+    }
+
+    /// Returns `true` if the node has not yet connected to `target_peers` since starting up, and
+    /// is searching for peers every `startup_peer_check_interval`.
+    pub fn in_startup_ramp(&self) -> bool {
+        self.startup_ramp
+    }
+
+    /// Returns up to `count` connected peers to disconnect, worst first.
+    ///
+    /// Persistent peers are never pruned, and peers which dialed this node are pruned before those
+    /// which this node dialed.
+    fn peers_to_prune(&self, count: usize) -> Vec<PeerId> {
+        let mut candidates = self
+            .swarm
+            .discovery()
+            .connected_endpoints()
+            .iter()
+            .filter(|(peer_id, _)| !self.persistent_peers.contains_key(peer_id))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, endpoint)| match endpoint {
+            ConnectedPoint::Listener { .. } => 0,
+            ConnectedPoint::Dialer { .. } => 1,
+        });
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect()
+    }
+
+    /// Disconnects and bans a peer without waiting for any pending messages (e.g. a Goodbye) to be
+    /// flushed.
+    ///
+    /// Intended for peers which have sent clearly malicious data.
+    pub fn ban_peer_immediately(&mut self, peer_id: PeerId) {
+        // a later timed ban of the same peer is now redundant
+        self.peers_to_ban.retain(|(id, _)| *id != peer_id);
+        self.ban_peer(peer_id);
+    }
+
+    /// Bans the peer from the swarm and informs the behaviour of the disconnect.
+    fn ban_peer(&mut self, peer_id: PeerId) {
+        warn!(self.log, "Disconnecting and banning peer"; "peer_id" => format!("{:?}", peer_id));
+        // a banned peer is never redialed
+        self.persistent_peers.remove(&peer_id);
+        self.peers_to_redial.retain(|(id, _)| *id != peer_id);
+        Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
+        self.inject_disconnected(&peer_id);
+        // inform the behaviour that the peer has been banned
+        self.swarm.peer_banned(peer_id);
+    }
+
+    /// Disconnects a peer without banning it.
+    fn disconnect_peer(&mut self, peer_id: PeerId) {
+        debug!(self.log, "Disconnecting peer"; "peer_id" => format!("{:?}", peer_id));
+        // banning the peer closes its connection, which may then be re-established
+        Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
+        Swarm::unban_peer_id(&mut self.swarm, peer_id.clone());
+        self.inject_disconnected(&peer_id);
+    }
+
+    /// Informs the behaviour that a peer has been disconnected.
+    fn inject_disconnected(&mut self, peer_id: &PeerId) {
+        // TODO: Correctly notify protocols of the disconnect
+        // TODO: Also remove peer from the DHT: https://github.com/sigp/lighthouse/issues/629
+        let dummy_connected_point = ConnectedPoint::Dialer {
+            address: "/ip4/0.0.0.0"
+                .parse::<Multiaddr>()
+                .expect("valid multiaddr"),
+        };
+        self.swarm
+            .inject_disconnected(peer_id, dummy_connected_point);
+    }
 }
 
 impl Stream for Service {
@@ -177,6 +783,53 @@ impl Stream for Service {
     type Error = crate::error::Error;
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        let event = self.poll_swarm()?;
+
+        if let Async::Ready(Some(event)) = &event {
+            #[cfg(unix)]
+            {
+                if let Some(event_socket) = &mut self.event_socket {
+                    event_socket.send(event);
+                }
+            }
+            if let Some(event_recorder) = &mut self.event_recorder {
+                event_recorder.record(event);
+            }
+        }
+
+        Ok(event)
+    }
+}
+
+impl Drop for Service {
+    fn drop(&mut self) {
+        if !self.publish_queue.is_empty() {
+            warn!(
+                self.log,
+                "Unpublished gossip messages dropped";
+                "count" => self.publish_queue.len()
+            );
+        }
+    }
+}
+
+impl Service {
+    /// Polls the swarm and the service's timers, returning the next event.
+    fn poll_swarm(&mut self) -> Poll<Option<Libp2pEvent>, error::Error> {
+        self.task = Some(task::current());
+
+        // publish a batch of queued messages before polling the swarm, which sends them. Any
+        // remaining messages are published by the following polls, once pending events have been
+        // returned.
+        let batch_size = std::cmp::min(self.publish_queue.len(), PUBLISH_BATCH_SIZE);
+        for (topics, message) in self.publish_queue.drain(..batch_size) {
+            // the result was returned when the message was queued
+            let _ = self.swarm.publish(&topics, message);
+        }
+        if !self.publish_queue.is_empty() {
+            task::current().notify();
+        }
+
         loop {
             match self.swarm.poll() {
                 Ok(Async::Ready(Some(event))) => match event {
@@ -187,20 +840,65 @@ impl Stream for Service {
                         message,
                     } => {
                         trace!(self.log, "Gossipsub message received"; "service" => "Swarm");
-                        return Ok(Async::Ready(Some(Libp2pEvent::PubsubMessage {
+                        // queued until the swarm is not ready, so that blocks are returned first
+                        if !self.gossip_queue.push(Libp2pEvent::PubsubMessage {
                             id,
                             source,
                             topics,
                             message,
-                        })));
+                        }) {
+                            debug!(self.log, "Gossip queue full, dropped a message");
+                        }
                     }
                     BehaviourEvent::RPC(peer_id, event) => {
+                        // penalize peers which repeatedly accept an RPC request but stall on the
+                        // response. Other timeouts may be caused by our own load and are not
+                        // penalized.
+                        match &event {
+                            RPCEvent::Error(_, RPCError::ResponseTimeout) => {
+                                self.record_rpc_timeout(&peer_id)
+                            }
+                            RPCEvent::Response(..) => {
+                                self.rpc_timeouts.remove(&peer_id);
+                            }
+                            _ => {}
+                        }
+                        // and those which open too many concurrent requests. The rejected request
+                        // was never returned, so neither is the error.
+                        if let RPCEvent::Error(_, RPCError::TooManyRequests) = event {
+                            debug!(self.log, "Peer exceeded the concurrent RPC request limit"; "peer_id" => format!("{:?}", peer_id));
+                            if !self.is_banned(&peer_id) {
+                                self.disconnect_and_ban_peer(peer_id);
+                            }
+                            continue;
+                        }
+                        match &event {
+                            RPCEvent::Response(
+                                id,
+                                RPCErrorResponse::Success(RPCResponse::Status(status)),
+                            ) => {
+                                if self.status_requests.remove(&(peer_id.clone(), *id)) {
+                                    return Ok(Async::Ready(Some(Libp2pEvent::StatusResponse {
+                                        peer_id,
+                                        status: status.clone(),
+                                    })));
+                                }
+                            }
+                            RPCEvent::Error(id, _) => {
+                                self.status_requests.remove(&(peer_id.clone(), *id));
+                            }
+                            _ => {}
+                        }
                         return Ok(Async::Ready(Some(Libp2pEvent::RPC(peer_id, event))));
                     }
                     BehaviourEvent::PeerDialed(peer_id) => {
                         return Ok(Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))));
                     }
                     BehaviourEvent::PeerDisconnected(peer_id) => {
+                        self.status_requests.retain(|(id, _)| *id != peer_id);
+                        self.rpc_timeouts.remove(&peer_id);
+                        self.gossip_rejections.remove(&peer_id);
+                        self.schedule_redial(&peer_id);
                         return Ok(Async::Ready(Some(Libp2pEvent::PeerDisconnected(peer_id))));
                     }
                     BehaviourEvent::PeerSubscribed(peer_id, topic) => {
@@ -208,6 +906,9 @@ impl Stream for Service {
                             peer_id, topic,
                         ))));
                     }
+                    BehaviourEvent::GossipRejected(peer_id) => {
+                        self.record_gossip_rejection(peer_id);
+                    }
                 },
                 Ok(Async::Ready(None)) => unreachable!("Swarm stream shouldn't end"),
                 Ok(Async::NotReady) => break,
@@ -215,74 +916,144 @@ impl Stream for Service {
             }
         }
         // swarm is not ready
-        // check to see if the address is different to the config. If so, update our ENR
-        if !self.verified_listen_address {
-            let multiaddr = Swarm::listeners(&self.swarm).next();
-            if let Some(multiaddr) = multiaddr {
-                self.verified_listen_address = true;
-                if let Some(socket_addr) = multiaddr_to_socket_addr(multiaddr) {
-                    self.swarm.update_local_enr_socket(socket_addr, true);
-                }
-            }
+        // once the grace period has elapsed, update our ENR with the best listening address
+        while let Some(Ok(Async::Ready(()))) =
+            self.enr_address_check.as_mut().map(|check| check.poll())
+        {
+            self.verify_listen_address();
+        }
+
+        // disconnect inbound peers which exceed the inbound peer limit
+        for peer_id in self.swarm.take_excess_inbound_peers() {
+            self.disconnect_peer(peer_id);
         }
 
         // check if there are peers to ban
         while !self.peers_to_ban.is_empty() {
             if self.peers_to_ban[0].1 < Instant::now() {
                 let (peer_id, _) = self.peers_to_ban.remove(0);
-                warn!(self.log, "Disconnecting and banning peer"; "peer_id" => format!("{:?}", peer_id));
-                Swarm::ban_peer_id(&mut self.swarm, peer_id.clone());
-                // TODO: Correctly notify protocols of the disconnect
-                // TODO: Also remove peer from the DHT: https://github.com/sigp/lighthouse/issues/629
-                let dummy_connected_point = ConnectedPoint::Dialer {
-                    address: "/ip4/0.0.0.0"
-                        .parse::<Multiaddr>()
-                        .expect("valid multiaddr"),
-                };
-                self.swarm
-                    .inject_disconnected(&peer_id, dummy_connected_point);
-                // inform the behaviour that the peer has been banned
-                self.swarm.peer_banned(peer_id);
+                self.ban_peer(peer_id);
             } else {
                 break;
             }
         }
 
-        Ok(Async::NotReady)
+        // check if there are persistent peers to redial
+        while !self.peers_to_redial.is_empty() {
+            if self.peers_to_redial[0].1 < Instant::now() {
+                let (peer_id, _) = self.peers_to_redial.remove(0);
+                self.redial_peer(&peer_id);
+            } else {
+                break;
+            }
+        }
+
+        // check that at least one of the boot nodes could be connected to
+        if let Some((next_check, _)) = &self.boot_node_check {
+            if *next_check < Instant::now() {
+                self.check_boot_node_connections();
+            }
+        }
+
+        // keep the connected peer count between the peer target and limit
+        while let Ok(Async::Ready(())) = self.peer_check.poll() {
+            self.maintain_peer_count();
+            let interval = if self.startup_ramp {
+                self.startup_peer_check_interval
+            } else {
+                self.peer_check_interval
+            };
+            self.peer_check.reset(Instant::now() + interval);
+        }
+
+        match self.gossip_queue.pop() {
+            Some(event) => Ok(Async::Ready(Some(event))),
+            None => Ok(Async::NotReady),
+        }
+    }
+}
+
+/// Removes a trailing `/p2p/<peer-id>` component from `multiaddr`, returning the remaining
+/// (dialable) address and the peer id, if there was one.
+fn split_peer_id(mut multiaddr: Multiaddr) -> (Multiaddr, Option<PeerId>) {
+    match multiaddr.pop() {
+        Some(Protocol::P2p(multihash)) => (multiaddr, PeerId::from_multihash(multihash).ok()),
+        Some(protocol) => {
+            multiaddr.push(protocol);
+            (multiaddr, None)
+        }
+        None => (multiaddr, None),
     }
 }
 
+/// Returns `true` if `multiaddr` contains a UDP component (e.g., the discovery address of an ENR).
+///
+/// The address may be IP or DNS based (`/dns4`, `/dns6`), so no fixed shape is assumed.
+fn is_udp_multiaddr(multiaddr: &Multiaddr) -> bool {
+    multiaddr.iter().any(|protocol| match protocol {
+        Protocol::Udp(_) => true,
+        _ => false,
+    })
+}
+
 /// Converts a multiaddr to a `SocketAddr` if the multiaddr has the TCP/IP form. Libp2p currently
 /// only supports TCP, so the UDP case is currently ignored.
+///
+/// DNS based multiaddrs are resolved by the transport when dialing and return `None`.
 fn multiaddr_to_socket_addr(multiaddr: &Multiaddr) -> Option<std::net::SocketAddr> {
-    let protocols = multiaddr.iter().collect::<Vec<_>>();
+    let mut protocols = multiaddr.iter();
     // assume the IP protocol
-    match protocols[0] {
-        Protocol::Ip4(address) => {
-            if let Protocol::Tcp(port) = protocols[1] {
-                Some(std::net::SocketAddr::new(address.into(), port))
-            } else {
-                None
-            }
+    let address: std::net::IpAddr = match protocols.next()? {
+        Protocol::Ip4(address) => address.into(),
+        Protocol::Ip6(address) => address.into(),
+        _ => return None,
+    };
+    match protocols.next()? {
+        Protocol::Tcp(port) => Some(std::net::SocketAddr::new(address, port)),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `ip` is publicly routable, i.e., it is not a private, shared (carrier-grade
+/// NAT), loopback, link-local or unspecified address.
+fn is_public_ip(ip: &std::net::IpAddr) -> bool {
+    match ip {
+        std::net::IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            // shared address space (100.64.0.0/10)
+            let shared = octets[0] == 100 && octets[1] & 0xc0 == 64;
+            !(ip.is_private()
+                || shared
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified())
         }
-        Protocol::Ip6(address) => {
-            if let Protocol::Tcp(port) = protocols[1] {
-                Some(std::net::SocketAddr::new(address.into(), port))
-            } else {
-                None
-            }
+        std::net::IpAddr::V6(ip) => {
+            let prefix = ip.segments()[0];
+            // unique local (fc00::/7) and link-local (fe80::/10) addresses
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || prefix & 0xfe00 == 0xfc00
+                || prefix & 0xffc0 == 0xfe80)
         }
-        _ => None,
     }
 }
 
 /// The implementation supports TCP/IP, WebSockets over TCP/IP, secio as the encryption layer, and
 /// mplex or yamux as the multiplexing layer.
-fn build_transport(local_private_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox), Error> {
+///
+/// The TCP connection of a dial must be made within `config.connect_timeout`, and the whole
+/// connection (including the upgrades) established within `config.handshake_timeout`.
+fn build_transport(
+    local_private_key: Keypair,
+    config: &NetworkConfig,
+) -> Boxed<(PeerId, StreamMuxerBox), Error> {
     // TODO: The Wire protocol currently doesn't specify encryption and this will need to be customised
     // in the future.
-    let transport = libp2p::tcp::TcpConfig::new().nodelay(true);
-    let transport = libp2p::dns::DnsConfig::new(transport);
+    // Note: On Unix the listener is bound with `SO_REUSEADDR` (by `mio`), so the port can be bound
+    // again straight after a restart. `TcpConfig` does not expose `SO_REUSEPORT`.
+    let transport = libp2p::tcp::TcpConfig::new().nodelay(config.tcp_nodelay);
+    let transport = libp2p::dns::DnsConfig::new(transport).outbound_timeout(config.connect_timeout);
     #[cfg(feature = "libp2p-websocket")]
     let transport = {
         let trans_clone = transport.clone();
@@ -296,9 +1067,8 @@ fn build_transport(local_private_key: Keypair) -> Boxed<(PeerId, StreamMuxerBox)
             libp2p::mplex::MplexConfig::new(),
         ))
         .map(|(peer, muxer), _| (peer, core::muxing::StreamMuxerBox::new(muxer)))
-        .timeout(Duration::from_secs(20))
-        .timeout(Duration::from_secs(20))
-        .map_err(|err| Error::new(ErrorKind::Other, err))
+        .timeout(config.handshake_timeout)
+        .map_err(|err| Error::new(std::io::ErrorKind::Other, err))
         .boxed()
 }
 
@@ -319,34 +1089,140 @@ pub enum Libp2pEvent {
     },
     /// Subscribed to peer for a topic hash.
     PeerSubscribed(PeerId, TopicHash),
+    /// A peer responded to a request made by `Service::send_status_request`.
+    StatusResponse {
+        peer_id: PeerId,
+        status: StatusMessage,
+    },
 }
 
-fn keypair_from_hex(hex_bytes: &str) -> error::Result<Keypair> {
-    let hex_bytes = if hex_bytes.starts_with("0x") {
-        hex_bytes[2..].to_string()
+/// Which side of the connection to a peer initiated it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum PeerDirection {
+    /// This node dialed the peer.
+    Dialer,
+    /// The peer dialed this node.
+    Listener,
+}
+
+/// A connected peer and its connection, as reported by `Service::peers_info`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeerInfo {
+    /// The base58 encoded `PeerId` of the peer.
+    pub peer_id: String,
+    /// The address of the peer on the connection.
+    pub multiaddr: String,
+    /// Which side of the connection dialed.
+    pub direction: PeerDirection,
+    /// The score of the peer, if peer scoring is enabled. Peer scoring is not yet implemented so
+    /// this is always `None`.
+    pub score: Option<f64>,
+}
+
+impl PeerInfo {
+    fn new(peer_id: &PeerId, endpoint: &ConnectedPoint) -> Self {
+        let (multiaddr, direction) = match endpoint {
+            ConnectedPoint::Dialer { address } => (address, PeerDirection::Dialer),
+            ConnectedPoint::Listener { send_back_addr, .. } => {
+                (send_back_addr, PeerDirection::Listener)
+            }
+        };
+
+        PeerInfo {
+            peer_id: peer_id.to_base58(),
+            multiaddr: multiaddr.to_string(),
+            direction,
+            score: None,
+        }
+    }
+}
+
+/// Decodes ASCII-encoded hex bytes, with or without a `0x` prefix.
+fn decode_hex(hex_bytes: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    if hex_bytes.starts_with("0x") {
+        hex::decode(&hex_bytes[2..])
     } else {
-        hex_bytes.to_string()
-    };
+        hex::decode(hex_bytes)
+    }
+}
 
-    hex::decode(&hex_bytes)
-        .map_err(|e| format!("Failed to parse p2p secret key bytes: {:?}", e).into())
+fn keypair_from_hex(hex_bytes: &str) -> error::Result<Keypair> {
+    decode_hex(hex_bytes)
+        .map_err(|e| {
+            ErrorKind::KeyParse(format!("Failed to parse p2p secret key bytes: {:?}", e)).into()
+        })
         .and_then(keypair_from_bytes)
 }
 
+/// Deterministically derives a secp256k1 keypair from `seed`.
+///
+/// The seed is hashed to 32 bytes, which are used as the secret key. In the (negligibly unlikely)
+/// case that the hash is not a valid secret key, it is hashed again until it is.
+///
+/// Intended for test networks which require known node identities, a seed must not be shared
+/// between nodes.
+pub fn keypair_from_seed(seed: &[u8]) -> Keypair {
+    let mut secret = eth2_hashing::hash(seed);
+    loop {
+        if let Ok(keypair) = keypair_from_bytes(secret.clone()) {
+            return keypair;
+        }
+        secret = eth2_hashing::hash(&secret);
+    }
+}
+
 fn keypair_from_bytes(mut bytes: Vec<u8>) -> error::Result<Keypair> {
     libp2p::core::identity::secp256k1::SecretKey::from_bytes(&mut bytes)
         .map(|secret| {
             let keypair: libp2p::core::identity::secp256k1::Keypair = secret.into();
             Keypair::Secp256k1(keypair)
         })
-        .map_err(|e| format!("Unable to parse p2p secret key: {:?}", e).into())
+        .map_err(|e| ErrorKind::KeyParse(format!("Unable to parse p2p secret key: {:?}", e)).into())
+}
+
+/// Returns the keypair given by the `secret_key_hex` or `p2p_seed_hex` of `config`, if any.
+fn keypair_from_config(config: &NetworkConfig) -> error::Result<Option<Keypair>> {
+    if let Some(hex_bytes) = &config.secret_key_hex {
+        keypair_from_hex(hex_bytes).map(Some)
+    } else if let Some(hex_bytes) = &config.p2p_seed_hex {
+        decode_hex(hex_bytes)
+            .map(|seed| Some(keypair_from_seed(&seed)))
+            .map_err(|e| {
+                ErrorKind::KeyParse(format!("Failed to parse p2p seed bytes: {:?}", e)).into()
+            })
+    } else {
+        Ok(None)
+    }
+}
+
+/// Returns the keypair a `Service` started with `config` would use, without generating or writing
+/// a key.
+///
+/// Returns an error if `config` gives no key and there is no valid key on disk.
+pub fn read_private_key(config: &NetworkConfig) -> error::Result<Keypair> {
+    if let Some(keypair) = keypair_from_config(config)? {
+        return Ok(keypair);
+    }
+
+    let network_key_f = config.network_dir.join(NETWORK_KEY_FILENAME);
+    let key_bytes = std::fs::read(&network_key_f).map_err(|e| {
+        ErrorKind::KeyParse(format!(
+            "Unable to read network key {:?}: {}",
+            network_key_f, e
+        ))
+    })?;
+    if let Some(keystore) = Keystore::from_json_bytes(&key_bytes) {
+        decrypt_keystore(&keystore, config)
+    } else {
+        keypair_from_bytes(key_bytes)
+    }
 }
 
 /// Loads a private key from disk. If this fails, a new key is
 /// generated and is then saved to disk.
 ///
 /// Currently only secp256k1 keys are allowed, as these are the only keys supported by discv5.
-fn load_private_key(config: &NetworkConfig, log: &slog::Logger) -> Keypair {
+pub fn load_private_key(config: &NetworkConfig, log: &slog::Logger) -> error::Result<Keypair> {
     // TODO: Currently using secp256k1 keypairs - currently required for discv5
     // check for key from disk
     let network_key_f = config.network_dir.join(NETWORK_KEY_FILENAME);
@@ -355,15 +1231,39 @@ fn load_private_key(config: &NetworkConfig, log: &slog::Logger) -> Keypair {
         match network_key_file.read_to_end(&mut key_bytes) {
             Err(_) => debug!(log, "Could not read network key file"),
             Ok(_) => {
+                if let Some(keystore) = Keystore::from_json_bytes(&key_bytes) {
+                    // A keystore that can't be decrypted must never be replaced by a new key.
+                    return decrypt_keystore(&keystore, config).map(|keypair| {
+                        debug!(log, "Loaded network key from keystore on disk.");
+                        keypair
+                    });
+                }
+
                 // only accept secp256k1 keys for now
                 if let Ok(secret_key) =
                     libp2p::core::identity::secp256k1::SecretKey::from_bytes(&mut key_bytes)
                 {
                     let kp: libp2p::core::identity::secp256k1::Keypair = secret_key.into();
                     debug!(log, "Loaded network key from disk.");
-                    return Keypair::Secp256k1(kp);
+                    return Ok(Keypair::Secp256k1(kp));
+                } else if config.force_regenerate_key {
+                    warn!(
+                        log,
+                        "Network key file is not a valid secp256k1 key, regenerating";
+                        "file" => format!("{:?}", network_key_f),
+                    );
                 } else {
-                    debug!(log, "Network key file is not a valid secp256k1 key");
+                    // A corrupt key must not silently change the identity of this node.
+                    crit!(
+                        log,
+                        "Network key file is not a valid secp256k1 key";
+                        "file" => format!("{:?}", network_key_f),
+                        "help" => "use --force-regenerate-key to replace it with a new key",
+                    );
+                    return Err(ErrorKind::KeyParse(
+                        "Network key file is not a valid secp256k1 key".into(),
+                    )
+                    .into());
                 }
             }
         }
@@ -387,5 +1287,146 @@ fn load_private_key(config: &NetworkConfig, log: &slog::Logger) -> Keypair {
             }
         }
     }
-    local_private_key
+    Ok(local_private_key)
+}
+
+/// Replaces the private key in `network_dir` with a newly generated secp256k1 key, after copying
+/// the existing key to `NETWORK_KEY_BACKUP_FILENAME`. Returns the peer ids of the previous and the
+/// new key.
+///
+/// A running node keeps the identity it was started with, it must be restarted to adopt the new
+/// key. Keys stored in a keystore are not rotated, and an existing backup is never overwritten.
+pub fn rotate_private_key(network_dir: &std::path::Path) -> error::Result<(PeerId, PeerId)> {
+    let key_path = network_dir.join(NETWORK_KEY_FILENAME);
+    let backup_path = network_dir.join(NETWORK_KEY_BACKUP_FILENAME);
+
+    let key_bytes = std::fs::read(&key_path).map_err(|e| {
+        ErrorKind::KeyRotation(format!("Unable to read network key {:?}: {}", key_path, e))
+    })?;
+    if Keystore::from_json_bytes(&key_bytes).is_some() {
+        return Err(ErrorKind::KeyRotation(
+            "Network keys stored in a keystore cannot be rotated".into(),
+        )
+        .into());
+    }
+    let previous_key = keypair_from_bytes(key_bytes)?;
+
+    if backup_path.exists() {
+        return Err(ErrorKind::KeyRotation(format!(
+            "A network key backup already exists at {:?}, remove it to rotate the key again",
+            backup_path
+        ))
+        .into());
+    }
+    std::fs::copy(&key_path, &backup_path).map_err(|e| {
+        ErrorKind::KeyRotation(format!(
+            "Unable to back up network key to {:?}: {}",
+            backup_path, e
+        ))
+    })?;
+
+    // the new key replaces the existing key only once it is fully written, so that a partial
+    // write cannot leave the node without a valid key
+    let new_key = Keypair::generate_secp256k1();
+    if let Keypair::Secp256k1(key) = &new_key {
+        let tmp_path = key_path.with_extension("tmp");
+        File::create(&tmp_path)
+            .and_then(|mut f| {
+                f.write_all(&key.secret().to_bytes())?;
+                f.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&tmp_path, &key_path))
+            .map_err(|e| {
+                ErrorKind::KeyRotation(format!("Unable to write network key {:?}: {}", key_path, e))
+            })?;
+    }
+
+    Ok((
+        PeerId::from(previous_key.public()),
+        PeerId::from(new_key.public()),
+    ))
+}
+
+/// Decrypts a network key stored in a JSON keystore with the password in the
+/// `p2p_key_password_file` of `config`.
+fn decrypt_keystore(keystore: &Keystore, config: &NetworkConfig) -> error::Result<Keypair> {
+    let password_file = config.p2p_key_password_file.as_ref().ok_or_else(|| {
+        ErrorKind::KeystoreDecrypt(
+            "Network key is an encrypted keystore, a password file must be provided".into(),
+        )
+    })?;
+    let password = std::fs::read_to_string(password_file).map_err(|e| {
+        ErrorKind::KeystoreDecrypt(format!("Unable to read network key password file: {}", e))
+    })?;
+
+    let mut secret_bytes = keystore
+        .decrypt(
+            password
+                .trim_end_matches(|c| c == '\n' || c == '\r')
+                .as_bytes(),
+        )
+        .map_err(|e| {
+            ErrorKind::KeystoreDecrypt(format!("Unable to decrypt network key keystore: {}", e))
+        })?;
+    let secret_key = libp2p::core::identity::secp256k1::SecretKey::from_bytes(&mut secret_bytes)
+        .map_err(|_| {
+            ErrorKind::KeystoreDecrypt(
+                "Network key keystore does not contain a valid secp256k1 key".into(),
+            )
+        })?;
+
+    Ok(Keypair::Secp256k1(secret_key.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    // A dial to a peer which accepts the TCP connection but never answers the handshake fails
+    // with a timeout once the handshake timeout, which is longer than the connect timeout, has
+    // elapsed.
+    #[test]
+    fn stalled_handshake_times_out() {
+        let mut config = NetworkConfig::default();
+        config.connect_timeout = Duration::from_millis(500);
+        config.handshake_timeout = Duration::from_millis(1500);
+
+        // accept connections and hold them open without responding
+        let listener = TcpListener::bind("127.0.0.1:0").expect("should bind listener");
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let _streams: Vec<_> = listener.incoming().collect();
+        });
+
+        let transport = build_transport(Keypair::generate_secp256k1(), &config);
+        let dial = transport
+            .dial(format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap())
+            .unwrap();
+
+        let start = Instant::now();
+        let result = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let thread_result = result.clone();
+        tokio::run(dial.then(move |dial_result| {
+            *thread_result.lock().unwrap() = Some((dial_result.err(), start.elapsed()));
+            Ok(())
+        }));
+
+        let (error, elapsed) = result.lock().unwrap().take().unwrap();
+        let error = error.expect("dial should fail");
+        assert!(format!("{}", error).contains("Timeout"), "{}", error);
+        assert!(elapsed >= config.handshake_timeout);
+        assert!(elapsed < config.handshake_timeout + Duration::from_secs(2));
+    }
+
+    #[test]
+    fn shared_addresses_are_not_public() {
+        let ip = |ip: &str| ip.parse::<std::net::IpAddr>().unwrap();
+
+        assert!(is_public_ip(&ip("203.0.113.7")));
+        assert!(is_public_ip(&ip("100.128.0.1")));
+        assert!(!is_public_ip(&ip("100.64.0.1")));
+        assert!(!is_public_ip(&ip("100.127.255.254")));
+        assert!(!is_public_ip(&ip("10.0.0.1")));
+    }
 }
@@ -0,0 +1,151 @@
+//! Helpers for testing networks of in-process `Service` instances.
+use crate::{boot_node_multiaddr, NetworkConfig, Service};
+use futures::prelude::*;
+use libp2p::Swarm;
+use slog::{debug, Logger};
+use std::net::{TcpListener, UdpSocket};
+use std::time::Duration;
+use tokio::prelude::FutureExt;
+
+/// The time allowed for all the services built by `build_connected_swarms` to connect.
+pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The shape of the connections between the services built by `build_swarms`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Topology {
+    /// Each service dials the next, forming a line.
+    Linear,
+    /// Each service dials every other service.
+    FullMesh,
+}
+
+/// Returns a port which is unused for both TCP and UDP on localhost.
+///
+/// The port is not reserved, so it is possible for it to be taken before it is used.
+pub fn unused_port() -> Result<u16, String> {
+    loop {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map(|addr| addr.port())
+            .map_err(|e| format!("Failed to find an unused TCP port: {:?}", e))?;
+
+        if UdpSocket::bind(("127.0.0.1", port)).is_ok() {
+            return Ok(port);
+        }
+    }
+}
+
+/// Returns a config for a service listening on `port` (for both libp2p and discovery), with
+/// shortened gossipsub heartbeats.
+///
+/// The network directory is an empty directory under the system temporary directory, unique to
+/// this process and `port`, so no network key or ENR is shared with other runs.
+pub fn build_config(port: u16) -> NetworkConfig {
+    let mut config = NetworkConfig::default();
+    config.libp2p_port = port;
+    config.discovery_port = port;
+    config.network_dir = std::env::temp_dir().join(format!(
+        "lighthouse_network_{}_{}",
+        std::process::id(),
+        port
+    ));
+    // clear the directory of any previous service built on `port` by this process
+    let _ = std::fs::remove_dir_all(&config.network_dir);
+    config.gs_config.heartbeat_initial_delay = Duration::from_millis(500);
+    config.gs_config.heartbeat_interval = Duration::from_millis(500);
+    config
+}
+
+/// Builds `n` services on unused ports and dials them into the given `topology`.
+///
+/// The returned services are not yet connected, their `poll` loops must be driven first (see
+/// `build_connected_swarms`).
+pub fn build_swarms(n: usize, topology: Topology, log: &Logger) -> Result<Vec<Service>, String> {
+    let mut nodes = vec![];
+    for _ in 0..n {
+        nodes.push(
+            Service::new(build_config(unused_port()?), log.clone())
+                .map_err(|e| format!("Failed to build service: {:?}", e))?,
+        );
+    }
+
+    dial_topology(&mut nodes, topology, log)?;
+    Ok(nodes)
+}
+
+/// Dials the `nodes` into the given `topology`, using the TCP address of their ENRs.
+pub fn dial_topology(
+    nodes: &mut [Service],
+    topology: Topology,
+    log: &Logger,
+) -> Result<(), String> {
+    let multiaddrs = nodes
+        .iter()
+        .map(|node| {
+            boot_node_multiaddr(node.swarm.discovery().local_enr())
+                .ok_or_else(|| "Test service has no TCP address in its ENR".to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let n = nodes.len();
+    for (i, node) in nodes.iter_mut().enumerate() {
+        let peers = match topology {
+            Topology::Linear => i + 1..std::cmp::min(i + 2, n),
+            Topology::FullMesh => i + 1..n,
+        };
+        for multiaddr in &multiaddrs[peers] {
+            Swarm::dial_addr(&mut node.swarm, multiaddr.clone())
+                .map_err(|e| format!("Failed to dial {}: {:?}", multiaddr, e))?;
+            debug!(log, "Dialed test service"; "index" => i, "address" => format!("{}", multiaddr));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a future which builds `n` services in a `Topology::Linear` topology and drives their
+/// `poll` loops until every service has at least one connected peer.
+///
+/// Any events produced by the services whilst connecting are discarded. The future must be run on
+/// the runtime which will continue to drive the services (e.g., within `tokio::run`).
+pub fn build_connected_swarms(
+    n: usize,
+    log: &Logger,
+) -> impl Future<Item = Vec<Service>, Error = String> {
+    let mut nodes = build_swarms(n, Topology::Linear, log).map(Some);
+
+    future::poll_fn(move || -> Poll<Vec<Service>, String> {
+        let services = match &mut nodes {
+            Ok(Some(services)) => services,
+            Ok(None) => panic!("polled after connecting"),
+            Err(e) => return Err(e.clone()),
+        };
+
+        for service in services.iter_mut() {
+            while let Async::Ready(Some(_)) = service
+                .poll()
+                .map_err(|e| format!("Service failed whilst connecting: {:?}", e))?
+            {}
+        }
+
+        if services
+            .iter()
+            .all(|service| service.swarm.connected_peers() >= 1)
+        {
+            Ok(Async::Ready(
+                nodes
+                    .as_mut()
+                    .ok()
+                    .and_then(Option::take)
+                    .expect("services are present until connected"),
+            ))
+        } else {
+            Ok(Async::NotReady)
+        }
+    })
+    .timeout(CONNECTION_TIMEOUT)
+    .map_err(|e| match e.into_inner() {
+        Some(e) => e,
+        None => "Timed out waiting for services to connect".to_string(),
+    })
+}
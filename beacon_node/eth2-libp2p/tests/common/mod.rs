@@ -1,10 +1,9 @@
 #![cfg(test)]
 use enr::Enr;
-use eth2_libp2p::Multiaddr;
+use eth2_libp2p::test_utils::{self, Topology};
 use eth2_libp2p::NetworkConfig;
 use eth2_libp2p::Service as LibP2PService;
 use slog::{debug, error, o, Drain};
-use std::time::Duration;
 
 pub fn build_log(level: slog::Level, enabled: bool) -> slog::Logger {
     let decorator = slog_term::TermDecorator::new().build();
@@ -23,15 +22,9 @@ pub fn build_config(
     mut boot_nodes: Vec<Enr>,
     secret_key: Option<String>,
 ) -> NetworkConfig {
-    let mut config = NetworkConfig::default();
-    config.libp2p_port = port; // tcp port
-    config.discovery_port = port; // udp port
+    let mut config = test_utils::build_config(port);
     config.boot_nodes.append(&mut boot_nodes);
     config.secret_key_hex = secret_key;
-    config.network_dir.push(port.to_string());
-    // Reduce gossipsub heartbeat parameters
-    config.gs_config.heartbeat_initial_delay = Duration::from_millis(500);
-    config.gs_config.heartbeat_interval = Duration::from_millis(500);
     config
 }
 
@@ -55,26 +48,7 @@ pub fn get_enr(node: &LibP2PService) -> Enr {
 // Returns `n` libp2p peers in fully connected topology.
 #[allow(dead_code)]
 pub fn build_full_mesh(log: slog::Logger, n: usize, start_port: Option<u16>) -> Vec<LibP2PService> {
-    let base_port = start_port.unwrap_or(9000);
-    let mut nodes: Vec<LibP2PService> = (base_port..base_port + n as u16)
-        .map(|p| build_libp2p_instance(p, vec![], None, log.clone()))
-        .collect();
-    let multiaddrs: Vec<Multiaddr> = nodes
-        .iter()
-        .map(|x| get_enr(&x).multiaddr()[1].clone())
-        .collect();
-
-    for i in 0..n {
-        for j in i..n {
-            if i != j {
-                match libp2p::Swarm::dial_addr(&mut nodes[i].swarm, multiaddrs[j].clone()) {
-                    Ok(()) => debug!(log, "Connected"),
-                    Err(_) => error!(log, "Failed to connect"),
-                };
-            }
-        }
-    }
-    nodes
+    build_topology(log, n, start_port, Topology::FullMesh)
 }
 
 // Constructs a pair of nodes with seperate loggers. The sender dials the receiver.
@@ -98,19 +72,20 @@ pub fn build_node_pair(log: &slog::Logger, start_port: u16) -> (LibP2PService, L
 // Returns `n` peers in a linear topology
 #[allow(dead_code)]
 pub fn build_linear(log: slog::Logger, n: usize, start_port: Option<u16>) -> Vec<LibP2PService> {
+    build_topology(log, n, start_port, Topology::Linear)
+}
+
+#[allow(dead_code)]
+fn build_topology(
+    log: slog::Logger,
+    n: usize,
+    start_port: Option<u16>,
+    topology: Topology,
+) -> Vec<LibP2PService> {
     let base_port = start_port.unwrap_or(9000);
     let mut nodes: Vec<LibP2PService> = (base_port..base_port + n as u16)
         .map(|p| build_libp2p_instance(p, vec![], None, log.clone()))
         .collect();
-    let multiaddrs: Vec<Multiaddr> = nodes
-        .iter()
-        .map(|x| get_enr(&x).multiaddr()[1].clone())
-        .collect();
-    for i in 0..n - 1 {
-        match libp2p::Swarm::dial_addr(&mut nodes[i].swarm, multiaddrs[i + 1].clone()) {
-            Ok(()) => debug!(log, "Connected"),
-            Err(_) => error!(log, "Failed to connect"),
-        };
-    }
+    test_utils::dial_topology(&mut nodes, topology, &log).expect("should dial test services");
     nodes
 }
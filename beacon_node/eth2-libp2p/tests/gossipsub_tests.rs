@@ -2,6 +2,9 @@
 use eth2_libp2p::*;
 use futures::prelude::*;
 use slog::{debug, Level};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
 
 mod common;
 
@@ -61,10 +64,11 @@ fn test_gossipsub_forward() {
                             subscribed_count += 1;
                             // Every node except the corner nodes are connected to 2 nodes.
                             if subscribed_count == (num_nodes * 2) - 2 {
-                                node.swarm.publish(
+                                node.publish(
                                     &vec![Topic::new(topic.into_string())],
                                     pubsub_message.clone(),
-                                );
+                                )
+                                .unwrap();
                             }
                         }
                     }
@@ -123,16 +127,788 @@ fn test_gossipsub_full_mesh_publish() {
                     if topic == TopicHash::from_raw("/eth2/beacon_block/ssz") {
                         subscribed_count += 1;
                         if subscribed_count == num_nodes - 1 {
-                            publishing_node.swarm.publish(
-                                &vec![Topic::new(topic.into_string())],
-                                pubsub_message.clone(),
+                            publishing_node
+                                .publish(
+                                    &vec![Topic::new(topic.into_string())],
+                                    pubsub_message.clone(),
+                                )
+                                .unwrap();
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok(Async::NotReady)
+    }))
+}
+
+// Test that inbound messages are accepted or rejected based on the size limit of their topic.
+#[test]
+fn test_gossip_max_size_per_topic() {
+    let config = NetworkConfig::default();
+    let limits = &config.gossip_max_size_per_topic;
+
+    let block_topic = TopicHash::from_raw("/eth2/beacon_block/ssz");
+    let attestation_topic = TopicHash::from_raw("/eth2/beacon_attestation/ssz");
+
+    // A message that is too large to be an attestation, but small enough to be a block.
+    let size = limits[BEACON_ATTESTATION_TOPIC] + 1;
+    assert!(size <= limits[BEACON_BLOCK_TOPIC]);
+
+    assert_eq!(
+        behaviour::gossip_size_acceptance(limits, &[attestation_topic.clone()], size),
+        MessageAcceptance::Reject
+    );
+    assert_eq!(
+        behaviour::gossip_size_acceptance(limits, &[block_topic.clone()], size),
+        MessageAcceptance::Accept
+    );
+
+    // The strictest limit applies to messages that are sent on multiple topics.
+    assert_eq!(
+        behaviour::gossip_size_acceptance(limits, &[block_topic, attestation_topic], size),
+        MessageAcceptance::Reject
+    );
+
+    // Topics without a specific limit are only bound by the global limit.
+    let unknown_topic = TopicHash::from_raw("/eth2/unknown/ssz");
+    assert_eq!(
+        behaviour::gossip_size_acceptance(limits, &[unknown_topic.clone()], GOSSIP_MAX_SIZE),
+        MessageAcceptance::Accept
+    );
+    assert_eq!(
+        behaviour::gossip_size_acceptance(limits, &[unknown_topic], GOSSIP_MAX_SIZE + 1),
+        MessageAcceptance::Reject
+    );
+}
+
+// Test that a peer which relays oversized messages is only banned once `MAX_GOSSIP_REJECTIONS` of
+// its messages have been rejected.
+#[test]
+fn test_gossipsub_rejections_ban_peer() {
+    use tokio::prelude::FutureExt;
+
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let mut publisher = common::build_libp2p_instance(19180, vec![], None, log.clone());
+    let publisher_id = publisher.local_peer_id.clone();
+    // the receiver rejects blocks larger than 8 bytes
+    let mut receiver_config = common::build_config(19181, vec![], None);
+    receiver_config
+        .gossip_max_size_per_topic
+        .insert(BEACON_BLOCK_TOPIC.to_string(), 8);
+    let mut receiver = Service::new(receiver_config, log.clone()).unwrap();
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut publisher.swarm, receiver_multiaddr).unwrap();
+
+    let publishing_topic = Topic::new("/eth2/beacon_block/ssz".into());
+    // distinct contents, so that the messages are not dropped as duplicates
+    let oversized_message = |i: u32| PubsubMessage::Block(vec![i as u8; 16]);
+    let mut published = false;
+    let mut received = false;
+    let test_result = Arc::new(Mutex::new(false));
+    let thread_result = test_result.clone();
+    tokio::run(
+        futures::future::poll_fn(move || -> Poll<bool, ()> {
+            loop {
+                match publisher.poll().unwrap() {
+                    Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, topic))) => {
+                        if !published && topic == publishing_topic.no_hash() {
+                            // all but one of the rejections, followed by a valid message
+                            for i in 1..MAX_GOSSIP_REJECTIONS {
+                                publisher
+                                    .publish(&[publishing_topic.clone()], oversized_message(i))
+                                    .unwrap();
+                            }
+                            publisher
+                                .publish(
+                                    &[publishing_topic.clone()],
+                                    PubsubMessage::Block(vec![0; 4]),
+                                )
+                                .unwrap();
+                            published = true;
+                        }
+                    }
+                    Async::Ready(Some(_)) => {}
+                    _ => break,
+                }
+            }
+            loop {
+                match receiver.poll().unwrap() {
+                    Async::Ready(Some(Libp2pEvent::PubsubMessage { source, .. })) => {
+                        // the earlier messages were rejected without banning the publisher
+                        assert!(!received);
+                        assert!(!receiver.is_banned(&source));
+                        received = true;
+                        publisher
+                            .publish(&[publishing_topic.clone()], oversized_message(0))
+                            .unwrap();
+                    }
+                    Async::Ready(Some(Libp2pEvent::PeerDisconnected(peer_id))) => {
+                        return Ok(Async::Ready(
+                            received && peer_id == publisher_id && receiver.is_banned(&peer_id),
+                        ));
+                    }
+                    Async::Ready(Some(_)) => {}
+                    _ => break,
+                }
+            }
+            Ok(Async::NotReady)
+        })
+        .timeout(Duration::from_millis(10000))
+        .map_err(|_| ())
+        .map(move |result| *thread_result.lock().unwrap() = result),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Test that a node which subscribes to a topic at runtime can replay the messages it recently
+// received on that topic.
+#[test]
+fn test_gossipsub_replay_recent() {
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let mut publisher = common::build_libp2p_instance(19100, vec![], None, log.clone());
+    let mut config = common::build_config(19101, vec![], None);
+    config.gossip_replay_buffer_size = 4;
+    let mut subscriber = Service::new(config, log.clone()).unwrap();
+    let subscriber_multiaddr = common::get_enr(&subscriber).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut publisher.swarm, subscriber_multiaddr).unwrap();
+
+    let pubsub_message = PubsubMessage::Block(vec![0; 4]);
+    let publishing_topic = Topic::new("/eth2/beacon_block/ssz".into());
+    let mut published = false;
+    tokio::run(futures::future::poll_fn(move || -> Result<_, ()> {
+        loop {
+            match publisher.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, topic))) => {
+                    if !published && topic == publishing_topic.no_hash() {
+                        publisher
+                            .publish(&[publishing_topic.clone()], pubsub_message.clone())
+                            .unwrap();
+                        published = true;
+                    }
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        loop {
+            match subscriber.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PubsubMessage { .. })) => {
+                    // the message is buffered even though it has already been received
+                    subscriber.swarm.unsubscribe(publishing_topic.clone());
+                    assert!(subscriber.swarm.subscribe(publishing_topic.clone()));
+                    assert_eq!(
+                        subscriber.replay_recent(&publishing_topic),
+                        vec![pubsub_message.clone()]
+                    );
+                    return Ok(Async::Ready(()));
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        Ok(Async::NotReady)
+    }))
+}
+
+// Test that a message published before a peer subscribed to its topic is republished to that
+// peer once it subscribes, and can then be replayed by it.
+#[test]
+fn test_gossipsub_replay_to_late_subscriber() {
+    use tokio::prelude::FutureExt;
+
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let mut publisher_config = common::build_config(19177, vec![], None);
+    publisher_config.gossip_replay_buffer_size = 4;
+    let mut publisher = Service::new(publisher_config, log.clone()).unwrap();
+    let mut subscriber_config = common::build_config(19178, vec![], None);
+    subscriber_config.gossip_replay_buffer_size = 4;
+    let mut subscriber = Service::new(subscriber_config, log.clone()).unwrap();
+    let subscriber_multiaddr = common::get_enr(&subscriber).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut publisher.swarm, subscriber_multiaddr).unwrap();
+
+    // the topic is not subscribed to by default, the subscriber joins it once connected
+    let late_topic = Topic::new("/eth2/late_topic/ssz".into());
+    let pubsub_message = PubsubMessage::Unknown(vec![1; 4]);
+    assert!(publisher.swarm.subscribe(late_topic.clone()));
+    assert_eq!(
+        publisher.publish(&[late_topic.clone()], pubsub_message.clone()),
+        Err(PublishError::NoPeers)
+    );
+
+    let mut subscribed = false;
+    let test_result = Arc::new(Mutex::new(false));
+    let thread_result = test_result.clone();
+    tokio::run(
+        futures::future::poll_fn(move || -> Poll<bool, ()> {
+            while let Async::Ready(Some(_)) = publisher.poll().unwrap() {}
+            loop {
+                match subscriber.poll().unwrap() {
+                    Async::Ready(Some(Libp2pEvent::PeerSubscribed(..))) => {
+                        if !subscribed {
+                            assert!(subscriber.swarm.subscribe(late_topic.clone()));
+                            subscribed = true;
+                        }
+                    }
+                    Async::Ready(Some(Libp2pEvent::PubsubMessage {
+                        topics, message, ..
+                    })) => {
+                        return Ok(Async::Ready(
+                            subscribed
+                                && topics == vec![late_topic.no_hash()]
+                                && message == pubsub_message
+                                && subscriber.replay_recent(&late_topic) == vec![message],
+                        ));
+                    }
+                    Async::Ready(Some(_)) => {}
+                    _ => break,
+                }
+            }
+            Ok(Async::NotReady)
+        })
+        .timeout(Duration::from_millis(10000))
+        .map_err(|_| ())
+        .map(move |result| *thread_result.lock().unwrap() = result),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Test that publishing reports the number of peers subscribed to the topic.
+#[test]
+fn test_gossipsub_publish_subscribed_peers() {
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let num_nodes = 3;
+    let mut nodes = common::build_full_mesh(log, num_nodes, Some(19110));
+    let mut publishing_node = nodes.pop().unwrap();
+    let publishing_topic = Topic::new("/eth2/beacon_block/ssz".into());
+    let mut subscribed_count = 0;
+    tokio::run(futures::future::poll_fn(move || -> Result<_, ()> {
+        for node in nodes.iter_mut() {
+            while let Async::Ready(Some(_)) = node.poll().unwrap() {}
+        }
+        loop {
+            match publishing_node.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, topic))) => {
+                    if topic == publishing_topic.no_hash() {
+                        subscribed_count += 1;
+                        if subscribed_count == num_nodes - 1 {
+                            let result = publishing_node.publish(
+                                &[publishing_topic.clone()],
+                                PubsubMessage::Block(vec![0; 4]),
                             );
+                            assert_eq!(
+                                result,
+                                Ok(PublishResult {
+                                    subscribed_peers: 2
+                                })
+                            );
+                            return Ok(Async::Ready(()));
                         }
                     }
                 }
+                Async::Ready(Some(_)) => {}
                 _ => break,
             }
         }
         Ok(Async::NotReady)
     }))
 }
+
+// Returns the value of the `gossipsub_subscribed_peers` gauge for `topic`.
+fn subscribed_peers_gauge(topic: &Topic) -> Option<f64> {
+    lighthouse_metrics::gather()
+        .iter()
+        .filter(|family| family.get_name() == "gossipsub_subscribed_peers")
+        .flat_map(|family| family.get_metric().iter())
+        .find(|metric| {
+            metric
+                .get_label()
+                .iter()
+                .any(|label| label.get_value() == topic.no_hash().as_str())
+        })
+        .map(|metric| metric.get_gauge().get_value())
+}
+
+// Test that the subscribed peers gauge counts the peers subscribed to a topic.
+//
+//                Topology used in test
+//
+//                node1 <-> node2 <-> node3
+//
+// Only node1 and node3 subscribe to the topic, so node2 is the only node with subscribed peers.
+#[test]
+fn test_gossipsub_subscribed_peers_gauge() {
+    let log = common::build_log(Level::Info, false);
+
+    let mut nodes = common::build_linear(log, 3, Some(19130));
+    // A topic no other test subscribes to, as the gauges are shared by all nodes in the process.
+    let topic = Topic::new("/eth2/subscribed_peers_gauge_test/ssz".into());
+    nodes[0].swarm.subscribe(topic.clone());
+    nodes[2].swarm.subscribe(topic.clone());
+
+    let mut subscribed_count = 0;
+    tokio::run(futures::future::poll_fn(move || -> Result<_, ()> {
+        for (i, node) in nodes.iter_mut().enumerate() {
+            loop {
+                match node.poll().unwrap() {
+                    Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, subscribed_topic))) => {
+                        if i == 1 && subscribed_topic == topic.no_hash() {
+                            subscribed_count += 1;
+                            if subscribed_count == 2 {
+                                assert_eq!(subscribed_peers_gauge(&topic), Some(2.0));
+                                return Ok(Async::Ready(()));
+                            }
+                        }
+                    }
+                    Async::Ready(Some(_)) => {}
+                    _ => break,
+                }
+            }
+        }
+        Ok(Async::NotReady)
+    }))
+}
+
+// Test that publishing without any subscribed peers is reported as an error.
+#[test]
+fn test_gossipsub_publish_no_peers() {
+    let log = common::build_log(Level::Info, false);
+
+    let mut node = common::build_libp2p_instance(19120, vec![], None, log);
+    let result = node.publish(
+        &[Topic::new("/eth2/beacon_block/ssz".into())],
+        PubsubMessage::Block(vec![0; 4]),
+    );
+    assert_eq!(result, Err(PublishError::NoPeers));
+}
+
+// Test that a payload on the beacon block topic decodes into a block.
+#[test]
+fn test_pubsub_message_decode_block() {
+    use ssz::Encode;
+    use types::{BeaconBlock, EthSpec, MinimalEthSpec};
+
+    let block = BeaconBlock::<MinimalEthSpec>::empty(&MinimalEthSpec::default_spec());
+    let message = PubsubMessage::Block(block.as_ssz_bytes());
+
+    assert_eq!(
+        message.decode::<MinimalEthSpec>(),
+        Ok(DecodedPubsubMessage::Block(block))
+    );
+}
+
+// Test that a payload which does not match the type of its topic is rejected.
+#[test]
+fn test_pubsub_message_decode_mismatched_topic() {
+    use ssz::Encode;
+    use types::{Epoch, MinimalEthSpec, Signature, VoluntaryExit};
+
+    let exit = VoluntaryExit {
+        epoch: Epoch::new(1),
+        validator_index: 2,
+        signature: Signature::empty_signature(),
+    };
+
+    let message = PubsubMessage::Block(exit.as_ssz_bytes());
+    assert!(match message.decode::<MinimalEthSpec>() {
+        Err(PubsubDecodeError::InvalidSsz(_)) => true,
+        _ => false,
+    });
+
+    let message = PubsubMessage::Unknown(exit.as_ssz_bytes());
+    assert_eq!(
+        message.decode::<MinimalEthSpec>(),
+        Err(PubsubDecodeError::UnknownTopic)
+    );
+}
+
+// Test that the propagation latency of a message is the time between receiving and validating it.
+#[test]
+fn test_propagation_latency() {
+    use std::time::{Duration, Instant};
+
+    let mut latencies = PropagationLatencies::default();
+    let received = Instant::now();
+    let message = PubsubMessage::Block(vec![0; 4]);
+
+    latencies.message_received("id".into(), message.kind(), received);
+    // a duplicate of the message does not restart the measurement
+    latencies.message_received(
+        "id".into(),
+        message.kind(),
+        received + Duration::from_millis(100),
+    );
+
+    assert_eq!(
+        latencies.message_validated("id", received + Duration::from_millis(250)),
+        Some((BEACON_BLOCK_TOPIC, Duration::from_millis(250)))
+    );
+    // the message is no longer tracked once validated
+    assert_eq!(
+        latencies.message_validated("id", received + Duration::from_millis(500)),
+        None
+    );
+}
+
+// Test that the number of messages awaiting validation is bounded, by dropping the oldest.
+#[test]
+fn test_propagation_latency_bounded() {
+    let mut latencies = PropagationLatencies::default();
+    let received = Instant::now();
+    let kind = PubsubMessage::Block(vec![]).kind();
+
+    // received within the maximum latency, so none of the messages are expired
+    for i in 0..MAX_PENDING_PROPAGATIONS + 10 {
+        latencies.message_received(
+            i.to_string(),
+            kind,
+            received + Duration::from_millis(i as u64),
+        );
+    }
+    assert_eq!(latencies.pending(), MAX_PENDING_PROPAGATIONS);
+
+    let validated = received + Duration::from_secs(10);
+    for i in 0..10 {
+        assert_eq!(latencies.message_validated(&i.to_string(), validated), None);
+    }
+    assert!(latencies
+        .message_validated(&MAX_PENDING_PROPAGATIONS.to_string(), validated)
+        .is_some());
+
+    // validated messages are not counted towards the bound
+    for i in 0..4 * MAX_PENDING_PROPAGATIONS {
+        let id = format!("validated_{}", i);
+        latencies.message_received(id.clone(), kind, validated);
+        assert!(latencies.message_validated(&id, validated).is_some());
+    }
+    assert_eq!(latencies.pending(), MAX_PENDING_PROPAGATIONS - 1);
+}
+
+#[test]
+fn test_gossip_message_id() {
+    let message = |topic: &str, data: &[u8]| libp2p::gossipsub::GossipsubMessage {
+        source: PeerId::random(),
+        data: data.to_vec(),
+        sequence_number: vec![],
+        topics: vec![Topic::new(topic.into()).no_hash()],
+    };
+
+    let block_topic = "/eth2/beacon_block/ssz";
+    let attestation_topic = "/eth2/beacon_attestation/ssz";
+
+    // the source and sequence number of a message do not change its id
+    assert_eq!(
+        gossip_message_id(&message(block_topic, &[1, 2, 3])),
+        gossip_message_id(&message(block_topic, &[1, 2, 3]))
+    );
+    assert_ne!(
+        gossip_message_id(&message(block_topic, &[1, 2, 3])),
+        gossip_message_id(&message(attestation_topic, &[1, 2, 3]))
+    );
+    assert_ne!(
+        gossip_message_id(&message(block_topic, &[1, 2, 3])),
+        gossip_message_id(&message(block_topic, &[1, 2, 4]))
+    );
+}
+
+// Test that gossipsub messages received while gossip is paused are only returned once gossip is
+// resumed.
+#[test]
+fn test_gossipsub_pause_resume() {
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let mut publisher = common::build_libp2p_instance(19140, vec![], None, log.clone());
+    let mut subscriber = common::build_libp2p_instance(19141, vec![], None, log.clone());
+    let subscriber_multiaddr = common::get_enr(&subscriber).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut publisher.swarm, subscriber_multiaddr).unwrap();
+    subscriber.pause_gossip();
+
+    let pubsub_message = PubsubMessage::Block(vec![0; 4]);
+    let publishing_topic = Topic::new("/eth2/beacon_block/ssz".into());
+    // gossip is resumed once this delay, started when the message is published, has elapsed
+    let mut resume_delay: Option<Delay> = None;
+    tokio::run(futures::future::poll_fn(move || -> Result<_, ()> {
+        loop {
+            match publisher.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, topic))) => {
+                    if resume_delay.is_none() && topic == publishing_topic.no_hash() {
+                        publisher
+                            .publish(&[publishing_topic.clone()], pubsub_message.clone())
+                            .unwrap();
+                        resume_delay = Some(Delay::new(Instant::now() + Duration::from_secs(2)));
+                    }
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        if let Some(delay) = resume_delay.as_mut() {
+            if subscriber.swarm.is_gossip_paused() && delay.poll().unwrap().is_ready() {
+                subscriber.resume_gossip();
+            }
+        }
+        loop {
+            match subscriber.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PubsubMessage { message, .. })) => {
+                    assert!(
+                        !subscriber.swarm.is_gossip_paused(),
+                        "message returned while gossip is paused"
+                    );
+                    assert_eq!(message, pubsub_message);
+                    return Ok(Async::Ready(()));
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        Ok(Async::NotReady)
+    }))
+}
+
+// Test that a block received at the same time as several attestations is returned before them.
+#[test]
+fn test_gossipsub_block_priority() {
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let mut publisher = common::build_libp2p_instance(19150, vec![], None, log.clone());
+    let mut subscriber = common::build_libp2p_instance(19151, vec![], None, log.clone());
+    let subscriber_multiaddr = common::get_enr(&subscriber).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut publisher.swarm, subscriber_multiaddr).unwrap();
+    // the messages are held while paused, so they are all received by the subscriber at once
+    subscriber.pause_gossip();
+
+    let block_topic = Topic::new("/eth2/beacon_block/ssz".into());
+    let attestation_topic = Topic::new("/eth2/beacon_attestation/ssz".into());
+    let mut subscribed_topics = vec![];
+    let mut resume_delay: Option<Delay> = None;
+    let mut received = vec![];
+    tokio::run(futures::future::poll_fn(move || -> Result<_, ()> {
+        loop {
+            match publisher.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, topic))) => {
+                    subscribed_topics.push(topic);
+                    let subscribed = subscribed_topics.contains(&block_topic.no_hash())
+                        && subscribed_topics.contains(&attestation_topic.no_hash());
+                    if resume_delay.is_none() && subscribed {
+                        for i in 0..4 {
+                            publisher
+                                .publish(
+                                    &[attestation_topic.clone()],
+                                    PubsubMessage::Attestation(vec![i; 4]),
+                                )
+                                .unwrap();
+                        }
+                        publisher
+                            .publish(&[block_topic.clone()], PubsubMessage::Block(vec![0; 4]))
+                            .unwrap();
+                        resume_delay = Some(Delay::new(Instant::now() + Duration::from_secs(2)));
+                    }
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        if let Some(delay) = resume_delay.as_mut() {
+            if subscriber.swarm.is_gossip_paused() && delay.poll().unwrap().is_ready() {
+                subscriber.resume_gossip();
+            }
+        }
+        loop {
+            match subscriber.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PubsubMessage { message, .. })) => {
+                    received.push(message);
+                    if received.len() == 5 {
+                        assert_eq!(received[0], PubsubMessage::Block(vec![0; 4]));
+                        for (i, message) in received[1..].iter().enumerate() {
+                            assert_eq!(*message, PubsubMessage::Attestation(vec![i as u8; 4]));
+                        }
+                        return Ok(Async::Ready(()));
+                    }
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        Ok(Async::NotReady)
+    }))
+}
+
+// Tests that a message received again after being evicted from the seen-message cache of
+// gossipsub is still dropped as a duplicate.
+#[test]
+fn test_gossipsub_duplicate_cache() {
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let mut publisher = common::build_libp2p_instance(19160, vec![], None, log.clone());
+    let mut config = common::build_config(19161, vec![], None);
+    config.duplicate_cache_size = 1_024;
+    config.duplicate_cache_time = Duration::from_secs(60);
+    let mut subscriber = Service::new(config, log.clone()).unwrap();
+    let subscriber_multiaddr = common::get_enr(&subscriber).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut publisher.swarm, subscriber_multiaddr).unwrap();
+
+    let block_topic = Topic::new("/eth2/beacon_block/ssz".into());
+    let attestation_topic = Topic::new("/eth2/beacon_attestation/ssz".into());
+    let duplicate = PubsubMessage::Block(vec![1; 4]);
+    let last = PubsubMessage::Block(vec![2; 4]);
+    let mut subscribed_topics = vec![];
+    let mut published = false;
+    let mut duplicates_received = 0;
+    tokio::run(futures::future::poll_fn(move || -> Result<_, ()> {
+        loop {
+            match publisher.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, topic))) => {
+                    subscribed_topics.push(topic);
+                    let subscribed = subscribed_topics.contains(&block_topic.no_hash())
+                        && subscribed_topics.contains(&attestation_topic.no_hash());
+                    if !published && subscribed {
+                        publisher
+                            .publish(&[block_topic.clone()], duplicate.clone())
+                            .unwrap();
+                        // enough distinct messages to evict the first from the gossipsub cache
+                        for i in 0..512_u16 {
+                            publisher
+                                .publish(
+                                    &[attestation_topic.clone()],
+                                    PubsubMessage::Attestation(i.to_le_bytes().to_vec()),
+                                )
+                                .unwrap();
+                        }
+                        publisher
+                            .publish(&[block_topic.clone()], duplicate.clone())
+                            .unwrap();
+                        publisher
+                            .publish(&[block_topic.clone()], last.clone())
+                            .unwrap();
+                        published = true;
+                    }
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        loop {
+            match subscriber.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PubsubMessage { message, .. })) => {
+                    if message == duplicate {
+                        duplicates_received += 1;
+                    } else if message == last {
+                        assert_eq!(duplicates_received, 1);
+                        return Ok(Async::Ready(()));
+                    }
+                }
+                Async::Ready(Some(_)) => {}
+                _ => break,
+            }
+        }
+        Ok(Async::NotReady)
+    }))
+}
+
+// Test that inbound gossipsub messages are returned whilst large messages are waiting to be
+// published.
+#[test]
+fn test_gossipsub_publish_queue() {
+    use tokio::prelude::FutureExt;
+
+    // set up the logging. The level and enabled or not
+    let log = common::build_log(Level::Info, false);
+
+    let (mut sender, mut receiver) = common::build_node_pair(&log, 19174);
+    let topic = Topic::new("/eth2/beacon_block/ssz".into());
+    let inbound_message = PubsubMessage::Block(vec![0; 4]);
+    let mut published = false;
+    let mut large_messages: u32 = 0;
+
+    let test_result = Arc::new(Mutex::new(false));
+    let thread_result = test_result.clone();
+    tokio::run(
+        futures::future::poll_fn(move || -> Poll<bool, ()> {
+            loop {
+                match sender.poll().unwrap() {
+                    Async::Ready(Some(Libp2pEvent::PeerSubscribed(_, subscribed))) => {
+                        if !published && subscribed == topic.no_hash() {
+                            sender
+                                .publish(&[topic.clone()], inbound_message.clone())
+                                .unwrap();
+                            published = true;
+                        }
+                    }
+                    Async::Ready(Some(_)) => {}
+                    _ => break,
+                }
+            }
+
+            loop {
+                // keep more large messages waiting to be published by the receiver than are
+                // published by each poll
+                while receiver.pending_publishes() < 2 * PUBLISH_BATCH_SIZE {
+                    large_messages += 1;
+                    let mut data = large_messages.to_le_bytes().to_vec();
+                    data.resize(64 * 1024, 0);
+                    let _ = receiver.publish(&[topic.clone()], PubsubMessage::Block(data));
+                }
+
+                match receiver.poll().unwrap() {
+                    Async::Ready(Some(Libp2pEvent::PubsubMessage { message, .. })) => {
+                        if message == inbound_message {
+                            return Ok(Async::Ready(receiver.pending_publishes() > 0));
+                        }
+                    }
+                    Async::Ready(Some(_)) => {}
+                    _ => break,
+                }
+            }
+            Ok(Async::NotReady)
+        })
+        .timeout(Duration::from_millis(10000))
+        .map_err(|_| ())
+        .map(move |result| *thread_result.lock().unwrap() = result),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Test that messages are dropped once the publish queue is full, and that each poll publishes a
+// bounded batch of them.
+#[test]
+fn test_gossipsub_publish_queue_bounded() {
+    let log = common::build_log(Level::Info, false);
+
+    let mut node = common::build_libp2p_instance(19176, vec![], None, log);
+    let topic = Topic::new("/eth2/beacon_block/ssz".into());
+    let message = PubsubMessage::Block(vec![0; 4]);
+
+    // without peers, messages are still queued
+    for _ in 0..MAX_PUBLISH_QUEUE_LEN {
+        assert_eq!(
+            node.publish(&[topic.clone()], message.clone()),
+            Err(PublishError::NoPeers)
+        );
+    }
+    assert_eq!(
+        node.publish(&[topic.clone()], message.clone()),
+        Err(PublishError::QueueFull)
+    );
+    assert_eq!(node.pending_publishes(), MAX_PUBLISH_QUEUE_LEN);
+
+    tokio::run(futures::future::poll_fn(move || -> Poll<(), ()> {
+        let _ = node.poll().unwrap();
+        assert_eq!(
+            node.pending_publishes(),
+            MAX_PUBLISH_QUEUE_LEN - PUBLISH_BATCH_SIZE
+        );
+        Ok(Async::Ready(()))
+    }));
+}
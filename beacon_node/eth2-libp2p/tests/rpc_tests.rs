@@ -1,8 +1,9 @@
 #![cfg(test)]
 use eth2_libp2p::rpc::methods::*;
 use eth2_libp2p::rpc::*;
-use eth2_libp2p::{Libp2pEvent, RPCEvent};
-use slog::{warn, Level};
+use eth2_libp2p::Service as LibP2PService;
+use eth2_libp2p::{Libp2pEvent, RPCEvent, MAX_RPC_TIMEOUTS};
+use slog::{o, warn, Level};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::prelude::*;
@@ -573,3 +574,365 @@ fn test_goodbye_rpc() {
     );
     assert!(*test_result.lock().unwrap());
 }
+
+#[test]
+// Tests that a peer is banned once it stalls on `MAX_RPC_TIMEOUTS` consecutive requests
+fn test_stalled_rpc_request_times_out() {
+    // set up the logging. The level and enabled logging or not
+    let log_level = Level::Trace;
+    let enable_logging = false;
+
+    let log = common::build_log(log_level, enable_logging);
+
+    // the sender only waits a short time for a STATUS response
+    let mut sender_config = common::build_config(10525, vec![], None);
+    sender_config
+        .rpc_request_timeouts
+        .insert(RPC_STATUS.to_string(), Duration::from_millis(200));
+    let mut sender = LibP2PService::new(sender_config, log.new(o!("who" => "sender"))).unwrap();
+    let mut receiver =
+        common::build_libp2p_instance(10526, vec![], None, log.new(o!("who" => "receiver")));
+
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut sender.swarm, receiver_multiaddr).unwrap();
+
+    let sender_log = log.clone();
+
+    // build the sender future
+    let sender_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    // Send a STATUS message
+                    warn!(sender_log, "Sending RPC");
+                    sender
+                        .swarm
+                        .send_rpc(peer_id, RPCEvent::Request(1, stalled_status_request()));
+                }
+                Async::Ready(Some(Libp2pEvent::RPC(peer_id, event))) => match event {
+                    // Should time out waiting for each response
+                    RPCEvent::Error(id, RPCError::ResponseTimeout) => {
+                        if id < MAX_RPC_TIMEOUTS as usize {
+                            // a peer is not banned for a single stalled request
+                            assert!(!sender.is_banned(&peer_id));
+                            sender.swarm.send_rpc(
+                                peer_id,
+                                RPCEvent::Request(id + 1, stalled_status_request()),
+                            );
+                        } else {
+                            assert!(sender.is_banned(&peer_id));
+                            return Ok(Async::Ready(true));
+                        }
+                    }
+                    _ => panic!("Received invalid RPC message"),
+                },
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            };
+        }
+    });
+
+    // build the receiver future, which receives the requests but never responds
+    let receiver_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the futures and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        sender_future
+            .select(receiver_future)
+            .timeout(Duration::from_millis(3000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result.0;
+                ()
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+#[test]
+// Tests that a persistent peer which stalls on `MAX_RPC_TIMEOUTS` consecutive requests is
+// disconnected, but not banned
+fn test_stalled_persistent_peer_is_not_banned() {
+    // set up the logging. The level and enabled logging or not
+    let log_level = Level::Trace;
+    let enable_logging = false;
+
+    let log = common::build_log(log_level, enable_logging);
+
+    // the receiver is a boot node of the sender, so a persistent peer
+    let mut receiver =
+        common::build_libp2p_instance(10536, vec![], None, log.new(o!("who" => "receiver")));
+    let mut sender_config = common::build_config(10535, vec![common::get_enr(&receiver)], None);
+    sender_config
+        .rpc_request_timeouts
+        .insert(RPC_STATUS.to_string(), Duration::from_millis(200));
+    let mut sender = LibP2PService::new(sender_config, log.new(o!("who" => "sender"))).unwrap();
+
+    // build the sender future
+    let mut timeouts = 0;
+    let sender_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    sender
+                        .swarm
+                        .send_rpc(peer_id, RPCEvent::Request(1, stalled_status_request()));
+                }
+                Async::Ready(Some(Libp2pEvent::RPC(peer_id, event))) => match event {
+                    RPCEvent::Error(id, RPCError::ResponseTimeout) => {
+                        timeouts += 1;
+                        if timeouts < MAX_RPC_TIMEOUTS {
+                            sender.swarm.send_rpc(
+                                peer_id,
+                                RPCEvent::Request(id + 1, stalled_status_request()),
+                            );
+                        }
+                    }
+                    _ => panic!("Received invalid RPC message"),
+                },
+                Async::Ready(Some(Libp2pEvent::PeerDisconnected(peer_id))) => {
+                    return Ok(Async::Ready(
+                        timeouts == MAX_RPC_TIMEOUTS && !sender.is_banned(&peer_id),
+                    ));
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            };
+        }
+    });
+
+    // build the receiver future, which receives the requests but never responds
+    let receiver_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the futures and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        sender_future
+            .select(receiver_future)
+            .timeout(Duration::from_millis(3000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result.0;
+                ()
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+/// A dummy STATUS request, which the receiver in the stalled request tests never responds to.
+fn stalled_status_request() -> RPCRequest {
+    RPCRequest::Status(StatusMessage {
+        fork_version: [0; 4],
+        finalized_root: Hash256::from_low_u64_be(0),
+        finalized_epoch: Epoch::new(1),
+        head_root: Hash256::from_low_u64_be(0),
+        head_slot: Slot::new(1),
+    })
+}
+
+#[test]
+// Tests requesting the STATUS of a peer with `Service::send_status_request`
+fn test_send_status_request() {
+    let log = common::build_log(Level::Trace, false);
+
+    // get sender/receiver
+    let (mut sender, mut receiver) = common::build_node_pair(&log, 10527);
+    let receiver_id = receiver.local_peer_id.clone();
+
+    let status = |head_slot| StatusMessage {
+        fork_version: [0; 4],
+        finalized_root: Hash256::from_low_u64_be(0),
+        finalized_epoch: Epoch::new(1),
+        head_root: Hash256::from_low_u64_be(0),
+        head_slot: Slot::new(head_slot),
+    };
+
+    // build the sender future
+    let sender_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    sender.send_status_request(peer_id, status(1));
+                }
+                Async::Ready(Some(Libp2pEvent::StatusResponse { peer_id, status })) => {
+                    return Ok(Async::Ready(
+                        peer_id == receiver_id && status.head_slot == Slot::new(42),
+                    ));
+                }
+                Async::Ready(Some(Libp2pEvent::RPC(_, RPCEvent::Response(..)))) => {
+                    panic!("Status response should not be returned as an RPC event")
+                }
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            };
+        }
+    });
+
+    // build the receiver future
+    let receiver_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::RPC(
+                    peer_id,
+                    RPCEvent::Request(id, RPCRequest::Status(request)),
+                ))) => {
+                    assert_eq!(request.head_slot, Slot::new(1));
+                    receiver.swarm.send_rpc(
+                        peer_id,
+                        RPCEvent::Response(
+                            id,
+                            RPCErrorResponse::Success(RPCResponse::Status(status(42))),
+                        ),
+                    );
+                }
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the futures and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        sender_future
+            .select(receiver_future)
+            .timeout(Duration::from_millis(1000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result.0;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+#[test]
+// Tests that inbound requests beyond the concurrent request limit of a peer are rejected, without
+// affecting other peers
+fn test_concurrent_rpc_limit() {
+    // set up the logging. The level and enabled logging or not
+    let log_level = Level::Trace;
+    let enable_logging = false;
+
+    let log = common::build_log(log_level, enable_logging);
+
+    let max_requests = 2;
+    let excess_requests = 2;
+
+    // the receiver never responds, so every accepted request stays open
+    let mut receiver_config = common::build_config(10530, vec![], None);
+    receiver_config.max_concurrent_rpc_per_peer = max_requests;
+    let mut receiver =
+        LibP2PService::new(receiver_config, log.new(o!("who" => "receiver"))).unwrap();
+    let mut flooder =
+        common::build_libp2p_instance(10531, vec![], None, log.new(o!("who" => "flooder")));
+    let mut other =
+        common::build_libp2p_instance(10532, vec![], None, log.new(o!("who" => "other")));
+    let flooder_id = flooder.local_peer_id.clone();
+    let other_id = other.local_peer_id.clone();
+
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut flooder.swarm, receiver_multiaddr.clone()).unwrap();
+    libp2p::Swarm::dial_addr(&mut other.swarm, receiver_multiaddr).unwrap();
+
+    // Dummy STATUS RPC message
+    let rpc_request = RPCRequest::Status(StatusMessage {
+        fork_version: [0; 4],
+        finalized_root: Hash256::from_low_u64_be(0),
+        finalized_epoch: Epoch::new(1),
+        head_root: Hash256::from_low_u64_be(0),
+        head_slot: Slot::new(1),
+    });
+
+    let mut flooder_requests = 0;
+    let mut other_requests = 0;
+    let mut rejected = 0;
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match flooder.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    for id in 1..=max_requests + excess_requests {
+                        flooder
+                            .swarm
+                            .send_rpc(peer_id.clone(), RPCEvent::Request(id, rpc_request.clone()));
+                    }
+                }
+                Async::Ready(Some(Libp2pEvent::RPC(
+                    _,
+                    RPCEvent::Response(_, RPCErrorResponse::ServerError(_)),
+                ))) => rejected += 1,
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => break,
+            };
+        }
+        loop {
+            match other.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    other
+                        .swarm
+                        .send_rpc(peer_id, RPCEvent::Request(1, rpc_request.clone()));
+                }
+                Async::Ready(Some(Libp2pEvent::RPC(_, RPCEvent::Response(..)))) => {
+                    panic!("Request within the limit was rejected")
+                }
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => break,
+            };
+        }
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::RPC(peer_id, RPCEvent::Request(..)))) => {
+                    if peer_id == flooder_id {
+                        flooder_requests += 1;
+                    } else if peer_id == other_id {
+                        other_requests += 1;
+                    }
+                }
+                Async::Ready(Some(Libp2pEvent::RPC(_, RPCEvent::Error(..)))) => {
+                    panic!("Rejected requests should not be returned")
+                }
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => break,
+            }
+        }
+
+        if rejected == excess_requests && other_requests == 1 {
+            return Ok(Async::Ready(flooder_requests == max_requests));
+        }
+        Ok(Async::NotReady)
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(2000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
@@ -0,0 +1,1712 @@
+#![cfg(test)]
+use eth2_libp2p::error::ErrorKind;
+use eth2_libp2p::rpc::{RPCError, RPCRequest, StatusMessage};
+use eth2_libp2p::Service as LibP2PService;
+use eth2_libp2p::{
+    boot_node_multiaddr, identity, keypair_from_seed, load_enr, load_private_key, replay_events,
+    rotate_private_key, Enr, EventRecorder, Libp2pEvent, PeerDirection, PeerId, PubsubMessage,
+    RPCEvent, TopicHash, NETWORK_KEY_BACKUP_FILENAME,
+};
+use slog::Level;
+use std::fs::File;
+use std::io::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempdir::TempDir;
+use tokio::prelude::*;
+use types::{Epoch, Hash256, Slot};
+
+mod common;
+
+/* Network key tests */
+
+const NETWORK_KEY_FILENAME: &str = "key";
+
+fn read_key_file(dir: &TempDir) -> Vec<u8> {
+    let mut bytes = vec![];
+    File::open(dir.path().join(NETWORK_KEY_FILENAME))
+        .expect("key file should exist")
+        .read_to_end(&mut bytes)
+        .expect("should read key file");
+    bytes
+}
+
+fn write_corrupt_key_file(dir: &TempDir) {
+    File::create(dir.path().join(NETWORK_KEY_FILENAME))
+        .and_then(|mut f| f.write_all(&[0xff; 12]))
+        .expect("should write key file");
+}
+
+// A missing key file results in a new key being generated and written to disk.
+#[test]
+fn test_missing_network_key_is_generated() {
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+
+    let mut config = common::build_config(10600, vec![], None);
+    config.network_dir = dir.path().to_path_buf();
+
+    let peer_id = {
+        let service = LibP2PService::new(config.clone(), log.clone())
+            .expect("should start with missing key file");
+        service.local_peer_id.clone()
+    };
+    let key_bytes = read_key_file(&dir);
+    assert_eq!(key_bytes.len(), 32);
+
+    // restarting re-uses the key that was written to disk
+    config.libp2p_port = 10601;
+    config.discovery_port = 10601;
+    let service = LibP2PService::new(config, log).expect("should start with existing key file");
+    assert_eq!(service.local_peer_id, peer_id);
+    assert_eq!(read_key_file(&dir), key_bytes);
+}
+
+// A corrupt key file must not silently change the identity of the node.
+#[test]
+fn test_corrupt_network_key_refuses_to_start() {
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+    write_corrupt_key_file(&dir);
+
+    let mut config = common::build_config(10605, vec![], None);
+    config.network_dir = dir.path().to_path_buf();
+
+    assert!(LibP2PService::new(config, log).is_err());
+    // the corrupt file is left untouched
+    assert_eq!(read_key_file(&dir), vec![0xff; 12]);
+}
+
+// A corrupt key file is replaced when `force_regenerate_key` is set.
+#[test]
+fn test_corrupt_network_key_force_regenerate() {
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+    write_corrupt_key_file(&dir);
+
+    let mut config = common::build_config(10610, vec![], None);
+    config.network_dir = dir.path().to_path_buf();
+    config.force_regenerate_key = true;
+
+    LibP2PService::new(config, log).expect("should start with a regenerated key");
+    let key_bytes = read_key_file(&dir);
+    assert_eq!(key_bytes.len(), 32);
+    assert_ne!(key_bytes, vec![0xff; 12]);
+}
+
+/// The secret key encrypted in `PBKDF2_KEYSTORE` and `SCRYPT_KEYSTORE`.
+const KEYSTORE_SECRET: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+const KEYSTORE_PASSWORD: &str = "testpassword";
+
+const PBKDF2_KEYSTORE: &str = r#"{
+    "crypto": {
+        "kdf": {
+            "function": "pbkdf2",
+            "params": {
+                "dklen": 32,
+                "c": 16,
+                "prf": "hmac-sha256",
+                "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+            },
+            "message": ""
+        },
+        "checksum": {
+            "function": "sha256",
+            "params": {},
+            "message": "f54c6394f3632a1d15b97da59ac765f53450ff13a5ae9267b260c7302a5b0bde"
+        },
+        "cipher": {
+            "function": "aes-128-ctr",
+            "params": {
+                "iv": "264daa3f303d7259501c93d997d84fe6"
+            },
+            "message": "1b8d943276b72efd63fe3f45e38e144d1d299a8f39b7a9f96e3dd6611651d348"
+        }
+    },
+    "path": "",
+    "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+    "version": 4
+}"#;
+
+const SCRYPT_KEYSTORE: &str = r#"{
+    "crypto": {
+        "kdf": {
+            "function": "scrypt",
+            "params": {
+                "dklen": 32,
+                "n": 16,
+                "r": 8,
+                "p": 1,
+                "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+            },
+            "message": ""
+        },
+        "checksum": {
+            "function": "sha256",
+            "params": {},
+            "message": "2d1a661f3eeecfa0f505fa44f56de9d025cb86c167de1268eb5f62283d5b2ebe"
+        },
+        "cipher": {
+            "function": "aes-128-ctr",
+            "params": {
+                "iv": "264daa3f303d7259501c93d997d84fe6"
+            },
+            "message": "673be8e156dd7406fa11f37b128cf296d4b22809618b3ffffd1ba0532d4bb3a5"
+        }
+    },
+    "path": "",
+    "uuid": "1d85ae20-35c5-4611-98e8-aa14a633906f",
+    "version": 4
+}"#;
+
+/// Writes `keystore` as the network key and `password` as the password file in `dir`.
+fn write_keystore(dir: &TempDir, keystore: &str, password: &str) -> eth2_libp2p::NetworkConfig {
+    File::create(dir.path().join(NETWORK_KEY_FILENAME))
+        .and_then(|mut f| f.write_all(keystore.as_bytes()))
+        .expect("should write key file");
+    let password_file = dir.path().join("password");
+    File::create(&password_file)
+        .and_then(|mut f| f.write_all(password.as_bytes()))
+        .expect("should write password file");
+
+    let mut config = common::build_config(10636, vec![], None);
+    config.network_dir = dir.path().to_path_buf();
+    config.p2p_key_password_file = Some(password_file);
+    config
+}
+
+fn keystore_secret_peer_id() -> PeerId {
+    let mut secret = hex::decode(KEYSTORE_SECRET).unwrap();
+    let secret_key = identity::secp256k1::SecretKey::from_bytes(&mut secret).unwrap();
+    let keypair: identity::secp256k1::Keypair = secret_key.into();
+    PeerId::from(identity::Keypair::Secp256k1(keypair).public())
+}
+
+// A network key stored in a keystore is decrypted with the password from the password file.
+#[test]
+fn test_keystore_network_key() {
+    let log = common::build_log(Level::Debug, false);
+
+    for keystore in &[PBKDF2_KEYSTORE, SCRYPT_KEYSTORE] {
+        let dir = TempDir::new("network_key").expect("should create temp dir");
+        // a trailing newline in the password file is ignored
+        let config = write_keystore(&dir, keystore, &format!("{}\n", KEYSTORE_PASSWORD));
+
+        let keypair = load_private_key(&config, &log).expect("should decrypt keystore");
+        assert_eq!(PeerId::from(keypair.public()), keystore_secret_peer_id());
+    }
+}
+
+// A wrong password is an error, the keystore is not replaced by a new key.
+#[test]
+fn test_keystore_network_key_wrong_password() {
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+    let mut config = write_keystore(&dir, PBKDF2_KEYSTORE, "wrongpassword");
+    config.force_regenerate_key = true;
+
+    let err = load_private_key(&config, &log).expect_err("should not decrypt keystore");
+    assert!(format!("{}", err).contains("Incorrect keystore password"));
+    assert_eq!(read_key_file(&dir), PBKDF2_KEYSTORE.as_bytes().to_vec());
+}
+
+// Rotating the key backs up the previous key and writes a new one, which is loaded on restart.
+#[test]
+fn test_rotate_network_key() {
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+
+    let mut config = common::build_config(10681, vec![], None);
+    config.network_dir = dir.path().to_path_buf();
+    // a new key is generated and written on the first load
+    let original_peer_id = PeerId::from(load_private_key(&config, &log).unwrap().public());
+    let original_key = read_key_file(&dir);
+
+    let (previous_peer_id, new_peer_id) =
+        rotate_private_key(dir.path()).expect("should rotate key");
+    assert_eq!(previous_peer_id, original_peer_id);
+    assert_ne!(new_peer_id, original_peer_id);
+
+    // the key file holds the new key, the backup the original
+    let keypair = load_private_key(&config, &log).expect("should load rotated key");
+    assert_eq!(PeerId::from(keypair.public()), new_peer_id);
+    let mut backup = vec![];
+    File::open(dir.path().join(NETWORK_KEY_BACKUP_FILENAME))
+        .and_then(|mut f| f.read_to_end(&mut backup))
+        .expect("should read backup");
+    assert_eq!(backup, original_key);
+    assert!(!dir.path().join("key.tmp").exists());
+
+    // an existing backup is not overwritten
+    assert!(rotate_private_key(dir.path()).is_err());
+    assert_eq!(
+        PeerId::from(load_private_key(&config, &log).unwrap().public()),
+        new_peer_id
+    );
+}
+
+// Keys stored in a keystore are not rotated.
+#[test]
+fn test_rotate_keystore_network_key() {
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+    write_keystore(&dir, PBKDF2_KEYSTORE, KEYSTORE_PASSWORD);
+
+    assert!(rotate_private_key(dir.path()).is_err());
+    assert_eq!(read_key_file(&dir), PBKDF2_KEYSTORE.as_bytes().to_vec());
+    assert!(!dir.path().join(NETWORK_KEY_BACKUP_FILENAME).exists());
+}
+
+// The ENR, including its sequence number, is reloaded from the network directory on restart.
+#[test]
+fn test_enr_seq_persisted_across_restart() {
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+
+    let mut config = common::build_config(10682, vec![], None);
+    config.network_dir = dir.path().to_path_buf();
+
+    let (peer_id, updated_seq) = {
+        let mut service = LibP2PService::new(config.clone(), log.clone()).unwrap();
+        let initial_seq = common::get_enr(&service).seq();
+        // the advertised address changes at runtime
+        service
+            .swarm
+            .update_local_enr_socket("127.0.0.1:10683".parse().unwrap(), true);
+        let enr = common::get_enr(&service);
+        assert!(enr.seq() > initial_seq);
+        (enr.peer_id(), enr.seq())
+    };
+
+    // restart with the same network directory
+    let local_key = load_private_key(&config, &log).unwrap();
+    let enr = load_enr(&local_key, &config, &log).expect("should load ENR");
+    assert_eq!(enr.peer_id(), peer_id);
+    assert!(enr.seq() >= updated_seq);
+
+    // a further restart leaves the sequence number unchanged
+    let reloaded = load_enr(&local_key, &config, &log).expect("should load ENR");
+    assert_eq!(reloaded.seq(), enr.seq());
+}
+
+/* Peer banning tests */
+
+// A peer banned immediately is disconnected without waiting for `BAN_PEER_TIMEOUT`.
+#[test]
+fn test_ban_peer_immediately() {
+    let log = common::build_log(Level::Debug, false);
+
+    let (mut sender, mut receiver) = common::build_node_pair(&log, 10615);
+
+    let sender_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    assert!(sender
+                        .swarm
+                        .discovery()
+                        .connected_peer_set()
+                        .contains(&peer_id));
+
+                    sender.ban_peer_immediately(peer_id.clone());
+
+                    // the peer is disconnected before the next poll
+                    return Ok(Async::Ready(
+                        !sender
+                            .swarm
+                            .discovery()
+                            .connected_peer_set()
+                            .contains(&peer_id),
+                    ));
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            };
+        }
+    });
+
+    let receiver_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the futures and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        sender_future
+            .select(receiver_future)
+            .timeout(Duration::from_millis(1000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result.0;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+/* Gossip disabled tests */
+
+// A node with gossip disabled subscribes to no topics, but still serves RPC requests.
+#[test]
+fn test_gossip_disabled() {
+    use eth2_libp2p::rpc::methods::*;
+    use eth2_libp2p::rpc::RPCRequest;
+    use eth2_libp2p::RPCEvent;
+    use slog::o;
+
+    let log = common::build_log(Level::Debug, false);
+
+    let mut sender_config = common::build_config(10620, vec![], None);
+    sender_config.gossip_enabled = false;
+    let mut receiver_config = common::build_config(10621, vec![], None);
+    receiver_config.gossip_enabled = false;
+
+    let mut sender = LibP2PService::new(sender_config, log.new(o!("who" => "sender"))).unwrap();
+    let mut receiver =
+        LibP2PService::new(receiver_config, log.new(o!("who" => "receiver"))).unwrap();
+
+    assert!(sender.swarm.subscribed_topics().is_empty());
+    assert!(receiver.swarm.subscribed_topics().is_empty());
+
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+    libp2p::Swarm::dial_addr(&mut sender.swarm, receiver_multiaddr).unwrap();
+
+    let rpc_request = RPCRequest::Goodbye(GoodbyeReason::ClientShutdown);
+    let sender_request = rpc_request.clone();
+
+    let sender_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    sender
+                        .swarm
+                        .send_rpc(peer_id, RPCEvent::Request(1, sender_request.clone()));
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            };
+        }
+    });
+
+    let receiver_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::RPC(_, RPCEvent::Request(_, request)))) => {
+                    assert_eq!(request, rpc_request);
+                    return Ok(Async::Ready(true));
+                }
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the futures and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        sender_future
+            .select(receiver_future)
+            .timeout(Duration::from_millis(1000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result.0;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+/* Persistent peer tests */
+
+// A configured libp2p node is redialed when it disconnects, a manually dialed peer is not.
+#[test]
+fn test_persistent_peer_redial() {
+    use eth2_libp2p::multiaddr::Protocol;
+    use std::collections::HashSet;
+
+    let log = common::build_log(Level::Debug, false);
+
+    let persistent = common::build_libp2p_instance(10626, vec![], None, log.clone());
+    let ephemeral = common::build_libp2p_instance(10627, vec![], None, log.clone());
+    let persistent_id = persistent.local_peer_id.clone();
+    let ephemeral_id = ephemeral.local_peer_id.clone();
+
+    let mut persistent_multiaddr = common::get_enr(&persistent).multiaddr()[1].clone();
+    persistent_multiaddr.push(Protocol::P2p(persistent_id.clone().into()));
+    let ephemeral_multiaddr = common::get_enr(&ephemeral).multiaddr()[1].clone();
+
+    let mut config = common::build_config(10625, vec![], None);
+    config.libp2p_nodes = vec![persistent_multiaddr];
+    let mut node = LibP2PService::new(config, log.clone()).unwrap();
+    libp2p::Swarm::dial_addr(&mut node.swarm, ephemeral_multiaddr).unwrap();
+
+    let mut peers = Some((persistent, ephemeral));
+    let mut dialed = HashSet::new();
+    let mut disconnected = HashSet::new();
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        // keep the peers alive until they have both been dialed
+        if let Some((persistent, ephemeral)) = peers.as_mut() {
+            while let Async::Ready(Some(_)) = persistent.poll().unwrap() {}
+            while let Async::Ready(Some(_)) = ephemeral.poll().unwrap() {}
+        }
+
+        loop {
+            match node.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    dialed.insert(peer_id);
+                    if dialed.contains(&persistent_id) && dialed.contains(&ephemeral_id) {
+                        // drop both peers, disconnecting them
+                        peers = None;
+                    }
+                }
+                Async::Ready(Some(Libp2pEvent::PeerDisconnected(peer_id))) => {
+                    disconnected.insert(peer_id);
+                    if disconnected.contains(&persistent_id) && disconnected.contains(&ephemeral_id)
+                    {
+                        return Ok(Async::Ready(
+                            node.is_redial_pending(&persistent_id)
+                                && !node.is_redial_pending(&ephemeral_id),
+                        ));
+                    }
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(2000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+/* Deterministic key tests */
+
+// The same seed always results in the same identity, different seeds in different identities.
+#[test]
+fn test_keypair_from_seed() {
+    let peer_id = |seed: &[u8]| PeerId::from(keypair_from_seed(seed).public());
+
+    assert_eq!(peer_id(&[1, 2, 3]), peer_id(&[1, 2, 3]));
+    assert_ne!(peer_id(&[1, 2, 3]), peer_id(&[1, 2, 4]));
+    assert_ne!(peer_id(&[]), peer_id(&[0]));
+}
+
+// A node configured with a seed uses the identity derived from it.
+#[test]
+fn test_p2p_seed_config() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut config = common::build_config(10635, vec![], None);
+    config.p2p_seed_hex = Some("0x01020304".into());
+    let node = LibP2PService::new(config, log).unwrap();
+
+    assert_eq!(
+        node.local_peer_id,
+        PeerId::from(keypair_from_seed(&[1, 2, 3, 4]).public())
+    );
+}
+
+/* Boot node tests */
+
+/// A drain that stores the message of every record it receives.
+struct MessageDrain(Arc<Mutex<Vec<String>>>);
+
+impl slog::Drain for MessageDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &slog::Record, _: &slog::OwnedKVList) -> Result<(), slog::Never> {
+        self.0.lock().unwrap().push(format!("{}", record.msg()));
+        Ok(())
+    }
+}
+
+// Unreachable boot nodes are reported once the grace period has elapsed.
+#[test]
+fn test_unreachable_boot_nodes_are_reported() {
+    use slog::Drain;
+    use std::time::Instant;
+    use tokio::timer::Delay;
+
+    // a boot node which is no longer running
+    let boot_node_enr = {
+        let boot_log = common::build_log(Level::Debug, false);
+        let boot_node = common::build_libp2p_instance(10640, vec![], None, boot_log);
+        common::get_enr(&boot_node)
+    };
+
+    let messages = Arc::new(Mutex::new(vec![]));
+    let log = slog::Logger::root(MessageDrain(messages.clone()).fuse(), slog::o!());
+    let failure_message = "Failed to connect to any boot node".to_string();
+
+    let mut config = common::build_config(10641, vec![boot_node_enr], None);
+    config.boot_node_grace_period = Duration::from_millis(200);
+    let node = Arc::new(Mutex::new(LibP2PService::new(config, log).unwrap()));
+
+    let poll_node = |node: Arc<Mutex<LibP2PService>>| {
+        future::poll_fn(move || -> Poll<(), ()> {
+            let mut node = node.lock().unwrap();
+            while let Async::Ready(Some(_)) = node.poll().unwrap() {}
+            Ok(Async::Ready(()))
+        })
+    };
+
+    // the failure is not reported within the grace period
+    tokio::run(poll_node(node.clone()));
+    assert!(!messages.lock().unwrap().contains(&failure_message));
+
+    // but is reported once it has elapsed
+    tokio::run(
+        Delay::new(Instant::now() + Duration::from_millis(300))
+            .map_err(|_| ())
+            .and_then(move |_| poll_node(node)),
+    );
+    assert!(messages.lock().unwrap().contains(&failure_message));
+}
+
+/* Peer limit tests */
+
+// Outbound connections are bounded by `max_outbound_peers`, inbound connections are still
+// accepted.
+#[test]
+fn test_outbound_peer_limit() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut first = common::build_libp2p_instance(10631, vec![], None, log.clone());
+    let mut second = common::build_libp2p_instance(10632, vec![], None, log.clone());
+    let first_multiaddr = common::get_enr(&first).multiaddr()[1].clone();
+    let second_multiaddr = common::get_enr(&second).multiaddr()[1].clone();
+
+    let mut config = common::build_config(10630, vec![], None);
+    config.max_outbound_peers = 1;
+    config.libp2p_nodes = vec![first_multiaddr, second_multiaddr];
+    let mut node = LibP2PService::new(config, log.clone()).unwrap();
+    let node_multiaddr = common::get_enr(&node).multiaddr()[1].clone();
+    let node_id = node.local_peer_id.clone();
+
+    let mut inbound = common::build_libp2p_instance(10633, vec![], None, log.clone());
+    libp2p::Swarm::dial_addr(&mut inbound.swarm, node_multiaddr).unwrap();
+
+    let mut inbound_connected = false;
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = first.poll().unwrap() {}
+        while let Async::Ready(Some(_)) = second.poll().unwrap() {}
+        while let Async::Ready(Some(_)) = node.poll().unwrap() {}
+
+        loop {
+            match inbound.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    inbound_connected = peer_id == node_id;
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => break,
+            }
+        }
+
+        let discovery = node.swarm.discovery();
+        if inbound_connected && discovery.outbound_peers() == 1 && discovery.inbound_peers() == 1 {
+            // only one of the configured nodes should have been dialed
+            let dialed = first.swarm.connected_peers() + second.swarm.connected_peers();
+            return Ok(Async::Ready(dialed == 1));
+        }
+        Ok(Async::NotReady)
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(2000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+#[test]
+fn test_peers_info() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut sender = common::build_libp2p_instance(10642, vec![], None, log.clone());
+    let mut receiver = common::build_libp2p_instance(10643, vec![], None, log.clone());
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+    let sender_id = sender.local_peer_id.clone();
+    let receiver_id = receiver.local_peer_id.clone();
+    libp2p::Swarm::dial_addr(&mut sender.swarm, receiver_multiaddr.clone()).unwrap();
+
+    let mut dialed = false;
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = receiver.poll().unwrap() {}
+
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    dialed = peer_id == receiver_id;
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => break,
+            }
+        }
+
+        let sender_peers = sender.peers_info();
+        let receiver_peers = receiver.peers_info();
+        if dialed && receiver_peers.len() == 1 {
+            let serializes = serde_json::to_string(&sender_peers).is_ok();
+            return Ok(Async::Ready(
+                serializes
+                    && sender_peers.len() == 1
+                    && sender_peers[0].peer_id == receiver_id.to_base58()
+                    && sender_peers[0].multiaddr == receiver_multiaddr.to_string()
+                    && sender_peers[0].direction == PeerDirection::Dialer
+                    && receiver_peers[0].peer_id == sender_id.to_base58()
+                    && receiver_peers[0].direction == PeerDirection::Listener,
+            ));
+        }
+        Ok(Async::NotReady)
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(2000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that the delay between searches never exceeds `discovery_interval`, even when it is
+// shorter than the initial search delay, and that searches must make at least one query.
+#[test]
+fn test_discovery_config() {
+    use std::time::Instant;
+
+    let log = common::build_log(Level::Debug, false);
+
+    let mut config = common::build_config(10644, vec![], None);
+    config.discovery_interval = Duration::from_secs(1);
+    config.discovery_parallelism = 3;
+    let mut service = LibP2PService::new(config.clone(), log).unwrap();
+    assert_eq!(service.swarm.discovery().discovery_parallelism(), 3);
+
+    // an on-demand search restarts the backoff from the initial search delay
+    service.discover_peers(1);
+    assert!(service.swarm.discovery().next_search() <= Instant::now() + Duration::from_secs(1));
+
+    config.discovery_parallelism = 0;
+    match service_error(config).kind() {
+        ErrorKind::DiscoveryFailed(_) => {}
+        e => panic!("unexpected error for zero discovery parallelism: {:?}", e),
+    }
+}
+
+// Tests that the configured client version is the agent version peers identify the node by.
+#[test]
+fn test_user_agent() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut config = common::build_config(10645, vec![], None);
+    config.client_version = "custom/user-agent".into();
+    let mut sender = LibP2PService::new(config, log.clone()).unwrap();
+    let mut receiver = common::build_libp2p_instance(10646, vec![], None, log.clone());
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+    let sender_id = sender.local_peer_id.clone();
+    libp2p::Swarm::dial_addr(&mut sender.swarm, receiver_multiaddr).unwrap();
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = sender.poll().unwrap() {}
+        while let Async::Ready(Some(_)) = receiver.poll().unwrap() {}
+
+        match receiver.swarm.peer_agent_version(&sender_id) {
+            Some(agent_version) => Ok(Async::Ready(agent_version == "custom/user-agent")),
+            None => Ok(Async::NotReady),
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(5000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+/// Builds a boot node and `count` nodes which have registered with it, on consecutive ports from
+/// `port`. Returns the ENR of the boot node and all of the nodes, the boot node first.
+fn build_discoverable_nodes(
+    port: u16,
+    count: u16,
+    log: &slog::Logger,
+) -> (Enr, Vec<LibP2PService>) {
+    let boot_node = common::build_libp2p_instance(port, vec![], None, log.clone());
+    let boot_node_enr = common::get_enr(&boot_node);
+    let mut nodes = vec![boot_node];
+    for port in port + 1..=port + count {
+        nodes.push(common::build_libp2p_instance(
+            port,
+            vec![boot_node_enr.clone()],
+            None,
+            log.clone(),
+        ));
+    }
+    (boot_node_enr, nodes)
+}
+
+// Tests that an on-demand peer search finds and connects to the requested number of peers.
+#[test]
+fn test_discover_peers() {
+    let log = common::build_log(Level::Debug, false);
+
+    let (boot_node_enr, mut nodes) = build_discoverable_nodes(10684, 3, &log);
+    let mut service = common::build_libp2p_instance(10647, vec![boot_node_enr], None, log);
+    assert_eq!(service.swarm.discovery().peer_target(), None);
+
+    service.discover_peers(3);
+    assert_eq!(service.swarm.discovery().peer_target(), Some(3));
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        for node in nodes.iter_mut() {
+            while let Async::Ready(Some(_)) = node.poll().unwrap() {}
+        }
+        while let Async::Ready(Some(_)) = service.poll().unwrap() {}
+
+        // the search ends once its target has been found
+        if service.swarm.discovery().peer_target().is_none() && service.swarm.connected_peers() >= 3
+        {
+            Ok(Async::Ready(true))
+        } else {
+            Ok(Async::NotReady)
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(10000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that the archive topics are subscribed to and can be unsubscribed from as a group.
+#[test]
+fn test_archive_topics() {
+    use eth2_libp2p::Topic;
+
+    let log = common::build_log(Level::Debug, false);
+
+    let core_topic = Topic::new("/eth2/beacon_block/ssz".into());
+    let archive_topics = vec![
+        Topic::new("/eth2/archive_a/ssz".into()),
+        Topic::new("/eth2/archive_b/ssz".into()),
+    ];
+
+    let mut config = common::build_config(10648, vec![], None);
+    config.archive_topics = archive_topics
+        .iter()
+        .chain(std::iter::once(&core_topic))
+        .map(|topic| topic.no_hash().as_str().to_string())
+        .collect();
+    let mut service = LibP2PService::new(config, log).unwrap();
+
+    // the core topic is not part of the archive set
+    assert_eq!(
+        service
+            .archive_topics()
+            .iter()
+            .map(Topic::no_hash)
+            .collect::<Vec<_>>(),
+        archive_topics
+            .iter()
+            .map(Topic::no_hash)
+            .collect::<Vec<_>>()
+    );
+    for topic in archive_topics.iter().chain(std::iter::once(&core_topic)) {
+        assert!(service.swarm.subscribed_topics().contains(&topic.no_hash()));
+    }
+
+    assert_eq!(service.unsubscribe_archive(), 2);
+    assert!(service.archive_topics().is_empty());
+    for topic in &archive_topics {
+        assert!(!service.swarm.subscribed_topics().contains(&topic.no_hash()));
+    }
+    assert!(service
+        .swarm
+        .subscribed_topics()
+        .contains(&core_topic.no_hash()));
+}
+
+/* Error tests */
+
+/// Returns the error from starting a service with `config`.
+fn service_error(config: eth2_libp2p::NetworkConfig) -> eth2_libp2p::error::Error {
+    let log = common::build_log(Level::Debug, false);
+    match LibP2PService::new(config, log) {
+        Ok(_) => panic!("service should fail to start"),
+        Err(e) => e,
+    }
+}
+
+// Invalid network keys and seeds are reported as a `KeyParse` error.
+#[test]
+fn test_invalid_network_key_errors() {
+    let mut config = common::build_config(10649, vec![], None);
+    config.secret_key_hex = Some("0xnothex".into());
+    match service_error(config).kind() {
+        ErrorKind::KeyParse(_) => {}
+        e => panic!("unexpected error for invalid secret key: {:?}", e),
+    }
+
+    let mut config = common::build_config(10649, vec![], None);
+    config.p2p_seed_hex = Some("nothex".into());
+    match service_error(config).kind() {
+        ErrorKind::KeyParse(_) => {}
+        e => panic!("unexpected error for invalid seed: {:?}", e),
+    }
+
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+    write_corrupt_key_file(&dir);
+    let mut config = common::build_config(10649, vec![], None);
+    config.network_dir = dir.path().to_path_buf();
+    match service_error(config).kind() {
+        ErrorKind::KeyParse(_) => {}
+        e => panic!("unexpected error for corrupt key file: {:?}", e),
+    }
+}
+
+// A keystore which can't be decrypted is reported as a `KeystoreDecrypt` error.
+#[test]
+fn test_keystore_decrypt_error() {
+    let dir = TempDir::new("network_key").expect("should create temp dir");
+    let mut config = write_keystore(&dir, PBKDF2_KEYSTORE, "wrongpassword");
+    match service_error(config.clone()).kind() {
+        ErrorKind::KeystoreDecrypt(_) => {}
+        e => panic!("unexpected error for wrong password: {:?}", e),
+    }
+
+    config.p2p_key_password_file = None;
+    match service_error(config).kind() {
+        ErrorKind::KeystoreDecrypt(_) => {}
+        e => panic!("unexpected error for missing password file: {:?}", e),
+    }
+}
+
+// Ports which are already in use are reported as `ListenFailed` and `DiscoveryFailed` errors.
+#[test]
+fn test_port_in_use_errors() {
+    let log = common::build_log(Level::Debug, false);
+    let _node = common::build_libp2p_instance(10650, vec![], None, log);
+
+    // the TCP port is in use
+    let mut config = common::build_config(10651, vec![], None);
+    config.libp2p_port = 10650;
+    match service_error(config).kind() {
+        ErrorKind::ListenFailed(address) => assert!(address.contains("10650")),
+        e => panic!("unexpected error for TCP port in use: {:?}", e),
+    }
+
+    // the UDP port is in use
+    let mut config = common::build_config(10652, vec![], None);
+    config.discovery_port = 10650;
+    match service_error(config).kind() {
+        ErrorKind::DiscoveryFailed(_) => {}
+        e => panic!("unexpected error for UDP port in use: {:?}", e),
+    }
+}
+
+/* Event socket tests */
+
+// Events returned by the service are also written to the clients of the event socket.
+#[cfg(unix)]
+#[test]
+fn test_event_socket() {
+    use std::os::unix::net::UnixStream;
+
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("event_socket").expect("should create temp dir");
+    let socket_path = dir.path().join("events.sock");
+
+    let mut config = common::build_config(10653, vec![], None);
+    config.event_socket_path = Some(socket_path.clone());
+    let mut sender = LibP2PService::new(config, log.clone()).unwrap();
+    let mut receiver = common::build_libp2p_instance(10654, vec![], None, log);
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+    let receiver_id = receiver.local_peer_id.clone();
+
+    let mut client = UnixStream::connect(&socket_path).expect("should connect to event socket");
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    libp2p::Swarm::dial_addr(&mut sender.swarm, receiver_multiaddr).unwrap();
+
+    let expected_peer_id = receiver_id.clone();
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = receiver.poll().unwrap() {}
+
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    return Ok(Async::Ready(peer_id == expected_peer_id));
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(5000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+
+    // the client receives the same events, the last of which is the dial
+    let mut last_event = None;
+    let mut len_bytes = [0; 4];
+    while client.read_exact(&mut len_bytes).is_ok() {
+        let mut event_bytes = vec![0; u32::from_le_bytes(len_bytes) as usize];
+        client
+            .read_exact(&mut event_bytes)
+            .expect("should read whole event");
+        let event: serde_json::Value =
+            serde_json::from_slice(&event_bytes).expect("event should be json");
+        let is_dial = event["event"] == "PeerDialed";
+        last_event = Some(event);
+        if is_dial {
+            break;
+        }
+    }
+
+    let last_event = last_event.expect("should receive events");
+    assert_eq!(last_event["event"], "PeerDialed");
+    assert_eq!(last_event["peer_id"], receiver_id.to_base58());
+}
+
+// Events are replayed from a recording as the events which were recorded, up to the limit of the
+// recording across restarts.
+#[test]
+fn test_record_and_replay_events() {
+    let log = common::build_log(Level::Debug, false);
+    let dir = TempDir::new("event_recording").expect("should create temp dir");
+    let path = dir.path().join("events.json");
+
+    let peer_id = PeerId::random();
+    let topic = TopicHash::from_raw("/eth2/beacon_block/ssz".to_string());
+    let status = StatusMessage {
+        fork_version: [1; 4],
+        finalized_root: Hash256::from_low_u64_be(1),
+        finalized_epoch: Epoch::new(2),
+        head_root: Hash256::from_low_u64_be(3),
+        head_slot: Slot::new(4),
+    };
+
+    let mut recorder =
+        EventRecorder::create(&path, 4, log.clone()).expect("should create recording");
+    recorder.record(&Libp2pEvent::PubsubMessage {
+        id: "message".to_string(),
+        source: peer_id.clone(),
+        topics: vec![topic.clone()],
+        message: PubsubMessage::Block(vec![1, 2, 3]),
+    });
+    recorder.record(&Libp2pEvent::RPC(
+        peer_id.clone(),
+        RPCEvent::Request(7, RPCRequest::Status(status.clone())),
+    ));
+    drop(recorder);
+
+    // events recorded by a previous run count towards the limit
+    let mut recorder = EventRecorder::create(&path, 4, log).expect("should reopen recording");
+    assert_eq!(recorder.recorded(), 2);
+    recorder.record(&Libp2pEvent::RPC(
+        peer_id.clone(),
+        RPCEvent::Error(8, RPCError::StreamTimeout),
+    ));
+    recorder.record(&Libp2pEvent::PeerSubscribed(peer_id.clone(), topic.clone()));
+    recorder.record(&Libp2pEvent::PeerDisconnected(peer_id.clone()));
+    assert_eq!(recorder.recorded(), 4);
+    drop(recorder);
+
+    let mut replayed = vec![];
+    assert_eq!(replay_events(&path, |event| replayed.push(event)), Ok(4));
+    assert!(replayed
+        .windows(2)
+        .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+    let mut events = replayed.into_iter().map(|recorded| recorded.event);
+    match events.next() {
+        Some(Libp2pEvent::PubsubMessage {
+            id,
+            source,
+            topics,
+            message,
+        }) => {
+            assert_eq!(id, "message");
+            assert_eq!(source, peer_id);
+            assert_eq!(topics, vec![topic.clone()]);
+            assert_eq!(message, PubsubMessage::Block(vec![1, 2, 3]));
+        }
+        _ => panic!("first event should be the pubsub message"),
+    }
+    match events.next() {
+        Some(Libp2pEvent::RPC(source, RPCEvent::Request(7, RPCRequest::Status(replayed)))) => {
+            assert_eq!(source, peer_id);
+            assert_eq!(replayed, status);
+        }
+        _ => panic!("second event should be the status request"),
+    }
+    match events.next() {
+        Some(Libp2pEvent::RPC(source, RPCEvent::Error(8, RPCError::StreamTimeout))) => {
+            assert_eq!(source, peer_id);
+        }
+        _ => panic!("third event should be the stream timeout"),
+    }
+    match events.next() {
+        Some(Libp2pEvent::PeerSubscribed(source, replayed_topic)) => {
+            assert_eq!(source, peer_id);
+            assert_eq!(replayed_topic, topic);
+        }
+        _ => panic!("fourth event should be the subscription"),
+    }
+    assert!(events.next().is_none());
+}
+
+/* Ban tests */
+
+// A peer is reported as banned both while its ban is pending and once it has been banned.
+#[test]
+fn test_is_banned() {
+    use std::time::Instant;
+    use tokio::timer::Delay;
+
+    let log = common::build_log(Level::Debug, false);
+
+    let (mut sender, mut receiver) = common::build_node_pair(&log, 10655);
+
+    // the peer is checked again once this delay, longer than the ban timeout, has elapsed
+    let mut ban_delay: Option<(PeerId, Delay)> = None;
+    let sender_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    assert!(!sender.is_banned(&peer_id));
+                    sender.disconnect_and_ban_peer(peer_id.clone());
+                    assert!(sender.is_banned(&peer_id));
+
+                    let delay = Delay::new(Instant::now() + Duration::from_millis(500));
+                    ban_delay = Some((peer_id, delay));
+                }
+                Async::Ready(Some(_)) => {}
+                Async::Ready(None) | Async::NotReady => break,
+            };
+        }
+        if let Some((peer_id, delay)) = ban_delay.as_mut() {
+            if delay.poll().unwrap().is_ready() {
+                // poll the service to action the ban, now that the timeout has elapsed
+                while let Async::Ready(Some(_)) = sender.poll().unwrap() {}
+                return Ok(Async::Ready(
+                    sender.is_banned(&*peer_id)
+                        && !sender
+                            .swarm
+                            .discovery()
+                            .connected_peer_set()
+                            .contains(&*peer_id),
+                ));
+            }
+        }
+        Ok(Async::NotReady)
+    });
+
+    let receiver_future = future::poll_fn(move || -> Poll<bool, ()> {
+        loop {
+            match receiver.poll().unwrap() {
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    // execute the futures and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        sender_future
+            .select(receiver_future)
+            .timeout(Duration::from_millis(2000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result.0;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+#[test]
+// Tests that the base64 ENR and boot node multiaddr shown to operators identify the node
+fn test_boot_node_details() {
+    let log = common::build_log(Level::Debug, false);
+
+    let node = common::build_libp2p_instance(10660, vec![], None, log);
+    let enr = common::get_enr(&node);
+
+    let decoded: Enr = enr.to_base64().parse().expect("should decode ENR");
+    assert_eq!(decoded.to_base64(), enr.to_base64());
+    assert_eq!(decoded.node_id(), enr.node_id());
+    assert_eq!(decoded.peer_id(), node.local_peer_id);
+
+    let multiaddr = boot_node_multiaddr(&decoded).expect("ENR should have an address");
+    assert_eq!(
+        multiaddr.to_string(),
+        format!(
+            "/ip4/127.0.0.1/tcp/10660/p2p/{}",
+            node.local_peer_id.to_base58()
+        )
+    );
+}
+
+#[test]
+// Tests that boot nodes added at runtime are dialed, and that known boot nodes are ignored
+fn test_add_boot_nodes() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut node = common::build_libp2p_instance(10661, vec![], None, log.clone());
+    let boot_node = common::build_libp2p_instance(10662, vec![], None, log);
+    let boot_node_id = boot_node.local_peer_id.clone();
+    let boot_node_enr = common::get_enr(&boot_node);
+
+    assert_eq!(node.add_boot_nodes(vec![boot_node_enr.clone()]), 1);
+    assert_eq!(node.add_boot_nodes(vec![boot_node_enr]), 0);
+
+    let mut boot_node = Some(boot_node);
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        if let Some(boot_node) = boot_node.as_mut() {
+            while let Async::Ready(Some(_)) = boot_node.poll().unwrap() {}
+        }
+        loop {
+            match node.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(peer_id))) => {
+                    return Ok(Async::Ready(peer_id == boot_node_id));
+                }
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(2000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+// Tests that the listen port can be bound again immediately after the node listening on it, with
+// an open connection, has shut down
+fn test_rebind_listen_port() {
+    let log = common::build_log(Level::Debug, false);
+
+    let (mut sender, receiver) = common::build_node_pair(&log, 10663);
+
+    let mut receiver = Some(receiver);
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        if let Some(receiver) = receiver.as_mut() {
+            while let Async::Ready(Some(_)) = receiver.poll().unwrap() {}
+        }
+        loop {
+            match sender.poll().unwrap() {
+                Async::Ready(Some(Libp2pEvent::PeerDialed(_))) => {
+                    // shut down the listening node first, so it closes the connection
+                    drop(receiver.take());
+                    return Ok(Async::Ready(true));
+                }
+                Async::Ready(Some(_)) => (),
+                Async::Ready(None) | Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    });
+
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(2000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+
+    let config = common::build_config(10664, vec![], None);
+    assert!(LibP2PService::new(config, log).is_ok());
+}
+
+#[test]
+// DNS based multiaddrs are resolved by the transport, the dial path must accept them
+fn test_dns_boot_node() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut config = common::build_config(10665, vec![], None);
+    config.libp2p_nodes = vec!["/dns4/example.com/tcp/9000".parse().unwrap()];
+    let mut node = LibP2PService::new(config, log).unwrap();
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = node.poll().unwrap() {}
+        Ok(Async::NotReady)
+    });
+
+    // the node should keep running until the timeout
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(500))
+            .map_err(move |_| *error_result.lock().unwrap() = true)
+            .map(|_| ()),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+#[test]
+fn test_build_connected_swarms() {
+    let log = common::build_log(Level::Debug, false);
+
+    let test_future = eth2_libp2p::test_utils::build_connected_swarms(3, &log).map(|nodes| {
+        nodes.len() == 3 && nodes.iter().all(|node| node.swarm.connected_peers() >= 1)
+    });
+
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that, while fewer than `target_peers` are connected, the node searches for and connects to
+// the remainder.
+#[test]
+fn test_target_peers_discovery() {
+    let log = common::build_log(Level::Debug, false);
+
+    let (boot_node_enr, mut nodes) = build_discoverable_nodes(10688, 3, &log);
+    let mut config = common::build_config(10666, vec![boot_node_enr], None);
+    config.target_peers = 3;
+    config.peer_check_interval = Duration::from_millis(100);
+    let mut node = LibP2PService::new(config, log).unwrap();
+    assert_eq!(node.swarm.discovery().peer_target(), None);
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        for peer in nodes.iter_mut() {
+            while let Async::Ready(Some(_)) = peer.poll().unwrap() {}
+        }
+        while let Async::Ready(Some(_)) = node.poll().unwrap() {}
+
+        if node.swarm.connected_peers() >= 3 {
+            Ok(Async::Ready(true))
+        } else {
+            Ok(Async::NotReady)
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(10000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that no on-demand peer search is started once `target_peers` are connected.
+#[test]
+fn test_target_peers_met() {
+    use std::time::Instant;
+
+    let log = common::build_log(Level::Debug, false);
+
+    let mut receiver = common::build_libp2p_instance(10668, vec![], None, log.clone());
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+
+    let mut config = common::build_config(10667, vec![], None);
+    config.target_peers = 1;
+    config.peer_check_interval = Duration::from_millis(500);
+    let mut sender = LibP2PService::new(config, log).unwrap();
+    libp2p::Swarm::dial_addr(&mut sender.swarm, receiver_multiaddr).unwrap();
+
+    // allow for several peer count checks
+    let deadline = Instant::now() + Duration::from_millis(1600);
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = receiver.poll().unwrap() {}
+        while let Async::Ready(Some(_)) = sender.poll().unwrap() {}
+
+        if Instant::now() > deadline && sender.swarm.connected_peers() == 1 {
+            Ok(Async::Ready(
+                sender.swarm.discovery().peer_target().is_none(),
+            ))
+        } else {
+            Ok(Async::NotReady)
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(5000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that peers are disconnected while more than `max_peers` are connected.
+#[test]
+fn test_max_peers_pruning() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut config = common::build_config(10669, vec![], None);
+    config.max_peers = 1;
+    config.peer_check_interval = Duration::from_millis(500);
+    let mut hub = LibP2PService::new(config, log.clone()).unwrap();
+    let hub_multiaddr = common::get_enr(&hub).multiaddr()[1].clone();
+
+    let mut spokes = vec![
+        common::build_libp2p_instance(10670, vec![], None, log.clone()),
+        common::build_libp2p_instance(10671, vec![], None, log.clone()),
+    ];
+    for spoke in spokes.iter_mut() {
+        libp2p::Swarm::dial_addr(&mut spoke.swarm, hub_multiaddr.clone()).unwrap();
+    }
+
+    let mut exceeded = false;
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        for spoke in spokes.iter_mut() {
+            while let Async::Ready(Some(_)) = spoke.poll().unwrap() {}
+        }
+        while let Async::Ready(Some(_)) = hub.poll().unwrap() {}
+
+        if hub.swarm.connected_peers() > 1 {
+            exceeded = true;
+        }
+
+        if exceeded && hub.swarm.connected_peers() == 1 {
+            Ok(Async::Ready(true))
+        } else {
+            Ok(Async::NotReady)
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(5000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that the transport is built with the configured nodelay value, and that peers connect with
+// Nagle's algorithm enabled.
+#[test]
+fn test_tcp_nodelay() {
+    let log = common::build_log(Level::Debug, false);
+
+    let mut receiver = common::build_libp2p_instance(10673, vec![], None, log.clone());
+    assert!(receiver.tcp_nodelay());
+    let receiver_multiaddr = common::get_enr(&receiver).multiaddr()[1].clone();
+
+    let mut config = common::build_config(10672, vec![], None);
+    config.tcp_nodelay = false;
+    let mut sender = LibP2PService::new(config, log).unwrap();
+    assert!(!sender.tcp_nodelay());
+    libp2p::Swarm::dial_addr(&mut sender.swarm, receiver_multiaddr).unwrap();
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = receiver.poll().unwrap() {}
+        while let Async::Ready(Some(_)) = sender.poll().unwrap() {}
+
+        if sender.swarm.connected_peers() == 1 && receiver.swarm.connected_peers() == 1 {
+            Ok(Async::Ready(true))
+        } else {
+            Ok(Async::NotReady)
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(5000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+/// Reports `ip` as the address of `service` observed by a new peer, as the identify protocol would.
+fn observe_ip(service: &mut LibP2PService, ip: std::net::Ipv4Addr) {
+    use libp2p::identify::{IdentifyEvent, IdentifyInfo};
+    use libp2p::swarm::NetworkBehaviourEventProcess;
+
+    let public_key = identity::Keypair::generate_secp256k1().public();
+    let event = IdentifyEvent::Received {
+        peer_id: public_key.clone().into_peer_id(),
+        info: IdentifyInfo {
+            public_key,
+            protocol_version: "lighthouse/libp2p".into(),
+            agent_version: "test".into(),
+            listen_addrs: vec![],
+            protocols: vec![],
+        },
+        observed_addr: format!("/ip4/{}/tcp/9000", ip).parse().unwrap(),
+    };
+    NetworkBehaviourEventProcess::<IdentifyEvent>::inject_event(&mut *service.swarm, event);
+}
+
+// Tests that the ENR address is set once the grace period has elapsed, preferring a public IP
+// observed by more than one peer to the private listening address.
+#[test]
+fn test_enr_address_grace_period() {
+    use std::net::Ipv4Addr;
+    use std::time::Instant;
+
+    let log = common::build_log(Level::Debug, false);
+    let external_ip: Ipv4Addr = "203.0.113.7".parse().unwrap();
+
+    let mut config = common::build_config(10675, vec![], None);
+    config.enr_address_grace_period = Duration::from_millis(1000);
+    let mut service = LibP2PService::new(config, log).unwrap();
+
+    let start = Instant::now();
+    let mut external_delay = tokio::timer::Delay::new(start + Duration::from_millis(200));
+    let mut external_observed = false;
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = service.poll().unwrap() {}
+
+        // only the internal listening address is known
+        if !external_observed {
+            if let Ok(Async::Ready(())) = external_delay.poll() {
+                observe_ip(&mut service, external_ip);
+                observe_ip(&mut service, external_ip);
+                external_observed = true;
+            }
+        }
+
+        let enr = common::get_enr(&service);
+        if enr.ip() == Some(external_ip) {
+            // the ENR is only set once the grace period has elapsed, on the listening port
+            Ok(Async::Ready(
+                external_observed
+                    && start.elapsed() >= Duration::from_millis(1000)
+                    && enr.tcp() == Some(10675),
+            ))
+        } else {
+            Ok(Async::NotReady)
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(5000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that an IP observed by a single peer, or in the shared (carrier-grade NAT) address space,
+// is not used as the ENR address.
+#[test]
+fn test_enr_address_requires_agreement() {
+    use std::net::Ipv4Addr;
+
+    let log = common::build_log(Level::Debug, false);
+    let single_ip: Ipv4Addr = "203.0.113.7".parse().unwrap();
+    let shared_ip: Ipv4Addr = "100.64.0.7".parse().unwrap();
+
+    let mut config = common::build_config(10692, vec![], None);
+    config.enr_address_grace_period = Duration::from_millis(500);
+    let mut service = LibP2PService::new(config, log).unwrap();
+    observe_ip(&mut service, single_ip);
+    observe_ip(&mut service, shared_ip);
+    observe_ip(&mut service, shared_ip);
+
+    // wait well past the grace period
+    let mut check_delay =
+        tokio::timer::Delay::new(std::time::Instant::now() + Duration::from_millis(1500));
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        while let Async::Ready(Some(_)) = service.poll().unwrap() {}
+
+        match check_delay.poll() {
+            Ok(Async::Ready(())) => {
+                let ip = common::get_enr(&service).ip();
+                Ok(Async::Ready(ip != Some(single_ip) && ip != Some(shared_ip)))
+            }
+            _ => Ok(Async::NotReady),
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(5000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
+
+// Tests that, starting with a single reachable boot node, the node searches for and dials peers
+// until `target_peers` are connected, then ends the startup ramp.
+#[test]
+fn test_startup_ramp() {
+    let log = common::build_log(Level::Debug, false);
+
+    let boot_node = common::build_libp2p_instance(10676, vec![], None, log.clone());
+    let boot_node_enr = common::get_enr(&boot_node);
+    let mut nodes = vec![boot_node];
+    for port in 10677..10680 {
+        nodes.push(common::build_libp2p_instance(
+            port,
+            vec![boot_node_enr.clone()],
+            None,
+            log.clone(),
+        ));
+    }
+
+    let mut config = common::build_config(10680, vec![boot_node_enr], None);
+    config.target_peers = 3;
+    config.startup_peer_check_interval = Duration::from_millis(200);
+    config.max_concurrent_dials = 1;
+    let mut node = LibP2PService::new(config, log).unwrap();
+    assert!(node.in_startup_ramp());
+
+    let test_future = future::poll_fn(move || -> Poll<bool, ()> {
+        for peer in nodes.iter_mut() {
+            while let Async::Ready(Some(_)) = peer.poll().unwrap() {}
+        }
+        while let Async::Ready(Some(_)) = node.poll().unwrap() {}
+
+        assert!(node.swarm.discovery().pending_dials() <= 1);
+        if !node.in_startup_ramp() {
+            Ok(Async::Ready(node.swarm.connected_peers() >= 3))
+        } else {
+            Ok(Async::NotReady)
+        }
+    });
+
+    // execute the future and check the result
+    let test_result = Arc::new(Mutex::new(false));
+    let error_result = test_result.clone();
+    let thread_result = test_result.clone();
+    tokio::run(
+        test_future
+            .timeout(Duration::from_millis(10000))
+            .map_err(move |_| *error_result.lock().unwrap() = false)
+            .map(move |result| {
+                *thread_result.lock().unwrap() = result;
+            }),
+    );
+    assert!(*test_result.lock().unwrap());
+}
@@ -4,7 +4,7 @@ use crate::service::NetworkMessage;
 use crate::sync::MessageProcessor;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use eth2_libp2p::{
-    behaviour::PubsubMessage,
+    behaviour::{DecodedPubsubMessage, PubsubDecodeError, PubsubMessage},
     rpc::{RPCError, RPCErrorResponse, RPCRequest, RPCResponse, RequestId, ResponseTermination},
     PeerId, RPCEvent,
 };
@@ -14,7 +14,7 @@ use slog::{debug, o, trace, warn};
 use ssz::{Decode, DecodeError};
 use std::sync::Arc;
 use tokio::sync::mpsc;
-use types::{Attestation, AttesterSlashing, BeaconBlock, ProposerSlashing, VoluntaryExit};
+use types::BeaconBlock;
 
 /// Handles messages received from the network and client and organises syncing. This
 /// functionality of this struct is to validate an decode messages from the network before
@@ -221,72 +221,47 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
 
     /// Handle RPC messages
     fn handle_gossip(&mut self, id: String, peer_id: PeerId, gossip_message: PubsubMessage) {
-        match gossip_message {
-            PubsubMessage::Block(message) => match self.decode_gossip_block(message) {
-                Ok(block) => {
-                    let should_forward_on = self
-                        .message_processor
-                        .on_block_gossip(peer_id.clone(), block);
-                    // TODO: Apply more sophisticated validation and decoding logic
-                    if should_forward_on {
-                        self.propagate_message(id, peer_id.clone());
-                    }
-                }
-                Err(e) => {
-                    debug!(self.log, "Invalid gossiped beacon block"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
-                }
-            },
-            PubsubMessage::Attestation(message) => match self.decode_gossip_attestation(message) {
-                Ok(attestation) => {
-                    // TODO: Apply more sophisticated validation and decoding logic
-                    self.propagate_message(id, peer_id.clone());
-                    self.message_processor
-                        .on_attestation_gossip(peer_id, attestation);
-                }
-                Err(e) => {
-                    debug!(self.log, "Invalid gossiped attestation"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
-                }
-            },
-            PubsubMessage::VoluntaryExit(message) => match self.decode_gossip_exit(message) {
-                Ok(_exit) => {
-                    // TODO: Apply more sophisticated validation and decoding logic
+        //TODO: Apply verification before decoding.
+        match gossip_message.decode::<T::EthSpec>() {
+            Ok(DecodedPubsubMessage::Block(block)) => {
+                let should_forward_on = self
+                    .message_processor
+                    .on_block_gossip(peer_id.clone(), block);
+                // TODO: Apply more sophisticated validation and decoding logic
+                if should_forward_on {
                     self.propagate_message(id, peer_id.clone());
-                    // TODO: Handle exits
-                    debug!(self.log, "Received a voluntary exit"; "peer_id" => format!("{}", peer_id) );
-                }
-                Err(e) => {
-                    debug!(self.log, "Invalid gossiped exit"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
-                }
-            },
-            PubsubMessage::ProposerSlashing(message) => {
-                match self.decode_gossip_proposer_slashing(message) {
-                    Ok(_slashing) => {
-                        // TODO: Apply more sophisticated validation and decoding logic
-                        self.propagate_message(id, peer_id.clone());
-                        // TODO: Handle proposer slashings
-                        debug!(self.log, "Received a proposer slashing"; "peer_id" => format!("{}", peer_id) );
-                    }
-                    Err(e) => {
-                        debug!(self.log, "Invalid gossiped proposer slashing"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
-                    }
                 }
             }
-            PubsubMessage::AttesterSlashing(message) => {
-                match self.decode_gossip_attestation_slashing(message) {
-                    Ok(_slashing) => {
-                        // TODO: Apply more sophisticated validation and decoding logic
-                        self.propagate_message(id, peer_id.clone());
-                        // TODO: Handle attester slashings
-                        debug!(self.log, "Received an attester slashing"; "peer_id" => format!("{}", peer_id) );
-                    }
-                    Err(e) => {
-                        debug!(self.log, "Invalid gossiped attester slashing"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
-                    }
-                }
+            Ok(DecodedPubsubMessage::Attestation(attestation)) => {
+                // TODO: Apply more sophisticated validation and decoding logic
+                self.propagate_message(id, peer_id.clone());
+                self.message_processor
+                    .on_attestation_gossip(peer_id, attestation);
+            }
+            Ok(DecodedPubsubMessage::VoluntaryExit(_exit)) => {
+                // TODO: Apply more sophisticated validation and decoding logic
+                self.propagate_message(id, peer_id.clone());
+                // TODO: Handle exits
+                debug!(self.log, "Received a voluntary exit"; "peer_id" => format!("{}", peer_id) );
             }
-            PubsubMessage::Unknown(message) => {
+            Ok(DecodedPubsubMessage::ProposerSlashing(_slashing)) => {
+                // TODO: Apply more sophisticated validation and decoding logic
+                self.propagate_message(id, peer_id.clone());
+                // TODO: Handle proposer slashings
+                debug!(self.log, "Received a proposer slashing"; "peer_id" => format!("{}", peer_id) );
+            }
+            Ok(DecodedPubsubMessage::AttesterSlashing(_slashing)) => {
+                // TODO: Apply more sophisticated validation and decoding logic
+                self.propagate_message(id, peer_id.clone());
+                // TODO: Handle attester slashings
+                debug!(self.log, "Received an attester slashing"; "peer_id" => format!("{}", peer_id) );
+            }
+            Err(PubsubDecodeError::UnknownTopic) => {
                 // Received a message from an unknown topic. Ignore for now
-                debug!(self.log, "Unknown Gossip Message"; "peer_id" => format!("{}", peer_id), "Message" => format!("{:?}", message));
+                debug!(self.log, "Unknown Gossip Message"; "peer_id" => format!("{}", peer_id), "Message" => format!("{:?}", gossip_message));
+            }
+            Err(e) => {
+                debug!(self.log, "Invalid gossip message"; "peer_id" => format!("{}", peer_id), "Error" => format!("{:?}", e));
             }
         }
     }
@@ -306,53 +281,13 @@ impl<T: BeaconChainTypes> MessageHandler<T> {
             });
     }
 
-    /* Decoding of gossipsub objects from the network.
+    /* Decoding of objects from the network.
      *
-     * The decoding is done in the message handler as it has access to to a `BeaconChain` and can
-     * therefore apply more efficient logic in decoding and verification.
+     * Gossipsub objects are decoded by `PubsubMessage::decode`.
      *
      * TODO: Apply efficient decoding/verification of these objects
      */
 
-    /* Gossipsub Domain Decoding */
-    // Note: These are not generics as type-specific verification will need to be applied.
-    fn decode_gossip_block(
-        &self,
-        beacon_block: Vec<u8>,
-    ) -> Result<BeaconBlock<T::EthSpec>, DecodeError> {
-        //TODO: Apply verification before decoding.
-        BeaconBlock::from_ssz_bytes(&beacon_block)
-    }
-
-    fn decode_gossip_attestation(
-        &self,
-        beacon_block: Vec<u8>,
-    ) -> Result<Attestation<T::EthSpec>, DecodeError> {
-        //TODO: Apply verification before decoding.
-        Attestation::from_ssz_bytes(&beacon_block)
-    }
-
-    fn decode_gossip_exit(&self, voluntary_exit: Vec<u8>) -> Result<VoluntaryExit, DecodeError> {
-        //TODO: Apply verification before decoding.
-        VoluntaryExit::from_ssz_bytes(&voluntary_exit)
-    }
-
-    fn decode_gossip_proposer_slashing(
-        &self,
-        proposer_slashing: Vec<u8>,
-    ) -> Result<ProposerSlashing, DecodeError> {
-        //TODO: Apply verification before decoding.
-        ProposerSlashing::from_ssz_bytes(&proposer_slashing)
-    }
-
-    fn decode_gossip_attestation_slashing(
-        &self,
-        attester_slashing: Vec<u8>,
-    ) -> Result<AttesterSlashing<T::EthSpec>, DecodeError> {
-        //TODO: Apply verification before decoding.
-        AttesterSlashing::from_ssz_bytes(&attester_slashing)
-    }
-
     /* Req/Resp Domain Decoding  */
 
     /// Verifies and decodes an ssz-encoded `BeaconBlock`. If `None` is passed, this represents a
@@ -3,13 +3,14 @@ use crate::message_handler::{HandlerMessage, MessageHandler};
 use crate::NetworkConfig;
 use beacon_chain::{BeaconChain, BeaconChainTypes};
 use core::marker::PhantomData;
+use environment::TaskTracker;
 use eth2_libp2p::Service as LibP2PService;
 use eth2_libp2p::{rpc::RPCRequest, Enr, Libp2pEvent, Multiaddr, PeerId, Swarm, Topic};
-use eth2_libp2p::{PubsubMessage, RPCEvent};
+use eth2_libp2p::{PublishError, PubsubMessage, RPCEvent};
 use futures::prelude::*;
 use futures::Stream;
 use parking_lot::Mutex;
-use slog::{debug, info, trace};
+use slog::{debug, info, trace, warn};
 use std::sync::Arc;
 use tokio::runtime::TaskExecutor;
 use tokio::sync::{mpsc, oneshot};
@@ -28,6 +29,7 @@ impl<T: BeaconChainTypes> Service<T> {
         beacon_chain: Arc<BeaconChain<T>>,
         config: &NetworkConfig,
         executor: &TaskExecutor,
+        task_tracker: &TaskTracker,
         network_log: slog::Logger,
     ) -> error::Result<(Arc<Self>, mpsc::UnboundedSender<NetworkMessage>)> {
         // build the network channel
@@ -51,6 +53,7 @@ impl<T: BeaconChainTypes> Service<T> {
             network_recv,
             message_handler_send,
             executor,
+            task_tracker,
             network_log,
             config.propagation_percentage,
         )?;
@@ -121,6 +124,7 @@ fn spawn_service(
     network_recv: mpsc::UnboundedReceiver<NetworkMessage>,
     message_handler_send: mpsc::UnboundedSender<HandlerMessage>,
     executor: &TaskExecutor,
+    task_tracker: &TaskTracker,
     log: slog::Logger,
     propagation_percentage: Option<u8>,
 ) -> error::Result<tokio::sync::oneshot::Sender<()>> {
@@ -128,19 +132,22 @@ fn spawn_service(
 
     // spawn on the current executor
     executor.spawn(
-        network_service(
-            libp2p_service,
-            network_recv,
-            message_handler_send,
-            log.clone(),
-            propagation_percentage,
-        )
-        // allow for manual termination
-        .select(exit_rx.then(|_| Ok(())))
-        .then(move |_| {
-            info!(log.clone(), "Network service shutdown");
-            Ok(())
-        }),
+        task_tracker.track(
+            "network",
+            network_service(
+                libp2p_service,
+                network_recv,
+                message_handler_send,
+                log.clone(),
+                propagation_percentage,
+            )
+            // allow for manual termination
+            .select(exit_rx.then(|_| Ok(())))
+            .then(move |_| {
+                info!(log.clone(), "Network service shutdown");
+                Ok(())
+            }),
+        ),
     );
 
     Ok(network_exit)
@@ -208,7 +215,17 @@ fn network_service(
                             info!(log, "Random filter did not publish message");
                         } else {
                             debug!(log, "Sending pubsub message"; "topics" => format!("{:?}",topics));
-                            libp2p_service.lock().swarm.publish(&topics, message);
+                            match libp2p_service.lock().publish(&topics, message) {
+                                Ok(result) => {
+                                    trace!(log, "Published pubsub message"; "subscribed_peers" => result.subscribed_peers)
+                                }
+                                Err(PublishError::QueueFull) => {
+                                    warn!(log, "Pubsub message dropped, the publish queue is full"; "topics" => format!("{:?}", topics))
+                                }
+                                Err(e) => {
+                                    debug!(log, "Pubsub message did not reach any peers"; "topics" => format!("{:?}", topics), "reason" => format!("{:?}", e))
+                                }
+                            }
                         }
                     }
                     NetworkMessage::Disconnect { peer_id } => {
@@ -264,6 +281,7 @@ fn network_service(
                             .map_err(|_| "Failed to send pubsub message to handler")?;
                     }
                     Libp2pEvent::PeerSubscribed(_, _) => {}
+                    Libp2pEvent::StatusResponse { .. } => {}
                 },
                 Ok(Async::Ready(None)) => unreachable!("Stream never ends"),
                 Ok(Async::NotReady) => break,
@@ -61,7 +61,7 @@
 use super::message_processor::{
     status_message, NetworkContext, PeerSyncInfo, FUTURE_SLOT_TOLERANCE,
 };
-use beacon_chain::{BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
+use beacon_chain::{timed_block_root, BeaconChain, BeaconChainTypes, BlockProcessingOutcome};
 use eth2_libp2p::rpc::methods::*;
 use eth2_libp2p::rpc::{RPCRequest, RequestId};
 use eth2_libp2p::PeerId;
@@ -534,7 +534,7 @@ impl<T: BeaconChainTypes> SyncManager<T> {
         expected_block_hash: Hash256,
     ) {
         // verify the hash is correct and try and process the block
-        if expected_block_hash != block.canonical_root() {
+        if expected_block_hash != timed_block_root(&block) {
             // the peer that sent this, sent us the wrong block
             downvote_peer(&mut self.network, &self.log, peer_id);
             return;
@@ -885,11 +885,12 @@ impl<T: BeaconChainTypes> SyncManager<T> {
             let previous_index = completed_request.downloaded_blocks.len() - 2;
             let expected_hash = completed_request.downloaded_blocks[previous_index].parent_root;
             // Note: the length must be greater than 2 so this cannot panic.
-            let block_hash = completed_request
-                .downloaded_blocks
-                .last()
-                .expect("Complete batch cannot be empty")
-                .canonical_root();
+            let block_hash = timed_block_root(
+                completed_request
+                    .downloaded_blocks
+                    .last()
+                    .expect("Complete batch cannot be empty"),
+            );
             if block_hash != expected_hash {
                 // remove the head block
                 let _ = completed_request.downloaded_blocks.pop();
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
 
 /// Defines the encoding for the API.
 #[derive(Clone, Serialize, Deserialize, Copy)]
@@ -38,6 +39,13 @@ pub struct Config {
     pub listen_address: Ipv4Addr,
     /// The port the REST API HTTP server will listen on.
     pub port: u16,
+    /// If set, the REST API HTTP server listens on a Unix domain socket at this path, instead of
+    /// `listen_address` and `port`. Only available on Unix.
+    #[cfg(unix)]
+    pub unix_socket_path: Option<PathBuf>,
+    /// Blocks are not produced for validators until the node is connected to at least this many
+    /// libp2p peers.
+    pub min_peers_for_production: usize,
 }
 
 impl Default for Config {
@@ -46,6 +54,9 @@ impl Default for Config {
             enabled: true,
             listen_address: Ipv4Addr::new(127, 0, 0, 1),
             port: 5052,
+            #[cfg(unix)]
+            unix_socket_path: None,
+            min_peers_for_production: 0,
         }
     }
 }
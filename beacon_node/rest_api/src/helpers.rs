@@ -1,5 +1,5 @@
 use crate::{ApiError, ApiResult};
-use beacon_chain::{BeaconChain, BeaconChainTypes};
+use beacon_chain::{timed_state_root, BeaconChain, BeaconChainTypes};
 use bls::PublicKey;
 use eth2_libp2p::{PubsubMessage, Topic};
 use eth2_libp2p::{
@@ -223,7 +223,7 @@ pub fn state_root_at_slot<T: BeaconChainTypes>(
 
         // Note: this is an expensive operation. Once the tree hash cache is implement it may be
         // used here.
-        Ok(state.canonical_root())
+        Ok(timed_state_root(&state))
     }
 }
 
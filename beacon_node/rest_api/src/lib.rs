@@ -21,6 +21,7 @@ use beacon_chain::{BeaconChain, BeaconChainTypes};
 use client_network::NetworkMessage;
 use client_network::Service as NetworkService;
 pub use config::ApiEncodingFormat;
+use environment::TaskTracker;
 use error::{ApiError, ApiResult};
 use eth2_config::Eth2Config;
 use hyper::rt::Future;
@@ -31,8 +32,14 @@ use parking_lot::RwLock;
 use slog::{info, warn};
 use std::net::SocketAddr;
 use std::ops::Deref;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
 use tokio::runtime::TaskExecutor;
 use tokio::sync::mpsc;
 use url_query::UrlQuery;
@@ -50,20 +57,27 @@ pub struct NetworkInfo<T: BeaconChainTypes> {
     pub network_chan: mpsc::UnboundedSender<NetworkMessage>,
 }
 
+/// Starts the REST API server on the executor, returning a signal to shut it down.
+///
+/// The server listens on `config.unix_socket_path` if it is set (on Unix), otherwise on TCP. The
+/// TCP address the server is listening on is returned, `None` for a Unix domain socket, whose file
+/// is removed once the server has shut down.
 pub fn start_server<T: BeaconChainTypes>(
     config: &Config,
     executor: &TaskExecutor,
+    task_tracker: &TaskTracker,
     beacon_chain: Arc<BeaconChain<T>>,
     network_info: NetworkInfo<T>,
     db_path: PathBuf,
     eth2_config: Eth2Config,
     log: slog::Logger,
-) -> Result<(exit_future::Signal, SocketAddr), hyper::Error> {
+) -> Result<(exit_future::Signal, Option<SocketAddr>), String> {
     let inner_log = log.clone();
     let eth2_config = Arc::new(eth2_config);
+    let min_peers_for_production = config.min_peers_for_production;
 
-    // Define the function that will build the request handler.
-    let make_service = make_service_fn(move |_socket: &AddrStream| {
+    // Define the function that will build the request handler for each connection.
+    let new_service = move || {
         let beacon_chain = beacon_chain.clone();
         let log = inner_log.clone();
         let eth2_config = eth2_config.clone();
@@ -80,9 +94,55 @@ pub fn start_server<T: BeaconChainTypes>(
                 eth2_config.clone(),
                 log.clone(),
                 db_path.clone(),
+                min_peers_for_production,
             )
         })
+    };
+
+    // Build a channel to kill the HTTP server.
+    let (exit_signal, exit) = exit_future::signal();
+    let inner_log = log.clone();
+    let server_exit = exit.and_then(move |_| {
+        info!(inner_log, "API service shutdown");
+        Ok(())
     });
+    let inner_log = log.clone();
+    let log_server_error = move |e: hyper::Error| {
+        warn!(
+        inner_log,
+        "API failed to start, Unable to bind"; "address" => format!("{:?}", e)
+        )
+    };
+
+    #[cfg(unix)]
+    if let Some(path) = &config.unix_socket_path {
+        let listener = bind_unix_socket(path)?;
+        let make_service = make_service_fn(move |_socket: &UnixStream| new_service());
+
+        // Configure the `hyper` server to gracefully shutdown when the shutdown channel is
+        // triggered, then remove the socket file.
+        let socket_path = path.clone();
+        let server_future = Server::builder(listener.incoming())
+            .serve(make_service)
+            .with_graceful_shutdown(server_exit)
+            .map_err(log_server_error)
+            .then(move |result| {
+                let _ = std::fs::remove_file(&socket_path);
+                result
+            });
+
+        info!(
+            log,
+            "REST API started";
+            "unix_socket" => format!("{:?}", path),
+        );
+
+        executor.spawn(task_tracker.track("http_server", server_future));
+
+        return Ok((exit_signal, None));
+    }
+
+    let make_service = make_service_fn(move |_socket: &AddrStream| new_service());
 
     let bind_addr = (config.listen_address, config.port).into();
     let server = Server::bind(&bind_addr).serve(make_service);
@@ -93,23 +153,10 @@ pub fn start_server<T: BeaconChainTypes>(
     // port).
     let actual_listen_addr = server.local_addr();
 
-    // Build a channel to kill the HTTP server.
-    let (exit_signal, exit) = exit_future::signal();
-    let inner_log = log.clone();
-    let server_exit = exit.and_then(move |_| {
-        info!(inner_log, "API service shutdown");
-        Ok(())
-    });
     // Configure the `hyper` server to gracefully shutdown when the shutdown channel is triggered.
-    let inner_log = log.clone();
     let server_future = server
         .with_graceful_shutdown(server_exit)
-        .map_err(move |e| {
-            warn!(
-            inner_log,
-            "API failed to start, Unable to bind"; "address" => format!("{:?}", e)
-            )
-        });
+        .map_err(log_server_error);
 
     info!(
         log,
@@ -118,9 +165,23 @@ pub fn start_server<T: BeaconChainTypes>(
         "port" => actual_listen_addr.port(),
     );
 
-    executor.spawn(server_future);
+    executor.spawn(task_tracker.track("http_server", server_future));
+
+    Ok((exit_signal, Some(actual_listen_addr)))
+}
+
+/// Binds a Unix domain socket at `path`, replacing a socket left by a previous run.
+#[cfg(unix)]
+fn bind_unix_socket(path: &Path) -> Result<UnixListener, String> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(format!("API socket path {:?} is not a socket", path));
+        }
+        std::fs::remove_file(path)
+            .map_err(|e| format!("Unable to remove stale API socket {:?}: {}", path, e))?;
+    }
 
-    Ok((exit_signal, actual_listen_addr))
+    UnixListener::bind(path).map_err(|e| format!("Unable to bind API socket {:?}: {}", path, e))
 }
 
 #[derive(Clone)]
@@ -27,6 +27,7 @@ pub fn route<T: BeaconChainTypes>(
     eth2_config: Arc<Eth2Config>,
     local_log: slog::Logger,
     db_path: PathBuf,
+    min_peers_for_production: usize,
 ) -> impl Future<Item = Response<Body>, Error = Error> {
     metrics::inc_counter(&metrics::REQUEST_COUNT);
     let timer = metrics::start_timer(&metrics::REQUEST_RESPONSE_TIME);
@@ -105,7 +106,13 @@ pub fn route<T: BeaconChainTypes>(
                 validator::post_validator_duties::<T>(req, beacon_chain)
             }
             (&Method::GET, "/validator/block") => {
-                into_boxfut(validator::get_new_beacon_block::<T>(req, beacon_chain))
+                into_boxfut(validator::get_new_beacon_block::<T>(
+                    req,
+                    beacon_chain,
+                    network_service,
+                    min_peers_for_production,
+                    log,
+                ))
             }
             (&Method::POST, "/validator/block") => {
                 validator::publish_beacon_block::<T>(req, beacon_chain, network_channel, log)
@@ -3,7 +3,7 @@ use crate::helpers::{
     publish_beacon_block_to_network,
 };
 use crate::response_builder::ResponseBuilder;
-use crate::{ApiError, ApiResult, BoxFut, NetworkChannel, UrlQuery};
+use crate::{ApiError, ApiResult, BoxFut, NetworkChannel, NetworkService, UrlQuery};
 use beacon_chain::{
     AttestationProcessingOutcome, BeaconChain, BeaconChainTypes, BlockProcessingOutcome,
 };
@@ -188,10 +188,30 @@ fn return_validator_duties<T: BeaconChainTypes>(
 }
 
 /// HTTP Handler to produce a new BeaconBlock from the current state, ready to be signed by a validator.
+///
+/// No block is produced whilst the node has fewer than `min_peers_for_production` peers, since it
+/// could not be published.
 pub fn get_new_beacon_block<T: BeaconChainTypes>(
     req: Request<Body>,
     beacon_chain: Arc<BeaconChain<T>>,
+    network_service: Arc<NetworkService<T>>,
+    min_peers_for_production: usize,
+    log: Logger,
 ) -> ApiResult {
+    let connected_peers = network_service.connected_peers();
+    if connected_peers < min_peers_for_production {
+        warn!(
+            log,
+            "Waiting for peers before producing blocks";
+            "connected_peers" => connected_peers,
+            "min_peers" => min_peers_for_production
+        );
+        return Err(ApiError::ServerError(format!(
+            "Beacon node is waiting for peers before producing blocks: {} of {} connected",
+            connected_peers, min_peers_for_production
+        )));
+    }
+
     let query = UrlQuery::from_request(&req)?;
 
     let slot = query.slot()?;
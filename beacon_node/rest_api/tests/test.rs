@@ -440,6 +440,57 @@ fn validator_block_get() {
     );
 }
 
+#[test]
+fn validator_block_get_min_peers() {
+    let mut env = build_env();
+
+    let spec = &E::default_spec();
+
+    // the node has no peers, so block production is suppressed
+    let mut config = testing_client_config();
+    config.rest_api.min_peers_for_production = 1;
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    let beacon_chain = node
+        .client
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let slot = Slot::new(1);
+    let randao_reveal = get_randao_reveal(beacon_chain.clone(), slot, spec);
+
+    assert!(
+        env.runtime()
+            .block_on(
+                remote_node
+                    .http
+                    .validator()
+                    .produce_block(slot, randao_reveal.clone()),
+            )
+            .is_err(),
+        "should not produce a block below the peer threshold"
+    );
+
+    // a threshold of zero peers is met, so block production is enabled
+    let mut config = testing_client_config();
+    config.rest_api.min_peers_for_production = 0;
+    let node = build_node(&mut env, config);
+    let remote_node = node.remote_node().expect("should produce remote node");
+
+    assert!(
+        env.runtime()
+            .block_on(
+                remote_node
+                    .http
+                    .validator()
+                    .produce_block(slot, randao_reveal),
+            )
+            .is_ok(),
+        "should produce a block at the peer threshold"
+    );
+}
+
 #[test]
 fn beacon_state() {
     let mut env = build_env();
@@ -614,3 +665,52 @@ fn get_version() {
 
     assert_eq!(version::version(), version, "result should be as expected");
 }
+
+#[test]
+#[cfg(unix)]
+fn get_version_over_unix_socket() {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    // the server runs in the background whilst this thread blocks on the socket
+    let mut env = EnvironmentBuilder::minimal()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    let socket_path =
+        std::env::temp_dir().join(format!("lighthouse_api_{}.sock", std::process::id()));
+    let mut config = testing_client_config();
+    config.rest_api.unix_socket_path = Some(socket_path.clone());
+
+    let node = build_node(&mut env, config);
+    assert_eq!(
+        node.client.http_listen_addr(),
+        None,
+        "should not listen on tcp"
+    );
+
+    let mut stream = UnixStream::connect(&socket_path).expect("should connect to api socket");
+    stream
+        .write_all(b"GET /node/version HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .expect("should write request");
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .expect("should read response");
+
+    assert!(
+        response.starts_with("HTTP/1.1 200 OK"),
+        "should succeed: {}",
+        response
+    );
+    assert!(
+        response.contains(&version::version()),
+        "result should be as expected"
+    );
+
+    std::fs::remove_file(&socket_path).expect("should remove api socket");
+}
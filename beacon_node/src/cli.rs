@@ -24,6 +24,39 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .help("Data directory for the freezer database.")
                 .takes_value(true)
         )
+        .arg(
+            Arg::with_name("prune-depth")
+                .long("prune-depth")
+                .value_name("EPOCHS")
+                .help("The number of finalized epochs of states to keep in the hot database. Older \
+                      states are migrated to the freezer database.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("max-db-size")
+                .long("max-db-size")
+                .value_name("BYTES")
+                .help("If the hot database grows larger than this, all finalized states are \
+                      migrated to the freezer database regardless of --prune-depth.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("freezer-compression")
+                .long("freezer-compression")
+                .value_name("LEVEL")
+                .help("Compress the freezer database with zstd at LEVEL (1-21). Must be given \
+                      every time the freezer database is opened, the hot database is never \
+                      compressed.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("in-memory-db")
+                .long("in-memory-db")
+                .help("Store the chain in memory instead of on disk. No database files are \
+                      created and all chain data is lost when the node exits. Intended for \
+                      testing.")
+                .takes_value(false)
+        )
         .arg(
             Arg::with_name("testnet-dir")
                 .long("testnet-dir")
@@ -56,7 +89,8 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
             Arg::with_name("port")
                 .long("port")
                 .value_name("PORT")
-                .help("The TCP/UDP port to listen on. The UDP port can be modified by the --discovery-port flag.")
+                .help("The TCP/UDP port to listen on. The UDP port can be modified by the --discovery-port flag. \
+                       Read from the PORT environment variable if not given.")
                 .default_value("9000")
                 .takes_value(true),
         )
@@ -67,6 +101,14 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("10")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("target-peers")
+                .long("target-peers")
+                .value_name("COUNT")
+                .help("The number of peers to maintain. Whenever fewer are connected, a search for \
+                       more peers is started. Limited to --maxpeers.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("boot-nodes")
                 .long("boot-nodes")
@@ -116,6 +158,67 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                        automatically.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("p2p-seed")
+                .long("p2p-seed")
+                .value_name("HEX")
+                .help("A seed, represented as ASCII-encoded hex bytes (with or without 0x prefix), \
+                       from which the secp256k1 secret key is derived deterministically. Intended \
+                       for test networks. Ignored if --p2p-priv-key is set.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("p2p-key-password-file")
+                .long("p2p-key-password-file")
+                .value_name("FILE")
+                .help("A file containing the password of the network key, required if the network \
+                       key on disk is an encrypted JSON keystore.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("print-enr-and-exit")
+                .long("print-enr-and-exit")
+                .help("Print the base64-encoded ENR and boot node multiaddr of this node, then exit \
+                       without starting the node.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("force-regenerate-key")
+                .long("force-regenerate-key")
+                .help("Generate a new network key if the key stored on disk is corrupt. Without \
+                       this flag the node refuses to start rather than change its identity.")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("event-socket")
+                .long("event-socket")
+                .value_name("PATH")
+                .help("Write the events of the libp2p service to clients of a Unix domain socket \
+                       at this path, for local tooling. Only supported on Unix.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("record-events")
+                .long("record-events")
+                .value_name("PATH")
+                .help("Append the events of the libp2p service, with timestamps, to a recording \
+                       at this path for debugging. Disabled by default.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("user-agent")
+                .long("user-agent")
+                .value_name("STRING")
+                .help("The agent version advertised to peers. Defaults to the Lighthouse version.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("disable-tcp-nodelay")
+                .long("disable-tcp-nodelay")
+                .help("Enable Nagle's algorithm on libp2p TCP connections, coalescing small \
+                       messages at the cost of latency. Useful on bandwidth-constrained links.")
+                .takes_value(false),
+        )
         /* REST API related arguments */
         .arg(
             Arg::with_name("http")
@@ -135,10 +238,29 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
             Arg::with_name("http-port")
                 .long("http-port")
                 .value_name("PORT")
-                .help("Set the listen TCP port for the RESTful HTTP API server.")
+                .help("Set the listen TCP port for the RESTful HTTP API server. Read from the \
+                       RPC_PORT environment variable if not given.")
                 .default_value("5052")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("http-unix-socket")
+                .long("http-unix-socket")
+                .value_name("PATH")
+                .help("Listen on a Unix domain socket at PATH for the RESTful HTTP API server, \
+                       instead of TCP. Cannot be used with --http-address or --http-port. Only \
+                       supported on Unix.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("min-peers-for-production")
+                .long("min-peers-for-production")
+                .value_name("COUNT")
+                .help("Refuse to produce blocks for validators until connected to at least COUNT \
+                       libp2p peers.")
+                .default_value("0")
+                .takes_value(true),
+        )
         /* Websocket related arguments */
         .arg(
             Arg::with_name("ws")
@@ -162,6 +284,22 @@ pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
                 .default_value("5053")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("sync-tolerance-slots")
+                .long("sync-tolerance-slots")
+                .value_name("SLOTS")
+                .help("The number of slots the head may be behind the current slot whilst the node \
+                       is still reported as synced.")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sync-stall-threshold")
+                .long("sync-stall-threshold")
+                .value_name("SLOTS")
+                .help("The number of consecutive slots the head may not advance whilst syncing \
+                       before a stalled sync is warned about. Set to 0 to disable the warning.")
+                .takes_value(true),
+        )
 
         /*
          * Eth1 Integration
@@ -1,12 +1,14 @@
+use crate::MEMORY_DB_TYPE;
 use clap::ArgMatches;
 use client::{ClientConfig, ClientGenesis, Eth2Config};
 use eth2_config::{read_from_file, write_to_file};
-use eth2_libp2p::{Enr, Multiaddr};
+use eth2_libp2p::{Enr, Multiaddr, NetworkConfig};
 use eth2_testnet_config::Eth2TestnetConfig;
 use genesis::recent_genesis_time;
 use rand::{distributions::Alphanumeric, Rng};
 use slog::{crit, info, Logger};
 use ssz::Encode;
+use std::env;
 use std::fs;
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
@@ -34,25 +36,7 @@ pub fn get_configs<E: EthSpec>(
 ) -> Result<Config> {
     let log = core_log.clone();
 
-    let mut client_config = ClientConfig::default();
-
-    // Read the `--datadir` flag.
-    //
-    // If it's not present, try and find the home directory (`~`) and push the default data
-    // directory onto it.
-    client_config.data_dir = cli_args
-        .value_of("datadir")
-        .map(PathBuf::from)
-        .or_else(|| dirs::home_dir().map(|home| home.join(".lighthouse").join(BEACON_NODE_DIR)))
-        .unwrap_or_else(|| PathBuf::from("."));
-
-    // Load the client config, if it exists .
-    let path = client_config.data_dir.join(CLIENT_CONFIG_FILENAME);
-    if path.exists() {
-        client_config = read_from_file(path.clone())
-            .map_err(|e| format!("Unable to parse {:?} file: {:?}", path, e))?
-            .ok_or_else(|| format!("{:?} file does not exist", path))?;
-    }
+    let mut client_config = read_client_config(cli_args)?;
 
     // Load the eth2 config, if it exists .
     let path = client_config.data_dir.join(ETH2_CONFIG_FILENAME);
@@ -62,80 +46,10 @@ pub fn get_configs<E: EthSpec>(
             .ok_or_else(|| format!("{:?} file does not exist", path))?;
     }
 
-    // Read the `--testnet-dir` flag.
-    if let Some(val) = cli_args.value_of("testnet-dir") {
-        client_config.testnet_dir = Some(PathBuf::from(val));
-    }
-
     /*
      * Networking
      */
-    // If a network dir has been specified, override the `datadir` definition.
-    if let Some(dir) = cli_args.value_of("network-dir") {
-        client_config.network.network_dir = PathBuf::from(dir);
-    } else {
-        client_config.network.network_dir = client_config.data_dir.join(NETWORK_DIR);
-    };
-
-    if let Some(listen_address_str) = cli_args.value_of("listen-address") {
-        let listen_address = listen_address_str
-            .parse()
-            .map_err(|_| format!("Invalid listen address: {:?}", listen_address_str))?;
-        client_config.network.listen_address = listen_address;
-        client_config.network.discovery_address = listen_address;
-    }
-
-    if let Some(max_peers_str) = cli_args.value_of("maxpeers") {
-        client_config.network.max_peers = max_peers_str
-            .parse::<usize>()
-            .map_err(|_| format!("Invalid number of max peers: {}", max_peers_str))?;
-    }
-
-    if let Some(port_str) = cli_args.value_of("port") {
-        let port = port_str
-            .parse::<u16>()
-            .map_err(|_| format!("Invalid port: {}", port_str))?;
-        client_config.network.libp2p_port = port;
-        client_config.network.discovery_port = port;
-    }
-
-    if let Some(boot_enr_str) = cli_args.value_of("boot-nodes") {
-        client_config.network.boot_nodes = boot_enr_str
-            .split(',')
-            .map(|enr| enr.parse().map_err(|_| format!("Invalid ENR: {}", enr)))
-            .collect::<Result<Vec<Enr>>>()?;
-    }
-
-    if let Some(libp2p_addresses_str) = cli_args.value_of("libp2p-addresses") {
-        client_config.network.libp2p_nodes = libp2p_addresses_str
-            .split(',')
-            .map(|multiaddr| {
-                multiaddr
-                    .parse()
-                    .map_err(|_| format!("Invalid Multiaddr: {}", multiaddr))
-            })
-            .collect::<Result<Vec<Multiaddr>>>()?;
-    }
-
-    if let Some(topics_str) = cli_args.value_of("topics") {
-        client_config.network.topics = topics_str.split(',').map(|s| s.into()).collect();
-    }
-
-    if let Some(discovery_address_str) = cli_args.value_of("discovery-address") {
-        client_config.network.discovery_address = discovery_address_str
-            .parse()
-            .map_err(|_| format!("Invalid discovery address: {:?}", discovery_address_str))?
-    }
-
-    if let Some(disc_port_str) = cli_args.value_of("disc-port") {
-        client_config.network.discovery_port = disc_port_str
-            .parse::<u16>()
-            .map_err(|_| format!("Invalid discovery port: {}", disc_port_str))?;
-    }
-
-    if let Some(p2p_priv_key) = cli_args.value_of("p2p-priv-key") {
-        client_config.network.secret_key_hex = Some(p2p_priv_key.to_string());
-    }
+    apply_network_flags(cli_args, &mut client_config)?;
 
     /*
      * Http server
@@ -151,12 +65,39 @@ pub fn get_configs<E: EthSpec>(
             .map_err(|_| "http-address is not a valid IPv4 address.")?;
     }
 
-    if let Some(port) = cli_args.value_of("http-port") {
+    let rpc_port = env::var("RPC_PORT").ok();
+    if let Some(port) = flag_or_env(cli_args, "http-port", rpc_port.as_ref().map(String::as_str)) {
         client_config.rest_api.port = port
             .parse::<u16>()
             .map_err(|_| "http-port is not a valid u16.")?;
     }
 
+    if let Some(path) = cli_args.value_of("http-unix-socket") {
+        // both TCP flags have default values, so only explicit occurrences conflict
+        if cli_args.occurrences_of("http-address") > 0 || cli_args.occurrences_of("http-port") > 0 {
+            return Err(
+                "--http-unix-socket cannot be used with --http-address or --http-port".into(),
+            );
+        }
+        #[cfg(unix)]
+        {
+            client_config.rest_api.unix_socket_path = Some(PathBuf::from(path));
+        }
+        #[cfg(not(unix))]
+        {
+            return Err(format!(
+                "Unable to use API socket {}, Unix domain sockets are only supported on Unix",
+                path
+            ));
+        }
+    }
+
+    if let Some(min_peers) = cli_args.value_of("min-peers-for-production") {
+        client_config.rest_api.min_peers_for_production = min_peers
+            .parse::<usize>()
+            .map_err(|_| "min-peers-for-production is not a valid integer.")?;
+    }
+
     /*
      * Websocket server
      */
@@ -195,6 +136,18 @@ pub fn get_configs<E: EthSpec>(
         client_config.sync_eth1_chain = true;
     }
 
+    if let Some(tolerance_str) = cli_args.value_of("sync-tolerance-slots") {
+        client_config.sync_tolerance_slots = tolerance_str
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid sync tolerance: {}", tolerance_str))?;
+    }
+
+    if let Some(threshold_str) = cli_args.value_of("sync-stall-threshold") {
+        client_config.sync_stall_threshold = threshold_str
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid sync stall threshold: {}", threshold_str))?;
+    }
+
     // Defines the URL to reach the eth1 node.
     if let Some(val) = cli_args.value_of("eth1-endpoint") {
         client_config.eth1.endpoint = val.to_string();
@@ -236,6 +189,33 @@ pub fn get_configs<E: EthSpec>(
         client_config.freezer_db_path = Some(PathBuf::from(freezer_dir));
     }
 
+    if let Some(prune_depth) = cli_args.value_of("prune-depth") {
+        client_config.prune_depth_epochs = prune_depth
+            .parse::<u64>()
+            .map_err(|_| format!("Invalid prune depth: {}", prune_depth))?;
+    }
+
+    if let Some(max_db_size) = cli_args.value_of("max-db-size") {
+        client_config.max_db_size = Some(
+            max_db_size
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid max db size: {}", max_db_size))?,
+        );
+    }
+
+    if let Some(level) = cli_args.value_of("freezer-compression") {
+        let level = level
+            .parse()
+            .ok()
+            .filter(|level| (1..=21).contains(level))
+            .ok_or_else(|| "freezer-compression must be a compression level from 1 to 21.")?;
+        client_config.freezer_compression = Some(level);
+    }
+
+    if cli_args.is_present("in-memory-db") {
+        client_config.db_type = MEMORY_DB_TYPE.to_string();
+    }
+
     if eth2_config.spec_constants != client_config.spec_constants {
         crit!(log, "Specification constants do not match.";
               "client_config" => client_config.spec_constants.to_string(),
@@ -250,8 +230,6 @@ pub fn get_configs<E: EthSpec>(
      * Replaces previously set flags.
      */
     if cli_args.is_present("zero-ports") {
-        client_config.network.libp2p_port = 0;
-        client_config.network.discovery_port = 0;
         client_config.rest_api.port = 0;
         client_config.websocket_server.port = 0;
     }
@@ -259,7 +237,193 @@ pub fn get_configs<E: EthSpec>(
     Ok((client_config, eth2_config, log))
 }
 
+/// Gets the network configuration of the node given by `cli_args`, as used by `get_configs`.
+///
+/// Unlike `get_configs`, never writes to the file system. The `testnet` subcommand is rejected,
+/// since it always starts a node with a new datadir.
+pub fn get_network_config(cli_args: &ArgMatches) -> Result<NetworkConfig> {
+    if cli_args.subcommand_name() == Some("testnet") {
+        return Err("The network config of a new testnet is not known until it is created.".into());
+    }
+
+    let mut client_config = read_client_config(cli_args)?;
+    apply_network_flags(cli_args, &mut client_config)?;
+
+    Ok(client_config.network)
+}
+
+/// Reads the client config in the datadir given by `cli_args`, or returns the default config for
+/// that datadir if it has none.
+fn read_client_config(cli_args: &ArgMatches) -> Result<ClientConfig> {
+    let mut client_config = ClientConfig::default();
+
+    // Read the `--datadir` flag.
+    //
+    // If it's not present, try and find the home directory (`~`) and push the default data
+    // directory onto it.
+    client_config.data_dir = cli_args
+        .value_of("datadir")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".lighthouse").join(BEACON_NODE_DIR)))
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    // Load the client config, if it exists .
+    let path = client_config.data_dir.join(CLIENT_CONFIG_FILENAME);
+    if path.exists() {
+        client_config = read_from_file(path.clone())
+            .map_err(|e| format!("Unable to parse {:?} file: {:?}", path, e))?
+            .ok_or_else(|| format!("{:?} file does not exist", path))?;
+    }
+
+    // Read the `--testnet-dir` flag.
+    if let Some(val) = cli_args.value_of("testnet-dir") {
+        client_config.testnet_dir = Some(PathBuf::from(val));
+    }
+
+    Ok(client_config)
+}
+
+/// Applies the networking flags of `cli_args` to `client_config`.
+fn apply_network_flags(cli_args: &ArgMatches, client_config: &mut ClientConfig) -> Result<()> {
+    // If a network dir has been specified, override the `datadir` definition.
+    if let Some(dir) = cli_args.value_of("network-dir") {
+        client_config.network.network_dir = PathBuf::from(dir);
+    } else {
+        client_config.network.network_dir = client_config.data_dir.join(NETWORK_DIR);
+    };
+
+    if let Some(listen_address_str) = cli_args.value_of("listen-address") {
+        let listen_address = listen_address_str
+            .parse()
+            .map_err(|_| format!("Invalid listen address: {:?}", listen_address_str))?;
+        client_config.network.listen_address = listen_address;
+        client_config.network.discovery_address = listen_address;
+    }
+
+    if let Some(max_peers_str) = cli_args.value_of("maxpeers") {
+        client_config.network.max_peers = max_peers_str
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid number of max peers: {}", max_peers_str))?;
+    }
+
+    if let Some(target_peers_str) = cli_args.value_of("target-peers") {
+        client_config.network.target_peers = target_peers_str
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid number of target peers: {}", target_peers_str))?;
+    }
+
+    let port = env::var("PORT").ok();
+    if let Some(port_str) = flag_or_env(cli_args, "port", port.as_ref().map(String::as_str)) {
+        let port = port_str
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid port: {}", port_str))?;
+        client_config.network.libp2p_port = port;
+        client_config.network.discovery_port = port;
+    }
+
+    if let Some(boot_enr_str) = cli_args.value_of("boot-nodes") {
+        client_config.network.boot_nodes = boot_enr_str
+            .split(',')
+            .map(|enr| enr.parse().map_err(|_| format!("Invalid ENR: {}", enr)))
+            .collect::<Result<Vec<Enr>>>()?;
+    }
+
+    if let Some(libp2p_addresses_str) = cli_args.value_of("libp2p-addresses") {
+        client_config.network.libp2p_nodes = libp2p_addresses_str
+            .split(',')
+            .map(|multiaddr| {
+                multiaddr
+                    .parse()
+                    .map_err(|_| format!("Invalid Multiaddr: {}", multiaddr))
+            })
+            .collect::<Result<Vec<Multiaddr>>>()?;
+    }
+
+    if let Some(topics_str) = cli_args.value_of("topics") {
+        client_config.network.topics = topics_str.split(',').map(|s| s.into()).collect();
+    }
+
+    if let Some(discovery_address_str) = cli_args.value_of("discovery-address") {
+        client_config.network.discovery_address = discovery_address_str
+            .parse()
+            .map_err(|_| format!("Invalid discovery address: {:?}", discovery_address_str))?
+    }
+
+    if let Some(disc_port_str) = cli_args.value_of("disc-port") {
+        client_config.network.discovery_port = disc_port_str
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid discovery port: {}", disc_port_str))?;
+    }
+
+    if let Some(p2p_priv_key) = cli_args.value_of("p2p-priv-key") {
+        client_config.network.secret_key_hex = Some(p2p_priv_key.to_string());
+    }
+
+    if let Some(p2p_seed) = cli_args.value_of("p2p-seed") {
+        client_config.network.p2p_seed_hex = Some(p2p_seed.to_string());
+    }
+
+    if let Some(password_file) = cli_args.value_of("p2p-key-password-file") {
+        client_config.network.p2p_key_password_file = Some(PathBuf::from(password_file));
+    }
+
+    if cli_args.is_present("force-regenerate-key") {
+        client_config.network.force_regenerate_key = true;
+    }
+
+    if let Some(socket_path) = cli_args.value_of("event-socket") {
+        #[cfg(unix)]
+        {
+            client_config.network.event_socket_path = Some(PathBuf::from(socket_path));
+        }
+        #[cfg(not(unix))]
+        {
+            return Err(format!(
+                "Unable to use event socket {}, event sockets are only supported on Unix",
+                socket_path
+            ));
+        }
+    }
+
+    if let Some(recording_path) = cli_args.value_of("record-events") {
+        client_config.network.record_events_path = Some(PathBuf::from(recording_path));
+    }
+
+    if let Some(user_agent) = cli_args.value_of("user-agent") {
+        client_config.network.client_version = user_agent.to_string();
+    }
+
+    if cli_args.is_present("disable-tcp-nodelay") {
+        client_config.network.tcp_nodelay = false;
+    }
+
+    // Replaces previously set ports, see `--zero-ports` in `get_configs`.
+    if cli_args.is_present("zero-ports") {
+        client_config.network.libp2p_port = 0;
+        client_config.network.discovery_port = 0;
+    }
+
+    Ok(())
+}
+
 /// Load from an existing database.
+/// Returns the value of the `flag` argument if it was given, otherwise `env_value` if it is set,
+/// otherwise the default value of `flag`.
+///
+/// `env_value` is read from the environment variable of the flag (e.g., `PORT` for `--port`) by the
+/// caller.
+fn flag_or_env<'a>(
+    cli_args: &'a ArgMatches,
+    flag: &str,
+    env_value: Option<&'a str>,
+) -> Option<&'a str> {
+    if cli_args.occurrences_of(flag) > 0 {
+        cli_args.value_of(flag)
+    } else {
+        env_value.or_else(|| cli_args.value_of(flag))
+    }
+}
+
 fn load_from_datadir(client_config: &mut ClientConfig) -> Result<()> {
     // Check to ensure the datadir exists.
     //
@@ -533,3 +697,101 @@ fn random_string(len: usize) -> String {
         .take(len)
         .collect::<String>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_app;
+    use tempdir::TempDir;
+    use types::MinimalEthSpec;
+
+    /// Returns the config for a new quick-start testnet in a temporary datadir, with the extra
+    /// `flags` given to the beacon node.
+    fn get_testnet_config(flags: &[&str]) -> ClientConfig {
+        try_get_testnet_config(flags).expect("should get configs")
+    }
+
+    /// As `get_testnet_config`, returning any error from `get_configs`.
+    fn try_get_testnet_config(flags: &[&str]) -> Result<ClientConfig> {
+        let datadir = TempDir::new("lighthouse_config").expect("should create temp dir");
+        let datadir_path = datadir.path().join(BEACON_NODE_DIR);
+        let datadir_str = datadir_path.to_str().expect("datadir should be utf-8");
+
+        let mut args = vec!["beacon_node", "--datadir", datadir_str];
+        args.extend_from_slice(flags);
+        args.extend_from_slice(&["testnet", "quick", "8", "0"]);
+        let matches = cli_app().get_matches_from(args);
+
+        let log = Logger::root(slog::Discard, slog::o!());
+        get_configs::<MinimalEthSpec>(&matches, Eth2Config::minimal(), log)
+            .map(|(client_config, _, _)| client_config)
+    }
+
+    #[test]
+    fn port_from_env() {
+        let matches = |flags: &[&str]| {
+            let mut args = vec!["beacon_node"];
+            args.extend_from_slice(flags);
+            cli_app().get_matches_from(args)
+        };
+
+        // the environment variable takes precedence over the default
+        let defaults = matches(&[]);
+        assert_eq!(flag_or_env(&defaults, "port", Some("19873")), Some("19873"));
+        assert_eq!(
+            flag_or_env(&defaults, "http-port", Some("19874")),
+            Some("19874")
+        );
+        assert_eq!(flag_or_env(&defaults, "port", None), Some("9000"));
+        assert_eq!(flag_or_env(&defaults, "http-port", None), Some("5052"));
+
+        // the flag takes precedence over the environment variable
+        let flags = matches(&["--port", "19875", "--http-port", "19876"]);
+        assert_eq!(flag_or_env(&flags, "port", Some("19873")), Some("19875"));
+        assert_eq!(
+            flag_or_env(&flags, "http-port", Some("19874")),
+            Some("19876")
+        );
+
+        // the resolved ports are used by the config
+        let config = get_testnet_config(&["--port", "19875", "--http-port", "19876"]);
+        assert_eq!(config.network.libp2p_port, 19875);
+        assert_eq!(config.rest_api.port, 19876);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn http_unix_socket_excludes_tcp_flags() {
+        let socket = "/tmp/lighthouse_api.sock";
+        assert_eq!(
+            get_testnet_config(&["--http-unix-socket", socket])
+                .rest_api
+                .unix_socket_path,
+            Some(PathBuf::from(socket))
+        );
+        assert!(
+            try_get_testnet_config(&["--http-unix-socket", socket, "--http-port", "5053"]).is_err()
+        );
+        assert!(try_get_testnet_config(&[
+            "--http-unix-socket",
+            socket,
+            "--http-address",
+            "127.0.0.1"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn freezer_compression_level_range() {
+        assert_eq!(get_testnet_config(&[]).freezer_compression, None);
+        for level in &["1", "21"] {
+            assert_eq!(
+                get_testnet_config(&["--freezer-compression", level]).freezer_compression,
+                Some(level.parse().unwrap())
+            );
+        }
+        for level in &["0", "22", "-1", "fast"] {
+            assert!(try_get_testnet_config(&["--freezer-compression", level]).is_err());
+        }
+    }
+}
@@ -14,27 +14,102 @@ use beacon_chain::{
     lmd_ghost::ThreadSafeReducedTree, slot_clock::SystemTimeSlotClock,
 };
 use clap::ArgMatches;
-use config::get_configs;
+use config::{get_configs, get_network_config};
 use environment::RuntimeContext;
-use futures::{Future, IntoFuture};
-use slog::{info, warn};
+use eth2_libp2p::{build_enr, read_private_key, Enr};
+use futures::{future::Either, Future, IntoFuture};
+use slog::{info, warn, Logger};
 use std::ops::{Deref, DerefMut};
-use store::{migrate::BackgroundMigrator, DiskStore};
+use store::{
+    migrate::{BackgroundMigrator, Migrate, MigratorConfig, NullMigrator},
+    DiskStore, MemoryStore, Store,
+};
 use types::EthSpec;
 
-/// A type-alias to the tighten the definition of a production-intended `Client`.
-pub type ProductionClient<E> = Client<
-    Witness<
-        DiskStore,
-        BackgroundMigrator<E>,
-        SystemTimeSlotClock,
-        ThreadSafeReducedTree<DiskStore, E>,
-        CachingEth1Backend<E, DiskStore>,
-        E,
-        WebSocketSender<E>,
-    >,
+/// The value of `ClientConfig::db_type` which selects a `DevBeaconNode`.
+pub const MEMORY_DB_TYPE: &str = "memory";
+
+/// The `Witness` types of a beacon node `Client`, which vary only by their store.
+pub type BeaconNodeWitness<TStore, TStoreMigrator, E> = Witness<
+    TStore,
+    TStoreMigrator,
+    SystemTimeSlotClock,
+    ThreadSafeReducedTree<TStore, E>,
+    CachingEth1Backend<E, TStore>,
+    E,
+    WebSocketSender<E>,
 >;
 
+/// A type-alias to the tighten the definition of a production-intended `Client`.
+pub type ProductionClient<E> = Client<BeaconNodeWitness<DiskStore, BackgroundMigrator<E>, E>>;
+
+/// A type-alias for a `Client` which only stores the chain in memory.
+pub type DevClient<E> = Client<BeaconNodeWitness<MemoryStore, NullMigrator, E>>;
+
+/// A beacon node `Client` using either an on-disk or in-memory store, selected by the `db_type`
+/// of its `ClientConfig`.
+pub enum BeaconNode<E: EthSpec> {
+    Production(ProductionBeaconNode<E>),
+    Dev(DevBeaconNode<E>),
+}
+
+impl<E: EthSpec> BeaconNode<E> {
+    /// Starts a new beacon node `Client` in the given `environment`.
+    ///
+    /// Identical to `start_from_client_config`, however the `client_config` is generated from the
+    /// given `matches` and potentially configuration files on the local filesystem or other
+    /// configurations hosted remotely.
+    pub fn new_from_cli<'a, 'b>(
+        mut context: RuntimeContext<E>,
+        matches: &ArgMatches<'b>,
+    ) -> impl Future<Item = Self, Error = String> + 'a {
+        let log = context.log.clone();
+
+        get_configs::<E>(&matches, context.eth2_config.clone(), log)
+            .into_future()
+            .and_then(move |(client_config, eth2_config, _log)| {
+                context.eth2_config = eth2_config;
+                Self::new(context, client_config)
+            })
+    }
+
+    /// Starts a new `DevBeaconNode` if the `db_type` of `client_config` is `MEMORY_DB_TYPE`,
+    /// otherwise a new `ProductionBeaconNode`.
+    pub fn new(
+        context: RuntimeContext<E>,
+        client_config: ClientConfig,
+    ) -> impl Future<Item = Self, Error = String> {
+        if client_config.db_type == MEMORY_DB_TYPE {
+            Either::A(DevBeaconNode::new(context, client_config).map(BeaconNode::Dev))
+        } else {
+            Either::B(ProductionBeaconNode::new(context, client_config).map(BeaconNode::Production))
+        }
+    }
+}
+
+/// Returns the ENR of the node which would be started with the given `matches`, without starting
+/// it.
+///
+/// Nothing is written to disk: the network key is only read, never generated, and an ENR with a
+/// new sequence number is not stored. Returns an error if the node has no network key yet.
+pub fn local_enr_from_cli(matches: &ArgMatches, log: &Logger) -> Result<Enr, String> {
+    let network_config = get_network_config(matches)?;
+
+    let local_key = read_private_key(&network_config)
+        .map_err(|e| format!("Unable to read network key: {}", e))?;
+    build_enr(&local_key, &network_config, log)
+}
+
+/// Returns the base64-encoded `enr` and, if it has an IP address and TCP port, its boot node
+/// multiaddr, one per line.
+pub fn boot_node_details(enr: &Enr) -> String {
+    let mut details = format!("ENR: {}", enr.to_base64());
+    if let Some(multiaddr) = eth2_libp2p::boot_node_multiaddr(enr) {
+        details.push_str(&format!("\nMultiaddr: {}", multiaddr));
+    }
+    details
+}
+
 /// The beacon node `Client` that will be used in production.
 ///
 /// Generic over some `EthSpec`.
@@ -77,12 +152,15 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
     ) -> impl Future<Item = Self, Error = String> {
         let http_eth2_config = context.eth2_config().clone();
         let spec = context.eth2_config().spec.clone();
-        let genesis_eth1_config = client_config.eth1.clone();
-        let client_genesis = client_config.genesis.clone();
         let log = context.log.clone();
 
         let db_path_res = client_config.create_db_path();
+        let freezer_compression = client_config.freezer_compression;
         let freezer_db_path_res = client_config.create_freezer_db_path();
+        let migrator_config = MigratorConfig {
+            max_finality_distance: client_config.prune_depth_epochs * E::slots_per_epoch(),
+            max_hot_db_size: client_config.max_db_size,
+        };
 
         db_path_res
             .into_future()
@@ -90,54 +168,15 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
                 Ok(ClientBuilder::new(context.eth_spec_instance.clone())
                     .runtime_context(context)
                     .chain_spec(spec)
-                    .disk_store(&db_path, &freezer_db_path_res?)?
-                    .background_migrator()?)
-            })
-            .and_then(move |builder| {
-                builder.beacon_chain_builder(client_genesis, genesis_eth1_config)
-            })
-            .and_then(move |builder| {
-                let builder = if client_config.sync_eth1_chain && !client_config.dummy_eth1_backend
-                {
-                    info!(
-                        log,
-                        "Block production enabled";
-                        "endpoint" => &client_config.eth1.endpoint,
-                        "method" => "json rpc via http"
-                    );
-                    builder.caching_eth1_backend(client_config.eth1.clone())?
-                } else if client_config.dummy_eth1_backend {
-                    warn!(
-                        log,
-                        "Block production impaired";
-                        "reason" => "dummy eth1 backend is enabled"
-                    );
-                    builder.dummy_eth1_backend()?
-                } else {
-                    info!(
-                        log,
-                        "Block production disabled";
-                        "reason" => "no eth1 backend configured"
-                    );
-                    builder.no_eth1_backend()?
-                };
-
-                let builder = builder
-                    .system_time_slot_clock()?
-                    .websocket_event_handler(client_config.websocket_server.clone())?
-                    .build_beacon_chain()?
-                    .libp2p_network(&client_config.network)?;
-
-                let builder = if client_config.rest_api.enabled {
-                    builder.http_server(&client_config, &http_eth2_config)?
-                } else {
-                    builder
-                };
-
-                let builder = builder.peer_count_notifier()?.slot_notifier()?;
-
-                Ok(Self(builder.build()))
+                    .disk_store(
+                        &db_path,
+                        &freezer_db_path_res?,
+                        freezer_compression,
+                    )?
+                    .background_migrator(migrator_config)?)
             })
+            .and_then(move |builder| build_client(builder, client_config, http_eth2_config, log))
+            .map(Self)
     }
 
     pub fn into_inner(self) -> ProductionClient<E> {
@@ -145,6 +184,115 @@ impl<E: EthSpec> ProductionBeaconNode<E> {
     }
 }
 
+/// A beacon node `Client` which stores the chain in memory, never touching the filesystem with
+/// its database.
+///
+/// Intended for testing and ephemeral development nodes.
+pub struct DevBeaconNode<E: EthSpec>(DevClient<E>);
+
+impl<E: EthSpec> DevBeaconNode<E> {
+    /// Starts a new in-memory beacon node `Client` in the given `environment`.
+    ///
+    /// Client behaviour is defined by the given `client_config`, the database paths it specifies
+    /// are ignored.
+    pub fn new(
+        context: RuntimeContext<E>,
+        client_config: ClientConfig,
+    ) -> impl Future<Item = Self, Error = String> {
+        let http_eth2_config = context.eth2_config().clone();
+        let spec = context.eth2_config().spec.clone();
+        let log = context.log.clone();
+
+        // The `NullMigrator` is used, there are no freezer database or background migrations.
+        let builder = ClientBuilder::new(context.eth_spec_instance.clone())
+            .runtime_context(context)
+            .chain_spec(spec)
+            .memory_store();
+
+        build_client(builder, client_config, http_eth2_config, log).map(Self)
+    }
+
+    pub fn into_inner(self) -> DevClient<E> {
+        self.0
+    }
+}
+
+/// Builds a `Client` from a `builder` which has been given its runtime context, chain spec, store
+/// and store migrator.
+fn build_client<E, TStore, TStoreMigrator>(
+    builder: ClientBuilder<BeaconNodeWitness<TStore, TStoreMigrator, E>>,
+    client_config: ClientConfig,
+    http_eth2_config: Eth2Config,
+    log: Logger,
+) -> impl Future<Item = Client<BeaconNodeWitness<TStore, TStoreMigrator, E>>, Error = String>
+where
+    E: EthSpec,
+    TStore: Store + 'static,
+    TStoreMigrator: Migrate<TStore, E>,
+{
+    let genesis_eth1_config = client_config.eth1.clone();
+    let client_genesis = client_config.genesis.clone();
+
+    builder
+        .beacon_chain_builder(client_genesis, genesis_eth1_config)
+        .and_then(move |builder| {
+            let builder = if client_config.sync_eth1_chain && !client_config.dummy_eth1_backend {
+                info!(
+                    log,
+                    "Block production enabled";
+                    "endpoint" => &client_config.eth1.endpoint,
+                    "method" => "json rpc via http",
+                    "min_peers" => client_config.rest_api.min_peers_for_production
+                );
+                builder.caching_eth1_backend(client_config.eth1.clone())?
+            } else if client_config.dummy_eth1_backend {
+                warn!(
+                    log,
+                    "Block production impaired";
+                    "reason" => "dummy eth1 backend is enabled"
+                );
+                builder.dummy_eth1_backend()?
+            } else {
+                info!(
+                    log,
+                    "Block production disabled";
+                    "reason" => "no eth1 backend configured"
+                );
+                builder.no_eth1_backend()?
+            };
+
+            let builder = builder
+                .system_time_slot_clock()?
+                .websocket_event_handler(client_config.websocket_server.clone())?
+                .build_beacon_chain()?
+                .libp2p_network(&client_config.network)?;
+
+            let builder = if client_config.rest_api.enabled {
+                builder.http_server(&client_config, &http_eth2_config)?
+            } else {
+                builder
+            };
+
+            let builder = if client_config.enable_peer_notifier {
+                builder.peer_count_notifier()?
+            } else {
+                builder
+            };
+
+            let builder = if client_config.enable_slot_notifier {
+                builder.slot_notifier(
+                    client_config.sync_tolerance_slots,
+                    client_config.sync_stall_threshold,
+                    None,
+                )?
+            } else {
+                builder
+            };
+
+            Ok(builder.build())
+        })
+}
+
 impl<E: EthSpec> Deref for ProductionBeaconNode<E> {
     type Target = ProductionClient<E>;
 
@@ -158,3 +306,83 @@ impl<E: EthSpec> DerefMut for ProductionBeaconNode<E> {
         &mut self.0
     }
 }
+
+impl<E: EthSpec> Deref for DevBeaconNode<E> {
+    type Target = DevClient<E>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<E: EthSpec> DerefMut for DevBeaconNode<E> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth2_libp2p::{identity::Keypair, PeerId, NETWORK_KEY_FILENAME};
+    use std::fs;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn null_logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    fn print_enr_matches<'a>(datadir: &Path) -> ArgMatches<'a> {
+        cli_app().get_matches_from(vec![
+            "beacon_node",
+            "--datadir",
+            datadir.to_str().expect("datadir should be utf-8"),
+            "--listen-address",
+            "127.0.0.1",
+            "--port",
+            "19100",
+            "--print-enr-and-exit",
+        ])
+    }
+
+    #[test]
+    fn print_enr_reads_existing_key_only() {
+        let datadir = TempDir::new("lighthouse_print_enr").expect("should create temp dir");
+        let network_dir = datadir.path().join("network");
+        fs::create_dir_all(&network_dir).expect("should create network dir");
+
+        let keypair = Keypair::generate_secp256k1();
+        if let Keypair::Secp256k1(key) = &keypair {
+            fs::write(
+                network_dir.join(NETWORK_KEY_FILENAME),
+                &key.secret().to_bytes(),
+            )
+            .expect("should write key file");
+        }
+
+        let enr = local_enr_from_cli(&print_enr_matches(datadir.path()), &null_logger())
+            .expect("should build the enr");
+        assert_eq!(enr.peer_id(), PeerId::from(keypair.public()));
+        assert_eq!(
+            boot_node_details(&enr),
+            format!(
+                "ENR: {}\nMultiaddr: /ip4/127.0.0.1/tcp/19100/p2p/{}",
+                enr.to_base64(),
+                enr.peer_id()
+            )
+        );
+
+        // neither a config nor an ENR was written
+        assert_eq!(fs::read_dir(datadir.path()).unwrap().count(), 1);
+        assert_eq!(fs::read_dir(&network_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn print_enr_without_key() {
+        let datadir = TempDir::new("lighthouse_print_enr").expect("should create temp dir");
+
+        assert!(local_enr_from_cli(&print_enr_matches(datadir.path()), &null_logger()).is_err());
+        assert_eq!(fs::read_dir(datadir.path()).unwrap().count(), 0);
+    }
+}
@@ -3,18 +3,35 @@ use crate::chunked_vector::{
 };
 use crate::iter::StateRootsIterator;
 use crate::{
-    leveldb_store::LevelDB, DBColumn, Error, PartialBeaconState, SimpleStoreItem, Store, StoreItem,
+    leveldb_store::{CompressionLevel, LevelDB},
+    DBColumn, Error, PartialBeaconState, SimpleStoreItem, Store, StoreItem,
 };
 use parking_lot::RwLock;
 use slog::{info, trace, Logger};
 use ssz::{Decode, Encode};
 use std::convert::TryInto;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use types::*;
 
 /// 32-byte key for accessing the `split_slot` of the freezer DB.
 pub const SPLIT_SLOT_DB_KEY: &str = "FREEZERDBSPLITSLOTFREEZERDBSPLIT";
+/// 32-byte key for accessing the schema version of the database.
+pub const SCHEMA_VERSION_DB_KEY: &str = "SCHEMAVERSIONSCHEMAVERSIONSCHEMA";
+/// 32-byte key for accessing whether the freezer DB is compressed.
+pub const FREEZER_COMPRESSION_DB_KEY: &str = "FREEZERCOMPRESSIONFREEZERCOMPRES";
+
+/// The version of the database schema written by this build.
+///
+/// Databases written before the schema version was stored are version `0`, which has the same
+/// format as version `1`. Version `2` encodes an `Option` as an SSZ union with a one-byte
+/// selector, rather than a four-byte index, which changes the stored committee caches of beacon
+/// states and the nodes of the fork choice tree.
+///
+/// The values of older versions can't be decoded by this build, so those databases must be
+/// removed and the chain re-synced.
+pub const CURRENT_SCHEMA_VERSION: u64 = 2;
 
 pub struct HotColdDB {
     /// The slot before which all data is stored in the cold database.
@@ -26,6 +43,8 @@ pub struct HotColdDB {
     cold_db: LevelDB,
     /// Hot database containing duplicated but quick-to-access recent data.
     hot_db: LevelDB,
+    /// The directory of the hot database.
+    hot_path: PathBuf,
     /// Chain spec.
     spec: ChainSpec,
     /// Logger.
@@ -38,6 +57,13 @@ pub enum HotColdDbError {
         current_split_slot: Slot,
         proposed_split_slot: Slot,
     },
+    /// The database was written by a newer build which uses an unsupported schema.
+    SchemaVersionTooNew { stored: u64, supported: u64 },
+    /// The database uses an older schema which cannot be migrated by this build.
+    UnsupportedSchemaMigration { from: u64 },
+    /// The freezer database was written with (or without) compression, and opened without (or
+    /// with) it.
+    FreezerCompressionMismatch { stored: bool, requested: bool },
 }
 
 impl Store for HotColdDB {
@@ -157,16 +183,27 @@ impl Store for HotColdDB {
 }
 
 impl HotColdDB {
+    /// Opens the hot and cold databases, checking that their schema version is supported.
+    ///
+    /// A database with an older or newer schema version than `CURRENT_SCHEMA_VERSION` results in
+    /// an error.
+    ///
+    /// Values in the cold database are compressed if `freezer_compression` is set. Whether the
+    /// cold database is compressed is stored in the hot database, and a database opened with a
+    /// different setting results in an error (the compression level may change). The hot database
+    /// is never compressed.
     pub fn open(
         hot_path: &Path,
         cold_path: &Path,
         spec: ChainSpec,
+        freezer_compression: Option<CompressionLevel>,
         log: Logger,
     ) -> Result<Self, Error> {
         let db = HotColdDB {
             split_slot: RwLock::new(Slot::new(0)),
-            cold_db: LevelDB::open(cold_path)?,
+            cold_db: LevelDB::open_with_compression(cold_path, freezer_compression)?,
             hot_db: LevelDB::open(hot_path)?,
+            hot_path: hot_path.to_path_buf(),
             spec,
             log,
         };
@@ -175,9 +212,85 @@ impl HotColdDB {
         if let Some(split_slot) = db.load_split_slot()? {
             *db.split_slot.write() = split_slot;
         }
+        db.check_schema_version()?;
+        db.check_freezer_compression(freezer_compression.is_some())?;
         Ok(db)
     }
 
+    /// Returns the schema version stored in the database, if any.
+    pub fn load_schema_version(&self) -> Result<Option<u64>, Error> {
+        let key = Hash256::from_slice(SCHEMA_VERSION_DB_KEY.as_bytes());
+        let schema_version: Option<SchemaVersion> = self.hot_db.get(&key)?;
+        Ok(schema_version.map(|v| v.0))
+    }
+
+    /// Stores `schema_version` as the schema version of the database.
+    pub fn store_schema_version(&self, schema_version: u64) -> Result<(), Error> {
+        let key = Hash256::from_slice(SCHEMA_VERSION_DB_KEY.as_bytes());
+        self.hot_db.put(&key, &SchemaVersion(schema_version))?;
+        Ok(())
+    }
+
+    /// Ensures the database is at `CURRENT_SCHEMA_VERSION`.
+    ///
+    /// A database without a schema version is new, and has the current version stored, only if
+    /// it holds no data. Otherwise it was written before the schema version was stored and is
+    /// version `0`.
+    fn check_schema_version(&self) -> Result<(), Error> {
+        let stored = match self.load_schema_version()? {
+            Some(stored) => stored,
+            None if self.is_empty()? => return self.store_schema_version(CURRENT_SCHEMA_VERSION),
+            None => 0,
+        };
+
+        if stored > CURRENT_SCHEMA_VERSION {
+            return Err(HotColdDbError::SchemaVersionTooNew {
+                stored,
+                supported: CURRENT_SCHEMA_VERSION,
+            }
+            .into());
+        }
+
+        if stored < CURRENT_SCHEMA_VERSION {
+            return Err(HotColdDbError::UnsupportedSchemaMigration { from: stored }.into());
+        }
+
+        Ok(())
+    }
+
+    /// Ensures the cold database was written with compression if `compressed` is `true`, or
+    /// without it otherwise.
+    ///
+    /// A database without the setting stored has `compressed` stored if the cold database is
+    /// empty. Otherwise the cold database was written before the setting was stored, without
+    /// compression.
+    fn check_freezer_compression(&self, compressed: bool) -> Result<(), Error> {
+        let key = Hash256::from_slice(FREEZER_COMPRESSION_DB_KEY.as_bytes());
+        let stored = match self.hot_db.get::<FreezerCompression>(&key)? {
+            None if !self.cold_db.is_empty() || self.load_split_slot()?.is_some() => {
+                self.hot_db.put(&key, &FreezerCompression(false))?;
+                Some(FreezerCompression(false))
+            }
+            stored => stored,
+        };
+        match stored {
+            Some(FreezerCompression(stored)) if stored != compressed => {
+                Err(HotColdDbError::FreezerCompressionMismatch {
+                    stored,
+                    requested: compressed,
+                }
+                .into())
+            }
+            Some(_) => Ok(()),
+            None => self.hot_db.put(&key, &FreezerCompression(compressed)),
+        }
+    }
+
+    /// Returns `true` if neither the hot nor the cold database holds any data.
+    fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.hot_db.is_empty() && self.cold_db.is_empty() && self.load_split_slot()?.is_none())
+    }
+
     pub fn store_archive_state<E: EthSpec>(
         &self,
         state_root: &Hash256,
@@ -227,6 +340,18 @@ impl HotColdDB {
         *self.split_slot.read()
     }
 
+    /// Returns the total size in bytes of the files of the hot database.
+    pub fn hot_db_size(&self) -> std::io::Result<u64> {
+        let mut size = 0;
+        for entry in fs::read_dir(&self.hot_path)? {
+            let metadata = entry?.metadata()?;
+            if metadata.is_file() {
+                size += metadata.len();
+            }
+        }
+        Ok(size)
+    }
+
     fn load_split_slot(&self) -> Result<Option<Slot>, Error> {
         let key = Hash256::from_slice(SPLIT_SLOT_DB_KEY.as_bytes());
         let split_slot: Option<SplitSlot> = self.hot_db.get(&key)?;
@@ -241,6 +366,42 @@ impl HotColdDB {
     }
 }
 
+/// Struct for storing the schema version in the database.
+#[derive(Clone, Copy)]
+struct SchemaVersion(u64);
+
+impl SimpleStoreItem for SchemaVersion {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.0.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(SchemaVersion(u64::from_ssz_bytes(bytes)?))
+    }
+}
+
+/// Struct for storing whether the freezer database is compressed.
+#[derive(Clone, Copy)]
+struct FreezerCompression(bool);
+
+impl SimpleStoreItem for FreezerCompression {
+    fn db_column() -> DBColumn {
+        DBColumn::BeaconMeta
+    }
+
+    fn as_store_bytes(&self) -> Vec<u8> {
+        self.0.as_ssz_bytes()
+    }
+
+    fn from_store_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(FreezerCompression(bool::from_ssz_bytes(bytes)?))
+    }
+}
+
 /// Struct for storing the split slot in the database.
 #[derive(Clone, Copy)]
 struct SplitSlot(u64);
@@ -5,24 +5,48 @@ use db_key::Key;
 use leveldb::database::kv::KV;
 use leveldb::database::Database;
 use leveldb::error::Error as LevelDBError;
+use leveldb::iterator::Iterable;
 use leveldb::options::{Options, ReadOptions, WriteOptions};
 use std::path::Path;
 
+/// The zstd compression level of the values in a `LevelDB`.
+pub type CompressionLevel = i32;
+
 /// A wrapped leveldb database.
 pub struct LevelDB {
     db: Database<BytesKey>,
+    /// If set, values are compressed with zstd at this level when written and decompressed when
+    /// read.
+    compression: Option<CompressionLevel>,
 }
 
 impl LevelDB {
     /// Open a database at `path`, creating a new database if one does not already exist.
     pub fn open(path: &Path) -> Result<Self, Error> {
+        Self::open_with_compression(path, None)
+    }
+
+    /// Open a database at `path` (see `open`), compressing values with zstd if `compression` is
+    /// set.
+    ///
+    /// The values are not tagged as compressed, so a database must always be opened with (or
+    /// always without) compression.
+    pub fn open_with_compression(
+        path: &Path,
+        compression: Option<CompressionLevel>,
+    ) -> Result<Self, Error> {
         let mut options = Options::new();
 
         options.create_if_missing = true;
 
         let db = Database::open(path, options)?;
 
-        Ok(Self { db })
+        Ok(Self { db, compression })
+    }
+
+    /// Returns `true` if the database holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.db.keys_iter(self.read_options()).next().is_none()
     }
 
     fn read_options(&self) -> ReadOptions<BytesKey> {
@@ -62,7 +86,7 @@ impl Store for LevelDB {
 
         metrics::inc_counter(&metrics::DISK_DB_READ_COUNT);
 
-        let result = self
+        let result: Result<Option<Vec<u8>>, Error> = self
             .db
             .get(self.read_options(), column_key)
             .map_err(Into::into);
@@ -71,13 +95,33 @@ impl Store for LevelDB {
             metrics::inc_counter_by(&metrics::DISK_DB_READ_BYTES, bytes.len() as i64)
         }
 
-        result
+        match (result?, self.compression) {
+            (Some(bytes), Some(_)) => {
+                zstd::decode_all(bytes.as_slice())
+                    .map(Some)
+                    .map_err(|e| Error::DBError {
+                        message: format!("Failed to decompress value: {:?}", e),
+                    })
+            }
+            (bytes, _) => Ok(bytes),
+        }
     }
 
     /// Store some `value` in `column`, indexed with `key`.
     fn put_bytes(&self, col: &str, key: &[u8], val: &[u8]) -> Result<(), Error> {
         let column_key = Self::get_key_for_col(col, key);
 
+        let compressed;
+        let val = match self.compression {
+            Some(level) => {
+                compressed = zstd::encode_all(val, level).map_err(|e| Error::DBError {
+                    message: format!("Failed to compress value: {:?}", e),
+                })?;
+                compressed.as_slice()
+            }
+            None => val,
+        };
+
         metrics::inc_counter(&metrics::DISK_DB_WRITE_COUNT);
         metrics::inc_counter_by(&metrics::DISK_DB_WRITE_BYTES, val.len() as i64);
 
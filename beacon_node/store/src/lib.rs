@@ -26,6 +26,8 @@ pub mod migrate;
 use std::sync::Arc;
 
 pub use self::hot_cold_store::HotColdDB as DiskStore;
+pub use self::hot_cold_store::{HotColdDbError, CURRENT_SCHEMA_VERSION};
+pub use self::leveldb_store::CompressionLevel;
 pub use self::leveldb_store::LevelDB as SimpleDiskStore;
 pub use self::memory_store::MemoryStore;
 pub use self::migrate::Migrate;
@@ -215,7 +217,7 @@ mod tests {
     use super::*;
     use ssz::{Decode, Encode};
     use ssz_derive::{Decode, Encode};
-    use tempfile::tempdir;
+    use tempfile::{tempdir, TempDir};
 
     #[derive(PartialEq, Debug, Encode, Decode)]
     struct StorableThing {
@@ -265,11 +267,177 @@ mod tests {
         let cold_dir = tempdir().unwrap();
         let spec = MinimalEthSpec::default_spec();
         let log = NullLoggerBuilder.build().unwrap();
-        let store = DiskStore::open(&hot_dir.path(), &cold_dir.path(), spec, log).unwrap();
+        let store = DiskStore::open(&hot_dir.path(), &cold_dir.path(), spec, None, log).unwrap();
 
         test_impl(store);
     }
 
+    #[test]
+    fn diskdb_schema_version() {
+        use crate::hot_cold_store::SCHEMA_VERSION_DB_KEY;
+        use sloggers::{null::NullLoggerBuilder, Build};
+
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+        let open = || {
+            let spec = MinimalEthSpec::default_spec();
+            let log = NullLoggerBuilder.build().unwrap();
+            DiskStore::open(&hot_dir.path(), &cold_dir.path(), spec, None, log)
+        };
+        // Writes the schema version directly, as a store with an unsupported version can't be
+        // opened.
+        let set_schema_version = |schema_version: u64| {
+            SimpleDiskStore::open(&hot_dir.path())
+                .unwrap()
+                .put_bytes(
+                    DBColumn::BeaconMeta.into(),
+                    SCHEMA_VERSION_DB_KEY.as_bytes(),
+                    &schema_version.as_ssz_bytes(),
+                )
+                .unwrap();
+        };
+
+        // A new database has the current schema version stored.
+        let store = open().unwrap();
+        assert_eq!(
+            store.load_schema_version(),
+            Ok(Some(CURRENT_SCHEMA_VERSION))
+        );
+        drop(store);
+
+        // A database from a newer build is never opened.
+        set_schema_version(CURRENT_SCHEMA_VERSION + 1);
+        assert_eq!(
+            open().err(),
+            Some(Error::HotColdDbError(HotColdDbError::SchemaVersionTooNew {
+                stored: CURRENT_SCHEMA_VERSION + 1,
+                supported: CURRENT_SCHEMA_VERSION,
+            }))
+        );
+
+        // A database from an older build is never opened.
+        for stored in 0..CURRENT_SCHEMA_VERSION {
+            set_schema_version(stored);
+            assert_eq!(
+                open().err(),
+                Some(Error::HotColdDbError(
+                    HotColdDbError::UnsupportedSchemaMigration { from: stored }
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn diskdb_without_schema_version() {
+        use crate::hot_cold_store::SCHEMA_VERSION_DB_KEY;
+        use sloggers::{null::NullLoggerBuilder, Build};
+
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+
+        // A database written before the schema version was stored.
+        let block = BeaconBlock::<MinimalEthSpec>::empty(&MinimalEthSpec::default_spec());
+        SimpleDiskStore::open(&hot_dir.path())
+            .unwrap()
+            .put(&block.canonical_root(), &block)
+            .unwrap();
+
+        let spec = MinimalEthSpec::default_spec();
+        let log = NullLoggerBuilder.build().unwrap();
+        assert_eq!(
+            DiskStore::open(&hot_dir.path(), &cold_dir.path(), spec, None, log).err(),
+            Some(Error::HotColdDbError(
+                HotColdDbError::UnsupportedSchemaMigration { from: 0 }
+            ))
+        );
+
+        // The database is not stamped with the current version.
+        assert_eq!(
+            SimpleDiskStore::open(&hot_dir.path())
+                .unwrap()
+                .get_bytes(
+                    DBColumn::BeaconMeta.into(),
+                    SCHEMA_VERSION_DB_KEY.as_bytes()
+                )
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn diskdb_freezer_compression() {
+        use crate::hot_cold_store::FREEZER_COMPRESSION_DB_KEY;
+        use sloggers::{null::NullLoggerBuilder, Build};
+
+        let open = |hot_dir: &TempDir, cold_dir: &TempDir, freezer_compression| {
+            let spec = MinimalEthSpec::default_spec();
+            let log = NullLoggerBuilder.build().unwrap();
+            DiskStore::open(
+                &hot_dir.path(),
+                &cold_dir.path(),
+                spec,
+                freezer_compression,
+                log,
+            )
+        };
+
+        // A new database stores the setting it was opened with, the level may change.
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+        drop(open(&hot_dir, &cold_dir, Some(3)).unwrap());
+        drop(open(&hot_dir, &cold_dir, Some(5)).unwrap());
+
+        assert_eq!(
+            open(&hot_dir, &cold_dir, None).err(),
+            Some(Error::HotColdDbError(
+                HotColdDbError::FreezerCompressionMismatch {
+                    stored: true,
+                    requested: false,
+                }
+            ))
+        );
+
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+        drop(open(&hot_dir, &cold_dir, None).unwrap());
+        assert_eq!(
+            open(&hot_dir, &cold_dir, Some(3)).err(),
+            Some(Error::HotColdDbError(
+                HotColdDbError::FreezerCompressionMismatch {
+                    stored: false,
+                    requested: true,
+                }
+            ))
+        );
+
+        // A freezer written before the setting was stored is not compressed.
+        let hot_dir = tempdir().unwrap();
+        let cold_dir = tempdir().unwrap();
+        drop(open(&hot_dir, &cold_dir, None).unwrap());
+        SimpleDiskStore::open(&hot_dir.path())
+            .unwrap()
+            .key_delete(
+                DBColumn::BeaconMeta.into(),
+                FREEZER_COMPRESSION_DB_KEY.as_bytes(),
+            )
+            .unwrap();
+        SimpleDiskStore::open(&cold_dir.path())
+            .unwrap()
+            .put_bytes(DBColumn::BeaconState.into(), &[1; 32], &[2; 64])
+            .unwrap();
+
+        assert_eq!(
+            open(&hot_dir, &cold_dir, Some(3)).err(),
+            Some(Error::HotColdDbError(
+                HotColdDbError::FreezerCompressionMismatch {
+                    stored: false,
+                    requested: true,
+                }
+            ))
+        );
+        drop(open(&hot_dir, &cold_dir, None).unwrap());
+    }
+
     #[test]
     fn simplediskdb() {
         let dir = tempdir().unwrap();
@@ -279,6 +447,43 @@ mod tests {
         test_impl(store);
     }
 
+    #[test]
+    fn compressed_simplediskdb() {
+        let dir = tempdir().unwrap();
+        let store = SimpleDiskStore::open_with_compression(&dir.path(), Some(3)).unwrap();
+
+        test_impl(store);
+    }
+
+    #[test]
+    fn compressed_simplediskdb_round_trip() {
+        let dir = tempdir().unwrap();
+        let column = DBColumn::BeaconState.into();
+        let key = Hash256::random();
+        let value: Vec<u8> = (0..4096).map(|i| (i % 16) as u8).collect();
+
+        let store = SimpleDiskStore::open_with_compression(&dir.path(), Some(3)).unwrap();
+        store.put_bytes(column, key.as_bytes(), &value).unwrap();
+        assert_eq!(
+            store.get_bytes(column, key.as_bytes()),
+            Ok(Some(value.clone()))
+        );
+        drop(store);
+
+        // Read the stored bytes without decompressing them.
+        let stored = SimpleDiskStore::open(&dir.path())
+            .unwrap()
+            .get_bytes(column, key.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert!(
+            stored.len() < value.len() / 4,
+            "compressed {} bytes to {} bytes",
+            value.len(),
+            stored.len()
+        );
+    }
+
     #[test]
     fn memorydb() {
         let store = MemoryStore::open();
@@ -58,9 +58,21 @@ impl<E: EthSpec, S: Store> Migrate<S, E> for BlockingMigrator<S> {
     }
 }
 
+/// The policy of a `BackgroundMigrator`, bounding the size of the hot database.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MigratorConfig {
+    /// The maximum number of finalized slots kept in the hot database, states at older slots are
+    /// migrated to the cold database.
+    pub max_finality_distance: u64,
+    /// If the hot database exceeds this many bytes, all finalized states are migrated to the cold
+    /// database regardless of `max_finality_distance`.
+    pub max_hot_db_size: Option<u64>,
+}
+
 /// Migrator that runs a background thread to migrate state from the hot to the cold database.
 pub struct BackgroundMigrator<E: EthSpec> {
     db: Arc<DiskStore>,
+    config: MigratorConfig,
     tx_thread: Mutex<(
         mpsc::Sender<(Hash256, BeaconState<E>)>,
         thread::JoinHandle<()>,
@@ -69,8 +81,7 @@ pub struct BackgroundMigrator<E: EthSpec> {
 
 impl<E: EthSpec> Migrate<DiskStore, E> for BackgroundMigrator<E> {
     fn new(db: Arc<DiskStore>) -> Self {
-        let tx_thread = Mutex::new(Self::spawn_thread(db.clone()));
-        Self { db, tx_thread }
+        Self::with_config(db, MigratorConfig::default())
     }
 
     /// Perform the freezing operation on the database,
@@ -109,10 +120,46 @@ impl<E: EthSpec> Migrate<DiskStore, E> for BackgroundMigrator<E> {
 }
 
 impl<E: EthSpec> BackgroundMigrator<E> {
+    /// Create a new migrator which migrates states according to the given `config`.
+    pub fn with_config(db: Arc<DiskStore>, config: MigratorConfig) -> Self {
+        let tx_thread = Mutex::new(Self::spawn_thread(db.clone()));
+        Self {
+            db,
+            config,
+            tx_thread,
+        }
+    }
+
     /// Return true if a migration needs to be performed, given a new `finalized_slot`.
+    ///
+    /// The larger of `max_finality_distance` and the distance in the config of `self` is used.
     fn needs_migration(&self, finalized_slot: Slot, max_finality_distance: u64) -> bool {
         let finality_distance = finalized_slot - self.db.get_split_slot();
+        let max_finality_distance =
+            std::cmp::max(max_finality_distance, self.config.max_finality_distance);
+
         finality_distance > max_finality_distance
+            || (finality_distance > 0 && self.hot_db_too_large())
+    }
+
+    /// Return true if the hot database exceeds the maximum size in the config of `self`.
+    fn hot_db_too_large(&self) -> bool {
+        let max_size = match self.config.max_hot_db_size {
+            Some(max_size) => max_size,
+            None => return false,
+        };
+
+        match self.db.hot_db_size() {
+            Ok(size) => size > max_size,
+            Err(e) => {
+                warn!(
+                    self.db.log,
+                    "Unable to read hot database size";
+                    "error" => format!("{:?}", e)
+                );
+                false
+            }
+        }
     }
 
     /// Spawn a new child thread to run the migration process.
@@ -1,10 +1,16 @@
 #![cfg(test)]
 
+use beacon_node::{ClientConfig, ClientGenesis, DevBeaconNode};
 use node_test_rig::{
-    environment::{Environment, EnvironmentBuilder},
+    environment::{Environment, EnvironmentBuilder, TaskTracker},
     testing_client_config, LocalBeaconNode,
 };
-use types::{EthSpec, MinimalEthSpec, Slot};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use store::Store;
+use tempdir::TempDir;
+use types::{BeaconBlock, EthSpec, MinimalEthSpec, Signature, Slot};
 
 fn env_builder() -> EnvironmentBuilder<MinimalEthSpec> {
     EnvironmentBuilder::minimal()
@@ -20,6 +26,42 @@ fn build_node<E: EthSpec>(env: &mut Environment<E>) -> LocalBeaconNode<E> {
         .expect("should block until node created")
 }
 
+/// Returns a testing config with its data in `datadir` and the HTTP API disabled.
+fn dev_client_config(datadir: &TempDir) -> ClientConfig {
+    let mut client_config = testing_client_config();
+    client_config.data_dir = datadir.path().into();
+    client_config.network.network_dir = PathBuf::from(datadir.path()).join("network");
+    client_config.rest_api.enabled = false;
+    client_config
+}
+
+/// Starts a `DevBeaconNode` with `client_config`, returning it and the tracker of the tasks it
+/// spawns.
+fn build_dev_node<E: EthSpec>(
+    env: &mut Environment<E>,
+    client_config: ClientConfig,
+) -> (DevBeaconNode<E>, TaskTracker) {
+    let context = env.core_context();
+    let task_tracker = context.task_tracker.clone();
+    let node = env
+        .runtime()
+        .block_on(DevBeaconNode::new(context, client_config))
+        .expect("should block until node created");
+    (node, task_tracker)
+}
+
+/// Polls `condition` until it holds, returning `false` if `timeout` elapses first.
+fn wait_until<F: Fn() -> bool>(timeout: Duration, condition: F) -> bool {
+    let deadline = Instant::now() + timeout;
+    while !condition() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    true
+}
+
 #[test]
 fn http_server_genesis_state() {
     let mut env = env_builder()
@@ -51,3 +93,164 @@ fn http_server_genesis_state() {
         "genesis state from api should match that from the DB"
     );
 }
+
+#[test]
+fn in_memory_store_produces_and_stores_block() {
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    let datadir = TempDir::new("lighthouse_in_memory_node")
+        .expect("should create temp directory for client datadir");
+
+    let client_config = dev_client_config(&datadir);
+    let (node, _task_tracker) = build_dev_node(&mut env, client_config.clone());
+
+    let beacon_chain = node
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    let (block, _state) = beacon_chain
+        .produce_block(Signature::empty_signature(), Slot::new(1))
+        .expect("should produce block");
+    let block_root = block.canonical_root();
+
+    beacon_chain
+        .store
+        .put(&block_root, &block)
+        .expect("should store block");
+
+    let stored_block: BeaconBlock<MinimalEthSpec> = beacon_chain
+        .store
+        .get(&block_root)
+        .expect("should read block")
+        .expect("block should be in store");
+
+    assert_eq!(
+        stored_block, block,
+        "stored block should match produced block"
+    );
+    assert!(
+        !client_config
+            .get_db_path()
+            .map_or(false, |path| path.exists()),
+        "should not create a database"
+    );
+    assert!(
+        !client_config
+            .get_freezer_db_path()
+            .map_or(false, |path| path.exists()),
+        "should not create a freezer database"
+    );
+}
+
+/// Starts a node with each notifier enabled or disabled, checking which notifier tasks it spawns.
+fn check_notifiers(enable_peer_notifier: bool, enable_slot_notifier: bool) {
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    let datadir = TempDir::new("lighthouse_notifier_node")
+        .expect("should create temp directory for client datadir");
+
+    let mut client_config = dev_client_config(&datadir);
+    client_config.enable_peer_notifier = enable_peer_notifier;
+    client_config.enable_slot_notifier = enable_slot_notifier;
+
+    let (_node, task_tracker) = build_dev_node(&mut env, client_config);
+
+    let running = task_tracker.running();
+    assert_eq!(
+        running.contains(&"peer_notifier".to_string()),
+        enable_peer_notifier,
+        "peer notifier should be running only if enabled"
+    );
+    assert_eq!(
+        running.contains(&"slot_notifier".to_string()),
+        enable_slot_notifier,
+        "slot notifier should be running only if enabled"
+    );
+}
+
+#[test]
+fn notifiers_enabled() {
+    check_notifiers(true, true);
+}
+
+#[test]
+fn notifiers_disabled() {
+    check_notifiers(false, false);
+}
+
+#[test]
+fn notifiers_disabled_independently() {
+    check_notifiers(true, false);
+    check_notifiers(false, true);
+}
+
+#[test]
+fn waits_for_genesis() {
+    let mut env = env_builder()
+        .null_logger()
+        .expect("should build env logger")
+        .multi_threaded_tokio_runtime()
+        .expect("should start tokio runtime")
+        .build()
+        .expect("environment should build");
+
+    let datadir = TempDir::new("lighthouse_pre_genesis_node")
+        .expect("should create temp directory for client datadir");
+
+    // leaves time to build the node and check it before genesis
+    let genesis_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("should get system time")
+        .as_secs()
+        + 8;
+
+    let mut client_config = dev_client_config(&datadir);
+    client_config.genesis = ClientGenesis::Interop {
+        validator_count: 8,
+        genesis_time,
+    };
+
+    let (node, task_tracker) = build_dev_node(&mut env, client_config);
+    let beacon_chain = node
+        .beacon_chain()
+        .expect("client should have beacon chain");
+
+    assert!(beacon_chain.slot().is_err(), "should be before genesis");
+    assert!(
+        beacon_chain
+            .produce_block(Signature::empty_signature(), Slot::new(1))
+            .is_err(),
+        "should not produce a block before genesis"
+    );
+    assert!(
+        task_tracker
+            .running()
+            .contains(&"genesis_countdown".to_string()),
+        "should count down to genesis"
+    );
+
+    assert!(
+        wait_until(Duration::from_secs(30), || beacon_chain.slot().is_ok()),
+        "should reach genesis"
+    );
+    assert!(
+        wait_until(Duration::from_secs(10), || {
+            let running = task_tracker.running();
+            !running.contains(&"genesis_countdown".to_string())
+                && running.contains(&"slot_notifier".to_string())
+        }),
+        "should stop counting down and begin normal operation once genesis arrives"
+    );
+}
@@ -9,6 +9,9 @@ pub struct Config {
     pub listen_address: Ipv4Addr,
     /// The port the REST API HTTP server will listen on.
     pub port: u16,
+    /// The maximum number of events held while no client is connected. They are sent to the next
+    /// client to connect, the oldest being dropped when the buffer is full.
+    pub event_buffer_size: usize,
 }
 
 impl Default for Config {
@@ -17,6 +20,7 @@ impl Default for Config {
             enabled: true,
             listen_address: Ipv4Addr::new(127, 0, 0, 1),
             port: 5053,
+            event_buffer_size: 1_024,
         }
     }
 }
@@ -1,18 +1,79 @@
+use environment::TaskTracker;
 use futures::Future;
+use parking_lot::Mutex;
 use slog::{debug, error, info, warn, Logger};
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::thread;
 use tokio::runtime::TaskExecutor;
 use types::EthSpec;
-use ws::{Sender, WebSocket};
+use ws::{CloseCode, Handler, Handshake, Sender, WebSocket};
 
 mod config;
 
 pub use config::Config;
 
+/// Holds the events sent while no client is connected, so that they can be sent to the next client
+/// to connect.
+struct EventBuffer {
+    /// The number of clients with an open connection.
+    clients: usize,
+    events: VecDeque<String>,
+    max_len: usize,
+    log: Logger,
+}
+
+impl EventBuffer {
+    /// Buffers `event`, dropping the oldest event if the buffer is full.
+    fn push(&mut self, event: String) {
+        if self.events.len() >= self.max_len && self.events.pop_front().is_some() {
+            debug!(
+                self.log,
+                "Dropped websocket event";
+                "reason" => "no connected clients and the event buffer is full",
+                "buffer_size" => self.max_len,
+            );
+        }
+
+        if self.max_len > 0 {
+            self.events.push_back(event);
+        }
+    }
+}
+
+/// A connection to a single websocket client, which ignores any incoming messages.
+struct Client {
+    out: Sender,
+    buffer: Arc<Mutex<EventBuffer>>,
+    /// Set once the connection has opened and the client has been counted in `buffer`.
+    open: bool,
+}
+
+impl Handler for Client {
+    fn on_open(&mut self, _: Handshake) -> ws::Result<()> {
+        let mut buffer = self.buffer.lock();
+        buffer.clients += 1;
+        self.open = true;
+
+        for event in buffer.events.drain(..) {
+            self.out.send(event)?;
+        }
+        Ok(())
+    }
+
+    fn on_close(&mut self, _: CloseCode, _: &str) {
+        if self.open {
+            self.open = false;
+            self.buffer.lock().clients -= 1;
+        }
+    }
+}
+
 pub struct WebSocketSender<T: EthSpec> {
     sender: Option<Sender>,
+    buffer: Option<Arc<Mutex<EventBuffer>>>,
     _phantom: PhantomData<T>,
 }
 
@@ -21,12 +82,23 @@ impl<T: EthSpec> WebSocketSender<T> {
     pub fn dummy() -> Self {
         Self {
             sender: None,
+            buffer: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Broadcasts `string` to all connected clients.
+    ///
+    /// If no client is connected, `string` is buffered and sent to the next client to connect.
     pub fn send_string(&self, string: String) -> Result<(), String> {
-        if let Some(sender) = &self.sender {
+        if let (Some(sender), Some(buffer)) = (&self.sender, &self.buffer) {
+            // Hold the lock whilst broadcasting so a connecting client can't miss the event.
+            let mut buffer = buffer.lock();
+            if buffer.clients == 0 {
+                buffer.push(string);
+                return Ok(());
+            }
+
             sender
                 .send(string)
                 .map_err(|e| format!("Unable to broadcast to websocket clients: {:?}", e))
@@ -34,25 +106,46 @@ impl<T: EthSpec> WebSocketSender<T> {
             Ok(())
         }
     }
+
+    /// Returns the number of clients with an open connection.
+    #[cfg(test)]
+    fn clients(&self) -> usize {
+        self.buffer
+            .as_ref()
+            .map_or(0, |buffer| buffer.lock().clients)
+    }
 }
 
 pub fn start_server<T: EthSpec>(
     config: &Config,
     executor: &TaskExecutor,
+    task_tracker: &TaskTracker,
     log: &Logger,
 ) -> Result<(WebSocketSender<T>, exit_future::Signal, SocketAddr), String> {
     let server_string = format!("{}:{}", config.listen_address, config.port);
 
+    let buffer = Arc::new(Mutex::new(EventBuffer {
+        clients: 0,
+        events: VecDeque::new(),
+        max_len: config.event_buffer_size,
+        log: log.clone(),
+    }));
+
     // Create a server that simply ignores any incoming messages.
-    let server = WebSocket::new(|_| |_| Ok(()))
-        .map_err(|e| format!("Failed to initialize websocket server: {:?}", e))?
-        .bind(server_string.clone())
-        .map_err(|e| {
-            format!(
-                "Failed to bind websocket server to {}: {:?}",
-                server_string, e
-            )
-        })?;
+    let buffer_inner = buffer.clone();
+    let server = WebSocket::new(move |out| Client {
+        out,
+        buffer: buffer_inner.clone(),
+        open: false,
+    })
+    .map_err(|e| format!("Failed to initialize websocket server: {:?}", e))?
+    .bind(server_string.clone())
+    .map_err(|e| {
+        format!(
+            "Failed to bind websocket server to {}: {:?}",
+            server_string, e
+        )
+    })?;
 
     let actual_listen_addr = server.local_addr().map_err(|e| {
         format!(
@@ -84,7 +177,7 @@ pub fn start_server<T: EthSpec>(
 
         // Place a future on the executor that will shutdown the websocket server when the
         // application exits.
-        executor.spawn(exit_future);
+        executor.spawn(task_tracker.track("websocket_server", exit_future));
 
         exit_signal
     };
@@ -116,9 +209,122 @@ pub fn start_server<T: EthSpec>(
     Ok((
         WebSocketSender {
             sender: Some(broadcaster),
+            buffer: Some(buffer),
             _phantom: PhantomData,
         },
         exit_signal,
         actual_listen_addr,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use environment::EnvironmentBuilder;
+    use std::cell::Cell;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use tokio::runtime::Runtime;
+    use types::MinimalEthSpec;
+
+    /// Connects a client to `addr` and returns the first `n` events it receives, closing the
+    /// connection afterwards.
+    fn receive_events(addr: SocketAddr, n: usize) -> Vec<String> {
+        let (tx, rx) = mpsc::channel();
+        let url = format!("ws://{}", addr);
+        let client = thread::spawn(move || {
+            ws::connect(url, |out| {
+                let tx = tx.clone();
+                let received = Cell::new(0);
+                move |msg: ws::Message| {
+                    tx.send(msg.into_text()?).expect("should return event");
+                    received.set(received.get() + 1);
+                    if received.get() == n {
+                        out.close(CloseCode::Normal)
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .expect("should connect to server");
+        });
+
+        let events = (0..n)
+            .map(|_| {
+                rx.recv_timeout(Duration::from_secs(5))
+                    .expect("should receive event")
+            })
+            .collect();
+        client.join().expect("client should disconnect");
+        events
+    }
+
+    #[test]
+    fn replays_events_sent_without_clients() {
+        let runtime = Runtime::new().expect("should start runtime");
+        let log = Logger::root(slog::Discard, slog::o!());
+        let config = Config {
+            port: 0,
+            event_buffer_size: 2,
+            ..Config::default()
+        };
+        let (sender, _exit_signal, addr) = start_server::<MinimalEthSpec>(
+            &config,
+            &runtime.executor(),
+            &TaskTracker::default(),
+            &log,
+        )
+        .expect("should start server");
+
+        for event in &["a", "b", "c"] {
+            sender
+                .send_string(event.to_string())
+                .expect("should send event");
+        }
+        // The oldest event was dropped when the buffer overflowed.
+        assert_eq!(receive_events(addr, 2), vec!["b", "c"]);
+
+        // Once the first client has disconnected, events are buffered for the next.
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while sender.clients() > 0 {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "server should see the client disconnect"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        sender
+            .send_string("d".to_string())
+            .expect("should send event");
+        assert_eq!(receive_events(addr, 1), vec!["d"]);
+    }
+
+    #[test]
+    fn running_server_holds_up_shutdown() {
+        let mut env = EnvironmentBuilder::minimal()
+            .null_logger()
+            .expect("should build env logger")
+            .single_thread_tokio_runtime()
+            .expect("should start tokio runtime")
+            .build()
+            .expect("environment should build");
+        let context = env.core_context();
+        let config = Config {
+            port: 0,
+            ..Config::default()
+        };
+        let (_sender, _exit_signal, _addr) = start_server::<MinimalEthSpec>(
+            &config,
+            &context.executor,
+            &context.task_tracker,
+            &context.log,
+        )
+        .expect("should start server");
+
+        // The server runs until its exit signal is sent, which never happens here.
+        assert_eq!(
+            env.shutdown_on_idle_with_timeout(Duration::from_millis(500)),
+            Ok(vec!["websocket_server".to_string()])
+        );
+    }
+}
@@ -29,4 +29,9 @@ mod tests {
     use super::*;
 
     ssz_tests!(AttestationData);
+
+    #[test]
+    fn ssz_fixed_len() {
+        ssz::assert_ssz_fixed_len!(AttestationData, 128);
+    }
 }
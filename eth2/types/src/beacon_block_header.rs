@@ -59,4 +59,9 @@ mod tests {
     use super::*;
 
     ssz_tests!(BeaconBlockHeader);
+
+    #[test]
+    fn ssz_fixed_len() {
+        ssz::assert_ssz_fixed_len!(BeaconBlockHeader, 200);
+    }
 }
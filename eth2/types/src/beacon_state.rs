@@ -13,7 +13,7 @@ use ssz_derive::{Decode, Encode};
 use ssz_types::{typenum::Unsigned, BitVector, FixedVector};
 use swap_or_not_shuffle::compute_shuffled_index;
 use test_random_derive::TestRandom;
-use tree_hash::TreeHash;
+use tree_hash::{TreeHash, TreeHashFields};
 use tree_hash_derive::{CachedTreeHash, TreeHash};
 
 pub use self::committee_cache::CommitteeCache;
@@ -257,6 +257,21 @@ impl<T: EthSpec> BeaconState<T> {
         Hash256::from_slice(&self.tree_hash_root()[..])
     }
 
+    /// Returns the root of each of the hashed fields of the state, in the order in which they are
+    /// hashed.
+    ///
+    /// The root of the state is `tree_hash::merkleize_roots(&state.field_roots(), 0)`.
+    pub fn field_roots(&self) -> Vec<[u8; 32]> {
+        self.tree_hash_field_roots()
+            .iter()
+            .map(|field_root| {
+                let mut root = [0; 32];
+                root.copy_from_slice(field_root);
+                root
+            })
+            .collect()
+    }
+
     pub fn historical_batch(&self) -> HistoricalBatch<T> {
         HistoricalBatch {
             block_roots: self.block_roots.clone(),
@@ -144,6 +144,21 @@ fn tree_hash_cache() {
     assert_eq!(root.as_bytes(), &state.tree_hash_root()[..]);
 }
 
+#[test]
+fn field_roots() {
+    use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use tree_hash::{merkleize_roots, TreeHash};
+
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+
+    let state: FoundationBeaconState = BeaconState::random_for_test(&mut rng);
+
+    assert_eq!(
+        &merkleize_roots(&state.field_roots(), 0)[..],
+        &state.tree_hash_root()[..]
+    );
+}
+
 /// Tests committee-specific components
 #[cfg(test)]
 mod committees {
@@ -32,4 +32,9 @@ mod tests {
     use super::*;
 
     ssz_tests!(Checkpoint);
+
+    #[test]
+    fn ssz_fixed_len() {
+        ssz::assert_ssz_fixed_len!(Checkpoint, 40);
+    }
 }
@@ -34,4 +34,9 @@ mod tests {
     use super::*;
 
     ssz_tests!(Eth1Data);
+
+    #[test]
+    fn ssz_fixed_len() {
+        ssz::assert_ssz_fixed_len!(Eth1Data, 72);
+    }
 }
@@ -45,6 +45,11 @@ mod tests {
 
     ssz_tests!(Fork);
 
+    #[test]
+    fn ssz_fixed_len() {
+        ssz::assert_ssz_fixed_len!(Fork, 16);
+    }
+
     #[test]
     fn get_fork_version() {
         let previous_version = [1; 4];
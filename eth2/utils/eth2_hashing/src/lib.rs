@@ -3,11 +3,25 @@
 //! The purpose of this crate is to provide an abstraction to whatever hash function Ethereum
 //! 2.0 is using. The hash function has been subject to change during the specification process, so
 //! defining it once in this crate makes it easy to replace.
+//!
+//! When built with the `no_std` feature in place of `std` (or for `wasm32`) the pure-Rust `sha2`
+//! crate is used. Without `std` the crate is `no_std`, only requiring `alloc`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "no_std", target_arch = "wasm32")))]
+compile_error!("either the `std` or the `no_std` feature of `eth2_hashing` must be enabled");
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 use ring::digest::{digest, SHA256};
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(any(not(feature = "std"), target_arch = "wasm32"))]
 use sha2::{Digest, Sha256};
 
 #[cfg(feature = "zero_hash_cache")]
@@ -15,12 +29,12 @@ use lazy_static::lazy_static;
 
 /// Returns the digest of `input`.
 ///
-/// Uses `ring::digest::SHA256`.
+/// Uses `ring::digest::SHA256`, or `sha2::Sha256` without `std` or on `wasm32`.
 pub fn hash(input: &[u8]) -> Vec<u8> {
-    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
     let h = digest(&SHA256, input).as_ref().into();
 
-    #[cfg(target_arch = "wasm32")]
+    #[cfg(any(not(feature = "std"), target_arch = "wasm32"))]
     let h = Sha256::digest(input).as_ref().into();
 
     h
@@ -33,6 +47,29 @@ pub fn hash_concat(h1: &[u8], h2: &[u8]) -> Vec<u8> {
     hash(&vec1)
 }
 
+/// Compute the hash of each pair of slices concatenated, returning the digests in the same order as
+/// `pairs`.
+///
+/// Each output is identical to `hash_concat(left, right)`. The pairs are independent of each other,
+/// providing a single point where a backend able to process several messages at once (e.g., using
+/// SHA extensions) can be used. Presently each pair is hashed in turn.
+pub fn hash_concat_batch(pairs: &[(&[u8], &[u8])]) -> Vec<[u8; 32]> {
+    let mut preimage = Vec::with_capacity(64);
+
+    pairs
+        .iter()
+        .map(|(left, right)| {
+            preimage.clear();
+            preimage.extend_from_slice(left);
+            preimage.extend_from_slice(right);
+
+            let mut digest = [0; 32];
+            digest.copy_from_slice(&hash(&preimage));
+            digest
+        })
+        .collect()
+}
+
 /// The max index that can be used with `ZERO_HASHES`.
 #[cfg(feature = "zero_hash_cache")]
 pub const ZERO_HASHES_MAX_INDEX: usize = 48;
@@ -70,6 +107,43 @@ mod tests {
         assert_eq!(expected, output);
     }
 
+    fn pairs(n: u8) -> Vec<(Vec<u8>, Vec<u8>)> {
+        (0..n).map(|i| (vec![i; 32], vec![i + 1; 32])).collect()
+    }
+
+    fn test_batch_against_scalar(n: u8) {
+        let pairs = pairs(n);
+        let refs: Vec<(&[u8], &[u8])> = pairs
+            .iter()
+            .map(|(left, right)| (left.as_slice(), right.as_slice()))
+            .collect();
+
+        let batched = hash_concat_batch(&refs);
+
+        assert_eq!(batched.len(), pairs.len());
+        for ((left, right), digest) in pairs.iter().zip(batched.iter()) {
+            assert_eq!(hash_concat(left, right), digest.to_vec());
+        }
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hash_concat_batch_empty() {
+        assert!(hash_concat_batch(&[]).is_empty());
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hash_concat_batch_odd_pairs() {
+        test_batch_against_scalar(3);
+    }
+
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hash_concat_batch_even_pairs() {
+        test_batch_against_scalar(4);
+    }
+
     #[cfg(feature = "zero_hash_cache")]
     mod zero_hash {
         use super::*;
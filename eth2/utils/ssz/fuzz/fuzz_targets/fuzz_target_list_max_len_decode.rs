@@ -0,0 +1,23 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate ssz;
+
+use ssz::{decode_list_with_max_len, DecodeError, BYTES_PER_LENGTH_OFFSET};
+
+const MAX_LEN: usize = 16;
+
+// Fuzz ssz_decode of a bounded list of variable-length items
+fuzz_target!(|data: &[u8]| {
+    match decode_list_with_max_len::<Vec<u8>>(data, MAX_LEN) {
+        Ok(list) => {
+            assert!(list.len() <= MAX_LEN);
+            assert!(list.len() * BYTES_PER_LENGTH_OFFSET <= data.len());
+        }
+        Err(DecodeError::ListTooLong { len, max_len }) => {
+            assert_eq!(max_len, MAX_LEN);
+            assert!(len > MAX_LEN);
+            assert!(len * BYTES_PER_LENGTH_OFFSET <= data.len());
+        }
+        Err(_) => {}
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate ssz;
+
+use ssz::{Decode, DecodeError, Encode};
+
+// Fuzz ssz_decode of an Option<u64> union, checking that it round trips
+fuzz_target!(|data: &[u8]| {
+    match <Option<u64>>::from_ssz_bytes(data) {
+        Ok(option) => {
+            // the encoding of a union is canonical, so decoded bytes re-encode identically
+            assert_eq!(option.as_ssz_bytes(), data);
+            assert_eq!(option.ssz_bytes_len(), data.len());
+        }
+        Err(DecodeError::UnionSelectorInvalid(selector)) => {
+            assert!(selector > 1);
+            assert_eq!(data[0], selector);
+        }
+        Err(_) => assert!(data.is_empty() || data[0] <= 1),
+    }
+});
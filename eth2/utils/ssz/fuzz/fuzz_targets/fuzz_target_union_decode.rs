@@ -0,0 +1,20 @@
+#![no_main]
+#[macro_use] extern crate libfuzzer_sys;
+extern crate ssz;
+
+use ssz::{split_union_bytes, DecodeError, MAX_UNION_SELECTOR};
+
+// Fuzz ssz_decode of a union selector
+fuzz_target!(|data: &[u8]| {
+    match split_union_bytes(data) {
+        Ok((selector, value_bytes)) => {
+            assert!(selector <= MAX_UNION_SELECTOR);
+            assert_eq!(data[0], selector);
+            assert_eq!(&data[1..], value_bytes);
+        }
+        Err(DecodeError::UnionSelectorInvalid(selector)) => {
+            assert!(selector > MAX_UNION_SELECTOR);
+        }
+        Err(_) => assert!(data.is_empty()),
+    }
+});
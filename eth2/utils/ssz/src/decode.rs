@@ -20,6 +20,10 @@ pub enum DecodeError {
     OutOfBoundsByte { i: usize },
     /// The given bytes were invalid for some application-level reason.
     BytesInvalid(String),
+    /// The given union selector is out of bounds, or does not match any variant of the union.
+    UnionSelectorInvalid(u8),
+    /// The number of items in a list exceeds its maximum length.
+    ListTooLong { len: usize, max_len: usize },
 }
 
 /// Provides SSZ decoding (de-serialization) via the `from_ssz_bytes(&bytes)` method.
@@ -218,6 +222,29 @@ impl<'a> SszDecoder<'a> {
     }
 }
 
+/// Splits the SSZ bytes of a union into its selector and the SSZ bytes of the selected value.
+///
+/// Returns an error if `bytes` is empty or the selector is greater than `MAX_UNION_SELECTOR`. It
+/// is the responsibility of the caller to reject selectors which do not match a variant of the
+/// union with `DecodeError::UnionSelectorInvalid`.
+pub fn split_union_bytes(bytes: &[u8]) -> Result<(u8, &[u8]), DecodeError> {
+    if bytes.len() < BYTES_PER_UNION_SELECTOR {
+        return Err(DecodeError::InvalidByteLength {
+            len: bytes.len(),
+            expected: BYTES_PER_UNION_SELECTOR,
+        });
+    }
+
+    let (selector_bytes, value_bytes) = bytes.split_at(BYTES_PER_UNION_SELECTOR);
+    let selector = selector_bytes[0];
+
+    if selector > MAX_UNION_SELECTOR {
+        Err(DecodeError::UnionSelectorInvalid(selector))
+    } else {
+        Ok((selector, value_bytes))
+    }
+}
+
 /// Reads a `BYTES_PER_LENGTH_OFFSET`-byte union index from `bytes`, where `bytes.len() >=
 /// BYTES_PER_LENGTH_OFFSET`.
 pub fn read_union_index(bytes: &[u8]) -> Result<usize, DecodeError> {
@@ -239,32 +239,22 @@ impl Decode for NonZeroUsize {
     }
 }
 
-/// The SSZ union type.
+/// The SSZ union type: a selector of `0` for `None`, or a selector of `1` followed by the value
+/// for `Some`. Any other selector is invalid.
 impl<T: Decode> Decode for Option<T> {
     fn is_ssz_fixed_len() -> bool {
         false
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        if bytes.len() < BYTES_PER_LENGTH_OFFSET {
-            return Err(DecodeError::InvalidByteLength {
+        match split_union_bytes(bytes)? {
+            (0, value_bytes) if value_bytes.is_empty() => Ok(None),
+            (0, _) => Err(DecodeError::InvalidByteLength {
                 len: bytes.len(),
-                expected: BYTES_PER_LENGTH_OFFSET,
-            });
-        }
-
-        let (index_bytes, value_bytes) = bytes.split_at(BYTES_PER_LENGTH_OFFSET);
-
-        let index = read_union_index(index_bytes)?;
-        if index == 0 {
-            Ok(None)
-        } else if index == 1 {
-            Ok(Some(T::from_ssz_bytes(value_bytes)?))
-        } else {
-            Err(DecodeError::BytesInvalid(format!(
-                "{} is not a valid union index for Option<T>",
-                index
-            )))
+                expected: BYTES_PER_UNION_SELECTOR,
+            }),
+            (1, value_bytes) => Ok(Some(T::from_ssz_bytes(value_bytes)?)),
+            (selector, _) => Err(DecodeError::UnionSelectorInvalid(selector)),
         }
     }
 }
@@ -362,6 +352,8 @@ macro_rules! impl_decodable_for_u8_array {
 
 impl_decodable_for_u8_array!(4);
 impl_decodable_for_u8_array!(32);
+impl_decodable_for_u8_array!(48);
+impl_decodable_for_u8_array!(96);
 
 impl<T: Decode> Decode for Vec<T> {
     fn is_ssz_fixed_len() -> bool {
@@ -369,16 +361,46 @@ impl<T: Decode> Decode for Vec<T> {
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        if bytes.is_empty() {
-            Ok(vec![])
-        } else if T::is_ssz_fixed_len() {
-            bytes
-                .chunks(T::ssz_fixed_len())
-                .map(|chunk| T::from_ssz_bytes(chunk))
-                .collect()
-        } else {
-            decode_list_of_variable_length_items(bytes)
+        decode_list(bytes, None)
+    }
+}
+
+/// Decodes `bytes` as a list of `T`, returning `DecodeError::ListTooLong` if it contains more
+/// than `max_len` items.
+///
+/// The number of items is determined from the length of `bytes` (fixed-length items) or the first
+/// offset (variable-length items) and is checked against `max_len` before any allocation occurs.
+pub fn decode_list_with_max_len<T: Decode>(
+    bytes: &[u8],
+    max_len: usize,
+) -> Result<Vec<T>, DecodeError> {
+    decode_list(bytes, Some(max_len))
+}
+
+fn decode_list<T: Decode>(bytes: &[u8], max_len: Option<usize>) -> Result<Vec<T>, DecodeError> {
+    if bytes.is_empty() {
+        Ok(vec![])
+    } else if T::is_ssz_fixed_len() {
+        let fixed_len = T::ssz_fixed_len();
+        if let Some(max_len) = max_len {
+            let num_items = (bytes.len() + fixed_len - 1) / fixed_len;
+            check_list_len(num_items, max_len)?;
         }
+
+        bytes
+            .chunks(fixed_len)
+            .map(|chunk| T::from_ssz_bytes(chunk))
+            .collect()
+    } else {
+        decode_variable_length_items(bytes, max_len)
+    }
+}
+
+fn check_list_len(len: usize, max_len: usize) -> Result<(), DecodeError> {
+    if len > max_len {
+        Err(DecodeError::ListTooLong { len, max_len })
+    } else {
+        Ok(())
     }
 }
 
@@ -389,6 +411,25 @@ impl<T: Decode> Decode for Vec<T> {
 /// differing types.
 pub fn decode_list_of_variable_length_items<T: Decode>(
     bytes: &[u8],
+) -> Result<Vec<T>, DecodeError> {
+    decode_variable_length_items(bytes, None)
+}
+
+/// Decodes `bytes` as if it were a list of variable-length items, returning
+/// `DecodeError::ListTooLong` if the number of items implied by the first offset exceeds
+/// `max_len`.
+///
+/// The number of items is checked before any allocation occurs.
+pub fn decode_list_of_variable_length_items_with_max_len<T: Decode>(
+    bytes: &[u8],
+    max_len: usize,
+) -> Result<Vec<T>, DecodeError> {
+    decode_variable_length_items(bytes, Some(max_len))
+}
+
+fn decode_variable_length_items<T: Decode>(
+    bytes: &[u8],
+    max_len: Option<usize>,
 ) -> Result<Vec<T>, DecodeError> {
     let mut next_variable_byte = read_offset(bytes)?;
 
@@ -410,6 +451,17 @@ pub fn decode_list_of_variable_length_items<T: Decode>(
         });
     }
 
+    // Reject an offset beyond the end of the bytes before `num_items` is used to allocate.
+    if next_variable_byte > bytes.len() {
+        return Err(DecodeError::OutOfBoundsByte {
+            i: next_variable_byte,
+        });
+    }
+
+    if let Some(max_len) = max_len {
+        check_list_len(num_items, max_len)?;
+    }
+
     let mut values = Vec::with_capacity(num_items);
     for i in 1..=num_items {
         let slice_option = if i == num_items {
@@ -440,6 +492,46 @@ mod tests {
     // Note: decoding of valid bytes is generally tested "indirectly" in the `/tests` dir, by
     // encoding then decoding the element.
 
+    // Note: arrays longer than 32 do not implement `Debug` or `PartialEq`, so only the errors are
+    // compared.
+    #[test]
+    fn invalid_u8_array_48() {
+        assert_eq!(
+            <[u8; 48]>::from_ssz_bytes(&[0; 47]).err(),
+            Some(DecodeError::InvalidByteLength {
+                len: 47,
+                expected: 48
+            })
+        );
+
+        assert_eq!(
+            <[u8; 48]>::from_ssz_bytes(&[0; 49]).err(),
+            Some(DecodeError::InvalidByteLength {
+                len: 49,
+                expected: 48
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_u8_array_96() {
+        assert_eq!(
+            <[u8; 96]>::from_ssz_bytes(&[0; 48]).err(),
+            Some(DecodeError::InvalidByteLength {
+                len: 48,
+                expected: 96
+            })
+        );
+
+        assert_eq!(
+            <[u8; 96]>::from_ssz_bytes(&[0; 97]).err(),
+            Some(DecodeError::InvalidByteLength {
+                len: 97,
+                expected: 96
+            })
+        );
+    }
+
     #[test]
     fn invalid_u8_array_4() {
         assert_eq!(
@@ -484,6 +576,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn option_u64() {
+        assert_eq!(<Option<u64>>::from_ssz_bytes(&[0]), Ok(None));
+        assert_eq!(
+            <Option<u64>>::from_ssz_bytes(&[1, 42, 0, 0, 0, 0, 0, 0, 0]),
+            Ok(Some(42))
+        );
+    }
+
+    #[test]
+    fn invalid_option_u64() {
+        assert_eq!(
+            <Option<u64>>::from_ssz_bytes(&[]),
+            Err(DecodeError::InvalidByteLength {
+                len: 0,
+                expected: 1
+            })
+        );
+
+        assert_eq!(
+            <Option<u64>>::from_ssz_bytes(&[2, 42, 0, 0, 0, 0, 0, 0, 0]),
+            Err(DecodeError::UnionSelectorInvalid(2))
+        );
+        assert_eq!(
+            <Option<u64>>::from_ssz_bytes(&[255]),
+            Err(DecodeError::UnionSelectorInvalid(255))
+        );
+
+        // `None` has no value bytes
+        assert_eq!(
+            <Option<u64>>::from_ssz_bytes(&[0, 42]),
+            Err(DecodeError::InvalidByteLength {
+                len: 2,
+                expected: 1
+            })
+        );
+
+        // the value of `Some` must be a valid `u64`
+        assert_eq!(
+            <Option<u64>>::from_ssz_bytes(&[1, 42]),
+            Err(DecodeError::InvalidByteLength {
+                len: 1,
+                expected: 8
+            })
+        );
+        assert_eq!(
+            <Option<u64>>::from_ssz_bytes(&[1]),
+            Err(DecodeError::InvalidByteLength {
+                len: 0,
+                expected: 8
+            })
+        );
+    }
+
     #[test]
     fn invalid_h256() {
         assert_eq!(
@@ -526,6 +672,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn list_exceeds_max_len() {
+        assert_eq!(
+            decode_list_with_max_len::<u16>(&[0, 0, 1, 0, 2, 0], 2),
+            Err(DecodeError::ListTooLong { len: 3, max_len: 2 })
+        );
+        assert_eq!(
+            decode_list_with_max_len::<u16>(&[0, 0, 1, 0], 2),
+            Ok(vec![0, 1])
+        );
+
+        assert_eq!(
+            decode_list_with_max_len::<Vec<u16>>(&[8, 0, 0, 0, 8, 0, 0, 0], 1),
+            Err(DecodeError::ListTooLong { len: 2, max_len: 1 })
+        );
+        assert_eq!(
+            decode_list_with_max_len::<Vec<u16>>(&[8, 0, 0, 0, 8, 0, 0, 0], 2),
+            Ok(vec![vec![], vec![]])
+        );
+    }
+
+    #[test]
+    fn first_length_out_of_bounds() {
+        assert_eq!(
+            <Vec<Vec<u16>>>::from_ssz_bytes(&[0, 0, 0, 128]),
+            Err(DecodeError::OutOfBoundsByte { i: 1 << 31 })
+        );
+    }
+
     #[test]
     fn lengths_are_decreasing() {
         assert_eq!(
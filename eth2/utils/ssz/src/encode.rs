@@ -37,7 +37,7 @@ pub trait Encode {
     ///
     /// The default implementation of this method should suffice for most cases.
     fn as_ssz_bytes(&self) -> Vec<u8> {
-        let mut buf = vec![];
+        let mut buf = Vec::with_capacity(self.ssz_bytes_len());
 
         self.ssz_append(&mut buf);
 
@@ -132,6 +132,17 @@ impl<'a> SszEncoder<'a> {
     }
 }
 
+/// Appends the SSZ encoding of a union to `buf`: the one-byte `selector` followed by the SSZ
+/// bytes of `value`.
+///
+/// If `selector` is larger than `MAX_UNION_SELECTOR`, a `debug_assert` is raised.
+pub fn ssz_append_union<T: Encode>(buf: &mut Vec<u8>, selector: u8, value: &T) {
+    debug_assert!(selector <= MAX_UNION_SELECTOR);
+
+    buf.push(selector);
+    value.ssz_append(buf);
+}
+
 /// Encode `index` as a little-endian byte vec of `BYTES_PER_LENGTH_OFFSET` length.
 ///
 /// If `len` is larger than `2 ^ BYTES_PER_LENGTH_OFFSET`, a `debug_assert` is raised.
@@ -200,7 +200,8 @@ impl_encode_for_tuples! {
     }
 }
 
-/// The SSZ "union" type.
+/// The SSZ "union" type: a selector of `0` for `None`, or a selector of `1` followed by the
+/// value for `Some`.
 impl<T: Encode> Encode for Option<T> {
     fn is_ssz_fixed_len() -> bool {
         false
@@ -213,19 +214,16 @@ impl<T: Encode> Encode for Option<T> {
             } else {
                 some.ssz_bytes_len()
             };
-            len + BYTES_PER_LENGTH_OFFSET
+            len + BYTES_PER_UNION_SELECTOR
         } else {
-            BYTES_PER_LENGTH_OFFSET
+            BYTES_PER_UNION_SELECTOR
         }
     }
 
     fn ssz_append(&self, buf: &mut Vec<u8>) {
         match self {
-            None => buf.append(&mut encode_union_index(0)),
-            Some(t) => {
-                buf.append(&mut encode_union_index(1));
-                t.ssz_append(buf);
-            }
+            None => buf.push(0),
+            Some(t) => ssz_append_union(buf, 1, t),
         }
     }
 }
@@ -386,6 +384,8 @@ macro_rules! impl_encodable_for_u8_array {
 
 impl_encodable_for_u8_array!(4);
 impl_encodable_for_u8_array!(32);
+impl_encodable_for_u8_array!(48);
+impl_encodable_for_u8_array!(96);
 
 #[cfg(test)]
 mod tests {
@@ -423,21 +423,28 @@ mod tests {
 
     #[test]
     fn ssz_encode_option_u16() {
-        assert_eq!(Some(65535_u16).as_ssz_bytes(), vec![1, 0, 0, 0, 255, 255]);
+        assert_eq!(Some(65535_u16).as_ssz_bytes(), vec![1, 255, 255]);
 
         let none: Option<u16> = None;
-        assert_eq!(none.as_ssz_bytes(), vec![0, 0, 0, 0]);
+        assert_eq!(none.as_ssz_bytes(), vec![0]);
     }
 
     #[test]
     fn ssz_encode_option_vec_u16() {
-        assert_eq!(
-            Some(vec![0_u16, 1]).as_ssz_bytes(),
-            vec![1, 0, 0, 0, 0, 0, 1, 0]
-        );
+        assert_eq!(Some(vec![0_u16, 1]).as_ssz_bytes(), vec![1, 0, 0, 1, 0]);
 
         let none: Option<Vec<u16>> = None;
-        assert_eq!(none.as_ssz_bytes(), vec![0, 0, 0, 0]);
+        assert_eq!(none.as_ssz_bytes(), vec![0]);
+    }
+
+    #[test]
+    fn ssz_encode_option_u64() {
+        assert_eq!(Some(1_u64).as_ssz_bytes(), vec![1, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Some(1_u64).ssz_bytes_len(), 9);
+
+        let none: Option<u64> = None;
+        assert_eq!(none.as_ssz_bytes(), vec![0]);
+        assert_eq!(none.ssz_bytes_len(), 1);
     }
 
     #[test]
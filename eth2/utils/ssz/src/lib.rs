@@ -36,14 +36,23 @@
 
 mod decode;
 mod encode;
+mod macros;
 
 pub use decode::{
-    impls::decode_list_of_variable_length_items, Decode, DecodeError, SszDecoder, SszDecoderBuilder,
+    impls::{
+        decode_list_of_variable_length_items, decode_list_of_variable_length_items_with_max_len,
+        decode_list_with_max_len,
+    },
+    split_union_bytes, Decode, DecodeError, SszDecoder, SszDecoderBuilder,
 };
-pub use encode::{Encode, SszEncoder};
+pub use encode::{ssz_append_union, Encode, SszEncoder};
 
 /// The number of bytes used to represent an offset.
 pub const BYTES_PER_LENGTH_OFFSET: usize = 4;
+/// The number of bytes used to represent a union selector.
+pub const BYTES_PER_UNION_SELECTOR: usize = 1;
+/// The highest union selector permitted by the SSZ specification.
+pub const MAX_UNION_SELECTOR: u8 = 127;
 /// The maximum value that can be represented using `BYTES_PER_LENGTH_OFFSET`.
 #[cfg(target_pointer_width = "32")]
 pub const MAX_LENGTH_VALUE: usize = (std::u32::MAX >> (8 * (4 - BYTES_PER_LENGTH_OFFSET))) as usize;
@@ -1 +1,30 @@
-
+/// Asserts that `$type` has a fixed-length SSZ encoding of exactly `$len` bytes.
+///
+/// Intended for tests of fixed-length containers, to catch changes to their wire format (e.g., an
+/// accidentally added field). The assertion is checked when it runs, not at compile time, since
+/// it reads `Encode::ssz_fixed_len`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ssz::assert_ssz_fixed_len;
+///
+/// assert_ssz_fixed_len!(u64, 8);
+/// assert_ssz_fixed_len!([u8; 32], 32);
+/// ```
+#[macro_export]
+macro_rules! assert_ssz_fixed_len {
+    ($type: ty, $len: expr) => {
+        assert!(
+            <$type as $crate::Encode>::is_ssz_fixed_len(),
+            "{} should have a fixed length",
+            stringify!($type)
+        );
+        assert_eq!(
+            <$type as $crate::Encode>::ssz_fixed_len(),
+            $len,
+            "ssz fixed length of {}",
+            stringify!($type)
+        );
+    };
+}
@@ -27,6 +27,42 @@ mod round_trip {
         round_trip(items);
     }
 
+    // Arrays longer than 32 do not implement `Debug` or `PartialEq`, so they are compared as
+    // slices rather than with `round_trip`.
+    #[test]
+    fn u8_array_48() {
+        let mut array = [0; 48];
+        for (i, byte) in array.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        for item in &[[0; 48], [255; 48], array] {
+            let encoded = item.as_ssz_bytes();
+            assert_eq!(encoded, item.to_vec());
+            assert_eq!(item.ssz_bytes_len(), encoded.len());
+
+            let decoded = <[u8; 48]>::from_ssz_bytes(&encoded).expect("should decode");
+            assert_eq!(&decoded[..], &item[..]);
+        }
+    }
+
+    #[test]
+    fn u8_array_96() {
+        let mut array = [0; 96];
+        for (i, byte) in array.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        for item in &[[0; 96], [255; 96], array] {
+            let encoded = item.as_ssz_bytes();
+            assert_eq!(encoded, item.to_vec());
+            assert_eq!(item.ssz_bytes_len(), encoded.len());
+
+            let decoded = <[u8; 96]>::from_ssz_bytes(&encoded).expect("should decode");
+            assert_eq!(&decoded[..], &item[..]);
+        }
+    }
+
     #[test]
     fn h256() {
         let items: Vec<H256> = vec![H256::zero(), H256::from([1; 32]), H256::random()];
@@ -305,12 +341,9 @@ mod round_trip {
         };
 
         let bytes = vec![
-            //  1   2   3   4   5   6   7   8   9   10  11  12  13  14  15  16  17  18  19  20  21
-            //      | option<u16>   | offset        | offset        | option<u16    | 1st list
-            42, 00, 14, 00, 00, 00, 18, 00, 00, 00, 24, 00, 00, 00, 00, 00, 00, 00, 01, 00, 00, 00,
-            //  23  24  25  26  27
-            //      | 2nd list
-            00, 00, 00, 00, 00, 00,
+            //  1   2   3   4   5   6   7   8   9   10  11  12  13  14  15  16  17  18  19
+            //      | offset        | offset        | offset        | b | c         | d
+            42, 00, 14, 00, 00, 00, 15, 00, 00, 00, 18, 00, 00, 00, 00, 01, 00, 00, 00,
         ];
 
         assert_eq!(s.as_ssz_bytes(), bytes);
@@ -378,3 +411,203 @@ mod round_trip {
         round_trip(vec);
     }
 }
+
+mod union {
+    use super::*;
+    use ssz::{split_union_bytes, ssz_append_union};
+
+    #[derive(Debug, PartialEq)]
+    enum TwoVariants {
+        A(u16),
+        B(Vec<u8>),
+    }
+
+    impl Encode for TwoVariants {
+        fn is_ssz_fixed_len() -> bool {
+            false
+        }
+
+        fn ssz_bytes_len(&self) -> usize {
+            match self {
+                TwoVariants::A(a) => 1 + a.ssz_bytes_len(),
+                TwoVariants::B(b) => 1 + b.ssz_bytes_len(),
+            }
+        }
+
+        fn ssz_append(&self, buf: &mut Vec<u8>) {
+            match self {
+                TwoVariants::A(a) => ssz_append_union(buf, 0, a),
+                TwoVariants::B(b) => ssz_append_union(buf, 1, b),
+            }
+        }
+    }
+
+    impl Decode for TwoVariants {
+        fn is_ssz_fixed_len() -> bool {
+            false
+        }
+
+        fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+            match split_union_bytes(bytes)? {
+                (0, value_bytes) => Ok(TwoVariants::A(u16::from_ssz_bytes(value_bytes)?)),
+                (1, value_bytes) => Ok(TwoVariants::B(<Vec<u8>>::from_ssz_bytes(value_bytes)?)),
+                (selector, _) => Err(DecodeError::UnionSelectorInvalid(selector)),
+            }
+        }
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(TwoVariants::A(258).as_ssz_bytes(), vec![0, 2, 1]);
+        assert_eq!(TwoVariants::B(vec![]).as_ssz_bytes(), vec![1]);
+        assert_eq!(TwoVariants::B(vec![1, 2]).as_ssz_bytes(), vec![1, 1, 2]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let items = vec![
+            TwoVariants::A(0),
+            TwoVariants::A(u16::max_value()),
+            TwoVariants::B(vec![]),
+            TwoVariants::B(vec![42; 64]),
+        ];
+
+        for item in items {
+            let encoded = item.as_ssz_bytes();
+            assert_eq!(item.ssz_bytes_len(), encoded.len());
+            assert_eq!(TwoVariants::from_ssz_bytes(&encoded), Ok(item));
+        }
+    }
+
+    #[test]
+    fn empty_bytes() {
+        assert_eq!(
+            TwoVariants::from_ssz_bytes(&[]),
+            Err(DecodeError::InvalidByteLength {
+                len: 0,
+                expected: 1
+            })
+        );
+    }
+
+    #[test]
+    fn selector_without_variant() {
+        assert_eq!(
+            TwoVariants::from_ssz_bytes(&[2, 1, 0]),
+            Err(DecodeError::UnionSelectorInvalid(2))
+        );
+    }
+
+    #[test]
+    fn selector_out_of_range() {
+        assert_eq!(
+            TwoVariants::from_ssz_bytes(&[128, 1, 0]),
+            Err(DecodeError::UnionSelectorInvalid(128))
+        );
+        assert_eq!(
+            split_union_bytes(&[255]),
+            Err(DecodeError::UnionSelectorInvalid(255))
+        );
+    }
+}
+
+mod ssz_bytes_len {
+    use super::*;
+
+    fn assert_len<T: Encode>(item: &T) {
+        assert_eq!(item.ssz_bytes_len(), item.as_ssz_bytes().len());
+    }
+
+    #[derive(Encode)]
+    struct Inner {
+        a: u16,
+        b: Vec<u64>,
+    }
+
+    #[derive(Encode)]
+    struct Outer {
+        a: u64,
+        b: Inner,
+        c: Vec<Inner>,
+        d: Option<u8>,
+    }
+
+    #[test]
+    fn u64() {
+        assert_eq!(42_u64.ssz_bytes_len(), 8);
+        assert_len(&42_u64);
+    }
+
+    #[test]
+    fn vec_u64() {
+        assert_eq!(<Vec<u64>>::new().ssz_bytes_len(), 0);
+        assert_eq!(vec![1_u64, 2, 3].ssz_bytes_len(), 24);
+        assert_len(&vec![1_u64, 2, 3]);
+    }
+
+    #[test]
+    fn nested_container() {
+        let items = vec![
+            Outer {
+                a: 0,
+                b: Inner { a: 0, b: vec![] },
+                c: vec![],
+                d: None,
+            },
+            Outer {
+                a: 1,
+                b: Inner {
+                    a: 2,
+                    b: vec![3, 4, 5],
+                },
+                c: vec![
+                    Inner { a: 6, b: vec![] },
+                    Inner {
+                        a: 7,
+                        b: vec![8; 16],
+                    },
+                ],
+                d: Some(9),
+            },
+        ];
+
+        for item in items {
+            assert_len(&item);
+        }
+    }
+}
+
+mod fixed_len {
+    use super::*;
+    use ssz::assert_ssz_fixed_len;
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Fixed {
+        a: u16,
+        b: u64,
+        c: H256,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Variable {
+        a: u16,
+        b: Vec<u8>,
+    }
+
+    #[test]
+    fn fixed_container() {
+        assert_ssz_fixed_len!(Fixed, 2 + 8 + 32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn changed_container() {
+        assert_ssz_fixed_len!(Fixed, 2 + 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn variable_container() {
+        assert_ssz_fixed_len!(Variable, 6);
+    }
+}
@@ -1,4 +1,3 @@
-use crate::tree_hash::bitfield_bytes_tree_hash_root;
 use crate::Error;
 use core::marker::PhantomData;
 use serde::de::{Deserialize, Deserializer};
@@ -593,8 +592,7 @@ impl<N: Unsigned + Clone> tree_hash::TreeHash for Bitfield<Variable<N>> {
     fn tree_hash_root(&self) -> Vec<u8> {
         // Note: we use `as_slice` because it does _not_ have the length-delimiting bit set (or
         // present).
-        let root = bitfield_bytes_tree_hash_root::<N>(self.as_slice());
-        tree_hash::mix_in_length(&root, self.len())
+        tree_hash::bitlist_tree_hash_root(self.as_slice(), self.len(), N::to_usize())
     }
 }
 
@@ -612,7 +610,7 @@ impl<N: Unsigned + Clone> tree_hash::TreeHash for Bitfield<Fixed<N>> {
     }
 
     fn tree_hash_root(&self) -> Vec<u8> {
-        bitfield_bytes_tree_hash_root::<N>(self.as_slice())
+        tree_hash::bitvector_tree_hash_root(self.as_slice(), N::to_usize())
     }
 }
 
@@ -1,4 +1,3 @@
-use crate::tree_hash::vec_tree_hash_root;
 use crate::Error;
 use serde_derive::{Deserialize, Serialize};
 use std::marker::PhantomData;
@@ -163,7 +162,7 @@ where
     }
 
     fn tree_hash_root(&self) -> Vec<u8> {
-        vec_tree_hash_root::<T, N>(&self.vec)
+        tree_hash::vector_tree_hash_root(&self.vec, N::to_usize()).to_vec()
     }
 }
 
@@ -245,7 +244,8 @@ where
                     }
                 })
         } else {
-            ssz::decode_list_of_variable_length_items(bytes).and_then(|vec| Ok(vec.into()))
+            ssz::decode_list_of_variable_length_items_with_max_len(bytes, N::to_usize())
+                .and_then(|vec| Ok(vec.into()))
         }
     }
 }
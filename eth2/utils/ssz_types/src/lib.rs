@@ -40,7 +40,6 @@
 #[macro_use]
 mod bitfield;
 mod fixed_vector;
-mod tree_hash;
 mod variable_list;
 
 pub use bitfield::{BitList, BitVector, Bitfield};
@@ -1,4 +1,3 @@
-use crate::tree_hash::vec_tree_hash_root;
 use crate::Error;
 use serde_derive::{Deserialize, Serialize};
 use std::marker::PhantomData;
@@ -190,9 +189,7 @@ where
     }
 
     fn tree_hash_root(&self) -> Vec<u8> {
-        let root = vec_tree_hash_root::<T, N>(&self.vec);
-
-        tree_hash::mix_in_length(&root, self.len())
+        tree_hash::list_tree_hash_root(&self.vec, N::to_usize()).to_vec()
     }
 }
 
@@ -230,7 +227,7 @@ where
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
-        let vec = <Vec<T>>::from_ssz_bytes(bytes)?;
+        let vec = ssz::decode_list_with_max_len(bytes, N::to_usize())?;
 
         Self::new(vec).map_err(|e| ssz::DecodeError::BytesInvalid(format!("VariableList {:?}", e)))
     }
@@ -318,6 +315,15 @@ mod test {
         round_trip::<VariableList<u16, U8>>(vec![0; 8].into());
     }
 
+    #[test]
+    fn decode_too_long() {
+        let bytes = vec![42_u16; 9].as_ssz_bytes();
+        assert_eq!(
+            <VariableList<u16, U8>>::from_ssz_bytes(&bytes),
+            Err(DecodeError::ListTooLong { len: 9, max_len: 8 })
+        );
+    }
+
     fn root_with_length(bytes: &[u8], len: usize) -> Vec<u8> {
         let root = merkle_root(bytes, 0);
         tree_hash::mix_in_length(&root, len)
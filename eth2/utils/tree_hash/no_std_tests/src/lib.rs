@@ -0,0 +1,8 @@
+//! Builds `tree_hash` without its `std` feature and tests the core merkleization functions.
+//!
+//! This crate is not a member of the workspace, otherwise the features of `tree_hash` would be
+//! unified with those of the rest of the workspace. Run with `make test-tree-hash-no-std`, and
+//! built for a target without `std` by `make build-tree-hash-no-std`.
+#![no_std]
+
+pub use tree_hash;
@@ -0,0 +1,54 @@
+use tree_hash::{
+    merkle_root, merkleize_padded, merkleize_roots, merkleize_standard, mix_in_length,
+    vector_tree_hash_root, TreeHash, BYTES_PER_CHUNK,
+};
+
+#[test]
+fn padded_matches_standard() {
+    for num_chunks in 1..=17_usize {
+        let bytes: Vec<u8> = (0..num_chunks * BYTES_PER_CHUNK).map(|i| i as u8).collect();
+
+        assert_eq!(
+            merkleize_padded(&bytes, 0),
+            merkleize_standard(&bytes),
+            "num_chunks: {}",
+            num_chunks
+        );
+    }
+}
+
+#[test]
+fn padded_min_leaves() {
+    let bytes = vec![42; BYTES_PER_CHUNK];
+    let mut padded = bytes.clone();
+    padded.resize(BYTES_PER_CHUNK * 8, 0);
+
+    assert_eq!(merkleize_padded(&bytes, 8), merkleize_standard(&padded));
+}
+
+#[test]
+fn roots() {
+    let roots = [[1; 32], [2; 32], [3; 32]];
+    let leaves: Vec<u8> = roots.iter().flat_map(|root| root.iter().cloned()).collect();
+
+    assert_eq!(merkleize_roots(&roots, 4).to_vec(), merkle_root(&leaves, 4));
+}
+
+#[test]
+fn basic_types() {
+    let mut expected = 42_u64.to_le_bytes().to_vec();
+    expected.resize(BYTES_PER_CHUNK, 0);
+    assert_eq!(42_u64.tree_hash_root(), expected);
+
+    let values = [1_u64, 2, 3];
+    let packed: Vec<u8> = values
+        .iter()
+        .flat_map(|value| value.to_le_bytes().to_vec())
+        .collect();
+    assert_eq!(
+        vector_tree_hash_root(&values, 8).to_vec(),
+        merkle_root(&packed, 2)
+    );
+
+    assert_eq!(mix_in_length(&[0; 32], 3).len(), BYTES_PER_CHUNK);
+}
@@ -1,5 +1,12 @@
 use super::*;
+#[cfg(not(feature = "std"))]
+use alloc::borrow::{Cow, ToOwned};
+use core::marker::PhantomData;
+use core::num::{NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize};
+use core::time::Duration;
 use ethereum_types::{H256, U128, U256};
+#[cfg(feature = "std")]
+use std::borrow::Cow;
 
 macro_rules! impl_for_bitsize {
     ($type: ident, $bit_size: expr) => {
@@ -48,30 +55,106 @@ impl TreeHash for bool {
     }
 }
 
-macro_rules! impl_for_u8_array {
+/// Hashed as the inner integer, i.e., `NonZeroU64` is hashed as a `u64`.
+macro_rules! impl_for_non_zero {
+    ($type: ident, $inner: ident) => {
+        impl TreeHash for $type {
+            fn tree_hash_type() -> TreeHashType {
+                $inner::tree_hash_type()
+            }
+
+            fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+                self.get().tree_hash_packed_encoding()
+            }
+
+            fn tree_hash_packing_factor() -> usize {
+                $inner::tree_hash_packing_factor()
+            }
+
+            fn tree_hash_root(&self) -> Vec<u8> {
+                self.get().tree_hash_root()
+            }
+        }
+    };
+}
+
+impl_for_non_zero!(NonZeroU8, u8);
+impl_for_non_zero!(NonZeroU16, u16);
+impl_for_non_zero!(NonZeroU32, u32);
+impl_for_non_zero!(NonZeroU64, u64);
+impl_for_non_zero!(NonZeroUsize, usize);
+
+/// Hashed as the number of whole milliseconds in the `Duration`, as a `u64`.
+///
+/// Durations of more than `u64::max_value()` milliseconds saturate to `u64::max_value()`.
+impl TreeHash for Duration {
+    fn tree_hash_type() -> TreeHashType {
+        TreeHashType::Basic
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        duration_to_millis(self).tree_hash_packed_encoding()
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        u64::tree_hash_packing_factor()
+    }
+
+    fn tree_hash_root(&self) -> Vec<u8> {
+        int_to_bytes32(duration_to_millis(self))
+    }
+}
+
+/// Returns the number of whole milliseconds in `duration`, saturating at `u64::max_value()`.
+fn duration_to_millis(duration: &Duration) -> u64 {
+    let millis = duration.as_millis();
+
+    if millis > u128::from(u64::max_value()) {
+        u64::max_value()
+    } else {
+        millis as u64
+    }
+}
+
+/// Hashed as an SSZ `Vector[T, N]`: basic elements are packed, others contribute their root. The
+/// length is not mixed in.
+///
+/// Byte arrays are `bytesN`, which is equivalent to `Vector[uint8, N]`.
+macro_rules! impl_for_array {
     ($len: expr) => {
-        impl TreeHash for [u8; $len] {
+        impl<T: TreeHash> TreeHash for [T; $len] {
             fn tree_hash_type() -> TreeHashType {
                 TreeHashType::Vector
             }
 
             fn tree_hash_packed_encoding(&self) -> Vec<u8> {
-                unreachable!("bytesN should never be packed.")
+                unreachable!("Vector should never be packed.")
             }
 
             fn tree_hash_packing_factor() -> usize {
-                unreachable!("bytesN should never be packed.")
+                unreachable!("Vector should never be packed.")
             }
 
             fn tree_hash_root(&self) -> Vec<u8> {
-                merkle_root(&self[..], 0)
+                vector_tree_hash_root(&self[..], $len).to_vec()
             }
         }
     };
 }
 
-impl_for_u8_array!(4);
-impl_for_u8_array!(32);
+impl_for_array!(1);
+impl_for_array!(2);
+impl_for_array!(3);
+impl_for_array!(4);
+impl_for_array!(5);
+impl_for_array!(6);
+impl_for_array!(7);
+impl_for_array!(8);
+impl_for_array!(16);
+impl_for_array!(32);
+impl_for_array!(48);
+impl_for_array!(64);
+impl_for_array!(96);
 
 impl TreeHash for U128 {
     fn tree_hash_type() -> TreeHashType {
@@ -131,6 +214,49 @@ impl TreeHash for H256 {
     }
 }
 
+/// Hashed as the borrowed or owned `T`.
+impl<'a, T: TreeHash + ToOwned> TreeHash for Cow<'a, T> {
+    fn tree_hash_type() -> TreeHashType {
+        T::tree_hash_type()
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        self.as_ref().tree_hash_packed_encoding()
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        T::tree_hash_packing_factor()
+    }
+
+    fn tree_hash_root(&self) -> Vec<u8> {
+        self.as_ref().tree_hash_root()
+    }
+
+    fn tree_hash_packing() -> TreeHashPacking {
+        T::tree_hash_packing()
+    }
+}
+
+/// `PhantomData` is zero-sized and contributes nothing to the hash; the `TreeHash` derive skips
+/// `PhantomData` fields entirely. Hashed on its own, its root is the zero root.
+impl<T> TreeHash for PhantomData<T> {
+    fn tree_hash_type() -> TreeHashType {
+        TreeHashType::Container
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        unreachable!("PhantomData should never be packed.")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("PhantomData should never be packed.")
+    }
+
+    fn tree_hash_root(&self) -> Vec<u8> {
+        vec![0; HASHSIZE]
+    }
+}
+
 /// Returns `int` as little-endian bytes with a length of 32.
 fn int_to_bytes32(int: u64) -> Vec<u8> {
     let mut vec = int.to_le_bytes().to_vec();
@@ -153,6 +279,122 @@ mod test {
         assert_eq!(false.tree_hash_root(), false_bytes);
     }
 
+    #[test]
+    fn duration() {
+        let duration = Duration::from_millis(12000);
+
+        assert_eq!(duration.tree_hash_root(), 12000u64.tree_hash_root());
+        assert_eq!(
+            duration.tree_hash_packed_encoding(),
+            12000u64.tree_hash_packed_encoding()
+        );
+        assert_eq!(
+            Duration::tree_hash_packing_factor(),
+            u64::tree_hash_packing_factor()
+        );
+    }
+
+    #[test]
+    fn duration_saturates() {
+        let duration = Duration::from_secs(u64::max_value());
+
+        assert_eq!(duration.tree_hash_root(), u64::max_value().tree_hash_root());
+    }
+
+    #[test]
+    fn non_zero() {
+        let seven = NonZeroU64::new(7).unwrap();
+
+        assert_eq!(seven.tree_hash_root(), 7u64.tree_hash_root());
+        assert_eq!(
+            seven.tree_hash_packed_encoding(),
+            7u64.tree_hash_packed_encoding()
+        );
+        assert_eq!(
+            NonZeroU64::tree_hash_packing_factor(),
+            u64::tree_hash_packing_factor()
+        );
+
+        let seven = NonZeroU32::new(7).unwrap();
+
+        assert_eq!(seven.tree_hash_root(), 7u32.tree_hash_root());
+        assert_eq!(NonZeroU32::tree_hash_packing(), TreeHashPacking::Packed(8));
+    }
+
+    #[test]
+    fn byte_array() {
+        let bytes: [u8; 4] = [1, 2, 3, 4];
+        let mut expected = bytes.to_vec();
+        expected.resize(32, 0);
+
+        assert_eq!(bytes.tree_hash_root(), expected);
+        assert_eq!([7u8; 32].tree_hash_root(), vec![7; 32]);
+    }
+
+    #[test]
+    fn bls_sized_byte_arrays() {
+        // e.g., a compressed public key and signature
+        let pubkey = [7u8; 48];
+        let mut expected = pubkey.to_vec();
+        expected.resize(64, 0);
+        assert_eq!(pubkey.tree_hash_root(), merkleize_standard(&expected));
+
+        let signature = [9u8; 96];
+        let mut expected = signature.to_vec();
+        expected.resize(128, 0);
+        assert_eq!(signature.tree_hash_root(), merkleize_standard(&expected));
+    }
+
+    #[test]
+    fn packed_array() {
+        let values: [u64; 4] = [1, 2, 3, 4];
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+
+        assert_eq!(values.tree_hash_root(), merkleize_standard(&bytes));
+        assert_eq!(<[u64; 4]>::tree_hash_packing(), TreeHashPacking::NotPacked);
+    }
+
+    #[test]
+    fn root_array() {
+        let values = [
+            H256::from_low_u64_be(1),
+            H256::from_low_u64_be(2),
+            H256::from_low_u64_be(3),
+        ];
+        let mut bytes: Vec<u8> = values.iter().flat_map(|v| v.as_bytes().to_vec()).collect();
+        bytes.resize(4 * 32, 0);
+
+        assert_eq!(values.tree_hash_root(), merkleize_standard(&bytes));
+    }
+
+    #[test]
+    fn cow() {
+        let borrowed: Cow<u64> = Cow::Borrowed(&42);
+        let owned: Cow<u64> = Cow::Owned(42);
+
+        assert_eq!(borrowed.tree_hash_root(), 42u64.tree_hash_root());
+        assert_eq!(owned.tree_hash_root(), 42u64.tree_hash_root());
+        assert_eq!(
+            owned.tree_hash_packed_encoding(),
+            42u64.tree_hash_packed_encoding()
+        );
+        assert_eq!(<Cow<u64>>::tree_hash_packing(), u64::tree_hash_packing());
+    }
+
+    #[test]
+    fn phantom_data() {
+        let marker: PhantomData<u64> = PhantomData;
+
+        assert_eq!(marker.tree_hash_root(), vec![0; 32]);
+        assert_eq!(
+            <PhantomData<u64>>::tree_hash_packing(),
+            TreeHashPacking::NotPacked
+        );
+    }
+
     #[test]
     fn int_to_bytes() {
         assert_eq!(&int_to_bytes32(0), &[0; 32]);
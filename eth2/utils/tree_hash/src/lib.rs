@@ -1,8 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+
 pub mod impls;
+#[cfg(feature = "debug-tree")]
+mod merkleize_debug;
 mod merkleize_padded;
+#[cfg(feature = "std")]
+mod merkleize_parallel;
+mod merkleize_sparse;
 mod merkleize_standard;
 
-pub use merkleize_padded::merkleize_padded;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "debug-tree")]
+pub use merkleize_debug::{layers_to_string, merkleize_with_layers};
+pub use merkleize_padded::{
+    merkleize_padded, merkleize_padded_bounded, merkleize_padded_hash_count,
+    merkleize_padded_working_set,
+};
+#[cfg(feature = "std")]
+pub use merkleize_parallel::{merkleize_chunks_in_pool, merkleize_chunks_parallel};
+pub use merkleize_sparse::merkleize_sparse;
 pub use merkleize_standard::merkleize_standard;
 
 pub const BYTES_PER_CHUNK: usize = 32;
@@ -17,6 +39,167 @@ pub fn merkle_root(bytes: &[u8], minimum_chunk_count: usize) -> Vec<u8> {
     merkleize_padded(&bytes, minimum_chunk_count)
 }
 
+/// Returns the number of hashes performed to merkleize `value_count` values of type `T`, without
+/// hashing them.
+///
+/// Basic values are packed into leaf chunks, others contribute one leaf each. Only the hashing of
+/// the tree built from those leaves is counted; neither the hashing of the values' own roots nor
+/// the mixing-in of a list length are included. Useful for estimating the cost of hashing
+/// large structures (e.g., a `BeaconState`).
+pub fn hash_op_count<T: TreeHash>(value_count: usize) -> usize {
+    let leaves = match T::tree_hash_packing().packing_factor() {
+        Some(factor) => (value_count + factor - 1) / factor,
+        None => value_count,
+    };
+
+    merkleize_padded_hash_count(leaves, 0)
+}
+
+/// Merkleizes `roots` as leaves and returns the root, padding the tree out to `min_leaves` number
+/// of leaves.
+///
+/// The common primitive for hashing a container from the roots of its fields, or a list from the
+/// roots of its elements.
+pub fn merkleize_roots(roots: &[[u8; 32]], min_leaves: usize) -> [u8; 32] {
+    let mut leaves = Vec::with_capacity(roots.len() * BYTES_PER_CHUNK);
+    for root in roots {
+        leaves.extend_from_slice(root);
+    }
+
+    let mut root = [0; 32];
+    root.copy_from_slice(&merkleize_padded(&leaves, min_leaves));
+    root
+}
+
+/// Merkleizes `leaves`, a list of pre-split 32-byte leaf chunks, and returns the root, padding the
+/// tree out to `min_leaves` number of leaves.
+///
+/// For chunks produced by other SSZ implementations. The chunks are hashed by `merkleize_padded`,
+/// which builds its own internal `ChunkStore` of parent nodes, identically to `merkleize_roots`.
+pub fn merkleize_leaf_chunks(leaves: &[[u8; 32]], min_leaves: usize) -> [u8; 32] {
+    merkleize_roots(leaves, min_leaves)
+}
+
+/// Returns the root of a container with exactly two fields, whose roots are `a` and `b`.
+///
+/// Two roots fill the two leaves of a tree of depth one without padding, so the root is a single
+/// hash of their concatenation. Equivalent to `merkleize_roots(&[*a, *b], 2)`, it should be
+/// preferred for two-field containers (e.g., `Checkpoint`, `Fork`) in hand-written `TreeHash`
+/// implementations.
+pub fn hash_two_roots(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut root = [0; 32];
+    root.copy_from_slice(&eth2_hashing::hash_concat(a, b));
+    root
+}
+
+/// Returns the root of an SSZ `Vector[T, fixed_len]` holding `elements`.
+///
+/// The tree is sized for `fixed_len` elements, regardless of the number of `elements`, and the
+/// length is not mixed into the root. Any missing elements are zero chunks in the tree, which for
+/// basic types is the same as padding with zero values, but for composite types is not the same
+/// as padding with default values.
+///
+/// ## Panics
+///
+/// If `elements.len() > fixed_len`.
+pub fn vector_tree_hash_root<T: TreeHash>(elements: &[T], fixed_len: usize) -> [u8; 32] {
+    assert!(
+        elements.len() <= fixed_len,
+        "vector has {} elements, more than its fixed length of {}",
+        elements.len(),
+        fixed_len
+    );
+
+    let (leaves, minimum_chunk_count) = match T::tree_hash_packing().packing_factor() {
+        Some(values_per_chunk) => {
+            let mut leaves =
+                Vec::with_capacity((BYTES_PER_CHUNK / values_per_chunk) * elements.len());
+            for element in elements {
+                leaves.append(&mut element.tree_hash_packed_encoding());
+            }
+
+            (
+                leaves,
+                (fixed_len + values_per_chunk - 1) / values_per_chunk,
+            )
+        }
+        None => {
+            let mut leaves = Vec::with_capacity(elements.len() * BYTES_PER_CHUNK);
+            for element in elements {
+                leaves.append(&mut element.tree_hash_root());
+            }
+
+            (leaves, fixed_len)
+        }
+    };
+
+    let mut root = [0; 32];
+    root.copy_from_slice(&merkleize_padded(&leaves, minimum_chunk_count));
+    root
+}
+
+/// Returns the root of an SSZ `List[T, max_len]` holding `elements`.
+///
+/// The tree is sized for `max_len` elements and the number of `elements` is mixed into the root.
+/// For packed types this is the number of values, not the number of chunks they occupy.
+///
+/// ## Panics
+///
+/// If `elements.len() > max_len`.
+pub fn list_tree_hash_root<T: TreeHash>(elements: &[T], max_len: usize) -> [u8; 32] {
+    let root = vector_tree_hash_root(elements, max_len);
+
+    let mut mixed = [0; 32];
+    mixed.copy_from_slice(&mix_in_length(&root, elements.len()));
+    mixed
+}
+
+/// Returns the root of the members of `set` as an SSZ `List[T, max_len]`, in ascending order.
+///
+/// The members are sorted before hashing, so the root does not depend on the order in which they
+/// were inserted or are iterated (e.g., from a `HashSet`), and duplicates are only counted once.
+///
+/// ## Panics
+///
+/// If `set` has more than `max_len` distinct members.
+pub fn tree_hash_sorted_set<'a, T, I>(set: I, max_len: usize) -> [u8; 32]
+where
+    T: TreeHash + Ord + Clone + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let mut members: Vec<T> = set.into_iter().cloned().collect();
+    members.sort();
+    members.dedup();
+
+    list_tree_hash_root(&members, max_len)
+}
+
+/// Returns the root of each of `items`, in order.
+///
+/// Each item is hashed independently, in parallel on the current `rayon` thread pool. Useful for
+/// many small values (e.g., the attestations of a block), rather than one large tree (see
+/// `merkleize_chunks_parallel`).
+#[cfg(feature = "std")]
+pub fn tree_hash_batch<T: TreeHash + Sync>(items: &[T]) -> Vec<[u8; 32]> {
+    use rayon::prelude::*;
+
+    items.par_iter().map(item_root).collect()
+}
+
+/// Returns the root of each of `items`, in order.
+///
+/// Without `std` there is no `rayon`, so the items are hashed sequentially.
+#[cfg(not(feature = "std"))]
+pub fn tree_hash_batch<T: TreeHash + Sync>(items: &[T]) -> Vec<[u8; 32]> {
+    items.iter().map(item_root).collect()
+}
+
+fn item_root<T: TreeHash>(item: &T) -> [u8; 32] {
+    let mut root = [0; 32];
+    root.copy_from_slice(&item.tree_hash_root());
+    root
+}
+
 /// Returns the node created by hashing `root` and `length`.
 ///
 /// Used in `TreeHash` for inserting the length of a list above it's root.
@@ -27,6 +210,36 @@ pub fn mix_in_length(root: &[u8], length: usize) -> Vec<u8> {
     eth2_hashing::hash_concat(root, &length_bytes)
 }
 
+/// Returns the number of chunks required to store `num_bits` packed bits.
+fn bitfield_chunk_count(num_bits: usize) -> usize {
+    let byte_size = (num_bits + 7) / 8;
+    (byte_size + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK
+}
+
+/// Returns the root of a bitvector of `num_bits` bits, packed into `bytes` (least-significant bit
+/// first).
+///
+/// The length of a bitvector is fixed, so it is not mixed into the root.
+pub fn bitvector_tree_hash_root(bytes: &[u8], num_bits: usize) -> Vec<u8> {
+    merkle_root(bytes, bitfield_chunk_count(num_bits))
+}
+
+/// Returns the root of a bitlist of `num_bits` bits and capacity `max_bits`, packed into `bytes`
+/// (least-significant bit first).
+///
+/// `bytes` must _not_ include the length-delimiting bit of the SSZ encoding.
+pub fn bitlist_tree_hash_root(bytes: &[u8], num_bits: usize, max_bits: usize) -> Vec<u8> {
+    let root = merkle_root(bytes, bitfield_chunk_count(max_bits));
+    mix_in_length(&root, num_bits)
+}
+
+/// An error returned when a tree cannot be hashed.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TreeHashError {
+    /// Hashing the tree would require `required` bytes of memory, more than `max_bytes`.
+    MemoryBudgetExceeded { required: usize, max_bytes: usize },
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TreeHashType {
     Basic,
@@ -35,6 +248,25 @@ pub enum TreeHashType {
     Container,
 }
 
+/// Whether values are packed together into chunks when hashed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TreeHashPacking {
+    /// The given number of values are packed into each chunk.
+    Packed(usize),
+    /// Each value is hashed to its own root.
+    NotPacked,
+}
+
+impl TreeHashPacking {
+    /// Returns the number of values packed into each chunk, or `None` if values are not packed.
+    pub fn packing_factor(&self) -> Option<usize> {
+        match self {
+            TreeHashPacking::Packed(factor) => Some(*factor),
+            TreeHashPacking::NotPacked => None,
+        }
+    }
+}
+
 pub trait TreeHash {
     fn tree_hash_type() -> TreeHashType;
 
@@ -43,12 +275,54 @@ pub trait TreeHash {
     fn tree_hash_packing_factor() -> usize;
 
     fn tree_hash_root(&self) -> Vec<u8>;
+
+    /// Calls `f` with the index and value of each leaf chunk of the tree of `self`, in order.
+    ///
+    /// The leaves of a container (i.e., a type deriving `TreeHash`) are the roots of its fields.
+    /// Other types are visited as a single leaf, their root.
+    fn tree_hash_visit_leaves<F: FnMut(usize, &[u8; 32])>(&self, mut f: F) {
+        let mut root = [0; 32];
+        root.copy_from_slice(&self.tree_hash_root());
+        f(0, &root);
+    }
+
+    /// Returns the packing of `Self`, only `TreeHashType::Basic` types are packed.
+    fn tree_hash_packing() -> TreeHashPacking {
+        match Self::tree_hash_type() {
+            TreeHashType::Basic => TreeHashPacking::Packed(Self::tree_hash_packing_factor()),
+            TreeHashType::Vector | TreeHashType::List | TreeHashType::Container => {
+                TreeHashPacking::NotPacked
+            }
+        }
+    }
 }
 
 pub trait SignedRoot: TreeHash {
     fn signed_root(&self) -> Vec<u8>;
 }
 
+/// Provides the roots of each of the fields of a container, in the order in which they are
+/// hashed.
+///
+/// Implemented by `#[derive(TreeHash)]`.
+pub trait TreeHashFields: TreeHash {
+    fn tree_hash_field_roots(&self) -> Vec<Vec<u8>>;
+}
+
+/// Returns the indices of the fields which have a different root in `a` and `b`.
+///
+/// Useful for determining which field of a container (e.g., a `BeaconState`) is the cause of two
+/// differing roots.
+pub fn tree_hash_diff<T: TreeHashFields>(a: &T, b: &T) -> Vec<usize> {
+    a.tree_hash_field_roots()
+        .iter()
+        .zip(b.tree_hash_field_roots().iter())
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+        .map(|(i, _)| i)
+        .collect()
+}
+
 #[macro_export]
 macro_rules! tree_hash_ssz_encoding_as_vector {
     ($type: ident) => {
@@ -99,6 +373,22 @@ macro_rules! tree_hash_ssz_encoding_as_list {
 mod test {
     use super::*;
 
+    #[test]
+    fn two_roots_match_container_root() {
+        for i in 0..8_u8 {
+            let mut a = [0; 32];
+            a.copy_from_slice(&eth2_hashing::hash(&[i]));
+            let mut b = [0; 32];
+            b.copy_from_slice(&eth2_hashing::hash(&[i, 1]));
+
+            let mut fields = a.to_vec();
+            fields.extend_from_slice(&b);
+
+            assert_eq!(hash_two_roots(&a, &b), merkleize_roots(&[a, b], 2));
+            assert_eq!(hash_two_roots(&a, &b).to_vec(), merkle_root(&fields, 0));
+        }
+    }
+
     #[test]
     fn mix_length() {
         let hash = {
@@ -110,4 +400,95 @@ mod test {
 
         assert_eq!(mix_in_length(&[42; BYTES_PER_CHUNK], 42), hash);
     }
+
+    #[test]
+    fn list_root_mixes_in_value_count() {
+        let values: Vec<u64> = (1..=5).collect();
+
+        let mut leaves = vec![];
+        for value in &values {
+            leaves.extend_from_slice(&value.to_le_bytes());
+        }
+        // 5 values packed 4 to a chunk fill 2 chunks, padded out to 16 values (4 chunks).
+        let expected = mix_in_length(&merkle_root(&leaves, 4), 5);
+
+        assert_eq!(list_tree_hash_root(&values, 16).to_vec(), expected);
+        assert_ne!(
+            list_tree_hash_root(&values, 16).to_vec(),
+            mix_in_length(&merkle_root(&leaves, 4), 2)
+        );
+    }
+
+    #[test]
+    fn list_root_unpacked() {
+        let values: Vec<ethereum_types::H256> =
+            (1..=3).map(ethereum_types::H256::from_low_u64_le).collect();
+
+        let mut leaves = vec![];
+        for value in &values {
+            leaves.extend_from_slice(value.as_bytes());
+        }
+        let expected = mix_in_length(&merkle_root(&leaves, 8), 3);
+
+        assert_eq!(list_tree_hash_root(&values, 8).to_vec(), expected);
+        assert_eq!(
+            list_tree_hash_root::<ethereum_types::H256>(&[], 8).to_vec(),
+            mix_in_length(&merkle_root(&[], 8), 0)
+        );
+    }
+
+    #[test]
+    fn sorted_set_root_ignores_insertion_order() {
+        use std::collections::BTreeSet;
+
+        let ascending: BTreeSet<u64> = (1..=9).collect();
+        let descending: BTreeSet<u64> = (1..=9).rev().collect();
+        let shuffled = vec![4_u64, 9, 1, 7, 2, 8, 3, 6, 5];
+
+        let root = tree_hash_sorted_set(&ascending, 16);
+        assert_eq!(tree_hash_sorted_set(&descending, 16), root);
+        assert_eq!(tree_hash_sorted_set(&shuffled, 16), root);
+        assert_eq!(
+            root,
+            list_tree_hash_root(&(1..=9).collect::<Vec<u64>>(), 16)
+        );
+
+        let mut removed = ascending.clone();
+        removed.remove(&7);
+        assert_ne!(tree_hash_sorted_set(&removed, 16), root);
+    }
+
+    #[test]
+    fn sorted_set_root_unpacked() {
+        let members: Vec<ethereum_types::H256> =
+            (1..=3).map(ethereum_types::H256::from_low_u64_le).collect();
+        let reversed: Vec<ethereum_types::H256> = members.iter().rev().cloned().collect();
+
+        assert_eq!(
+            tree_hash_sorted_set(&reversed, 4),
+            list_tree_hash_root(&members, 4)
+        );
+        assert_ne!(
+            tree_hash_sorted_set(&members[1..], 4),
+            tree_hash_sorted_set(&members, 4)
+        );
+    }
+
+    #[test]
+    fn packing_factor() {
+        assert_eq!(TreeHashPacking::Packed(4).packing_factor(), Some(4));
+        assert_eq!(TreeHashPacking::NotPacked.packing_factor(), None);
+    }
+
+    #[test]
+    fn tree_hash_packing() {
+        assert_eq!(u8::tree_hash_packing(), TreeHashPacking::Packed(32));
+        assert_eq!(u64::tree_hash_packing(), TreeHashPacking::Packed(4));
+        assert_eq!(bool::tree_hash_packing(), TreeHashPacking::Packed(32));
+        assert_eq!(<[u8; 32]>::tree_hash_packing(), TreeHashPacking::NotPacked);
+        assert_eq!(
+            ethereum_types::H256::tree_hash_packing(),
+            TreeHashPacking::NotPacked
+        );
+    }
 }
@@ -0,0 +1,129 @@
+use super::BYTES_PER_CHUNK;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+use eth2_hashing::hash_concat;
+
+/// Merkleizes `bytes` as per `merkleize_padded`, additionally returning every node of the tree.
+///
+/// The nodes are returned layer-by-layer, from the root (`layers[0] == vec![root]`) down to the
+/// leaves, which are `bytes` split into chunks and padded out to a power-of-two number of at least
+/// `min_leaves` leaves.
+///
+/// Every padding node is stored and hashed, so this is far slower than `merkleize_padded` and is
+/// only intended for diagnosing root mismatches (see `layers_to_string`).
+pub fn merkleize_with_layers(bytes: &[u8], min_leaves: usize) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let mut leaves: Vec<[u8; 32]> = bytes
+        .chunks(BYTES_PER_CHUNK)
+        .map(|chunk| {
+            let mut leaf = [0; 32];
+            leaf[..chunk.len()].copy_from_slice(chunk);
+            leaf
+        })
+        .collect();
+
+    let num_leaves = core::cmp::max(core::cmp::max(leaves.len(), min_leaves), 1);
+    leaves.resize(num_leaves.next_power_of_two(), [0; 32]);
+
+    let mut layers = vec![leaves];
+    while layers[0].len() > 1 {
+        let parents = layers[0]
+            .chunks(2)
+            .map(|pair| {
+                let mut parent = [0; 32];
+                parent.copy_from_slice(&hash_concat(&pair[0], &pair[1]));
+                parent
+            })
+            .collect();
+        layers.insert(0, parents);
+    }
+
+    (layers[0][0], layers)
+}
+
+/// Renders the `layers` returned by `merkleize_with_layers` as a tree, with one node per line.
+///
+/// Each node is indented by its depth and printed as its generalized index (the root is `1` and
+/// the children of node `i` are `2i` and `2i + 1`) followed by its hex value. The children of a
+/// node directly follow it.
+pub fn layers_to_string(layers: &[Vec<[u8; 32]>]) -> String {
+    let mut output = String::new();
+    if !layers.is_empty() {
+        write_node(&mut output, layers, 0, 0);
+    }
+    output
+}
+
+/// Writes the node at `index` of `layers[depth]` to `output`, followed by all of its descendants.
+fn write_node(output: &mut String, layers: &[Vec<[u8; 32]>], depth: usize, index: usize) {
+    let node = match layers[depth].get(index) {
+        Some(node) => node,
+        None => return,
+    };
+
+    for _ in 0..depth {
+        output.push_str("  ");
+    }
+    // Writing to a `String` cannot fail.
+    let _ = write!(output, "{}: 0x", (1 << depth) + index);
+    for byte in node.iter() {
+        let _ = write!(output, "{:02x}", byte);
+    }
+    output.push('\n');
+
+    if depth + 1 < layers.len() {
+        write_node(output, layers, depth + 1, index * 2);
+        write_node(output, layers, depth + 1, index * 2 + 1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkle_root;
+
+    #[test]
+    fn four_leaf_tree() {
+        let bytes: Vec<u8> = (0..4 * BYTES_PER_CHUNK).map(|i| i as u8).collect();
+        let (root, layers) = merkleize_with_layers(&bytes, 0);
+
+        assert_eq!(root.to_vec(), merkle_root(&bytes, 0));
+        assert_eq!(
+            layers.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![1, 2, 4]
+        );
+        assert_eq!(&layers[2][3][..], &bytes[3 * BYTES_PER_CHUNK..]);
+
+        let printed = layers_to_string(&layers);
+        let lines: Vec<&str> = printed.lines().collect();
+        let depths: Vec<usize> = lines
+            .iter()
+            .map(|line| (line.len() - line.trim_start().len()) / 2)
+            .collect();
+        let indices: Vec<&str> = lines
+            .iter()
+            .map(|line| line.trim_start().split(':').next().unwrap())
+            .collect();
+
+        // The tree has 3 levels, printed depth-first.
+        assert_eq!(depths, vec![0, 1, 2, 2, 1, 2, 2]);
+        assert_eq!(indices, vec!["1", "2", "4", "5", "3", "6", "7"]);
+        assert!(lines[0].ends_with(&hex::encode(root)));
+    }
+
+    #[test]
+    fn padded_tree() {
+        for num_leaves in 0..9 {
+            for min_leaves in 0..9 {
+                let bytes = vec![42; num_leaves * BYTES_PER_CHUNK];
+                let (root, layers) = merkleize_with_layers(&bytes, min_leaves);
+
+                assert_eq!(root.to_vec(), merkle_root(&bytes, min_leaves));
+                assert_eq!(
+                    layers.len(),
+                    layers[layers.len() - 1].len().trailing_zeros() as usize + 1
+                );
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use super::BYTES_PER_CHUNK;
+use super::{TreeHashError, BYTES_PER_CHUNK};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 use eth2_hashing::{hash, hash_concat, ZERO_HASHES, ZERO_HASHES_MAX_INDEX};
 
 /// The size of the cache that stores padding nodes for a given height.
@@ -35,6 +37,75 @@ pub const MAX_TREE_DEPTH: usize = ZERO_HASHES_MAX_INDEX;
 /// _Note: there are some minor memory overheads, including a handful of usizes and a list of
 /// `MAX_TREE_DEPTH` hashes as `lazy_static` constants._
 pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
+    merkleize(bytes, min_leaves)
+}
+
+/// Returns the number of hashes performed by `merkleize_padded` for a tree of `leaves` leaf
+/// chunks, padded out to `min_leaves` leaves.
+///
+/// The padding nodes (and their parents) are cached, so they are not counted. Since trailing zero
+/// chunks are treated as padding, `leaves` should not include them; for leaves which may be zero,
+/// the count is an upper bound.
+pub fn merkleize_padded_hash_count(leaves: usize, min_leaves: usize) -> usize {
+    if leaves <= 1 && min_leaves <= 1 {
+        return 0;
+    }
+
+    let num_leaves = core::cmp::max(leaves, min_leaves).next_power_of_two();
+    let height = num_leaves.trailing_zeros() as usize + 1;
+
+    // The first round of hashing, from the leaves.
+    let mut parent_nodes = core::cmp::max(1, next_even_number(leaves) / 2);
+    let mut hashes = parent_nodes;
+
+    // Each of the heights above, excluding the root.
+    for _ in 1..height - 1 {
+        parent_nodes = next_even_number(parent_nodes) / 2;
+        hashes += parent_nodes;
+    }
+
+    hashes
+}
+
+/// Returns the size (in bytes) of the buffer allocated by `merkleize_padded` to merkleize `bytes`,
+/// which is the bulk of its memory footprint (see "Memory Performance" on `merkleize_padded`).
+///
+/// Only the chunks of `bytes` before any trailing zero chunks are counted, as padding nodes are
+/// never stored. The `bytes` themselves are not copied, so are not included.
+pub fn merkleize_padded_working_set(bytes: &[u8], min_leaves: usize) -> usize {
+    if bytes.len() <= BYTES_PER_CHUNK && min_leaves <= 1 {
+        return BYTES_PER_CHUNK;
+    }
+
+    let leaves_with_values =
+        (trim_zero_chunks(bytes).len() + (BYTES_PER_CHUNK - 1)) / BYTES_PER_CHUNK;
+    core::cmp::max(1, next_even_number(leaves_with_values) / 2) * BYTES_PER_CHUNK
+}
+
+/// Merkleizes `bytes` as per `merkleize_padded`, unless its working set (see
+/// `merkleize_padded_working_set`) would exceed `max_bytes`.
+///
+/// Allows a pathologically large input to be refused, rather than exhausting memory.
+pub fn merkleize_padded_bounded(
+    bytes: &[u8],
+    min_leaves: usize,
+    max_bytes: usize,
+) -> Result<[u8; 32], TreeHashError> {
+    let required = merkleize_padded_working_set(bytes, min_leaves);
+    if required > max_bytes {
+        return Err(TreeHashError::MemoryBudgetExceeded {
+            required,
+            max_bytes,
+        });
+    }
+
+    let mut root = [0; 32];
+    root.copy_from_slice(&merkleize(bytes, min_leaves));
+    Ok(root)
+}
+
+/// Merkleize `bytes`, as per `merkleize_padded`.
+fn merkleize(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
     // If the bytes are just one chunk or less, pad to one chunk and return without hashing.
     if bytes.len() <= BYTES_PER_CHUNK && min_leaves <= 1 {
         let mut o = bytes.to_vec();
@@ -47,6 +118,15 @@ pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
         "Merkle hashing only needs to happen if there is more than one chunk"
     );
 
+    // Trailing zero chunks are indistinguishable from padding nodes, so they are treated as
+    // padding. This allows subtrees of zero chunks to be substituted with cached padding nodes
+    // rather than being hashed.
+    let min_leaves = core::cmp::max(
+        min_leaves,
+        (bytes.len() + (BYTES_PER_CHUNK - 1)) / BYTES_PER_CHUNK,
+    );
+    let bytes = trim_zero_chunks(bytes);
+
     // The number of leaves that can be made directly from `bytes`.
     let leaves_with_values = (bytes.len() + (BYTES_PER_CHUNK - 1)) / BYTES_PER_CHUNK;
 
@@ -54,10 +134,10 @@ pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
     //
     // Since there is more than one node in this tree (see prior assertion), there should always be
     // one or more initial parent nodes.
-    let initial_parents_with_values = std::cmp::max(1, next_even_number(leaves_with_values) / 2);
+    let initial_parents_with_values = core::cmp::max(1, next_even_number(leaves_with_values) / 2);
 
     // The number of leaves in the full tree (including padding nodes).
-    let num_leaves = std::cmp::max(leaves_with_values, min_leaves).next_power_of_two();
+    let num_leaves = core::cmp::max(leaves_with_values, min_leaves).next_power_of_two();
 
     // The number of levels in the tree.
     //
@@ -122,21 +202,7 @@ pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
         // - If two nodes are available, hash them to form a parent.
         // - If one node is available, hash it and a cached padding node to form a parent.
         for i in 0..parent_nodes {
-            let (left, right) = match (chunks.get(i * 2), chunks.get(i * 2 + 1)) {
-                (Ok(left), Ok(right)) => (left, right),
-                (Ok(left), Err(_)) => (left, get_zero_hash(height)),
-                // Deriving `parent_nodes` from `chunks.len()` has ensured that we never encounter the
-                // scenario where we expect two nodes but there are none.
-                (Err(_), Err(_)) => unreachable!("Parent must have one child"),
-                // `chunks` is a contiguous array so it is impossible for an index to be missing
-                // when a higher index is present.
-                (Err(_), Ok(_)) => unreachable!("Parent must have a left child"),
-            };
-
-            assert!(
-                left.len() == right.len() && right.len() == BYTES_PER_CHUNK,
-                "Both children should be `BYTES_PER_CHUNK` bytes."
-            );
+            let (left, right) = get_children(&chunks, i, height);
 
             let hash = hash_concat(left, right);
 
@@ -160,7 +226,37 @@ pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
     root
 }
 
+/// Returns the children of the `i`th parent of the nodes stored in `chunks`, substituting a cached
+/// padding node for a missing right child.
+///
+/// Relies on `chunks` being dense (see `ChunkStore`), so that a right child is never present
+/// without a left child.
+fn get_children(chunks: &ChunkStore, i: usize, height: usize) -> (&[u8], &[u8]) {
+    let (left, right) = match (chunks.get(i * 2), chunks.get(i * 2 + 1)) {
+        (Ok(left), Ok(right)) => (left, right),
+        (Ok(left), Err(_)) => (left, get_zero_hash(height)),
+        // Deriving `parent_nodes` from `chunks.len()` has ensured that we never encounter the
+        // scenario where we expect two nodes but there are none.
+        (Err(_), Err(_)) => unreachable!("Parent must have one child"),
+        // `chunks` is a contiguous array so it is impossible for an index to be missing
+        // when a higher index is present.
+        (Err(_), Ok(_)) => unreachable!("Parent must have a left child"),
+    };
+
+    assert!(
+        left.len() == right.len() && right.len() == BYTES_PER_CHUNK,
+        "Both children should be `BYTES_PER_CHUNK` bytes."
+    );
+
+    (left, right)
+}
+
 /// A helper struct for storing words of `BYTES_PER_CHUNK` size in a flat byte array.
+///
+/// The store is always dense: every index less than `self.len()` holds a chunk and there are no
+/// chunks at higher indices. This follows from storing the chunks contiguously and only ever
+/// setting or truncating whole chunks. The merkleization algorithm relies on it to determine that
+/// a parent with a right child also has a left child.
 #[derive(Debug)]
 struct ChunkStore(Vec<u8>);
 
@@ -212,6 +308,19 @@ impl ChunkStore {
     }
 }
 
+/// Returns `bytes` without any trailing chunks which are entirely zero.
+///
+/// The final chunk may be partial, in which case it is removed if all of its bytes are zero.
+fn trim_zero_chunks(bytes: &[u8]) -> &[u8] {
+    match bytes.iter().rposition(|byte| *byte != 0) {
+        Some(i) => {
+            let num_chunks = i / BYTES_PER_CHUNK + 1;
+            &bytes[0..core::cmp::min(bytes.len(), num_chunks * BYTES_PER_CHUNK)]
+        }
+        None => &[],
+    }
+}
+
 /// Returns a cached padding node for a given height.
 fn get_zero_hash(height: usize) -> &'static [u8] {
     if height <= MAX_TREE_DEPTH {
@@ -320,6 +429,136 @@ mod test {
         common_tests!(random_bytes);
     }
 
+    #[test]
+    fn dense_chunk_store_roots() {
+        for num_chunks in 1..=16 {
+            let mut chunks = ChunkStore::with_capacity(num_chunks);
+            for i in 0..num_chunks {
+                chunks
+                    .set(i, &[i as u8 + 1; BYTES_PER_CHUNK])
+                    .expect("index should be in bounds");
+            }
+
+            assert_eq!(chunks.len(), num_chunks);
+
+            test_against_reference(&chunks.into_vec(), 0);
+        }
+    }
+
+    #[test]
+    fn zero_right_subtree_is_not_hashed() {
+        let mut input = vec![1; 4 * BYTES_PER_CHUNK];
+        input.resize(8 * BYTES_PER_CHUNK, 0);
+
+        test_against_reference(&input, 0);
+
+        // Hashing all eight leaves takes 4 + 2 + 1 hashes, however the four zero leaves form a
+        // padding subtree which only has its root hashed with the left subtree.
+        assert_eq!(merkleize_padded_hash_count(8, 0), 7);
+        assert_eq!(merkleize_padded_hash_count(4, 8), 4);
+    }
+
+    #[test]
+    fn trailing_zeros_match_reference() {
+        for num_chunks in 1..=16 {
+            for zero_bytes in 0..=num_chunks * BYTES_PER_CHUNK {
+                let mut input = vec![42; num_chunks * BYTES_PER_CHUNK];
+                let len = input.len();
+                for byte in &mut input[len - zero_bytes..] {
+                    *byte = 0;
+                }
+                test_against_reference(&input, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn trim_zero_chunks_keeps_partial_chunks() {
+        assert_eq!(trim_zero_chunks(&[]), &[] as &[u8]);
+        assert_eq!(trim_zero_chunks(&[0; 40]), &[] as &[u8]);
+        assert_eq!(trim_zero_chunks(&[1; 40]).len(), 40);
+
+        let mut bytes = vec![0; 3 * BYTES_PER_CHUNK];
+        bytes[BYTES_PER_CHUNK] = 1;
+        assert_eq!(trim_zero_chunks(&bytes).len(), 2 * BYTES_PER_CHUNK);
+    }
+
+    #[test]
+    fn bounded_within_budget_matches_unbounded() {
+        for num_chunks in 0..=16 {
+            let input = vec![42; num_chunks * BYTES_PER_CHUNK];
+            for &min_leaves in &[0, 1, 8, 64] {
+                let max_bytes = merkleize_padded_working_set(&input, min_leaves);
+                let root = merkleize_padded_bounded(&input, min_leaves, max_bytes)
+                    .expect("should be within budget");
+                assert_eq!(root.to_vec(), merkleize_padded(&input, min_leaves));
+            }
+        }
+    }
+
+    #[test]
+    fn bounded_exceeding_budget_errors() {
+        // 64 leaves are hashed into 32 parents, which are stored.
+        let input = vec![42; 64 * BYTES_PER_CHUNK];
+        assert_eq!(
+            merkleize_padded_working_set(&input, 0),
+            32 * BYTES_PER_CHUNK
+        );
+        assert_eq!(
+            merkleize_padded_bounded(&input, 0, 32 * BYTES_PER_CHUNK - 1),
+            Err(TreeHashError::MemoryBudgetExceeded {
+                required: 32 * BYTES_PER_CHUNK,
+                max_bytes: 32 * BYTES_PER_CHUNK - 1,
+            })
+        );
+        assert!(merkleize_padded_bounded(&input, 0, 32 * BYTES_PER_CHUNK).is_ok());
+    }
+
+    #[test]
+    fn working_set_ignores_padding() {
+        let mut input = vec![42; 4 * BYTES_PER_CHUNK];
+        let working_set = merkleize_padded_working_set(&input, 0);
+
+        // neither trailing zero chunks nor padding leaves are stored
+        input.resize(64 * BYTES_PER_CHUNK, 0);
+        assert_eq!(merkleize_padded_working_set(&input, 0), working_set);
+        assert_eq!(merkleize_padded_working_set(&input, 1 << 20), working_set);
+    }
+
+    #[test]
+    fn hash_count_of_padded_trees() {
+        // a single chunk is its own root
+        assert_eq!(merkleize_padded_hash_count(0, 0), 0);
+        assert_eq!(merkleize_padded_hash_count(1, 0), 0);
+        assert_eq!(merkleize_padded_hash_count(1, 1), 0);
+
+        // a full tree hashes every parent node
+        for depth in 1..8 {
+            let leaves = 1 << depth;
+            assert_eq!(merkleize_padded_hash_count(leaves, 0), leaves - 1);
+            assert_eq!(merkleize_padded_hash_count(leaves, leaves), leaves - 1);
+        }
+
+        // a partial tree hashes its last node with a padding node at each height: 3 + 2 + 1
+        assert_eq!(merkleize_padded_hash_count(5, 0), 6);
+        assert_eq!(merkleize_padded_hash_count(5, 8), 6);
+
+        // a single leaf is hashed with a padding node at each of the 6 heights
+        assert_eq!(merkleize_padded_hash_count(1, 64), 6);
+    }
+
+    #[test]
+    fn hash_op_count_packs_basic_values() {
+        use crate::hash_op_count;
+
+        // four `u64` values are packed into each leaf chunk
+        assert_eq!(hash_op_count::<u64>(0), 0);
+        assert_eq!(hash_op_count::<u64>(4), 0);
+        assert_eq!(hash_op_count::<u64>(5), 1);
+        assert_eq!(hash_op_count::<u64>(16), 3);
+        assert_eq!(hash_op_count::<u64>(17), 6);
+    }
+
     fn test_against_reference(input: &[u8], min_nodes: usize) {
         let mut reference_input = input.to_vec();
         reference_input.resize(
@@ -0,0 +1,116 @@
+use super::BYTES_PER_CHUNK;
+use crate::merkleize_padded::MAX_TREE_DEPTH;
+use eth2_hashing::{hash_concat, ZERO_HASHES};
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+/// Merkleize `chunks` into a tree of the given `height` (i.e., with `2^height` leaves) and return
+/// the root, hashing each layer of the tree in parallel on the current `rayon` thread pool.
+///
+/// `chunks` is padded with `0` to a multiple of `BYTES_PER_CHUNK` and the tree is padded with
+/// zero-value leaves. The result is identical to `merkleize_padded(chunks, 2^height)`.
+///
+/// ## Panics
+///
+/// If `chunks` contains more than `2^height` chunks, or `height > MAX_TREE_DEPTH`.
+pub fn merkleize_chunks_parallel(chunks: &[u8], height: usize) -> Vec<u8> {
+    assert!(
+        height <= MAX_TREE_DEPTH,
+        "Tree exceeds MAX_TREE_DEPTH of {}",
+        MAX_TREE_DEPTH
+    );
+
+    let num_chunks = (chunks.len() + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK;
+    assert!(
+        num_chunks <= 1 << height,
+        "{} chunks do not fit in a tree of height {}",
+        num_chunks,
+        height
+    );
+
+    if num_chunks == 0 {
+        return ZERO_HASHES[height].clone();
+    }
+
+    let mut layer = chunks.to_vec();
+    layer.resize(num_chunks * BYTES_PER_CHUNK, 0);
+
+    for zero_hash in ZERO_HASHES.iter().take(height) {
+        layer = layer
+            .par_chunks(2 * BYTES_PER_CHUNK)
+            .map(|pair| {
+                if pair.len() == 2 * BYTES_PER_CHUNK {
+                    hash_concat(&pair[0..BYTES_PER_CHUNK], &pair[BYTES_PER_CHUNK..])
+                } else {
+                    // The last node of an odd-length layer is paired with padding.
+                    hash_concat(pair, zero_hash)
+                }
+            })
+            .collect::<Vec<Vec<u8>>>()
+            .concat();
+    }
+
+    layer
+}
+
+/// Performs `merkleize_chunks_parallel` on the supplied `pool`, rather than the global `rayon`
+/// thread pool.
+///
+/// Allows the caller to bound the number of threads used for hashing.
+pub fn merkleize_chunks_in_pool(chunks: &[u8], height: usize, pool: &ThreadPool) -> Vec<u8> {
+    pool.install(|| merkleize_chunks_parallel(chunks, height))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkleize_padded;
+    use rayon::ThreadPoolBuilder;
+
+    fn two_thread_pool() -> ThreadPool {
+        ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .expect("should build thread pool")
+    }
+
+    /// Returns `n` bytes of non-zero values.
+    fn bytes(n: usize) -> Vec<u8> {
+        (0..n).map(|i| (i % 255) as u8 + 1).collect()
+    }
+
+    #[test]
+    fn matches_sequential() {
+        let pool = two_thread_pool();
+
+        for height in 0..6 {
+            for len in 0..=(1 << height) * BYTES_PER_CHUNK {
+                let input = bytes(len);
+                assert_eq!(
+                    merkleize_chunks_in_pool(&input, height, &pool),
+                    merkleize_padded(&input, 1 << height),
+                    "height: {}, len: {}",
+                    height,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_sequential_large_tree() {
+        let pool = two_thread_pool();
+        let input = bytes(1000 * BYTES_PER_CHUNK);
+
+        assert_eq!(
+            merkleize_chunks_in_pool(&input, 20, &pool),
+            merkleize_padded(&input, 1 << 20)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_chunks() {
+        merkleize_chunks_parallel(&bytes(3 * BYTES_PER_CHUNK), 1);
+    }
+}
@@ -0,0 +1,170 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use eth2_hashing::hash_concat;
+
+/// Merkleizes a tree of `2^height` leaves, where every leaf is `default_leaf` apart from the
+/// leaves given by `overrides` as `(index, leaf)` pairs, and returns the root.
+///
+/// Subtrees which only contain `default_leaf` are never hashed, instead their roots are taken from
+/// a list of the `height` ancestors of `default_leaf`. The cost of this function is therefore
+/// proportional to `overrides.len() * height`, rather than to the number of leaves.
+///
+/// If an index appears more than once in `overrides`, the last leaf given for that index is used.
+///
+/// ## Panics
+///
+/// If any index in `overrides` is not less than `2^height`.
+pub fn merkleize_sparse(
+    default_leaf: [u8; 32],
+    overrides: &[(usize, [u8; 32])],
+    height: usize,
+) -> [u8; 32] {
+    for (index, _) in overrides {
+        assert!(
+            height >= core::mem::size_of::<usize>() * 8 || *index < 1 << height,
+            "leaf index {} is outside of a tree of height {}",
+            index,
+            height
+        );
+    }
+
+    // The root of a subtree of only default leaves, at each height.
+    let mut default_nodes = Vec::with_capacity(height + 1);
+    default_nodes.push(default_leaf);
+    for i in 0..height {
+        default_nodes.push(concat(&default_nodes[i], &default_nodes[i]));
+    }
+
+    // The nodes at the current height which are not default nodes, sorted by index without
+    // duplicates. A stable sort keeps the last override for an index at the end of its run.
+    let mut nodes = overrides.to_vec();
+    nodes.sort_by_key(|(index, _)| *index);
+    nodes.reverse();
+    nodes.dedup_by_key(|(index, _)| *index);
+    nodes.reverse();
+
+    for default_node in default_nodes.iter().take(height) {
+        let mut parents = Vec::with_capacity((nodes.len() + 1) / 2);
+        let mut i = 0;
+
+        while i < nodes.len() {
+            let (index, node) = nodes[i];
+
+            let (left, right) = if index % 2 == 0 {
+                match nodes.get(i + 1) {
+                    Some((sibling_index, sibling)) if *sibling_index == index + 1 => {
+                        i += 1;
+                        (node, *sibling)
+                    }
+                    _ => (node, *default_node),
+                }
+            } else {
+                (*default_node, node)
+            };
+
+            parents.push((index / 2, concat(&left, &right)));
+            i += 1;
+        }
+
+        nodes = parents;
+    }
+
+    nodes
+        .first()
+        .map_or(default_nodes[height], |(_, root)| *root)
+}
+
+/// Returns the hash of `left` concatenated with `right`.
+fn concat(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut node = [0; 32];
+    node.copy_from_slice(&hash_concat(left, right));
+    node
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkleize_roots;
+
+    /// Merkleizes the same leaves as `merkleize_sparse`, from a list of every leaf.
+    fn dense_root(
+        default_leaf: [u8; 32],
+        overrides: &[(usize, [u8; 32])],
+        height: usize,
+    ) -> [u8; 32] {
+        let mut leaves = vec![default_leaf; 1 << height];
+        for (index, leaf) in overrides {
+            leaves[*index] = *leaf;
+        }
+        merkleize_roots(&leaves, 1 << height)
+    }
+
+    fn test_against_dense(default_leaf: [u8; 32], overrides: &[(usize, [u8; 32])], height: usize) {
+        assert_eq!(
+            merkleize_sparse(default_leaf, overrides, height),
+            dense_root(default_leaf, overrides, height),
+            "height: {}, overrides: {:?}",
+            height,
+            overrides
+                .iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn no_overrides() {
+        for height in 0..8 {
+            test_against_dense([0; 32], &[], height);
+            test_against_dense([7; 32], &[], height);
+        }
+    }
+
+    #[test]
+    fn single_override() {
+        for height in 0..6 {
+            for index in 0..1 << height {
+                test_against_dense([7; 32], &[(index, [index as u8 + 1; 32])], height);
+            }
+        }
+    }
+
+    #[test]
+    fn override_pairs() {
+        for height in 1..5 {
+            for a in 0..1 << height {
+                for b in 0..1 << height {
+                    test_against_dense(
+                        [7; 32],
+                        &[(a, [a as u8 + 1; 32]), (b, [b as u8 + 100; 32])],
+                        height,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn all_overridden() {
+        let height = 5;
+        let overrides: Vec<_> = (0..1 << height)
+            .rev()
+            .map(|index| (index, [index as u8; 32]))
+            .collect();
+        test_against_dense([7; 32], &overrides, height);
+    }
+
+    #[test]
+    fn last_duplicate_override_is_used() {
+        assert_eq!(
+            merkleize_sparse([0; 32], &[(3, [1; 32]), (1, [5; 32]), (3, [2; 32])], 3),
+            dense_root([0; 32], &[(1, [5; 32]), (3, [2; 32])], 3)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_bounds() {
+        merkleize_sparse([0; 32], &[(8, [1; 32])], 3);
+    }
+}
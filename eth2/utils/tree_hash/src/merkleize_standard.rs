@@ -29,7 +29,7 @@ pub fn merkleize_standard(bytes: &[u8]) -> Vec<u8> {
     let nodes = num_nodes(leaves);
     let internal_nodes = nodes - leaves;
 
-    let num_bytes = std::cmp::max(internal_nodes, 1) * HASHSIZE + bytes.len();
+    let num_bytes = core::cmp::max(internal_nodes, 1) * HASHSIZE + bytes.len();
 
     let mut o: Vec<u8> = vec![0; internal_nodes * HASHSIZE];
 
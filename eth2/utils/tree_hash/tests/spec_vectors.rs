@@ -0,0 +1,301 @@
+//! Checks `tree_hash_root` against known-answer test vectors of the SSZ spec.
+//!
+//! A handful of vectors are embedded below, as `(serialized_bytes, expected_root)` pairs, so that
+//! they always run. Their roots were computed with the `hash_tree_root` pseudocode from the spec,
+//! independently of this crate.
+//!
+//! With the `ef_tests` feature, the roots are also checked against all of the `ssz_generic`
+//! vectors from the eth2.0-spec-tests. These are read from the directory in the
+//! `SSZ_GENERIC_VECTORS` environment variable, defaulting to the vectors downloaded by `make` in
+//! `tests/ef_tests`.
+
+use ssz::Decode;
+use ssz_derive::Decode;
+use tree_hash::TreeHash;
+use tree_hash_derive::TreeHash;
+use types::typenum::*;
+use types::{BitList, BitVector};
+
+/// Decodes each of the hex-encoded `vectors` as a `T` and asserts its root is the expected root.
+fn check_vectors<T: Decode + TreeHash>(vectors: &[(&str, &str)]) {
+    for (ssz_hex, root_hex) in vectors {
+        let bytes = hex::decode(ssz_hex).expect("vector should be valid hex");
+        let value = T::from_ssz_bytes(&bytes)
+            .unwrap_or_else(|e| panic!("Unable to decode {}: {:?}", ssz_hex, e));
+
+        assert_eq!(
+            hex::encode(value.tree_hash_root()),
+            *root_hex,
+            "ssz: {}",
+            ssz_hex
+        );
+    }
+}
+
+#[derive(Decode, TreeHash)]
+struct SmallTestStruct {
+    a: u16,
+    b: u16,
+}
+
+#[derive(Decode, TreeHash)]
+struct FixedTestStruct {
+    a: u8,
+    b: u64,
+    c: u32,
+}
+
+#[test]
+fn embedded_uint8() {
+    check_vectors::<u8>(&[
+        (
+            "00",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "2a",
+            "2a00000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "ff",
+            "ff00000000000000000000000000000000000000000000000000000000000000",
+        ),
+    ]);
+}
+
+#[test]
+fn embedded_uint16() {
+    check_vectors::<u16>(&[
+        (
+            "0000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "cdab",
+            "cdab000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "ffff",
+            "ffff000000000000000000000000000000000000000000000000000000000000",
+        ),
+    ]);
+}
+
+#[test]
+fn embedded_uint32() {
+    check_vectors::<u32>(&[
+        (
+            "67452301",
+            "6745230100000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "ffffffff",
+            "ffffffff00000000000000000000000000000000000000000000000000000000",
+        ),
+    ]);
+}
+
+#[test]
+fn embedded_uint64() {
+    check_vectors::<u64>(&[
+        (
+            "0000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "0100000000000000",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "efcdab8967452301",
+            "efcdab8967452301000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "ffffffffffffffff",
+            "ffffffffffffffff000000000000000000000000000000000000000000000000",
+        ),
+    ]);
+}
+
+#[test]
+fn embedded_boolean() {
+    check_vectors::<bool>(&[
+        (
+            "00",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+        ),
+        (
+            "01",
+            "0100000000000000000000000000000000000000000000000000000000000000",
+        ),
+    ]);
+}
+
+#[test]
+fn embedded_bitvector() {
+    check_vectors::<BitVector<U4>>(&[(
+        "05",
+        "0500000000000000000000000000000000000000000000000000000000000000",
+    )]);
+    check_vectors::<BitVector<U8>>(&[(
+        "d3",
+        "d300000000000000000000000000000000000000000000000000000000000000",
+    )]);
+    check_vectors::<BitVector<U512>>(&[
+        ("49922449922449922449922449922449922449922449922449922449922449922449922449922449922449922449922449922449922449922449922449922449", "146a85e85e44a166b9d324a4f3d871f9342f78c5555649db3f949952a7530cdc"),
+    ]);
+}
+
+#[test]
+fn embedded_bitlist() {
+    check_vectors::<BitList<U8>>(&[
+        (
+            "01",
+            "f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b",
+        ),
+        (
+            "1b",
+            "9d2816f451512382c000156fad1578555537321084d091d3c7b228aa705c36aa",
+        ),
+        (
+            "ff01",
+            "017d2fa0f6934ed2354e4cdb7a2230ccf8f31fe758c7a47442e37fdea1d68bfe",
+        ),
+    ]);
+    check_vectors::<BitList<U512>>(&[(
+        "4208218410420821841042082184104208218410420821841042082184104208218410420811",
+        "af4372d26d94158beedbf5a5645530d012b0d781b49eeb8cae9cc08e18dc86ea",
+    )]);
+}
+
+#[test]
+fn embedded_container() {
+    check_vectors::<SmallTestStruct>(&[(
+        "67452301",
+        "db229ae71ad551a68d8895b6ce6dddeb5dcb4b38508c1350af87031ec2ed82f4",
+    )]);
+    check_vectors::<FixedTestStruct>(&[(
+        "ab33221100ddccbbaa78563412",
+        "ad4e3e1f3337621f04c2d9962cae7c6cab505f10bbaadcba914504254944be58",
+    )]);
+}
+
+#[cfg(feature = "ef_tests")]
+mod ssz_generic {
+    use serde::de::DeserializeOwned;
+    use serde_derive::Deserialize;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tree_hash::TreeHash;
+    use types::typenum::*;
+    use types::FixedVector;
+
+    /// The environment variable which may be set to the `ssz_generic` test vector directory.
+    const VECTORS_DIR_ENV: &str = "SSZ_GENERIC_VECTORS";
+
+    #[derive(Debug, Deserialize)]
+    struct Metadata {
+        root: String,
+    }
+
+    fn vectors_dir() -> PathBuf {
+        std::env::var(VECTORS_DIR_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(
+                    "../../../tests/ef_tests/eth2.0-spec-tests/tests/general/phase0/ssz_generic",
+                )
+            })
+    }
+
+    /// Returns the directories of the valid cases of the given `handler` (e.g., `uints`) which have
+    /// a name starting with `prefix`.
+    fn valid_cases(handler: &str, prefix: &str) -> Vec<PathBuf> {
+        let dir = vectors_dir().join(handler).join("valid");
+        let mut cases = fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("Unable to read test vectors in {:?}: {:?}", dir, e))
+            .map(|entry| entry.expect("should read dir entry").path())
+            .filter(|path| case_name(path).starts_with(prefix))
+            .collect::<Vec<_>>();
+        cases.sort();
+
+        assert!(
+            !cases.is_empty(),
+            "No test vectors for {} in {:?}",
+            prefix,
+            dir
+        );
+
+        cases
+    }
+
+    fn case_name(case: &Path) -> String {
+        case.file_name()
+            .expect("case should have a name")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn yaml_decode_file<T: DeserializeOwned>(path: &Path) -> T {
+        let file =
+            fs::File::open(path).unwrap_or_else(|e| panic!("Unable to open {:?}: {:?}", path, e));
+        serde_yaml::from_reader(file)
+            .unwrap_or_else(|e| panic!("Unable to decode {:?}: {:?}", path, e))
+    }
+
+    /// Asserts the root of the case's `value.yaml` matches the root in its `meta.yaml`.
+    fn check_case<T: TreeHash + DeserializeOwned>(case: &Path) {
+        let value: T = yaml_decode_file(&case.join("value.yaml"));
+        let meta: Metadata = yaml_decode_file(&case.join("meta.yaml"));
+
+        let expected_root = hex::decode(meta.root.trim_start_matches("0x"))
+            .unwrap_or_else(|e| panic!("Invalid root in {:?}: {:?}", case, e));
+
+        assert_eq!(
+            value.tree_hash_root(),
+            expected_root,
+            "case: {}",
+            case_name(case)
+        );
+    }
+
+    #[test]
+    fn uint64() {
+        for case in valid_cases("uints", "uint_64_") {
+            check_case::<u64>(&case);
+        }
+    }
+
+    #[test]
+    fn boolean() {
+        for case in valid_cases("boolean", "") {
+            check_case::<bool>(&case);
+        }
+    }
+
+    #[test]
+    fn vector_uint64() {
+        for case in valid_cases("basic_vector", "vec_uint64_") {
+            // case names are of the form `vec_uint64_{length}_{description}`
+            let name = case_name(&case);
+            let length = name
+                .split('_')
+                .nth(2)
+                .expect("case name should include length");
+
+            match length {
+                "1" => check_case::<FixedVector<u64, U1>>(&case),
+                "2" => check_case::<FixedVector<u64, U2>>(&case),
+                "3" => check_case::<FixedVector<u64, U3>>(&case),
+                "4" => check_case::<FixedVector<u64, U4>>(&case),
+                "5" => check_case::<FixedVector<u64, U5>>(&case),
+                "8" => check_case::<FixedVector<u64, U8>>(&case),
+                "16" => check_case::<FixedVector<u64, U16>>(&case),
+                "31" => check_case::<FixedVector<u64, U31>>(&case),
+                "512" => check_case::<FixedVector<u64, U512>>(&case),
+                "513" => check_case::<FixedVector<u64, U513>>(&case),
+                _ => panic!("Unsupported vector length in case: {}", name),
+            }
+        }
+    }
+}
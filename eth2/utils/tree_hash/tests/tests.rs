@@ -0,0 +1,371 @@
+use tree_hash::{
+    bitlist_tree_hash_root, bitvector_tree_hash_root, merkleize_leaf_chunks, merkleize_roots,
+    merkleize_standard, mix_in_length, tree_hash_batch, tree_hash_diff, vector_tree_hash_root,
+    TreeHash, TreeHashFields,
+};
+use tree_hash_derive::TreeHash;
+use types::{Checkpoint, Epoch, Hash256};
+
+mod diff {
+    use super::*;
+
+    #[derive(Clone, TreeHash)]
+    struct FourFields {
+        a: u64,
+        b: u64,
+        c: bool,
+        d: [u8; 32],
+    }
+
+    fn four_fields() -> FourFields {
+        FourFields {
+            a: 1,
+            b: 2,
+            c: true,
+            d: [42; 32],
+        }
+    }
+
+    #[test]
+    fn field_roots() {
+        let value = four_fields();
+
+        assert_eq!(
+            value.tree_hash_field_roots(),
+            vec![
+                value.a.tree_hash_root(),
+                value.b.tree_hash_root(),
+                value.c.tree_hash_root(),
+                value.d.tree_hash_root(),
+            ]
+        );
+    }
+
+    #[test]
+    fn visit_leaves() {
+        let value = four_fields();
+
+        let mut leaves = vec![];
+        value.tree_hash_visit_leaves(|i, leaf| leaves.push((i, leaf.to_vec())));
+
+        assert_eq!(
+            leaves,
+            vec![
+                (0, value.a.tree_hash_root()),
+                (1, value.b.tree_hash_root()),
+                (2, value.c.tree_hash_root()),
+                (3, value.d.tree_hash_root()),
+            ]
+        );
+
+        let bytes: Vec<u8> = leaves.iter().flat_map(|(_, leaf)| leaf.clone()).collect();
+        assert_eq!(merkleize_standard(&bytes), value.tree_hash_root());
+    }
+
+    #[test]
+    fn visit_leaves_of_basic_value() {
+        let mut leaves = vec![];
+        42u64.tree_hash_visit_leaves(|i, leaf| leaves.push((i, leaf.to_vec())));
+
+        assert_eq!(leaves, vec![(0, 42u64.tree_hash_root())]);
+    }
+
+    #[test]
+    fn identical() {
+        assert!(tree_hash_diff(&four_fields(), &four_fields()).is_empty());
+    }
+
+    #[test]
+    fn single_field_differs() {
+        let a = four_fields();
+        let mut b = four_fields();
+        b.c = false;
+
+        assert_ne!(a.tree_hash_root(), b.tree_hash_root());
+        assert_eq!(tree_hash_diff(&a, &b), vec![2]);
+    }
+}
+
+mod bitfield {
+    use super::*;
+
+    /// Returns `num_bytes` bytes with every third bit set.
+    fn bits(num_bytes: usize) -> Vec<u8> {
+        (0..num_bytes * 8)
+            .collect::<Vec<_>>()
+            .chunks(8)
+            .map(|byte| {
+                byte.iter()
+                    .enumerate()
+                    .filter(|(_, bit)| *bit % 3 == 0)
+                    .fold(0, |acc, (i, _)| acc | (1 << i))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bitvector_100_bits() {
+        // 100 bits fit in 13 bytes, which are padded to a single chunk.
+        let bytes = bits(13);
+        let mut padded = bytes.clone();
+        padded.resize(32, 0);
+
+        assert_eq!(
+            bitvector_tree_hash_root(&bytes, 100),
+            merkleize_standard(&padded)
+        );
+    }
+
+    #[test]
+    fn bitlist_max_2048_bits() {
+        // 2048 bits fill 8 chunks.
+        for num_bits in &[0, 10, 300, 2048] {
+            let bytes = bits((num_bits + 7) / 8);
+            let mut padded = bytes.clone();
+            padded.resize(8 * 32, 0);
+
+            assert_eq!(
+                bitlist_tree_hash_root(&bytes, *num_bits, 2048),
+                mix_in_length(&merkleize_standard(&padded), *num_bits),
+                "num_bits: {}",
+                num_bits
+            );
+        }
+    }
+}
+
+mod roots {
+    use super::*;
+
+    fn roots(n: usize) -> Vec<[u8; 32]> {
+        (0..n).map(|i| [i as u8 + 1; 32]).collect()
+    }
+
+    /// Returns the root of `roots` padded with zero leaves to at least `min_leaves`.
+    fn reference_root(roots: &[[u8; 32]], min_leaves: usize) -> Vec<u8> {
+        let mut bytes: Vec<u8> = roots.iter().flat_map(|root| root.to_vec()).collect();
+        if min_leaves > roots.len() {
+            bytes.resize(min_leaves * 32, 0);
+        }
+        merkleize_standard(&bytes)
+    }
+
+    #[test]
+    fn merkleize_roots_against_standard() {
+        for num_roots in &[0, 1, 3, 5] {
+            for min_leaves in 0..10 {
+                let roots = roots(*num_roots);
+                assert_eq!(
+                    merkleize_roots(&roots, min_leaves).to_vec(),
+                    reference_root(&roots, min_leaves),
+                    "num_roots: {}, min_leaves: {}",
+                    num_roots,
+                    min_leaves
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn merkleize_leaf_chunks_against_standard() {
+        for num_leaves in &[0, 1, 9] {
+            for min_leaves in 0..17 {
+                let leaves = roots(*num_leaves);
+                assert_eq!(
+                    merkleize_leaf_chunks(&leaves, min_leaves).to_vec(),
+                    reference_root(&leaves, min_leaves),
+                    "num_leaves: {}, min_leaves: {}",
+                    num_leaves,
+                    min_leaves
+                );
+            }
+        }
+    }
+}
+
+mod vector {
+    use super::*;
+
+    #[test]
+    fn padded_uint64_vector() {
+        let values: Vec<u64> = vec![1, 2, 3, 4, 5];
+
+        let mut bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+        bytes.resize(16 * 8, 0);
+
+        assert_eq!(
+            vector_tree_hash_root(&values, 16).to_vec(),
+            merkleize_standard(&bytes)
+        );
+    }
+
+    #[test]
+    fn padded_root_vector() {
+        let values: Vec<[u8; 32]> = vec![[1; 32], [2; 32], [3; 32]];
+
+        let mut bytes: Vec<u8> = values.iter().flat_map(|v| v.to_vec()).collect();
+        bytes.resize(4 * 32, 0);
+
+        assert_eq!(
+            vector_tree_hash_root(&values, 4).to_vec(),
+            merkleize_standard(&bytes)
+        );
+    }
+
+    #[test]
+    fn full_vector() {
+        let values: Vec<u64> = (0..8).collect();
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+
+        assert_eq!(
+            vector_tree_hash_root(&values, 8).to_vec(),
+            merkleize_standard(&bytes)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_elements() {
+        vector_tree_hash_root(&[1u64, 2, 3], 2);
+    }
+}
+
+mod batch {
+    use super::*;
+    use types::{typenum::U32, VariableList};
+
+    #[test]
+    fn batch_matches_individual_roots() {
+        let items: Vec<VariableList<u64, U32>> = (0..20)
+            .map(|i| VariableList::from((0..i).collect::<Vec<u64>>()))
+            .collect();
+
+        let roots = tree_hash_batch(&items);
+
+        assert_eq!(roots.len(), items.len());
+        for (item, root) in items.iter().zip(roots.iter()) {
+            assert_eq!(root.to_vec(), item.tree_hash_root());
+        }
+    }
+
+    #[test]
+    fn empty_batch() {
+        let items: Vec<VariableList<u64, U32>> = vec![];
+
+        assert!(tree_hash_batch(&items).is_empty());
+    }
+}
+
+mod packing {
+    use super::*;
+    use ssz::Encode;
+    use tree_hash::HASHSIZE;
+
+    /// Basic values are packed into chunks as their SSZ encoding.
+    fn check_packing<T: Encode + TreeHash>() {
+        assert_eq!(
+            T::tree_hash_packing_factor(),
+            HASHSIZE / <T as Encode>::ssz_fixed_len()
+        );
+    }
+
+    #[test]
+    fn packing_matches_ssz_fixed_len() {
+        ssz::assert_ssz_fixed_len!(u8, 1);
+        ssz::assert_ssz_fixed_len!(u16, 2);
+        ssz::assert_ssz_fixed_len!(u32, 4);
+        ssz::assert_ssz_fixed_len!(u64, 8);
+        ssz::assert_ssz_fixed_len!(bool, 1);
+
+        check_packing::<u8>();
+        check_packing::<u16>();
+        check_packing::<u32>();
+        check_packing::<u64>();
+        check_packing::<bool>();
+    }
+}
+
+mod phantom_data {
+    use super::*;
+    use std::marker::PhantomData;
+
+    #[derive(TreeHash)]
+    struct WithMarker<T> {
+        a: u64,
+        _marker: PhantomData<T>,
+        b: [u8; 32],
+    }
+
+    #[derive(TreeHash)]
+    struct WithoutMarker {
+        a: u64,
+        b: [u8; 32],
+    }
+
+    #[test]
+    fn marker_is_skipped() {
+        let with_marker = WithMarker::<String> {
+            a: 42,
+            _marker: PhantomData,
+            b: [7; 32],
+        };
+        let without_marker = WithoutMarker { a: 42, b: [7; 32] };
+
+        assert_eq!(
+            with_marker.tree_hash_root(),
+            without_marker.tree_hash_root()
+        );
+        assert_eq!(with_marker.tree_hash_field_roots().len(), 2);
+    }
+}
+
+mod two_fields {
+    use super::*;
+
+    #[derive(TreeHash)]
+    struct Three {
+        a: u64,
+        b: u64,
+        c: u64,
+    }
+
+    /// The root of the leaves made from `field_roots`, hashed by the general merkleization path.
+    fn merkleized_root(field_roots: &[Vec<u8>]) -> Vec<u8> {
+        let mut leaves = vec![];
+        for field_root in field_roots {
+            leaves.extend_from_slice(field_root);
+        }
+        merkleize_standard(&leaves)
+    }
+
+    #[test]
+    fn two_fields_match_merkleized_root() {
+        for i in 0..8 {
+            let checkpoint = Checkpoint {
+                epoch: Epoch::new(i),
+                root: Hash256::from_low_u64_be(i * 31 + 7),
+            };
+
+            assert_eq!(
+                checkpoint.tree_hash_root(),
+                merkleized_root(&checkpoint.tree_hash_field_roots())
+            );
+        }
+    }
+
+    #[test]
+    fn other_field_counts_match_merkleized_root() {
+        let three = Three { a: 1, b: 2, c: 3 };
+
+        assert_eq!(
+            three.tree_hash_root(),
+            merkleized_root(&three.tree_hash_field_roots())
+        );
+    }
+}
@@ -26,7 +26,7 @@ fn get_hashable_fields_and_their_caches<'a>(
         .fields
         .iter()
         .filter_map(|f| {
-            if should_skip_hashing(&f) {
+            if should_skip_hashing(&f) || is_phantom_data(&f.ty) {
                 None
             } else {
                 let ident = f
@@ -109,9 +109,29 @@ fn should_skip_hashing(field: &syn::Field) -> bool {
     })
 }
 
+/// Returns true if the type of a field is `PhantomData` (e.g., `PhantomData<E>` or
+/// `std::marker::PhantomData<E>`).
+///
+/// `PhantomData` fields are zero-sized markers, so they are never hashed.
+fn is_phantom_data(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.value().ident == "PhantomData"),
+        _ => false,
+    }
+}
+
 /// Implements `tree_hash::TreeHash` for some `struct`.
 ///
 /// Fields are hashed in the order they are defined.
+///
+/// Fields marked with `#[tree_hash(skip_hashing)]` and `PhantomData` fields are not hashed.
+///
+/// Structs with exactly two hashed fields are hashed with `tree_hash::hash_two_roots`, rather than
+/// by merkleizing a buffer of their field roots.
 #[proc_macro_derive(TreeHash, attributes(tree_hash))]
 pub fn tree_hash_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
@@ -126,6 +146,26 @@ pub fn tree_hash_derive(input: TokenStream) -> TokenStream {
 
     let idents = get_hashable_fields(&struct_data);
 
+    let root = match idents.as_slice() {
+        [first, second] => quote! {
+            let mut first = [0; 32];
+            first.copy_from_slice(&self.#first.tree_hash_root());
+            let mut second = [0; 32];
+            second.copy_from_slice(&self.#second.tree_hash_root());
+
+            tree_hash::hash_two_roots(&first, &second).to_vec()
+        },
+        _ => quote! {
+            let mut leaves = Vec::with_capacity(4 * tree_hash::HASHSIZE);
+
+            #(
+                leaves.append(&mut self.#idents.tree_hash_root());
+            )*
+
+            tree_hash::merkle_root(&leaves, 0)
+        },
+    };
+
     let output = quote! {
         impl #impl_generics tree_hash::TreeHash for #name #ty_generics #where_clause {
             fn tree_hash_type() -> tree_hash::TreeHashType {
@@ -141,13 +181,26 @@ pub fn tree_hash_derive(input: TokenStream) -> TokenStream {
             }
 
             fn tree_hash_root(&self) -> Vec<u8> {
-                let mut leaves = Vec::with_capacity(4 * tree_hash::HASHSIZE);
+                #root
+            }
 
-                #(
-                    leaves.append(&mut self.#idents.tree_hash_root());
-                )*
+            fn tree_hash_visit_leaves<F: FnMut(usize, &[u8; 32])>(&self, mut f: F) {
+                let field_roots = tree_hash::TreeHashFields::tree_hash_field_roots(self);
+                for (i, field_root) in field_roots.iter().enumerate() {
+                    let mut leaf = [0; 32];
+                    leaf.copy_from_slice(field_root);
+                    f(i, &leaf);
+                }
+            }
+        }
 
-                tree_hash::merkle_root(&leaves, 0)
+        impl #impl_generics tree_hash::TreeHashFields for #name #ty_generics #where_clause {
+            fn tree_hash_field_roots(&self) -> Vec<Vec<u8>> {
+                vec![
+                    #(
+                        tree_hash::TreeHash::tree_hash_root(&self.#idents),
+                    )*
+                ]
             }
         }
     };
@@ -266,7 +319,7 @@ fn get_signed_root_named_field_idents(struct_data: &syn::DataStruct) -> Vec<&syn
         .fields
         .iter()
         .filter_map(|f| {
-            if should_skip_signed_root(&f) {
+            if should_skip_signed_root(&f) || is_phantom_data(&f.ty) {
                 None
             } else {
                 Some(match &f.ident {
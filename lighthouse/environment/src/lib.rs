@@ -9,12 +9,15 @@
 
 use eth2_config::Eth2Config;
 use futures::{sync::oneshot, Future};
-use slog::{info, o, Drain, Level, Logger};
+use slog::{info, o, warn, Drain, Level, Logger};
 use sloggers::{null::NullLoggerBuilder, Build};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use tokio::runtime::{Builder as RuntimeBuilder, Runtime, TaskExecutor};
 use types::{EthSpec, InteropEthSpec, MainnetEthSpec, MinimalEthSpec};
 
@@ -129,10 +132,80 @@ impl<E: EthSpec> EnvironmentBuilder<E> {
                 .ok_or_else(|| "Cannot build environment without log".to_string())?,
             eth_spec_instance: self.eth_spec_instance,
             eth2_config: self.eth2_config,
+            task_tracker: TaskTracker::default(),
         })
     }
 }
 
+/// Counts the running instances of named tasks, so the tasks which are still running when the
+/// runtime fails to shutdown in time can be reported.
+#[derive(Clone, Default)]
+pub struct TaskTracker {
+    running: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl TaskTracker {
+    /// Returns a future which resolves to `future`, counting it as a running `name` task until it
+    /// resolves or is dropped.
+    pub fn track<F: Future>(
+        &self,
+        name: &str,
+        future: F,
+    ) -> impl Future<Item = F::Item, Error = F::Error> {
+        let guard = TaskGuard::new(name.to_string(), self.running.clone());
+
+        future.then(move |result| {
+            drop(guard);
+            result
+        })
+    }
+
+    /// Returns the sorted names of the tracked tasks which have not yet finished.
+    pub fn running(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .running
+            .lock()
+            .expect("task tracker lock should not be poisoned")
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// Decrements the running count of its task once dropped.
+struct TaskGuard {
+    name: String,
+    running: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl TaskGuard {
+    fn new(name: String, running: Arc<Mutex<HashMap<String, usize>>>) -> Self {
+        *running
+            .lock()
+            .expect("task tracker lock should not be poisoned")
+            .entry(name.clone())
+            .or_insert(0) += 1;
+
+        Self { name, running }
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.running.lock() {
+            let finished = running.get_mut(&self.name).map_or(true, |count| {
+                *count -= 1;
+                *count == 0
+            });
+            if finished {
+                running.remove(&self.name);
+            }
+        }
+    }
+}
+
 /// An execution context that can be used by a service.
 ///
 /// Distinct from an `Environment` because a `Context` is not able to give a mutable reference to a
@@ -143,6 +216,7 @@ pub struct RuntimeContext<E: EthSpec> {
     pub log: Logger,
     pub eth_spec_instance: E,
     pub eth2_config: Eth2Config,
+    pub task_tracker: TaskTracker,
 }
 
 impl<E: EthSpec> RuntimeContext<E> {
@@ -155,6 +229,7 @@ impl<E: EthSpec> RuntimeContext<E> {
             log: self.log.new(o!("service" => service_name)),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            task_tracker: self.task_tracker.clone(),
         }
     }
 
@@ -171,6 +246,7 @@ pub struct Environment<E: EthSpec> {
     log: Logger,
     eth_spec_instance: E,
     pub eth2_config: Eth2Config,
+    task_tracker: TaskTracker,
 }
 
 impl<E: EthSpec> Environment<E> {
@@ -189,6 +265,7 @@ impl<E: EthSpec> Environment<E> {
             log: self.log.clone(),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            task_tracker: self.task_tracker.clone(),
         }
     }
 
@@ -199,6 +276,7 @@ impl<E: EthSpec> Environment<E> {
             log: self.log.new(o!("service" => service_name)),
             eth_spec_instance: self.eth_spec_instance.clone(),
             eth2_config: self.eth2_config.clone(),
+            task_tracker: self.task_tracker.clone(),
         }
     }
 
@@ -227,6 +305,39 @@ impl<E: EthSpec> Environment<E> {
             .map_err(|e| format!("Tokio runtime shutdown returned an error: {:?}", e))
     }
 
+    /// Shutdown the `tokio` runtime when all tasks are idle, or after `timeout` if some tasks fail
+    /// to finish in time.
+    ///
+    /// Returns the names of the tracked tasks which had not finished when the `timeout` elapsed.
+    /// These tasks are abandoned on the runtime, which is stopped once the process exits.
+    pub fn shutdown_on_idle_with_timeout(self, timeout: Duration) -> Result<Vec<String>, String> {
+        let (shutdown_send, shutdown_recv) = mpsc::channel();
+        let shutdown = self.runtime.shutdown_on_idle();
+
+        thread::spawn(move || {
+            // The receiver is dropped if the shutdown timed out, ignore the failed send.
+            let _ = shutdown_send.send(shutdown.wait());
+        });
+
+        match shutdown_recv.recv_timeout(timeout) {
+            Ok(result) => result
+                .map(|()| vec![])
+                .map_err(|e| format!("Tokio runtime shutdown returned an error: {:?}", e)),
+            Err(_) => {
+                let unfinished = self.task_tracker.running();
+
+                warn!(
+                    self.log,
+                    "Shutdown timed out, forcing exit";
+                    "timeout_secs" => timeout.as_secs(),
+                    "unfinished_tasks" => format!("{:?}", unfinished)
+                );
+
+                Ok(unfinished)
+            }
+        }
+    }
+
     /// Sets the logger (and all child loggers) to log to a file.
     pub fn log_to_json_file(&mut self, path: PathBuf) -> Result<(), String> {
         let file = OpenOptions::new()
@@ -264,3 +375,67 @@ pub fn null_logger() -> Result<Logger, String> {
         .build()
         .map_err(|e| format!("Failed to start null logger: {:?}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use std::time::Instant;
+
+    fn build_env() -> Environment<MinimalEthSpec> {
+        EnvironmentBuilder::minimal()
+            .null_logger()
+            .expect("should build env logger")
+            .single_thread_tokio_runtime()
+            .expect("should start tokio runtime")
+            .build()
+            .expect("environment should build")
+    }
+
+    #[test]
+    fn shutdown_completes_when_idle() {
+        let mut env = build_env();
+        let context = env.core_context();
+        context.executor.spawn(
+            context
+                .task_tracker
+                .track("finished", future::ok::<(), ()>(())),
+        );
+
+        assert_eq!(
+            env.shutdown_on_idle_with_timeout(Duration::from_secs(10)),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn shutdown_is_forced_after_timeout() {
+        let mut env = build_env();
+        let context = env.core_context();
+
+        // A task which never finishes. See the `websocket_server` tests for a real service.
+        context.executor.spawn(
+            context
+                .task_tracker
+                .track("never_finishes", future::empty::<(), ()>()),
+        );
+        context.executor.spawn(
+            context
+                .task_tracker
+                .track("finished", future::ok::<(), ()>(())),
+        );
+
+        let timeout = Duration::from_millis(200);
+        let start = Instant::now();
+        let unfinished = env
+            .shutdown_on_idle_with_timeout(timeout)
+            .expect("should force shutdown");
+
+        assert!(start.elapsed() >= timeout, "should wait for the timeout");
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "should not hang after the timeout"
+        );
+        assert_eq!(unfinished, vec!["never_finishes".to_string()]);
+    }
+}
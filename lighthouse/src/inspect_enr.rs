@@ -0,0 +1,117 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use eth2_libp2p::{identity::PublicKey, Enr};
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("inspect-enr")
+        .about("Decodes an ENR and prints its fields, then exits.")
+        .arg(
+            Arg::with_name("enr")
+                .value_name("ENR_STRING")
+                .help("The base64-encoded ENR, with or without the \"enr:\" prefix.")
+                .takes_value(true)
+                .required(true),
+        )
+}
+
+/// Prints the fields of the ENR given on the command line.
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let enr_str = matches
+        .value_of("enr")
+        .ok_or_else(|| "Expected an ENR".to_string())?;
+
+    for (name, value) in enr_fields(&parse_enr(enr_str)?) {
+        println!("{}: {}", name, value);
+    }
+
+    Ok(())
+}
+
+/// Parses a base64-encoded ENR, adding the `enr:` prefix if it is missing.
+fn parse_enr(enr_str: &str) -> Result<Enr, String> {
+    let enr_str = enr_str.trim();
+    let enr_str = if enr_str.starts_with("enr:") {
+        enr_str.to_string()
+    } else {
+        format!("enr:{}", enr_str)
+    };
+
+    enr_str.parse().map_err(|e| format!("Invalid ENR: {:?}", e))
+}
+
+/// Returns the name and value of each of the printed fields of `enr`, in order.
+fn enr_fields(enr: &Enr) -> Vec<(&'static str, String)> {
+    fn or_none<T: ToString>(value: Option<T>) -> String {
+        value.map_or_else(|| "none".to_string(), |value| value.to_string())
+    }
+
+    vec![
+        ("Node ID", hex::encode(enr.node_id().raw())),
+        ("Peer ID", enr.peer_id().to_string()),
+        ("IP", or_none(enr.ip())),
+        ("TCP port", or_none(enr.tcp())),
+        ("UDP port", or_none(enr.udp())),
+        ("Sequence number", enr.seq().to_string()),
+        ("Public key", public_key_hex(&enr.public_key())),
+    ]
+}
+
+/// Returns the compressed encoding of `public_key` as hex.
+fn public_key_hex(public_key: &PublicKey) -> String {
+    match public_key {
+        PublicKey::Secp256k1(key) => hex::encode(&key.encode()[..]),
+        PublicKey::Ed25519(key) => hex::encode(&key.encode()[..]),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example record of EIP-778.
+    const EXAMPLE_ENR: &str = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+
+    fn field<'a>(fields: &'a [(&'static str, String)], name: &str) -> &'a str {
+        fields
+            .iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, value)| value.as_str())
+            .expect("field should be printed")
+    }
+
+    #[test]
+    fn example_enr_fields() {
+        let fields = enr_fields(&parse_enr(EXAMPLE_ENR).expect("should parse ENR"));
+
+        assert_eq!(
+            field(&fields, "Node ID"),
+            "a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7"
+        );
+        assert_eq!(field(&fields, "IP"), "127.0.0.1");
+        assert_eq!(field(&fields, "TCP port"), "none");
+        assert_eq!(field(&fields, "UDP port"), "30303");
+        assert_eq!(field(&fields, "Sequence number"), "1");
+        assert_eq!(
+            field(&fields, "Public key"),
+            "03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138"
+        );
+    }
+
+    #[test]
+    fn enr_without_prefix() {
+        let enr = parse_enr(EXAMPLE_ENR).expect("should parse ENR");
+
+        assert_eq!(
+            parse_enr(&EXAMPLE_ENR["enr:".len()..])
+                .expect("should parse ENR without prefix")
+                .to_base64(),
+            enr.to_base64()
+        );
+    }
+
+    #[test]
+    fn invalid_enr() {
+        assert!(parse_enr("enr:not-an-enr").is_err());
+        assert!(parse_enr("").is_err());
+    }
+}
@@ -1,13 +1,18 @@
 #[macro_use]
 extern crate clap;
 
-use beacon_node::ProductionBeaconNode;
+mod inspect_enr;
+mod peer_id;
+mod rotate_p2p_key;
+
+use beacon_node::BeaconNode;
 use clap::{App, Arg, ArgMatches};
 use env_logger::{Builder, Env};
 use environment::EnvironmentBuilder;
 use slog::{crit, info, warn};
 use std::path::PathBuf;
 use std::process::exit;
+use std::time::Duration;
 use types::EthSpec;
 use validator_client::ProductionValidatorClient;
 
@@ -60,9 +65,24 @@ fn main() {
                 .help("Data directory for lighthouse keys and databases.")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("shutdown-timeout")
+                .long("shutdown-timeout")
+                .value_name("SECS")
+                .help(
+                    "The number of seconds to wait for all services to stop after a shutdown is \
+                      requested, after which Lighthouse exits regardless.",
+                )
+                .takes_value(true)
+                .global(true)
+                .default_value("10"),
+        )
         .subcommand(beacon_node::cli_app())
         .subcommand(validator_client::cli_app())
         .subcommand(account_manager::cli_app())
+        .subcommand(peer_id::cli_app())
+        .subcommand(inspect_enr::cli_app())
+        .subcommand(rotate_p2p_key::cli_app())
         .get_matches();
 
     macro_rules! run_with_spec {
@@ -110,6 +130,13 @@ fn run<E: EthSpec>(
         environment.log_to_json_file(path)?;
     }
 
+    let shutdown_timeout = matches
+        .value_of("shutdown-timeout")
+        .ok_or_else(|| "Expected --shutdown-timeout flag".to_string())?
+        .parse::<u64>()
+        .map(Duration::from_secs)
+        .map_err(|e| format!("Unable to parse --shutdown-timeout: {:?}", e))?;
+
     if std::mem::size_of::<usize>() != 8 {
         crit!(
             log,
@@ -140,15 +167,34 @@ fn run<E: EthSpec>(
         return Ok(());
     };
 
+    if let Some(sub_matches) = matches.subcommand_matches("peer-id") {
+        // Only reads (or generates) the network key, the network and database are never started.
+        return peer_id::run(sub_matches, &log);
+    };
+
+    if let Some(sub_matches) = matches.subcommand_matches("inspect-enr") {
+        return inspect_enr::run(sub_matches);
+    };
+
+    if let Some(sub_matches) = matches.subcommand_matches("rotate-p2p-key") {
+        // The running node, if any, keeps its identity until it is restarted.
+        return rotate_p2p_key::run(sub_matches);
+    };
+
+    if let Some(sub_matches) = matches.subcommand_matches("beacon_node") {
+        if sub_matches.is_present("print-enr-and-exit") {
+            let enr = beacon_node::local_enr_from_cli(sub_matches, &log)?;
+            println!("{}", beacon_node::boot_node_details(&enr));
+            return Ok(());
+        }
+    };
+
     let beacon_node = if let Some(sub_matches) = matches.subcommand_matches("beacon_node") {
         let runtime_context = environment.core_context();
 
         let beacon = environment
             .runtime()
-            .block_on(ProductionBeaconNode::new_from_cli(
-                runtime_context,
-                sub_matches,
-            ))
+            .block_on(BeaconNode::new_from_cli(runtime_context, sub_matches))
             .map_err(|e| format!("Failed to start beacon node: {}", e))?;
 
         Some(beacon)
@@ -190,6 +236,9 @@ fn run<E: EthSpec>(
     drop(beacon_node);
     drop(validator_client);
 
-    // Shutdown the environment once all tasks have completed.
-    environment.shutdown_on_idle()
+    // Shutdown the environment once all tasks have completed, or force the exit if they fail to
+    // complete before the timeout.
+    environment
+        .shutdown_on_idle_with_timeout(shutdown_timeout)
+        .map(|_unfinished| ())
 }
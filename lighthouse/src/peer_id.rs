@@ -0,0 +1,150 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use eth2_libp2p::{
+    build_enr, load_private_key, read_enr_from_disc, read_private_key, Enr, NetworkConfig, PeerId,
+    NETWORK_KEY_FILENAME,
+};
+use slog::Logger;
+use std::path::PathBuf;
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("peer-id")
+        .about("Prints the peer id and ENR of the network key in a network directory, then exits.")
+        .arg(
+            Arg::with_name("network-dir")
+                .long("network-dir")
+                .value_name("DIR")
+                .help("The network directory containing the network key.")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("generate")
+                .long("generate")
+                .help("Generate a new network key if none exists in the network directory."),
+        )
+}
+
+/// Prints the peer id and ENR derived from the network key, without starting the network.
+pub fn run(matches: &ArgMatches, log: &Logger) -> Result<(), String> {
+    let network_dir = matches
+        .value_of("network-dir")
+        .map(PathBuf::from)
+        .ok_or_else(|| "Expected --network-dir flag".to_string())?;
+
+    let (peer_id, enr) = load_identity(network_dir, matches.is_present("generate"), log)?;
+
+    println!("Peer ID: {}", peer_id);
+    println!("ENR: {}", enr.to_base64());
+
+    Ok(())
+}
+
+/// Loads the network key in `network_dir` in the same way as the beacon node, returning the
+/// associated peer id and ENR.
+///
+/// The ENR stored in `network_dir` is returned as-is if it belongs to the key. Otherwise an ENR
+/// for the default network config is built, which is not stored.
+///
+/// Returns an error if there is no network key, unless `generate` is `true`.
+fn load_identity(
+    network_dir: PathBuf,
+    generate: bool,
+    log: &Logger,
+) -> Result<(PeerId, Enr), String> {
+    if !generate && !network_dir.join(NETWORK_KEY_FILENAME).exists() {
+        return Err(format!(
+            "No network key in {:?}. Use --generate to create one.",
+            network_dir
+        ));
+    }
+
+    let config = NetworkConfig {
+        network_dir,
+        ..NetworkConfig::default()
+    };
+
+    let local_key = if generate {
+        load_private_key(&config, log)
+    } else {
+        read_private_key(&config)
+    }
+    .map_err(|e| format!("Unable to load network key: {}", e))?;
+    let peer_id = PeerId::from(local_key.public());
+
+    let enr = match read_enr_from_disc(&config.network_dir)? {
+        Some(enr) if enr.peer_id() == peer_id => enr,
+        _ => build_enr(&local_key, &config, log)?,
+    };
+
+    Ok((peer_id, enr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth2_libp2p::{identity::Keypair, load_enr};
+    use std::fs::File;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    fn null_logger() -> Logger {
+        Logger::root(slog::Discard, slog::o!())
+    }
+
+    #[test]
+    fn peer_id_from_key_file() {
+        let dir = TempDir::new("peer_id").expect("should create temp dir");
+
+        let keypair = Keypair::generate_secp256k1();
+        if let Keypair::Secp256k1(key) = &keypair {
+            File::create(dir.path().join(NETWORK_KEY_FILENAME))
+                .and_then(|mut f| f.write_all(&key.secret().to_bytes()))
+                .expect("should write key file");
+        }
+
+        let (peer_id, _) = load_identity(dir.path().to_path_buf(), false, &null_logger())
+            .expect("should load identity");
+
+        assert_eq!(peer_id, PeerId::from(keypair.public()));
+    }
+
+    #[test]
+    fn stored_enr_is_not_modified() {
+        let dir = TempDir::new("peer_id").expect("should create temp dir");
+        let (_, built_enr) = load_identity(dir.path().to_path_buf(), true, &null_logger())
+            .expect("should generate identity");
+        assert!(read_enr_from_disc(dir.path()).unwrap().is_none());
+
+        // store an ENR with non-default ports, as a beacon node would
+        let config = NetworkConfig {
+            network_dir: dir.path().to_path_buf(),
+            libp2p_port: 19200,
+            discovery_port: 19200,
+            ..NetworkConfig::default()
+        };
+        let local_key = read_private_key(&config).expect("should read key");
+        let stored_enr = load_enr(&local_key, &config, &null_logger()).expect("should store enr");
+        assert_ne!(stored_enr.to_base64(), built_enr.to_base64());
+
+        let (_, enr) = load_identity(dir.path().to_path_buf(), false, &null_logger())
+            .expect("should load identity");
+        assert_eq!(enr.to_base64(), stored_enr.to_base64());
+        assert_eq!(
+            read_enr_from_disc(dir.path())
+                .unwrap()
+                .map(|enr| enr.to_base64()),
+            Some(stored_enr.to_base64())
+        );
+    }
+
+    #[test]
+    fn missing_key_file() {
+        let dir = TempDir::new("peer_id").expect("should create temp dir");
+
+        assert!(load_identity(dir.path().to_path_buf(), false, &null_logger()).is_err());
+        assert!(!dir.path().join(NETWORK_KEY_FILENAME).exists());
+
+        assert!(load_identity(dir.path().to_path_buf(), true, &null_logger()).is_ok());
+        assert!(dir.path().join(NETWORK_KEY_FILENAME).exists());
+    }
+}
@@ -0,0 +1,61 @@
+use clap::{App, Arg, ArgMatches, SubCommand};
+use eth2_libp2p::{rotate_private_key, NETWORK_KEY_BACKUP_FILENAME};
+use std::path::PathBuf;
+
+pub fn cli_app<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("rotate-p2p-key")
+        .about(
+            "Replaces the network key in a network directory with a new one, backing up the \
+             existing key, then exits. A running beacon node keeps its previous identity until \
+             it is restarted.",
+        )
+        .arg(
+            Arg::with_name("network-dir")
+                .long("network-dir")
+                .value_name("DIR")
+                .help("The network directory containing the network key.")
+                .takes_value(true)
+                .required(true),
+        )
+}
+
+/// Rotates the network key in the given network directory, printing the previous and new peer
+/// ids.
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let network_dir = matches
+        .value_of("network-dir")
+        .map(PathBuf::from)
+        .ok_or_else(|| "Expected --network-dir flag".to_string())?;
+
+    let (previous_peer_id, new_peer_id) = rotate_private_key(&network_dir)
+        .map_err(|e| format!("Unable to rotate network key: {}", e))?;
+
+    println!("Previous peer ID: {}", previous_peer_id);
+    println!("New peer ID: {}", new_peer_id);
+    println!(
+        "The previous key was backed up to {:?}",
+        network_dir.join(NETWORK_KEY_BACKUP_FILENAME)
+    );
+    println!("Restart the beacon node to use the new key.");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn missing_key_file() {
+        let dir = TempDir::new("rotate_p2p_key").expect("should create temp dir");
+        let matches = cli_app().get_matches_from(vec![
+            "rotate-p2p-key",
+            "--network-dir",
+            dir.path().to_str().expect("path should be utf-8"),
+        ]);
+
+        assert!(run(&matches).is_err());
+        assert!(!dir.path().join(NETWORK_KEY_BACKUP_FILENAME).exists());
+    }
+}